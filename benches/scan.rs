@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Capture and scan throughput.
+//!
+//! The capture benches parse the bench executable itself, which is always at hand, into owned
+//! captures and (`borrowed`) into an `AbiCaptureRef`, copying no symbol names. Set
+//! `ABIREPORT_BENCH_CORPUS` to a directory (e.g. a copy of a distro's /usr/lib) to also time
+//! end-to-end scans of it, single threaded, with one job per CPU and (`compact`) into a
+//! `CompactReport`.
+
+use abireport_rs::{ElfInspector, Limits, ScanBuilder, Scanner};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn capture(c: &mut Criterion) {
+    let exe = env::current_exe().expect("the bench executable");
+    let size = fs::metadata(&exe).map_or(0, |metadata| metadata.len());
+    let mut group = c.benchmark_group("capture");
+    group.throughput(Throughput::Bytes(size));
+    for (name, sonames_only) in [("full", false), ("sonames-only", true)] {
+        let scanner = scanner(&exe, sonames_only, 1);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                scanner
+                    .capture(&exe)
+                    .expect("the bench executable captures")
+            })
+        });
+    }
+    let data = fs::read(&exe).expect("the bench executable reads");
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            let mut inspector = ElfInspector::from_bytes("bench", data.clone(), &Limits::default())
+                .expect("the bench executable parses");
+            let capture = inspector
+                .capture_ref()
+                .expect("the bench executable captures");
+            capture.dynsym_exports().len() + capture.dynsym_imports().len()
+        })
+    });
+    group.finish();
+}
+
+fn scan_corpus(c: &mut Criterion) {
+    let Some(corpus) = env::var_os("ABIREPORT_BENCH_CORPUS").map(PathBuf::from) else {
+        return;
+    };
+    let files = scanner(&corpus, false, 1).discover();
+    let size = files
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    let mut group = c.benchmark_group("scan");
+    group
+        .throughput(Throughput::Bytes(size))
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(30));
+    for (name, jobs) in [("single", 1), ("parallel", 0)] {
+        let scanner = scanner(&corpus, false, jobs);
+        group.bench_function(name, |b| {
+            b.iter(|| scanner.scan().expect("the corpus scans"))
+        });
+    }
+    let scanner = scanner(&corpus, false, 0);
+    group.bench_function("compact", |b| {
+        b.iter(|| scanner.scan_compact().expect("the corpus scans"))
+    });
+    group.finish();
+}
+
+fn scanner(path: &Path, sonames_only: bool, jobs: usize) -> Scanner {
+    ScanBuilder::new()
+        .path(path)
+        .sonames_only(sonames_only)
+        .jobs(jobs)
+        .build()
+        .expect("no patterns to reject")
+}
+
+criterion_group!(benches, capture, scan_corpus);
+criterion_main!(benches);
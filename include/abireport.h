@@ -0,0 +1,52 @@
+/*
+ * SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+ *
+ * SPDX-License-Identifier: MPL-2.0
+ */
+
+/* C bindings for abireport-rs, built with the `capi` feature. See src/capi.rs. */
+
+#ifndef ABIREPORT_H
+#define ABIREPORT_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The ABI of one ELF file */
+typedef struct AbiCapture AbiCapture;
+
+/* A report under construction: captures are added one by one, and sorted when serialized */
+typedef struct AbiReportBuilder AbiReportBuilder;
+
+/* The last error on this thread, or NULL. Valid until the next failing call on this thread. */
+const char *abireport_last_error(void);
+
+/* Capture the ABI of the ELF file at `path`, or NULL on failure. */
+AbiCapture *abireport_parse_elf(const char *path);
+
+/* The capture as JSON, or NULL on failure. Release with abireport_string_free(). */
+char *abireport_capture_json(const AbiCapture *capture);
+
+void abireport_capture_free(AbiCapture *capture);
+
+/* Start an empty report. */
+AbiReportBuilder *abireport_report_new(void);
+
+/* Add a capture to the report, which takes ownership of it (even on failure). Returns 0 on
+ * success, -1 on failure. */
+int abireport_report_add_capture(AbiReportBuilder *report, AbiCapture *capture);
+
+/* The report as JSON (with the captures sorted and the digest filled in), or NULL on failure.
+ * Release with abireport_string_free(). */
+char *abireport_report_json(const AbiReportBuilder *report);
+
+void abireport_report_free(AbiReportBuilder *report);
+
+void abireport_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ABIREPORT_H */
@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! The GNU ABI tag note (NT_GNU_ABI_TAG, usually `.note.ABI-tag`).
+//!
+//! glibc's startup files stamp executables (and glibc itself its libraries) with the oldest
+//! kernel they were built to run on, and the dynamic linker refuses to load a library declaring
+//! a newer kernel than the running one. Raising a distro's kernel baseline means checking that
+//! nothing declares more than the new baseline, and seeing what could be rebuilt for it.
+
+use crate::section::find_note;
+use crate::Limits;
+use elf::endian::AnyEndian;
+use elf::note::Note;
+use elf::ElfBytes;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Result;
+
+/// The OS of the ABI tag, by its number in the note
+const OS_NAMES: [&str; 4] = ["Linux", "Hurd", "Solaris", "FreeBSD"];
+
+/// The OS and oldest OS version a file declares it runs on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AbiTag {
+    pub os: u32,           // 0 for Linux, see ELF_NOTE_OS_* in <elf.h>
+    pub version: [u32; 3], // major, minor, subminor
+}
+
+impl AbiTag {
+    /// The oldest Linux kernel the file runs on, None for other OSes.
+    pub fn min_kernel(&self) -> Option<[u32; 3]> {
+        (self.os == 0).then_some(self.version)
+    }
+}
+
+impl fmt::Display for AbiTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, subminor] = self.version;
+        match OS_NAMES.get(self.os as usize) {
+            Some(os) => write!(f, "{os} {major}.{minor}.{subminor}"),
+            None => write!(f, "OS {} {major}.{minor}.{subminor}", self.os),
+        }
+    }
+}
+
+/// Extract the NT_GNU_ABI_TAG note, found like the build-id.
+pub(crate) fn parse_abi_tag(
+    elf_file: &ElfBytes<AnyEndian>,
+    limits: &Limits,
+) -> Result<Option<AbiTag>> {
+    find_note(elf_file, limits, |note| match note {
+        Note::GnuAbiTag(tag) => Some(AbiTag {
+            os: tag.os,
+            version: [tag.major, tag.minor, tag.subminor],
+        }),
+        _ => None,
+    })
+}
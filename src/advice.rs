@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Versioning advice for changed shared objects, following libtool's current:revision:age rules.
+//!
+//! - Only the implementation changed: bump revision.
+//! - Exports were added: bump current and age, reset revision. The soname stays the same.
+//! - Exports were removed, changed type or (data objects) size: bump current, reset revision and age. The soname
+//!   (current - age) changes, so consumers have to be rebuilt.
+
+use crate::diff::symbol_key;
+use crate::{AbiCapture, AbiReport, ElfKind};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Which part of the libtool version has to move
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum VersionBump {
+    Revision, // c:r+1:a
+    Age,      // c+1:0:a+1
+    Current,  // c+1:0:0
+}
+
+/// What one shared object should do about its version
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct VersionAdvice {
+    pub filename: String,
+    pub bump: VersionBump,
+    pub old_soname: Option<String>,
+    pub new_soname: Option<String>,
+    pub recommended_soname: Option<String>, // None if the old soname has no number to bump
+}
+
+impl VersionAdvice {
+    /// Whether the new soname is what the changes call for.
+    pub fn is_followed(&self) -> bool {
+        match self.bump {
+            VersionBump::Current => self.new_soname != self.old_soname,
+            VersionBump::Revision | VersionBump::Age => self.new_soname == self.old_soname,
+        }
+    }
+}
+
+impl AbiCapture {
+    /// The version bump going from this (old) capture to a new one calls for.
+    pub fn version_bump(&self, new: &AbiCapture) -> VersionBump {
+        if !self.broken_exports(new).is_empty() {
+            return VersionBump::Current;
+        }
+        let old_exports = self
+            .dynsym_exports
+            .iter()
+            .map(symbol_key)
+            .collect::<HashSet<_>>();
+        match new
+            .dynsym_exports
+            .iter()
+            .filter(|symbol| !symbol.is_boilerplate())
+            .all(|symbol| old_exports.contains(&symbol_key(symbol)))
+        {
+            true => VersionBump::Revision,
+            false => VersionBump::Age,
+        }
+    }
+}
+
+impl AbiReport {
+    /// Advice for each shared object present in both reports whose capture changed, in report
+    /// order.
+    pub fn version_advice(&self, new: &AbiReport) -> Vec<VersionAdvice> {
+        let new_by_name = new
+            .captures()
+            .iter()
+            .map(|capture| (capture.filename.as_str(), capture))
+            .collect::<HashMap<_, _>>();
+
+        self.captures()
+            .iter()
+            .filter(|old| old.elf_kind == ElfKind::SharedObject)
+            .filter_map(|old| {
+                let new = new_by_name.get(old.filename.as_str())?;
+                if !old.is_same_arch(new) || old.diff(new).is_empty() {
+                    return None;
+                }
+                let bump = old.version_bump(new);
+                let recommended_soname = match bump {
+                    VersionBump::Current => old.soname.as_deref().and_then(next_soname),
+                    VersionBump::Revision | VersionBump::Age => old.soname.clone(),
+                };
+                Some(VersionAdvice {
+                    filename: old.filename.clone(),
+                    bump,
+                    old_soname: old.soname.clone(),
+                    new_soname: new.soname.clone(),
+                    recommended_soname,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `libfoo.so.3` -> `libfoo.so.4`
+fn next_soname(soname: &str) -> Option<String> {
+    let (stem, number) = soname.rsplit_once('.')?;
+    let number = number.parse::<u64>().ok()?;
+    Some(format!("{stem}.{}", number + 1))
+}
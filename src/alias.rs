@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Exports that are aliases of each other.
+//!
+//! Libraries often export one definition under several names, e.g. glibc's `malloc` and
+//! `__libc_malloc`, or a public name that is a weak alias of an internal one. Such names share
+//! their address and size. One name of each group is taken to be the canonical one: the one
+//! with the fewest leading underscores, which is usually the public API. Dropping one of the
+//! other names while the canonical one survives is an alias reshuffle rather than a removal.
+
+use crate::{AbiCapture, Symbol};
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Exported names sharing one definition
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AliasGroup {
+    pub canonical: String,
+    pub aliases: Vec<String>, // the other names, natural sorted
+}
+
+impl AliasGroup {
+    /// Every name of the group, the canonical one first.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.canonical.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Group `names` (as many as there are, duplicates allowed), or None if they are all the
+    /// same name.
+    fn new<'a>(names: impl IntoIterator<Item = (&'a str, bool)>) -> Option<Self> {
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        // fewest leading underscores, then global before weak, then natural order
+        names.sort_by(|(a, a_weak), (b, b_weak)| {
+            leading_underscores(a)
+                .cmp(&leading_underscores(b))
+                .then(a_weak.cmp(b_weak))
+                .then_with(|| a.natural_cmp(b))
+        });
+        let mut names = names.into_iter().map(|(name, _)| name.to_string());
+        let canonical = names.next()?;
+        let mut aliases = names.filter(|name| *name != canonical).collect::<Vec<_>>();
+        aliases.sort_by(|a, b| a.natural_cmp(b));
+        aliases.dedup();
+        (!aliases.is_empty()).then_some(Self { canonical, aliases })
+    }
+
+    /// Keep the names `keep` accepts, promoting an alias if the canonical name goes.
+    pub(crate) fn retain(mut self, keep: impl Fn(&str) -> bool) -> Option<Self> {
+        self.aliases.retain(|alias| keep(alias));
+        if !keep(&self.canonical) {
+            let promoted = (0..self.aliases.len())
+                .min_by_key(|&idx| leading_underscores(&self.aliases[idx]))?;
+            self.canonical = self.aliases.remove(promoted);
+        }
+        (!self.aliases.is_empty()).then_some(self)
+    }
+}
+
+/// A defined export, as far as finding aliases goes
+pub(crate) struct AliasCandidate<'a> {
+    pub(crate) address: u64, // st_value
+    pub(crate) size: u64,    // st_size
+    pub(crate) name: &'a str,
+    pub(crate) weak: bool, // STB_WEAK
+}
+
+/// The groups of exports sharing an address and size, in natural order of their canonical
+/// names. The versions of a symbol share its definition too, but aren't aliases.
+pub(crate) fn alias_groups<'a>(
+    candidates: impl IntoIterator<Item = AliasCandidate<'a>>,
+) -> Vec<AliasGroup> {
+    let mut by_definition = BTreeMap::<(u64, u64), Vec<(&str, bool)>>::new();
+    for candidate in candidates {
+        // undefined and absolute zero addresses don't identify a definition
+        if candidate.address != 0 {
+            let names = by_definition
+                .entry((candidate.address, candidate.size))
+                .or_default();
+            names.push((candidate.name, candidate.weak));
+        }
+    }
+    let mut groups = by_definition
+        .into_values()
+        .filter_map(AliasGroup::new)
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| a.canonical.natural_cmp(&b.canonical));
+    groups
+}
+
+fn leading_underscores(name: &str) -> usize {
+    name.len() - name.trim_start_matches('_').len()
+}
+
+impl AbiCapture {
+    /// The canonical name of the group an exported name belongs to, if it is an alias.
+    pub fn canonical_name(&self, name: &str) -> Option<&str> {
+        self.alias_groups
+            .iter()
+            .find(|group| group.aliases.iter().any(|alias| alias == name))
+            .map(|group| group.canonical.as_str())
+    }
+
+    /// Whether `symbol`, an export of this (old) capture, is an alias whose canonical name the
+    /// new capture still exports.
+    pub(crate) fn is_surviving_alias(&self, symbol: &Symbol, new: &AbiCapture) -> bool {
+        self.canonical_name(&symbol.name).is_some_and(|canonical| {
+            new.dynsym_exports
+                .iter()
+                .any(|export| export.name == canonical)
+        })
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! The object file formats captures can be made from.
+//!
+//! Each format is an [ObjectBackend]: it recognizes its files by their first bytes, parses them
+//! (lazily, where that pays off) and answers the questions a capture is assembled from: what
+//! kind of file it is, which libraries it needs and which symbols it imports and exports. ELF is
+//! always available, Mach-O and PE come with the `macho` and `pe` features. Scans pick the
+//! backend per file; reports, diffs and checks only ever see the captures.
+
+use crate::{AbiCapture, DynamicInfo, DynamicSymbols, ElfInspector, ElfKind, ExportPolicy, Limits};
+use std::io::Result;
+
+/// How many bytes from the start of a file backends get to recognize it by
+pub const HEADER_LEN: usize = 4096;
+
+/// Choices about what a backend captures
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParseOptions {
+    pub export_policy: ExportPolicy, // ELF only
+    pub audit_hidden: bool,          // ELF only, see [ElfInspector::with_hidden_audit]
+    pub raw_dynamic: bool,           // ELF only, see [ElfInspector::with_raw_dynamic]
+}
+
+/// A parsed object file of some format
+pub trait ObjectBackend {
+    /// Whether a file starting with `header` (its first [HEADER_LEN] bytes, or all of a shorter
+    /// one) is of this format.
+    fn detect(header: &[u8]) -> bool
+    where
+        Self: Sized;
+
+    /// Parse a file already in memory, within `limits`. Parts not needed for classifying the
+    /// file may be left for later.
+    fn parse(
+        file_name: &str,
+        data: Vec<u8>,
+        limits: &Limits,
+        options: ParseOptions,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Whether this is an executable or a shared object.
+    fn classify(&self) -> ElfKind;
+
+    /// The target architecture, in the customary (uname -m style) naming of the format.
+    fn arch(&self) -> &str;
+
+    /// The soname, needed libraries and search paths, or their equivalents.
+    fn deps(&mut self) -> &DynamicInfo;
+
+    /// The imported and exported symbols, failing if they exceed the limits.
+    fn symbols(&mut self) -> Result<&DynamicSymbols>;
+
+    /// Parse everything into a full capture.
+    fn capture(self: Box<Self>) -> Result<AbiCapture>;
+
+    /// Capture only what [ObjectBackend::deps] covers, see [crate::parse_elf_sonames].
+    fn capture_sonames(self: Box<Self>) -> AbiCapture;
+
+    /// Parse everything into a full capture, keeping only the symbols whose names `keep`
+    /// accepts. Backends that can look at symbols before copying them override this.
+    fn capture_filtered(self: Box<Self>, keep: &dyn Fn(&str) -> bool) -> Result<AbiCapture> {
+        let mut capture = self.capture()?;
+        capture.retain_symbols(keep);
+        Ok(capture)
+    }
+}
+
+/// Assemble a capture from the parts a backend parsed (symbols left unparsed being empty).
+pub(crate) fn assemble(
+    filename: String,
+    kind: ElfKind,
+    arch: String,
+    dynamic: DynamicInfo,
+    symbols: DynamicSymbols,
+) -> AbiCapture {
+    AbiCapture {
+        export_filter: crate::export_filter(&symbols.exports),
+        dynsym_imports: symbols.imports,
+        dynsym_exports: symbols.exports,
+        protected_exports: symbols.protected,
+        hidden_symbols: symbols.hidden,
+        visibility: symbols.visibility,
+        alias_groups: symbols.aliases,
+        needed_deps: dynamic.needed,
+        needed_link_order: dynamic.needed_order,
+        rpath: dynamic.rpath,
+        runpath: dynamic.runpath,
+        soname: dynamic.soname,
+        init_fini: dynamic.init_fini,
+        relocations: dynamic.relocations,
+        bind_now: dynamic.bind_now,
+        relro: dynamic.relro,
+        thread_local: dynamic.thread_local,
+        nodelete: dynamic.nodelete,
+        segments: dynamic.segments,
+        dynamic_entries: dynamic.raw,
+        arch: Some(arch),
+        ..AbiCapture::new(filename, kind)
+    }
+}
+
+/// [ObjectBackend::parse], boxed up
+type ParseFn = fn(&str, Vec<u8>, &Limits, ParseOptions) -> Result<Box<dyn ObjectBackend>>;
+
+/// A format files can be captured from
+struct Format {
+    detect: fn(&[u8]) -> bool,
+    parse: ParseFn,
+}
+
+impl Format {
+    const fn of<B: ObjectBackend + 'static>() -> Self {
+        Self {
+            detect: B::detect,
+            parse: parse_boxed::<B>,
+        }
+    }
+}
+
+fn parse_boxed<B: ObjectBackend + 'static>(
+    file_name: &str,
+    data: Vec<u8>,
+    limits: &Limits,
+    options: ParseOptions,
+) -> Result<Box<dyn ObjectBackend>> {
+    Ok(Box::new(B::parse(file_name, data, limits, options)?))
+}
+
+/// The known formats. ELF comes first: files of no known format are handed to it, as its errors
+/// are the ones people expect to see.
+const FORMATS: &[Format] = &[
+    Format::of::<ElfInspector>(),
+    #[cfg(feature = "macho")]
+    Format::of::<crate::macho::MachOFile>(),
+    #[cfg(feature = "pe")]
+    Format::of::<crate::pe::PeFile>(),
+];
+
+/// Whether a file starting with `header` is of one of the known formats.
+pub fn is_object(header: &[u8]) -> bool {
+    let header = &header[..header.len().min(HEADER_LEN)];
+    FORMATS.iter().any(|format| (format.detect)(header))
+}
+
+/// Parse a file already in memory with the backend of its format, within `limits`.
+///
+/// `file_name` only labels the capture; nothing is read from the filesystem.
+pub fn open_object_bytes(
+    file_name: &str,
+    data: Vec<u8>,
+    limits: &Limits,
+    options: ParseOptions,
+) -> Result<Box<dyn ObjectBackend>> {
+    let header = &data[..data.len().min(HEADER_LEN)];
+    let format = FORMATS
+        .iter()
+        .find(|format| (format.detect)(header))
+        .unwrap_or(&FORMATS[0]);
+    (format.parse)(file_name, data, limits, options)
+}
+
+/// Read a file and parse it with the backend of its format, within `limits`.
+#[cfg(feature = "fs")]
+pub fn open_object(
+    file_name: &str,
+    limits: &Limits,
+    options: ParseOptions,
+) -> Result<Box<dyn ObjectBackend>> {
+    open_object_bytes(file_name, read_file(file_name, limits)?, limits, options)
+}
+
+/// Whether the file at `path` is of one of the known formats.
+#[cfg(feature = "fs")]
+pub(crate) fn has_object_magic(path: &std::path::Path) -> bool {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_LEN as u64).read_to_end(&mut header))
+        .is_ok()
+        && is_object(&header)
+}
+
+/// Read a whole file, held to the file size limit.
+#[cfg(feature = "fs")]
+pub(crate) fn read_file(file_name: &str, limits: &Limits) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(file_name)?;
+    let file_size = file.metadata()?.len();
+    limits.check_file_size(file_size)?;
+    // the file may still grow between the stat and the read, so hold the read to the limit too
+    let mut data = Vec::with_capacity(file_size as usize);
+    file.take(limits.max_file_size).read_to_end(&mut data)?;
+    Ok(data)
+}
@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Limits on how much libraries export.
+//!
+//! A build system change (a lost version script, `-fvisibility=hidden` falling out of the flags)
+//! can make a library export thousands of internal symbols without anyone noticing. A budgets
+//! file (TOML) caps the export surface of each library, and the `export-budget` check reports
+//! the libraries going over:
+//!
+//! ```toml
+//! [[budget]]
+//! file = "**/libfoo.so*"           # glob, matched against the path, basename or soname
+//! max_exports = 1200               # exported symbols, optional
+//! max_objects = 40                 # exported data objects (STT_OBJECT and STT_TLS), optional
+//! ```
+//!
+//! The first budget matching a file applies to it; files matching none have no budget.
+
+use crate::{AbiCapture, AbiReport, Check, Finding, SymbolKind};
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// The limits for the files matching one pattern
+#[derive(Clone, Debug)]
+pub struct Budget {
+    pub file: String,               // glob matched against the path, basename or soname
+    pub max_exports: Option<usize>, // exported symbols
+    pub max_objects: Option<usize>, // exported data objects
+    glob: GlobMatcher,
+}
+
+/// The budgets of a budgets file, in file order
+#[derive(Clone, Debug, Default)]
+pub struct ExportBudgets {
+    budgets: Vec<Budget>,
+}
+
+/// A `[[budget]]` table as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BudgetEntry {
+    file: String,
+    max_exports: Option<usize>,
+    max_objects: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BudgetsFile {
+    #[serde(default)]
+    budget: Vec<BudgetEntry>,
+}
+
+impl ExportBudgets {
+    /// Parse a budgets file, see the module docs.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let file = toml::from_str::<BudgetsFile>(contents)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        let budgets = file
+            .budget
+            .into_iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                Budget::new(entry)
+                    .map_err(|err| Error::new(err.kind(), format!("budget {}: {err}", idx + 1)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { budgets })
+    }
+
+    pub fn budgets(&self) -> &[Budget] {
+        &self.budgets
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.budgets.is_empty()
+    }
+
+    /// The budget applying to a capture: the first one matching it.
+    pub fn lookup(&self, capture: &AbiCapture) -> Option<&Budget> {
+        self.budgets.iter().find(|budget| budget.matches(capture))
+    }
+
+    /// Run the `export-budget` check: one finding per limit a file goes over.
+    pub fn check(&self, report: &AbiReport) -> Vec<Finding> {
+        let mut findings = vec![];
+        for capture in report.captures() {
+            let Some(budget) = self.lookup(capture) else {
+                continue;
+            };
+            let surface = ExportSurface::of(capture);
+            let limits = [
+                ("exported symbols", surface.exports, budget.max_exports),
+                ("exported data objects", surface.objects, budget.max_objects),
+            ];
+            for (what, count, max) in limits {
+                if let Some(max) = max.filter(|max| count > *max) {
+                    let message = format!(
+                        "{count} {what}, over the budget of {max} for {} by {}",
+                        budget.file,
+                        count - max
+                    );
+                    findings.push(Finding::new(
+                        Check::ExportBudget,
+                        capture.filename(),
+                        message,
+                    ));
+                }
+            }
+        }
+        findings
+    }
+}
+
+impl Budget {
+    fn new(entry: BudgetEntry) -> Result<Self> {
+        if entry.max_exports.is_none() && entry.max_objects.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "neither max_exports nor max_objects is set",
+            ));
+        }
+        let glob = Glob::new(&entry.file)
+            .map(|glob| glob.compile_matcher())
+            .map_err(|err| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid pattern {:?}: {err}", entry.file),
+                )
+            })?;
+        Ok(Self {
+            file: entry.file,
+            max_exports: entry.max_exports,
+            max_objects: entry.max_objects,
+            glob,
+        })
+    }
+
+    /// Whether the budget applies to the capture, by its path, basename or soname.
+    pub fn matches(&self, capture: &AbiCapture) -> bool {
+        let path = capture.filename();
+        let basename = Path::new(path).file_name().and_then(|name| name.to_str());
+        [Some(path), basename, capture.soname()]
+            .into_iter()
+            .flatten()
+            .any(|name| self.glob.is_match(name))
+    }
+}
+
+/// How much a file exports, counting protected symbols whatever the export policy
+struct ExportSurface {
+    exports: usize,
+    objects: usize,
+}
+
+impl ExportSurface {
+    fn of(capture: &AbiCapture) -> Self {
+        let mut seen = BTreeSet::new();
+        let mut objects = 0;
+        for symbol in capture
+            .dynsym_exports()
+            .iter()
+            .chain(capture.protected_exports())
+        {
+            if seen.insert((&symbol.name, &symbol.version))
+                && matches!(symbol.kind, SymbolKind::Object | SymbolKind::Tls)
+                && !symbol.is_version_node()
+            {
+                objects += 1;
+            }
+        }
+        Self {
+            exports: seen.len(),
+            objects,
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Spotting private copies of well-known libraries.
+//!
+//! A file exporting most of a library's characteristic symbols while not being that library
+//! carries a copy of it, either linked in statically or as a renamed shared object. Such copies
+//! miss security updates of the system library, and their exports may interpose on it. Only
+//! exported symbols are looked at, so copies hidden behind a version script or linked into an
+//! executable without `-rdynamic` go unnoticed.
+
+use crate::check::{Check, Finding};
+use crate::{AbiCapture, AbiReport};
+use std::collections::HashSet;
+
+/// What identifies a library by its exports
+struct Fingerprint {
+    name: &'static str,
+    sonames: &'static [&'static str], // unversioned sonames of the real library
+    symbols: &'static [&'static str], // exports unlikely to show up anywhere else
+    min_matches: usize,               // how many of them a copy has to export
+}
+
+const FINGERPRINTS: &[Fingerprint] = &[
+    Fingerprint {
+        name: "zlib",
+        sonames: &["libz.so"],
+        symbols: &[
+            "zlibVersion",
+            "deflateInit_",
+            "deflateInit2_",
+            "inflateInit_",
+            "inflateInit2_",
+            "compress2",
+            "uncompress",
+            "adler32",
+        ],
+        min_matches: 4,
+    },
+    Fingerprint {
+        name: "libpng",
+        sonames: &["libpng.so", "libpng12.so", "libpng16.so"],
+        symbols: &[
+            "png_get_libpng_ver",
+            "png_create_read_struct",
+            "png_create_write_struct",
+            "png_destroy_read_struct",
+            "png_read_info",
+            "png_set_IHDR",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "SQLite",
+        sonames: &["libsqlite3.so"],
+        symbols: &[
+            "sqlite3_libversion",
+            "sqlite3_open",
+            "sqlite3_open_v2",
+            "sqlite3_prepare_v2",
+            "sqlite3_step",
+            "sqlite3_close",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "bzip2",
+        sonames: &["libbz2.so"],
+        symbols: &[
+            "BZ2_bzlibVersion",
+            "BZ2_bzCompressInit",
+            "BZ2_bzCompress",
+            "BZ2_bzDecompressInit",
+            "BZ2_bzDecompress",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "liblzma",
+        sonames: &["liblzma.so"],
+        symbols: &[
+            "lzma_version_string",
+            "lzma_code",
+            "lzma_end",
+            "lzma_easy_encoder",
+            "lzma_stream_decoder",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "zstd",
+        sonames: &["libzstd.so"],
+        symbols: &[
+            "ZSTD_versionNumber",
+            "ZSTD_compress",
+            "ZSTD_decompress",
+            "ZSTD_createCCtx",
+            "ZSTD_createDCtx",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "Expat",
+        sonames: &["libexpat.so", "libexpatw.so"],
+        symbols: &[
+            "XML_ExpatVersion",
+            "XML_ParserCreate",
+            "XML_ParserFree",
+            "XML_Parse",
+            "XML_SetElementHandler",
+        ],
+        min_matches: 3,
+    },
+    Fingerprint {
+        name: "libjpeg",
+        sonames: &["libjpeg.so"],
+        symbols: &[
+            "jpeg_std_error",
+            "jpeg_CreateCompress",
+            "jpeg_CreateDecompress",
+            "jpeg_read_header",
+            "jpeg_start_decompress",
+        ],
+        min_matches: 3,
+    },
+];
+
+impl Fingerprint {
+    /// Whether the capture is the library itself, by soname or else by file name.
+    fn is_library(&self, capture: &AbiCapture) -> bool {
+        let filename = capture.filename();
+        let name = capture
+            .soname()
+            .unwrap_or_else(|| filename.rsplit('/').next().unwrap_or(filename));
+        self.sonames.iter().any(|soname| {
+            name.strip_prefix(soname)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    }
+}
+
+/// A finding for every capture that looks like it carries a copy of a fingerprinted library.
+pub(crate) fn bundled_libraries(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        let exports = capture
+            .dynsym_exports()
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<HashSet<_>>();
+        for fingerprint in FINGERPRINTS {
+            let matches = fingerprint
+                .symbols
+                .iter()
+                .filter(|symbol| exports.contains(*symbol))
+                .map(|symbol| symbol.to_string())
+                .collect::<Vec<_>>();
+            if matches.len() < fingerprint.min_matches || fingerprint.is_library(capture) {
+                continue;
+            }
+            let message = format!(
+                "appears to bundle {} (exports {} of its {} characteristic symbols)",
+                fingerprint.name,
+                matches.len(),
+                fingerprint.symbols.len()
+            );
+            findings.push(
+                Finding::new(Check::BundledLibrary, capture.filename(), message)
+                    .with_symbols(matches),
+            );
+        }
+    }
+    findings
+}
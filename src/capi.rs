@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! C bindings (the `capi` feature), declared in `include/abireport.h`.
+//!
+//! Captures and reports are opaque pointers owned by the caller, to be released with the
+//! matching `*_free` function. Strings handed out are NUL terminated UTF-8 and must be released
+//! with [abireport_string_free]. Functions signal failure by returning NULL (or -1), after
+//! which [abireport_last_error] describes what went wrong on the calling thread.
+//!
+//! To get a shared or static library: `cargo rustc --lib --features capi --crate-type cdylib`
+//! (or `staticlib`). The header can be regenerated with `cbindgen --config cbindgen.toml`.
+
+use crate::{parse_elf, AbiCapture, AbiReport};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::fmt::Display;
+use std::ptr;
+
+/// A report under construction: captures are added one by one, and sorted when serialized
+pub struct AbiReportBuilder {
+    captures: Vec<AbiCapture>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The last error on this thread, or NULL. Valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn abireport_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| error.as_ref().map_or(ptr::null(), |error| error.as_ptr()))
+}
+
+/// Capture the ABI of the ELF file at `path`, or NULL on failure.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_parse_elf(path: *const c_char) -> *mut AbiCapture {
+    let Some(path) = str_arg(path) else {
+        return ptr::null_mut();
+    };
+    match parse_elf(path) {
+        Ok(capture) => Box::into_raw(Box::new(capture)),
+        Err(err) => fail(err, ptr::null_mut()),
+    }
+}
+
+/// The capture as JSON, or NULL on failure.
+///
+/// # Safety
+///
+/// `capture` must come from [abireport_parse_elf] and not have been freed or added to a report.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_capture_json(capture: *const AbiCapture) -> *mut c_char {
+    match capture.as_ref() {
+        Some(capture) => json_string(capture),
+        None => fail("capture is NULL", ptr::null_mut()),
+    }
+}
+
+/// # Safety
+///
+/// `capture` must be NULL or come from [abireport_parse_elf], and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_capture_free(capture: *mut AbiCapture) {
+    if !capture.is_null() {
+        drop(Box::from_raw(capture));
+    }
+}
+
+/// Start an empty report.
+#[no_mangle]
+pub extern "C" fn abireport_report_new() -> *mut AbiReportBuilder {
+    Box::into_raw(Box::new(AbiReportBuilder { captures: vec![] }))
+}
+
+/// Add a capture to the report, which takes ownership of it (even on failure). Returns 0 on
+/// success, -1 on failure.
+///
+/// # Safety
+///
+/// `report` must come from [abireport_report_new], `capture` from [abireport_parse_elf].
+#[no_mangle]
+pub unsafe extern "C" fn abireport_report_add_capture(
+    report: *mut AbiReportBuilder,
+    capture: *mut AbiCapture,
+) -> c_int {
+    if capture.is_null() {
+        return fail("capture is NULL", -1);
+    }
+    let capture = Box::from_raw(capture);
+    match report.as_mut() {
+        Some(report) => {
+            report.captures.push(*capture);
+            0
+        }
+        None => fail("report is NULL", -1),
+    }
+}
+
+/// The report as JSON (with the captures sorted and the digest filled in), or NULL on failure.
+///
+/// # Safety
+///
+/// `report` must come from [abireport_report_new] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_report_json(report: *const AbiReportBuilder) -> *mut c_char {
+    match report.as_ref() {
+        Some(report) => json_string(&AbiReport::new(report.captures.clone())),
+        None => fail("report is NULL", ptr::null_mut()),
+    }
+}
+
+/// # Safety
+///
+/// `report` must be NULL or come from [abireport_report_new], and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_report_free(report: *mut AbiReportBuilder) {
+    if !report.is_null() {
+        drop(Box::from_raw(report));
+    }
+}
+
+/// # Safety
+///
+/// `string` must be NULL or a string returned by this library, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn abireport_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Record `error` as the last error and return `value`.
+fn fail<T>(error: impl Display, value: T) -> T {
+    // an error containing NUL would be truncated by C anyway
+    let error = error.to_string().replace('\0', " ");
+    let error = CString::new(error).expect("NULs were replaced");
+    LAST_ERROR.with_borrow_mut(|last| *last = Some(error));
+    value
+}
+
+unsafe fn str_arg<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return fail("string argument is NULL", None);
+    }
+    match CStr::from_ptr(string).to_str() {
+        Ok(string) => Some(string),
+        Err(err) => fail(err, None),
+    }
+}
+
+fn json_string(value: &impl serde::Serialize) -> *mut c_char {
+    match serde_json::to_string(value) {
+        // JSON escapes control characters, so there are no NULs in it
+        Ok(json) => CString::new(json).expect("JSON has no NULs").into_raw(),
+        Err(err) => fail(err, ptr::null_mut()),
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Captures borrowing their symbols from the file they were parsed from.
+//!
+//! An [AbiCapture] owns a `String` for the name (and version) of every symbol, which across a
+//! distro's worth of libraries adds up to millions of small allocations. An [AbiCaptureRef]
+//! holds its symbols as [SymbolRef]s into the file data instead, for consumers that only look at
+//! them, or only keep a few ([AbiCaptureRef::retain_symbols]), and converts into the owned form
+//! when it has to outlive the data.
+
+use crate::{export_filter, AbiCapture, ElfKind, Symbol, SymbolRef};
+
+/// A capture whose symbols borrow from the file data, see [crate::ElfInspector::capture_ref]
+#[derive(Clone, Debug)]
+pub struct AbiCaptureRef<'data> {
+    capture: AbiCapture, // everything but the symbols, whose lists are left empty
+    imports: Vec<SymbolRef<'data>>,
+    exports: Vec<SymbolRef<'data>>,
+    protected: Vec<SymbolRef<'data>>,
+    hidden: Vec<SymbolRef<'data>>,
+}
+
+impl<'data> AbiCaptureRef<'data> {
+    pub(crate) fn new(
+        capture: AbiCapture,
+        imports: Vec<SymbolRef<'data>>,
+        exports: Vec<SymbolRef<'data>>,
+        protected: Vec<SymbolRef<'data>>,
+        hidden: Vec<SymbolRef<'data>>,
+    ) -> Self {
+        Self {
+            capture,
+            imports,
+            exports,
+            protected,
+            hidden,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        self.capture.filename()
+    }
+
+    pub fn elf_kind(&self) -> ElfKind {
+        self.capture.elf_kind()
+    }
+
+    pub fn soname(&self) -> Option<&str> {
+        self.capture.soname()
+    }
+
+    /// See [AbiCapture::dynsym_imports].
+    pub fn dynsym_imports(&self) -> &[SymbolRef<'data>] {
+        &self.imports
+    }
+
+    /// See [AbiCapture::dynsym_exports].
+    pub fn dynsym_exports(&self) -> &[SymbolRef<'data>] {
+        &self.exports
+    }
+
+    /// See [AbiCapture::protected_exports].
+    pub fn protected_exports(&self) -> &[SymbolRef<'data>] {
+        &self.protected
+    }
+
+    /// See [AbiCapture::hidden_symbols].
+    pub fn hidden_symbols(&self) -> &[SymbolRef<'data>] {
+        &self.hidden
+    }
+
+    /// Everything else the capture records (dependencies, notes, warnings, ...), as an
+    /// [AbiCapture] with no symbols.
+    pub fn without_symbols(&self) -> &AbiCapture {
+        &self.capture
+    }
+
+    /// Drop the symbols (and alias groups) whose names `keep` rejects, before they are ever
+    /// copied.
+    pub fn retain_symbols(&mut self, keep: impl Fn(&str) -> bool) {
+        for symbols in [
+            &mut self.imports,
+            &mut self.exports,
+            &mut self.protected,
+            &mut self.hidden,
+        ] {
+            symbols.retain(|symbol| keep(symbol.name));
+        }
+        self.capture.retain_symbols(keep);
+    }
+
+    /// The owned capture, copying the symbols.
+    pub fn into_owned(self) -> AbiCapture {
+        let owned = |symbols: Vec<SymbolRef>| symbols.iter().map(SymbolRef::to_symbol).collect();
+        let exports: Vec<Symbol> = owned(self.exports);
+        AbiCapture {
+            export_filter: export_filter(&exports),
+            dynsym_imports: owned(self.imports),
+            dynsym_exports: exports,
+            protected_exports: owned(self.protected),
+            hidden_symbols: owned(self.hidden),
+            ..self.capture
+        }
+    }
+}
+
+impl From<AbiCaptureRef<'_>> for AbiCapture {
+    fn from(capture: AbiCaptureRef<'_>) -> Self {
+        capture.into_owned()
+    }
+}
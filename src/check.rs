@@ -0,0 +1,839 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packaging checks over a report.
+//!
+//! Each check looks at the captures of a report for a known packaging mistake and turns what it
+//! finds into [Finding]s, which carry the check's severity. Checks are identified by a short
+//! kebab-case id, which is what users pass on the command line. Some checks look at an
+//! [InstallTree](crate::InstallTree) instead, e.g. to cross-check the non-ELF files installed
+//! along with the libraries.
+//!
+//! Findings of some checks come with a [Fix]: a command fixing the installed file, usually a
+//! patchelf invocation, and what to change in the build so the next one doesn't need it.
+
+use crate::bundled::bundled_libraries;
+#[cfg(feature = "fs")]
+use crate::cmake::cmake_imports;
+#[cfg(feature = "fs")]
+use crate::libtool::libtool_archives;
+#[cfg(feature = "fs")]
+use crate::pkgconfig::pkgconfig_libs;
+use crate::resolve::Libraries;
+use crate::shadow::libc_shadows;
+#[cfg(feature = "fs")]
+use crate::tree::EntryKind;
+#[cfg(feature = "fs")]
+use crate::InstallTree;
+use crate::{AbiCapture, AbiReport, ElfKind, SymbolKind};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// How bad a finding is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The least severe finding that makes a run of checks fail
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FailOn {
+    Warning, // warnings and errors
+    #[default]
+    Error, // errors only
+    Never,   // nothing, for advisory runs
+}
+
+/// Which findings fail a run of checks: by default those of error severity, with a threshold
+/// for all checks and overrides for some of them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FailPolicy {
+    pub default: FailOn,
+    pub checks: HashMap<Check, FailOn>, // thresholds of checks differing from the default
+}
+
+/// The built-in checks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Check {
+    DuplicateSoname, // several files declaring the same soname
+    BundledLibrary,  // a private copy of a well-known library
+    PkgConfig,       // .pc files linking against libraries that aren't shipped
+    CMakeImports,    // CMake exports pointing at libraries that aren't shipped
+    LibtoolArchive,  // installed .la files
+    Setuid,          // setuid/setgid binaries and binaries with file capabilities
+    ProtectedData,   // STV_PROTECTED data objects, which copy relocations can't handle
+    SanitizerOrder,  // a sanitizer runtime that isn't the first needed library
+    SonameMismatch,  // a library no file or symlink of its soname leads to
+    StaticTls,       // a library that may not fit into the static TLS left when dlopen()ed
+    ExportBudget,    // a library exporting more than its budget allows
+    LibcShadow,      // a library exporting libc or libm names, interposing on them
+    MissingSoname,   // a library in a library directory without a soname
+    Rpath,           // RPATH/RUNPATH entries of the working directory, the build or default dirs
+    Overlinking,     // DT_NEEDED entries none of the file's imports bind to
+}
+
+impl Check {
+    pub const ALL: [Check; 15] = [
+        Check::DuplicateSoname,
+        Check::BundledLibrary,
+        Check::PkgConfig,
+        Check::CMakeImports,
+        Check::LibtoolArchive,
+        Check::Setuid,
+        Check::ProtectedData,
+        Check::SanitizerOrder,
+        Check::SonameMismatch,
+        Check::StaticTls,
+        Check::ExportBudget,
+        Check::LibcShadow,
+        Check::MissingSoname,
+        Check::Rpath,
+        Check::Overlinking,
+    ];
+
+    /// The id users select the check by.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::DuplicateSoname => "duplicate-soname",
+            Self::BundledLibrary => "bundled-library",
+            Self::PkgConfig => "pkg-config",
+            Self::CMakeImports => "cmake-imports",
+            Self::LibtoolArchive => "libtool-archive",
+            Self::Setuid => "setuid",
+            Self::ProtectedData => "protected-data",
+            Self::SanitizerOrder => "sanitizer-order",
+            Self::SonameMismatch => "soname-mismatch",
+            Self::StaticTls => "static-tls",
+            Self::ExportBudget => "export-budget",
+            Self::LibcShadow => "libc-shadow",
+            Self::MissingSoname => "missing-soname",
+            Self::Rpath => "rpath",
+            Self::Overlinking => "overlinking",
+        }
+    }
+
+    /// One line on what the check looks for.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::DuplicateSoname => "several files in the report declare the same soname",
+            Self::BundledLibrary => "a file exports the symbols of a well-known library",
+            Self::PkgConfig => "a .pc file links against a library the package doesn't ship",
+            Self::CMakeImports => "a CMake export points at a library that isn't shipped as such",
+            Self::LibtoolArchive => "a libtool archive (.la) is installed",
+            Self::Setuid => "a file is setuid/setgid or has file capabilities",
+            Self::ProtectedData => "a shared object exports data objects with protected visibility",
+            Self::SanitizerOrder => "an executable needs a sanitizer runtime after other libraries",
+            Self::SonameMismatch => "a library's soname names no file or symlink leading to it",
+            Self::StaticTls => "a library needs static TLS, so dlopen()ing it may fail",
+            Self::ExportBudget => "a library exports more symbols or data objects than budgeted",
+            Self::LibcShadow => "a library exports libc or libm names, interposing on them",
+            Self::MissingSoname => "a shared library in a library directory has no soname",
+            Self::Rpath => {
+                "an RPATH/RUNPATH names the working directory, the build or a default dir"
+            }
+            Self::Overlinking => "a file needs libraries none of its imports bind to",
+        }
+    }
+
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Self::DuplicateSoname
+            | Self::BundledLibrary
+            | Self::PkgConfig
+            | Self::CMakeImports
+            | Self::LibtoolArchive
+            | Self::Setuid
+            | Self::ProtectedData
+            | Self::SonameMismatch
+            | Self::StaticTls
+            | Self::LibcShadow
+            | Self::MissingSoname
+            | Self::Rpath => Severity::Warning,
+            Self::SanitizerOrder | Self::ExportBudget => Severity::Error,
+            Self::Overlinking => Severity::Info,
+        }
+    }
+
+    /// Whether the check looks at an install tree (with [Check::run_on_tree]) rather than at
+    /// a report.
+    pub fn needs_tree(self) -> bool {
+        matches!(
+            self,
+            Self::PkgConfig | Self::CMakeImports | Self::LibtoolArchive | Self::SonameMismatch
+        )
+    }
+
+    /// Run this check over a report. Checks needing an install tree find nothing, and so does
+    /// the export budget check, which needs budgets (see `ExportBudgets::check`).
+    pub fn run(self, report: &AbiReport) -> Vec<Finding> {
+        match self {
+            Self::DuplicateSoname => duplicate_sonames(report),
+            Self::BundledLibrary => bundled_libraries(report),
+            Self::Setuid => privileged_files(report),
+            Self::ProtectedData => protected_data(report),
+            Self::SanitizerOrder => sanitizer_order(report),
+            Self::StaticTls => static_tls(report),
+            Self::LibcShadow => libc_shadows(report),
+            Self::MissingSoname => missing_sonames(report),
+            Self::Rpath => rpath_entries(report),
+            Self::Overlinking => overlinking(report),
+            Self::PkgConfig
+            | Self::CMakeImports
+            | Self::LibtoolArchive
+            | Self::SonameMismatch
+            | Self::ExportBudget => vec![],
+        }
+    }
+
+    /// Run this check over an install tree. Checks of reports find nothing.
+    #[cfg(feature = "fs")]
+    pub fn run_on_tree(self, tree: &InstallTree) -> Vec<Finding> {
+        match self {
+            Self::PkgConfig => pkgconfig_libs(tree),
+            Self::CMakeImports => cmake_imports(tree),
+            Self::LibtoolArchive => libtool_archives(tree),
+            Self::SonameMismatch => soname_mismatches(tree),
+            Self::DuplicateSoname
+            | Self::BundledLibrary
+            | Self::Setuid
+            | Self::ProtectedData
+            | Self::SanitizerOrder
+            | Self::StaticTls
+            | Self::ExportBudget
+            | Self::LibcShadow
+            | Self::MissingSoname
+            | Self::Rpath
+            | Self::Overlinking => vec![],
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown severity {name} (expected info, warning or error)"),
+            )),
+        }
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            "never" => Ok(Self::Never),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown threshold {name} (expected warning, error or never)"),
+            )),
+        }
+    }
+}
+
+impl FailOn {
+    /// Whether findings of this severity fail the run.
+    pub fn fails(self, severity: Severity) -> bool {
+        match self {
+            Self::Warning => severity >= Severity::Warning,
+            Self::Error => severity >= Severity::Error,
+            Self::Never => false,
+        }
+    }
+}
+
+impl FailPolicy {
+    /// Whether the finding fails the run, by the threshold of its check.
+    pub fn fails(&self, finding: &Finding) -> bool {
+        let threshold = self.checks.get(&finding.check).unwrap_or(&self.default);
+        threshold.fails(finding.severity)
+    }
+
+    /// The findings failing the run.
+    pub fn failures<'a>(&self, findings: &'a [Finding]) -> Vec<&'a Finding> {
+        findings
+            .iter()
+            .filter(|finding| self.fails(finding))
+            .collect()
+    }
+}
+
+impl FromStr for Check {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|check| check.id() == id)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("unknown check {id}")))
+    }
+}
+
+/// A problem one check found with one file
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    pub filename: String,
+    pub message: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub symbols: Vec<String>, // the symbols the finding is about, if it is about symbols
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub fix: Option<Fix>,
+}
+
+/// A suggested remediation of a [Finding]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Fix {
+    pub command: String, // fixing the installed file, e.g. a patchelf invocation
+    pub build: String,   // fixing the build instead, e.g. a linker flag to add or drop
+}
+
+impl Finding {
+    /// Give the findings of `check` another severity than the check's default.
+    pub fn override_severity(findings: &mut [Finding], check: Check, severity: Severity) {
+        for finding in findings.iter_mut().filter(|finding| finding.check == check) {
+            finding.severity = severity;
+        }
+    }
+
+    pub(crate) fn new(check: Check, filename: &str, message: String) -> Self {
+        Self {
+            check,
+            severity: check.default_severity(),
+            filename: filename.to_string(),
+            message,
+            symbols: vec![],
+            fix: None,
+        }
+    }
+
+    pub(crate) fn with_symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub(crate) fn with_fix(mut self, command: String, build: String) -> Self {
+        self.fix = Some(Fix { command, build });
+        self
+    }
+}
+
+impl AbiReport {
+    /// Run the given checks, returning their findings in check and then report order.
+    pub fn check(&self, checks: &[Check]) -> Vec<Finding> {
+        checks.iter().flat_map(|check| check.run(self)).collect()
+    }
+}
+
+#[cfg(feature = "fs")]
+impl InstallTree {
+    /// Run the given checks that need an install tree, in check and then path order.
+    pub fn check(&self, checks: &[Check]) -> Vec<Finding> {
+        checks
+            .iter()
+            .flat_map(|check| check.run_on_tree(self))
+            .collect()
+    }
+}
+
+/// A soname should name exactly one file per architecture. A second one (often a bundled copy
+/// of a system library) means which one a dependent gets depends on the search path.
+fn duplicate_sonames(report: &AbiReport) -> Vec<Finding> {
+    let mut by_soname = BTreeMap::<(&str, Option<&str>), Vec<&AbiCapture>>::new();
+    for capture in report.captures() {
+        if let Some(soname) = capture.soname() {
+            let files = by_soname.entry((soname, capture.arch())).or_default();
+            files.push(capture);
+        }
+    }
+
+    let mut findings = vec![];
+    for ((soname, _), files) in by_soname.into_iter().filter(|(_, files)| files.len() > 1) {
+        for capture in &files {
+            let others = files
+                .iter()
+                .filter(|other| other.filename() != capture.filename())
+                .map(|other| other.filename())
+                .collect::<Vec<_>>();
+            let message = format!("soname {soname} is also declared by {}", others.join(", "));
+            findings.push(Finding::new(
+                Check::DuplicateSoname,
+                capture.filename(),
+                message,
+            ));
+        }
+    }
+    let order = report
+        .captures()
+        .iter()
+        .enumerate()
+        .map(|(idx, capture)| (capture.filename(), idx))
+        .collect::<BTreeMap<_, _>>();
+    findings.sort_by_key(|finding| order[finding.filename.as_str()]);
+    findings
+}
+
+/// Dependents ask the dynamic linker for a library by its soname, so a file or symlink of that
+/// name has to lead to it from its directory. A soname nothing installed is called (a library
+/// `libfoo.so.2.1` declaring `libbar.so.3`) usually means the build system got the soname or the
+/// file name wrong, and everything linked against the library won't find it.
+#[cfg(feature = "fs")]
+fn soname_mismatches(tree: &InstallTree) -> Vec<Finding> {
+    let mut findings = vec![];
+    for entry in tree.entries() {
+        let EntryKind::Elf {
+            elf_kind: ElfKind::SharedObject,
+            soname: Some(soname),
+        } = &entry.kind
+        else {
+            continue;
+        };
+        let dir = &entry.path[..entry.path.len() - entry.file_name().len()];
+        let expected = format!("{dir}{soname}");
+        let message = match (tree.get(&expected), tree.resolve(&expected)) {
+            (_, Some(target)) if target.path == entry.path => continue,
+            (None, _) => format!("soname {soname} names no file or symlink in {dir}"),
+            (Some(_), Some(target)) => format!(
+                "soname {soname} doesn't lead to it: {expected} is {}",
+                target.path
+            ),
+            (Some(_), None) => {
+                format!("soname {soname} doesn't lead to it: {expected} is a dangling symlink")
+            }
+        };
+        findings.push(Finding::new(Check::SonameMismatch, &entry.path, message));
+    }
+    findings
+}
+
+/// Privileged files are worth a look in any case; one that also searches for libraries relative
+/// to itself or the working directory lets whoever controls that location run code with its
+/// privileges, unless the dynamic linker is careful to ignore such paths for privileged files.
+fn privileged_files(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        let Some(metadata) = capture.metadata().filter(|m| m.is_privileged()) else {
+            continue;
+        };
+        let mut privileges = vec![];
+        if metadata.is_setuid() {
+            privileges.push("setuid".to_string());
+        }
+        if metadata.is_setgid() {
+            privileges.push("setgid".to_string());
+        }
+        if let Some(capabilities) = &metadata.capabilities {
+            privileges.push(format!("has file capabilities {capabilities}"));
+        }
+        let mut finding = Finding::new(Check::Setuid, capture.filename(), privileges.join(", "));
+
+        let unsafe_paths = [capture.rpath(), capture.runpath()]
+            .into_iter()
+            .flatten()
+            .flat_map(|paths| paths.split(':'))
+            .filter(|path| !path.starts_with('/'))
+            .map(|path| match path {
+                "" => "the working directory",
+                path => path,
+            })
+            .collect::<Vec<_>>();
+        if !unsafe_paths.is_empty() {
+            finding.message += &format!(", and searches libraries in {}", unsafe_paths.join(", "));
+            finding.severity = Severity::Error;
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+/// An executable referencing a data object of a shared object gets a copy of it (a copy
+/// relocation), which everyone else then has to use. A protected definition keeps binding to
+/// its own copy from within the shared object though, so the two silently diverge (or the link
+/// fails, depending on the toolchain).
+fn protected_data(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        let objects = capture
+            .protected_exports()
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Object)
+            .collect::<Vec<_>>();
+        if objects.is_empty() {
+            continue;
+        }
+        let message = format!(
+            "protected data objects break copy relocations: {}",
+            objects
+                .iter()
+                .map(|symbol| symbol.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let symbols = objects.iter().map(|symbol| symbol.name.clone()).collect();
+        findings.push(
+            Finding::new(Check::ProtectedData, capture.filename(), message).with_symbols(symbols),
+        );
+    }
+    findings
+}
+
+/// Sonames (or their prefixes) of the sanitizer runtimes that have to be loaded first
+const SANITIZER_RUNTIMES: [&str; 5] = [
+    "libasan.so",
+    "libtsan.so",
+    "libhwasan.so",
+    "liblsan.so",
+    "libclang_rt.", // e.g. libclang_rt.asan-x86_64.so
+];
+
+/// The ASan, TSan and HWASan runtimes refuse to start unless they come first in the initial
+/// library list, so that their malloc and friends interpose everyone else's. That list is the
+/// executable's DT_NEEDED in link order, so the runtime has to be its first entry.
+fn sanitizer_order(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        if capture.elf_kind() != ElfKind::Executable {
+            continue;
+        }
+        let order = capture.needed_link_order();
+        let runtime = order.iter().enumerate().skip(1).find(|(_, needed)| {
+            SANITIZER_RUNTIMES
+                .iter()
+                .any(|runtime| needed.starts_with(runtime))
+        });
+        if let Some((idx, runtime)) = runtime {
+            let message = format!(
+                "{runtime} is needed after {}, but has to come first",
+                order[..idx].join(", ")
+            );
+            findings.push(Finding::new(
+                Check::SanitizerOrder,
+                capture.filename(),
+                message,
+            ));
+        }
+    }
+    findings
+}
+
+/// The static TLS glibc sets aside for libraries dlopen()ed later (glibc.rtld.optional_static_tls)
+const STATIC_TLS_SURPLUS: u64 = 512;
+
+/// Libraries with an initial-exec TLS block get it allocated in the static TLS area, sized at
+/// startup. Loaded at startup, that is fine; dlopen()ed later (as a plugin, or as a dependency
+/// of one), the block has to fit into the little surplus left, shared by everything dlopen()ed,
+/// or the dlopen() fails with "cannot allocate memory in static TLS block". A block bigger than
+/// the whole surplus never fits. NODELETE libraries keep theirs until the process exits.
+fn static_tls(report: &AbiReport) -> Vec<Finding> {
+    let startup = startup_sonames(report);
+    let mut findings = vec![];
+    for capture in report.captures() {
+        let Some(tls) = capture.thread_local() else {
+            continue;
+        };
+        if capture.elf_kind() != ElfKind::SharedObject || !tls.static_tls || tls.size == 0 {
+            continue;
+        }
+        let mut finding = Finding::new(
+            Check::StaticTls,
+            capture.filename(),
+            format!(
+                "needs {} bytes of static TLS (initial-exec model), so dlopen()ing it fails once \
+                 the static TLS surplus is used up",
+                tls.size
+            ),
+        );
+        if tls.size > STATIC_TLS_SURPLUS {
+            finding.message = format!(
+                "needs {} bytes of static TLS (initial-exec model), more than the {} bytes glibc \
+                 leaves for dlopen()ed libraries by default, so dlopen()ing it fails",
+                tls.size, STATIC_TLS_SURPLUS
+            );
+            finding.severity = Severity::Error;
+        }
+        if capture.nodelete() == Some(true) {
+            finding.message += ", and being NODELETE, it never gives it back";
+        }
+        if capture
+            .soname()
+            .is_some_and(|soname| startup.contains(soname))
+        {
+            finding.message += " (executables of the report load it at startup, which is safe)";
+            finding.severity = Severity::Info;
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+/// The sonames the executables of a report load at startup: what they need, and what that
+/// needs in turn.
+fn startup_sonames(report: &AbiReport) -> HashSet<&str> {
+    let mut providers = HashMap::<&str, Vec<&AbiCapture>>::new();
+    for capture in report.captures() {
+        if let Some(soname) = capture.soname() {
+            providers.entry(soname).or_default().push(capture);
+        }
+    }
+    let mut queue = report
+        .captures()
+        .iter()
+        .filter(|capture| capture.elf_kind() == ElfKind::Executable)
+        .collect::<Vec<_>>();
+    let mut startup = HashSet::new();
+    while let Some(capture) = queue.pop() {
+        for needed in capture.needed_deps() {
+            if startup.insert(needed.as_str()) {
+                queue.extend(providers.get(needed.as_str()).into_iter().flatten());
+            }
+        }
+    }
+    startup
+}
+
+/// Where a shared object is a library to link against rather than a plugin: a `lib`, `lib64`,
+/// `lib32` or `libx32` directory or a multiarch one within `lib` (`/usr/lib/x86_64-linux-gnu`),
+/// looking only at the end of the path so scans of a staging root without `--root` count too
+fn is_library_dir(dir: &str) -> bool {
+    let is_lib = |name: &str| matches!(name, "lib" | "lib64" | "lib32" | "libx32");
+    let mut components = dir.rsplit('/');
+    match (components.next(), components.next()) {
+        (Some(name), _) if is_lib(name) => true,
+        (Some(triplet), Some("lib")) => triplet.contains('-'),
+        _ => false,
+    }
+}
+
+/// Without a soname, whatever links against a library records the name it was linked by, often
+/// the unversioned `libfoo.so` symlink only the -devel package ships, or even a path of the
+/// build. Plugins get by without one, but a `lib*.so*` in a library directory is there to be
+/// linked against. The suggested soname keeps the major version of the file name.
+fn missing_sonames(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        if capture.elf_kind() != ElfKind::SharedObject || capture.soname().is_some() {
+            continue;
+        }
+        let (dir, file_name) = capture.filename().rsplit_once('/').unwrap_or(("", ""));
+        if !is_library_dir(dir) || !file_name.starts_with("lib") || !file_name.contains(".so") {
+            continue;
+        }
+        let soname = match file_name.split_once(".so.") {
+            Some((stem, version)) => {
+                format!("{stem}.so.{}", version.split('.').next().unwrap_or(version))
+            }
+            None => file_name.to_string(),
+        };
+        let message = format!(
+            "has no soname, so what links against it records the name it was linked by; \
+             {soname} would do"
+        );
+        findings.push(
+            Finding::new(Check::MissingSoname, capture.filename(), message).with_fix(
+                format!(
+                    "patchelf --set-soname {} {}",
+                    shell_quote(&soname),
+                    shell_quote(capture.filename())
+                ),
+                format!("link with -Wl,-soname,{soname}"),
+            ),
+        );
+    }
+    findings
+}
+
+/// Directories of build sandboxes and temporary files, which have no business in an installed
+/// file's library search path
+const BUILD_DIRS: [&str; 7] = [
+    "/build",
+    "/builddir",
+    "/tmp",
+    "/var/tmp",
+    "/home",
+    "/root",
+    "/usr/src",
+];
+
+/// The directories the dynamic linker searches anyway
+const DEFAULT_DIRS: [&str; 4] = ["/lib", "/lib64", "/usr/lib", "/usr/lib64"];
+
+/// An RPATH or RUNPATH entry is searched before (or, for RPATH, even instead of) the system's
+/// directories. An empty or relative one means the working directory of the process, letting
+/// whoever controls it supply libraries; one of the build sandbox or a temporary directory
+/// doesn't exist on the installed system, unless someone creates it; and a default directory is
+/// searched anyway, but now ahead of LD_LIBRARY_PATH (RPATH) or of ld.so.conf, which breaks
+/// multilib setups. Entries starting with `$ORIGIN` (or `$LIB`, `$PLATFORM`) are fine.
+fn rpath_entries(report: &AbiReport) -> Vec<Finding> {
+    let mut findings = vec![];
+    for capture in report.captures() {
+        for (tag, path) in [("RPATH", capture.rpath()), ("RUNPATH", capture.runpath())] {
+            let Some(path) = path else {
+                continue;
+            };
+            let mut kept = vec![];
+            let mut dropped = vec![];
+            let mut reasons = vec![];
+            for dir in path.split(':') {
+                let Some(reason) = rpath_problem(dir) else {
+                    kept.push(dir);
+                    continue;
+                };
+                dropped.push(dir);
+                reasons.push(match dir {
+                    "" => reason.to_string(),
+                    dir => format!("{dir} ({reason})"),
+                });
+            }
+            if dropped.is_empty() {
+                continue;
+            }
+            let message = format!("{tag} searches libraries in {}", reasons.join(", "));
+            let filename = shell_quote(capture.filename());
+            let command = match (kept.is_empty(), tag) {
+                (true, _) => format!("patchelf --remove-rpath {filename}"),
+                (false, "RPATH") => format!(
+                    "patchelf --force-rpath --set-rpath {} {filename}",
+                    shell_quote(&kept.join(":"))
+                ),
+                (false, _) => format!(
+                    "patchelf --set-rpath {} {filename}",
+                    shell_quote(&kept.join(":"))
+                ),
+            };
+            let flags = dropped
+                .iter()
+                .map(|dir| shell_quote(&format!("-Wl,-rpath,{dir}")))
+                .collect::<Vec<_>>();
+            findings.push(
+                Finding::new(Check::Rpath, capture.filename(), message)
+                    .with_fix(command, format!("link without {}", flags.join(" "))),
+            );
+        }
+    }
+    findings
+}
+
+/// What is wrong with an RPATH or RUNPATH entry, if anything.
+fn rpath_problem(dir: &str) -> Option<&'static str> {
+    if dir.is_empty() {
+        return Some("the working directory");
+    }
+    // `$ORIGIN`, `$LIB` or `$PLATFORM`, which the dynamic linker expands
+    if dir.starts_with('$') {
+        return None;
+    }
+    if !dir.starts_with('/') {
+        return Some("relative to the working directory");
+    }
+    let dir = dir.trim_end_matches('/');
+    let under = |parent: &str| {
+        dir.strip_prefix(parent)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+    if BUILD_DIRS.into_iter().any(under) {
+        return Some("a build or temporary directory");
+    }
+    DEFAULT_DIRS.contains(&dir).then_some("a default directory")
+}
+
+/// A DT_NEEDED entry none of a file's imports bind to still gets loaded, costing startup time and
+/// tying the file to a library it doesn't use (and to its soname bumps). Only libraries of the
+/// report are looked at; one needed merely for its constructors is a false positive. Imports are
+/// attributed in link order, as the dynamic linker binds them; a library that (itself or through
+/// what it needs) exports some of the file's imports anyway gets no fix, as dropping it could
+/// change what they bind to, e.g. an allocator listed after libc or one the file underlinks.
+fn overlinking(report: &AbiReport) -> Vec<Finding> {
+    let mut unused = BTreeMap::<&str, Vec<&str>>::new();
+    let usage = report.dependency_usage(None);
+    for entry in usage.iter().filter(|entry| entry.is_unused()) {
+        unused
+            .entry(entry.filename.as_str())
+            .or_default()
+            .push(entry.library.as_str());
+    }
+    let libraries = Libraries::new(report, None);
+    let mut findings = vec![];
+    for capture in report.captures() {
+        let Some(unused) = unused.get(capture.filename()) else {
+            continue;
+        };
+        let (ambiguous, removable): (Vec<&str>, Vec<&str>) = unused.iter().partition(|library| {
+            libraries.provides_any(library, capture, capture.dynsym_imports())
+        });
+        let mut message = format!(
+            "needs {} but imports nothing from {}",
+            unused.join(", "),
+            match unused.len() {
+                1 => "it",
+                _ => "them",
+            }
+        );
+        if !ambiguous.is_empty() {
+            message += &format!(
+                "; {} (or what it needs) exports some of its imports too, so dropping it could \
+                 change what they bind to",
+                ambiguous.join(", ")
+            );
+        }
+        let mut finding = Finding::new(Check::Overlinking, capture.filename(), message);
+        if !removable.is_empty() {
+            let removals = removable
+                .iter()
+                .map(|library| format!("--remove-needed {}", shell_quote(library)))
+                .collect::<Vec<_>>();
+            finding = finding.with_fix(
+                format!(
+                    "patchelf {} {}",
+                    removals.join(" "),
+                    shell_quote(capture.filename())
+                ),
+                "link with -Wl,--as-needed".to_string(),
+            );
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+/// `text` as a single word for a POSIX shell, quoted only if it has to be.
+fn shell_quote(text: &str) -> String {
+    let plain = |byte: u8| byte.is_ascii_alphanumeric() || b"_-+./:=,@%".contains(&byte);
+    match !text.is_empty() && text.bytes().all(plain) {
+        true => text.to_string(),
+        false => format!("'{}'", text.replace('\'', "'\\''")),
+    }
+}
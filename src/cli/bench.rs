@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport bench` (hidden): time end-to-end scans of a corpus, to catch parser slowdowns.
+
+use abireport_rs::ScanBuilder;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A directory of ELF files to scan, e.g. a copy of a distro's /usr/lib
+    corpus: PathBuf,
+
+    /// Scan the corpus this many times
+    #[arg(short = 'n', long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    iterations: u32,
+
+    /// Parse this many files in parallel (0: one per CPU)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Only capture the dynamic section, as `scan --sonames-only`
+    #[arg(long)]
+    sonames_only: bool,
+
+    /// Print the results as JSON, to keep and compare across builds
+    #[arg(long)]
+    json: bool,
+}
+
+/// The results of a bench run
+#[derive(Serialize)]
+struct Bench {
+    corpus: String,
+    files: usize,
+    bytes: u64,
+    jobs: usize,
+    sonames_only: bool,
+    discover_secs: f64,
+    scan_secs: Vec<f64>, // one per iteration, in order
+    mib_per_sec: f64,    // of the fastest scan
+    files_per_sec: f64,  // of the fastest scan
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let scanner = ScanBuilder::new()
+        .path(&args.corpus)
+        .jobs(args.jobs)
+        .sonames_only(args.sonames_only)
+        .build()?;
+
+    let start = Instant::now();
+    let files = scanner.discover();
+    let discover_secs = start.elapsed().as_secs_f64();
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} holds no files to scan", args.corpus.display()),
+        ));
+    }
+    let bytes = files
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    let mut scan_secs = vec![];
+    for _ in 0..args.iterations {
+        let start = Instant::now();
+        // the whole scan, discovery included, as `abireport scan` would run it
+        scanner.scan()?;
+        scan_secs.push(start.elapsed().as_secs_f64());
+    }
+    let fastest = scan_secs.iter().copied().fold(f64::INFINITY, f64::min);
+    let bench = Bench {
+        corpus: args.corpus.to_string_lossy().into_owned(),
+        files: files.len(),
+        bytes,
+        jobs: scanner_jobs(args.jobs),
+        sonames_only: args.sonames_only,
+        discover_secs,
+        mib_per_sec: mib(bytes) / fastest,
+        files_per_sec: files.len() as f64 / fastest,
+        scan_secs,
+    };
+
+    let mut stdout = io::stdout().lock();
+    match args.json {
+        true => {
+            serde_json::to_writer_pretty(&mut stdout, &bench)?;
+            writeln!(stdout)
+        }
+        false => write_text(&bench, stdout),
+    }
+}
+
+/// The jobs a scan runs with, 0 standing for one per CPU.
+fn scanner_jobs(jobs: usize) -> usize {
+    match jobs {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        jobs => jobs,
+    }
+}
+
+fn write_text(bench: &Bench, mut w: impl Write) -> io::Result<()> {
+    let mut sorted = bench.scan_secs.clone();
+    sorted.sort_by(f64::total_cmp);
+    writeln!(
+        w,
+        "{:<10} {} ({} files, {:.1} MiB)",
+        "corpus",
+        bench.corpus,
+        bench.files,
+        mib(bench.bytes)
+    )?;
+    writeln!(w, "{:<10} {:.3}s", "discover", bench.discover_secs)?;
+    writeln!(
+        w,
+        "{:<10} min {:.3}s, median {:.3}s, max {:.3}s over {} run(s) with {} job(s)",
+        "scan",
+        sorted[0],
+        sorted[sorted.len() / 2],
+        sorted[sorted.len() - 1],
+        sorted.len(),
+        bench.jobs
+    )?;
+    writeln!(
+        w,
+        "{:<10} {:.1} MiB/s, {:.0} files/s (fastest run)",
+        "throughput", bench.mib_per_sec, bench.files_per_sec
+    )
+}
+
+fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
@@ -0,0 +1,403 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport browse`: explore a report interactively (needs the `browse` feature).
+//!
+//! Three panes: the files of the report, the symbols of the selected file, and who is on the
+//! other side of the selection (the providers and users of the selected symbol, or the
+//! dependencies and dependents of the selected file). Enter in the right pane jumps to a file.
+
+use super::read_report;
+use abireport_rs::{AbiCapture, AbiReport, Symbol};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report to browse
+    report: PathBuf,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let report = read_report(&args.report)?;
+    let mut browser = Browser::new(&report);
+
+    let mut terminal = ratatui::init();
+    let result = browser.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pane {
+    Files,
+    Symbols,
+    Related,
+}
+
+/// Who provides and uses a symbol (indices into the captures)
+#[derive(Default)]
+struct SymbolUsers {
+    providers: Vec<usize>,
+    consumers: Vec<usize>,
+}
+
+struct Browser<'a> {
+    captures: &'a [AbiCapture],
+    users: HashMap<(&'a str, Option<&'a str>), SymbolUsers>,
+    providers: HashMap<&'a str, Vec<usize>>, // soname -> files with that soname
+    needed_by: HashMap<&'a str, Vec<usize>>, // soname -> files needing it
+    filter: String,
+    filtering: bool,     // typing into the filter
+    visible: Vec<usize>, // the files matching the filter
+    focus: Pane,
+    imports: bool, // showing imports rather than exports
+    files: ListState,
+    symbols: ListState,
+    related: ListState,
+}
+
+/// One line of the right pane, possibly leading to a file
+struct Related {
+    text: String,
+    target: Option<usize>,
+}
+
+impl<'a> Browser<'a> {
+    fn new(report: &'a AbiReport) -> Self {
+        let captures = report.captures();
+        let mut users = HashMap::<_, SymbolUsers>::new();
+        let mut providers = HashMap::<_, Vec<_>>::new();
+        let mut needed_by = HashMap::<_, Vec<_>>::new();
+        for (idx, capture) in captures.iter().enumerate() {
+            for symbol in capture.dynsym_exports() {
+                users.entry(key(symbol)).or_default().providers.push(idx);
+            }
+            for symbol in capture.dynsym_imports() {
+                users.entry(key(symbol)).or_default().consumers.push(idx);
+            }
+            if let Some(soname) = capture.soname() {
+                providers.entry(soname).or_default().push(idx);
+            }
+            for needed in capture.needed_deps() {
+                needed_by.entry(needed.as_str()).or_default().push(idx);
+            }
+        }
+
+        let mut browser = Self {
+            captures,
+            users,
+            providers,
+            needed_by,
+            filter: String::new(),
+            filtering: false,
+            visible: vec![],
+            focus: Pane::Files,
+            imports: false,
+            files: ListState::default(),
+            symbols: ListState::default(),
+            related: ListState::default(),
+        };
+        browser.apply_filter();
+        browser
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if self.filtering {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.filtering = false,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                        self.apply_filter();
+                    }
+                    KeyCode::Char(c) => {
+                        self.filter.push(c);
+                        self.apply_filter();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('/') => {
+                    self.focus = Pane::Files;
+                    self.filtering = true;
+                }
+                KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => self.cycle_focus(true),
+                KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => self.cycle_focus(false),
+                KeyCode::Char('e') => self.show_imports(false),
+                KeyCode::Char('i') => self.show_imports(true),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(20),
+                KeyCode::PageUp => self.move_selection(-20),
+                KeyCode::Enter if self.focus == Pane::Related => self.follow(),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [files, symbols, related] = Layout::horizontal([
+            Constraint::Percentage(35),
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
+        ])
+        .areas(main);
+
+        self.draw_files(frame, files);
+        self.draw_symbols(frame, symbols);
+        self.draw_related(frame, related);
+
+        let help = match self.filtering {
+            true => format!("filter: {}_  (Enter/Esc: done)", self.filter),
+            false => "q: quit  /: filter files  Tab: next pane  e/i: exports/imports  \
+                      Enter: go to file"
+                .to_string(),
+        };
+        frame.render_widget(Paragraph::new(help), status);
+    }
+
+    fn draw_files(&mut self, frame: &mut Frame, area: Rect) {
+        let items = self
+            .visible
+            .iter()
+            .map(|&idx| ListItem::new(self.captures[idx].filename()))
+            .collect::<Vec<_>>();
+        let title = match self.filter.is_empty() {
+            true => format!("Files ({})", items.len()),
+            false => format!("Files matching {:?} ({})", self.filter, items.len()),
+        };
+        let list = List::new(items)
+            .block(self.block(title, Pane::Files))
+            .highlight_style(highlight());
+        frame.render_stateful_widget(list, area, &mut self.files);
+    }
+
+    fn draw_symbols(&mut self, frame: &mut Frame, area: Rect) {
+        let symbols = self.current_symbols();
+        let items = symbols
+            .iter()
+            .map(|symbol| ListItem::new(symbol.to_string()))
+            .collect::<Vec<_>>();
+        let what = match self.imports {
+            true => "Imports",
+            false => "Exports",
+        };
+        let list = List::new(items)
+            .block(self.block(format!("{what} ({})", symbols.len()), Pane::Symbols))
+            .highlight_style(highlight());
+        frame.render_stateful_widget(list, area, &mut self.symbols);
+    }
+
+    fn draw_related(&mut self, frame: &mut Frame, area: Rect) {
+        let (title, related) = self.related();
+        let items = related
+            .iter()
+            .map(|line| match line.target {
+                Some(_) => ListItem::new(line.text.as_str()),
+                None => ListItem::new(Line::styled(
+                    line.text.as_str(),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(self.block(title, Pane::Related))
+            .highlight_style(highlight());
+        frame.render_stateful_widget(list, area, &mut self.related);
+    }
+
+    fn block(&self, title: String, pane: Pane) -> Block<'static> {
+        let block = Block::bordered().title(title);
+        match self.focus == pane {
+            true => block.border_style(Style::new().add_modifier(Modifier::BOLD)),
+            false => block.border_style(Style::new().add_modifier(Modifier::DIM)),
+        }
+    }
+
+    fn current_file(&self) -> Option<&'a AbiCapture> {
+        let idx = *self.visible.get(self.files.selected()?)?;
+        Some(&self.captures[idx])
+    }
+
+    fn current_symbols(&self) -> &'a [Symbol] {
+        match (self.current_file(), self.imports) {
+            (Some(capture), false) => capture.dynsym_exports(),
+            (Some(capture), true) => capture.dynsym_imports(),
+            (None, _) => &[],
+        }
+    }
+
+    /// The right pane: users of the selected symbol once one is selected, else the
+    /// dependencies of the selected file (Shift+Tab from the files goes straight there).
+    fn related(&self) -> (String, Vec<Related>) {
+        let mut lines = vec![];
+        let mut section = |heading: &str, files: &[usize]| {
+            lines.push(Related {
+                text: format!("{heading} ({})", files.len()),
+                target: None,
+            });
+            lines.extend(files.iter().map(|&idx| Related {
+                text: format!("  {}", self.captures[idx].filename()),
+                target: Some(idx),
+            }));
+        };
+
+        let symbol = self
+            .symbols
+            .selected()
+            .and_then(|idx| self.current_symbols().get(idx));
+        if let (Some(symbol), Pane::Symbols | Pane::Related) = (symbol, self.focus) {
+            let users = &self.users[&key(symbol)];
+            section("provided by", &users.providers);
+            section("used by", &users.consumers);
+            return (symbol.to_string(), lines);
+        }
+
+        let Some(capture) = self.current_file() else {
+            return ("Dependencies".to_string(), lines);
+        };
+        for needed in capture.needed_deps() {
+            match self.providers.get(needed.as_str()) {
+                Some(providers) => section(&format!("needs {needed}"), providers),
+                None => section(&format!("needs {needed} (not in report)"), &[]),
+            }
+        }
+        if let Some(soname) = capture.soname() {
+            let dependents = self.needed_by.get(soname).map(Vec::as_slice);
+            section("needed by", dependents.unwrap_or_default());
+        }
+        ("Dependencies".to_string(), lines)
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = (0..self.captures.len())
+            .filter(|&idx| {
+                let capture = &self.captures[idx];
+                capture.filename().to_lowercase().contains(&filter)
+                    || capture
+                        .soname()
+                        .is_some_and(|soname| soname.to_lowercase().contains(&filter))
+            })
+            .collect();
+        self.select_file(0);
+    }
+
+    fn select_file(&mut self, position: usize) {
+        self.files
+            .select((!self.visible.is_empty()).then_some(position));
+        self.symbols.select(None);
+        self.related.select(None);
+    }
+
+    fn show_imports(&mut self, imports: bool) {
+        self.imports = imports;
+        self.symbols.select(None);
+        self.related.select(None);
+        if self.focus == Pane::Related {
+            self.focus = Pane::Symbols;
+        }
+    }
+
+    fn cycle_focus(&mut self, forward: bool) {
+        self.focus = match (self.focus, forward) {
+            (Pane::Files, true) | (Pane::Related, false) => Pane::Symbols,
+            (Pane::Symbols, true) | (Pane::Files, false) => Pane::Related,
+            (Pane::Related, true) | (Pane::Symbols, false) => Pane::Files,
+        };
+        let state = self.focused_state();
+        if state.selected().is_none() {
+            state.select(Some(0));
+        }
+        self.related_clamp();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let len = match self.focus {
+            Pane::Files => self.visible.len(),
+            Pane::Symbols => self.current_symbols().len(),
+            Pane::Related => self.related().1.len(),
+        };
+        if len == 0 {
+            return;
+        }
+        let state = self.focused_state();
+        let current = state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len as isize - 1) as usize;
+        match self.focus {
+            Pane::Files => self.select_file(next),
+            Pane::Symbols => {
+                self.symbols.select(Some(next));
+                self.related.select(None);
+            }
+            Pane::Related => self.related.select(Some(next)),
+        }
+    }
+
+    /// Jump to the file selected in the right pane.
+    fn follow(&mut self) {
+        let related = self.related().1;
+        let target = self
+            .related
+            .selected()
+            .and_then(|idx| related.get(idx))
+            .and_then(|line| line.target);
+        let Some(target) = target else {
+            return;
+        };
+        // the target may be hidden by the filter
+        if !self.visible.contains(&target) {
+            self.filter.clear();
+            self.apply_filter();
+        }
+        let position = self.visible.iter().position(|&idx| idx == target);
+        self.select_file(position.unwrap_or(0));
+        self.focus = Pane::Files;
+    }
+
+    fn focused_state(&mut self) -> &mut ListState {
+        match self.focus {
+            Pane::Files => &mut self.files,
+            Pane::Symbols => &mut self.symbols,
+            Pane::Related => &mut self.related,
+        }
+    }
+
+    fn related_clamp(&mut self) {
+        let len = self.related().1.len();
+        if self.related.selected().is_some_and(|idx| idx >= len) {
+            self.related.select(len.checked_sub(1));
+        }
+    }
+}
+
+fn key(symbol: &Symbol) -> (&str, Option<&str>) {
+    (&symbol.name, symbol.version.as_deref())
+}
+
+fn highlight() -> Style {
+    Style::new().add_modifier(Modifier::REVERSED)
+}
@@ -0,0 +1,278 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport check`: look for packaging mistakes in a report.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{
+    Check, ExportBudgets, FailOn, FailPolicy, Finding, InstallTree, LibcNames, ScanBuilder,
+    Severity, Waivers,
+};
+use clap::ValueEnum;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report to check (any format; default: a scan of --root)
+    #[arg(required_unless_present_any = ["list", "root"])]
+    report: Option<PathBuf>,
+
+    /// The install root the report was taken of, for the checks looking at non-ELF files too
+    #[arg(long, value_name = "DIR")]
+    root: Option<PathBuf>,
+
+    /// Only run this check (may be repeated; default: all of them)
+    #[arg(long = "check", value_name = "ID", value_parser = Check::from_str)]
+    checks: Vec<Check>,
+
+    /// Report the findings of a check with another severity, e.g. libtool-archive=error (may be
+    /// repeated)
+    #[arg(long, value_name = "ID=SEVERITY", value_parser = parse_severity)]
+    severity: Vec<(Check, Severity)>,
+
+    /// Fail on findings of this severity or worse: warning, error (the default) or never, for
+    /// all checks or, as ID=LEVEL, for one (may be repeated)
+    #[arg(long, value_name = "[ID=]LEVEL", value_parser = parse_fail_on)]
+    fail_on: Vec<(Option<Check>, FailOn)>,
+
+    /// Accepted exceptions (TOML, see the README): waived findings are left out, expired and
+    /// unused waivers pointed out on stderr
+    #[arg(long, value_name = "FILE")]
+    waivers: Option<PathBuf>,
+
+    /// Per-library limits on exported symbols and data objects (TOML, see the README), for the
+    /// export-budget check
+    #[arg(long, value_name = "FILE")]
+    budgets: Option<PathBuf>,
+
+    /// More names for the libc-shadow check to flag exports of, one per line, on top of the
+    /// built-in libc and libm ones
+    #[arg(long, value_name = "FILE")]
+    libc_names: Option<PathBuf>,
+
+    /// List the available checks and exit
+    #[arg(long)]
+    list: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = CheckFormat::Text)]
+    format: CheckFormat,
+
+    /// Where to write the findings (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CheckFormat {
+    /// Human readable, one finding per line
+    Text,
+    /// The findings as JSON
+    Json,
+}
+
+/// Fails (after writing all findings) if there are findings of error severity, or whatever
+/// --fail-on says instead.
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let mut writer = output_writer(args.output.as_deref())?;
+    if args.list {
+        for check in Check::ALL {
+            writeln!(
+                writer,
+                "{:<20} {:<8} {}",
+                check,
+                check.default_severity(),
+                check.description()
+            )?;
+        }
+        return writer.flush();
+    }
+
+    let checks = match args.checks.is_empty() {
+        true => Check::ALL.to_vec(),
+        false => args.checks.clone(),
+    };
+    let report = match (&args.report, &args.root) {
+        (Some(report), _) => read_report(report)?,
+        (None, Some(root)) => ScanBuilder::new().path(root).jobs(0).scan()?,
+        (None, None) => unreachable!("required unless listing"),
+    };
+    let mut findings = report.check(&checks);
+    match &args.root {
+        Some(root) => findings.extend(InstallTree::walk(root)?.check(&checks)),
+        None => {
+            if let Some(check) = args.checks.iter().find(|check| check.needs_tree()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the {check} check needs --root"),
+                ));
+            }
+        }
+    }
+    match &args.budgets {
+        Some(path) if checks.contains(&Check::ExportBudget) => {
+            let budgets = fs::read_to_string(path)
+                .and_then(|contents| ExportBudgets::parse(&contents))
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+            findings.extend(budgets.check(&report));
+        }
+        Some(_) => {}
+        None => {
+            if args.checks.contains(&Check::ExportBudget) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the {} check needs --budgets", Check::ExportBudget),
+                ));
+            }
+        }
+    }
+    if let Some(path) = args
+        .libc_names
+        .as_ref()
+        .filter(|_| checks.contains(&Check::LibcShadow))
+    {
+        let mut names = LibcNames::builtin();
+        names.extend_from_list(
+            &fs::read_to_string(path)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?,
+        );
+        findings.retain(|finding| finding.check != Check::LibcShadow);
+        findings.extend(names.check(&report));
+    }
+
+    for (check, severity) in &args.severity {
+        Finding::override_severity(&mut findings, *check, *severity);
+    }
+
+    let waivers = match &args.waivers {
+        Some(path) => fs::read_to_string(path)
+            .and_then(|contents| Waivers::parse(&contents))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?,
+        None => Waivers::default(),
+    };
+    let waived = waivers.apply(findings, today());
+    for waiver in &waived.expired {
+        eprintln!("Waiver {waiver} has expired: {}", waiver.justification);
+    }
+    for waiver in &waived.unused {
+        eprintln!("Waiver {waiver} matches no finding and can go");
+    }
+    let findings = waived.findings;
+
+    match args.format {
+        CheckFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&findings, &palette, &mut writer)?;
+            if !waived.waived.is_empty() {
+                writeln!(writer, "{} finding(s) waived", waived.waived.len())?;
+            }
+        }
+        CheckFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &findings)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()?;
+
+    let failures = args.fail_policy().failures(&findings).len();
+    match failures {
+        0 => Ok(()),
+        failures => Err(io::Error::other(format!(
+            "{failures} finding(s) at or above their --fail-on severity"
+        ))),
+    }
+}
+
+impl Args {
+    /// The thresholds given, later ones overriding earlier ones for the same checks.
+    fn fail_policy(&self) -> FailPolicy {
+        let mut policy = FailPolicy::default();
+        for (check, fail_on) in &self.fail_on {
+            match check {
+                Some(check) => {
+                    policy.checks.insert(*check, *fail_on);
+                }
+                None => policy.default = *fail_on,
+            }
+        }
+        policy
+    }
+}
+
+/// The current UTC date as year, month and day, for waiver expiry.
+fn today() -> [u32; 3] {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    // days since 1970-01-01 to a proleptic Gregorian date, after Howard Hinnant's
+    // civil_from_days()
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // from March
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    [year as u32, month as u32, day as u32]
+}
+
+fn parse_fail_on(arg: &str) -> io::Result<(Option<Check>, FailOn)> {
+    match arg.split_once('=') {
+        Some((check, fail_on)) => Ok((Some(check.parse()?), fail_on.parse()?)),
+        None => Ok((None, arg.parse()?)),
+    }
+}
+
+fn parse_severity(arg: &str) -> io::Result<(Check, Severity)> {
+    let (check, severity) = arg
+        .split_once('=')
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "expected ID=SEVERITY"))?;
+    Ok((check.parse()?, severity.parse()?))
+}
+
+fn count(findings: &[Finding], severity: Severity) -> usize {
+    findings
+        .iter()
+        .filter(|finding| finding.severity == severity)
+        .count()
+}
+
+fn write_text(findings: &[Finding], palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    for finding in findings {
+        let severity = match finding.severity {
+            Severity::Error => palette.removed(finding.severity),
+            Severity::Warning => palette.changed(finding.severity),
+            Severity::Info => palette.heading(finding.severity),
+        };
+        writeln!(
+            w,
+            "{}: {severity} [{}]: {}",
+            finding.filename, finding.check, finding.message
+        )?;
+        if let Some(fix) = &finding.fix {
+            writeln!(w, "  fix: {}", fix.command)?;
+            writeln!(w, "  or in the build: {}", fix.build)?;
+        }
+    }
+    writeln!(
+        w,
+        "{} finding(s): {} error(s), {} warning(s)",
+        findings.len(),
+        palette.removed(count(findings, Severity::Error)),
+        palette.changed(count(findings, Severity::Warning)),
+    )
+}
@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport closure`: every library an executable loads, and which lookup found it.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{DependencyClosure, LibrarySearch};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The executable (or library) to start from
+    binary: PathBuf,
+
+    /// Look libraries up within this directory, e.g. a staging root or mounted image
+    #[arg(long, value_name = "DIR", default_value = "/")]
+    sysroot: PathBuf,
+
+    /// Also suggest versions of missing libraries from this report (any format)
+    #[arg(long, value_name = "REPORT")]
+    db: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ClosureFormat::Text)]
+    format: ClosureFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ClosureFormat {
+    /// A tree of the libraries, like lddtree
+    Text,
+    /// The libraries in load order as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let search = LibrarySearch::new(&args.sysroot);
+    let mut closure = search.closure(&args.binary)?;
+    if let Some(db) = &args.db {
+        closure.suggest_from(&read_report(db)?);
+    }
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        ClosureFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&closure, &palette, &mut writer)?
+        }
+        ClosureFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &closure)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(closure: &DependencyClosure, palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    writeln!(w, "{}", palette.heading(&closure.root))?;
+    write_children(closure, &closure.root, palette, &mut w)?;
+    let missing = closure.missing().count();
+    writeln!(
+        w,
+        "{} libraries, {} not found",
+        closure.libraries.len(),
+        palette.removed(missing)
+    )
+}
+
+/// The libraries `parent` needs first, each followed by its own.
+fn write_children(
+    closure: &DependencyClosure,
+    parent: &str,
+    palette: &Palette,
+    w: &mut impl Write,
+) -> io::Result<()> {
+    for entry in closure.libraries.iter().filter(|e| e.needed_by == parent) {
+        let indent = "    ".repeat(entry.depth);
+        match (&entry.path, entry.found_by) {
+            (Some(path), Some(rule)) => {
+                writeln!(w, "{indent}{} => {path} ({rule})", entry.soname)?;
+                write_children(closure, path, palette, w)?;
+            }
+            _ if entry.alternatives.is_empty() => writeln!(
+                w,
+                "{indent}{} => {}",
+                entry.soname,
+                palette.removed("not found")
+            )?,
+            _ => writeln!(
+                w,
+                "{indent}{} => {}, the tree provides {}",
+                entry.soname,
+                palette.removed("not found"),
+                palette.changed(entry.alternatives.join(", "))
+            )?,
+        }
+    }
+    Ok(())
+}
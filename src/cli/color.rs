@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Terminal colors, honoring `--color` and NO_COLOR (<https://no-color.org>).
+
+use clap::ValueEnum;
+use std::fmt::{self, Display};
+use std::io::IsTerminal;
+
+const GREEN: &str = "32";
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const BOLD: &str = "1";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color if writing to a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decides whether (and how) text gets colored
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    /// A palette for output to stdout, or to a file if `to_file` (never colored unless asked).
+    pub fn new(choice: ColorChoice, to_file: bool) -> Self {
+        let enabled = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                !to_file
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        };
+        Self { enabled }
+    }
+
+    /// Something that appeared
+    pub fn added<T: Display>(&self, text: T) -> Painted<T> {
+        self.paint(GREEN, text)
+    }
+
+    /// Something that disappeared (or broke)
+    pub fn removed<T: Display>(&self, text: T) -> Painted<T> {
+        self.paint(RED, text)
+    }
+
+    /// Something that is still there, but differs
+    pub fn changed<T: Display>(&self, text: T) -> Painted<T> {
+        self.paint(YELLOW, text)
+    }
+
+    pub fn heading<T: Display>(&self, text: T) -> Painted<T> {
+        self.paint(BOLD, text)
+    }
+
+    fn paint<T: Display>(&self, code: &'static str, text: T) -> Painted<T> {
+        Painted {
+            text,
+            code: self.enabled.then_some(code),
+        }
+    }
+}
+
+/// Text wrapped in an SGR escape sequence, if colors are enabled
+pub struct Painted<T> {
+    text: T,
+    code: Option<&'static str>,
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "\x1b[{code}m{}\x1b[0m", self.text),
+            None => self.text.fmt(f),
+        }
+    }
+}
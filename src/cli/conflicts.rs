@@ -0,0 +1,133 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport conflicts`: the symbols more than one object of a process defines, and which
+//! definition wins, and the imports none of them defines.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::ProcessConflicts;
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A report containing the executable and its libraries (any format)
+    #[arg(long)]
+    db: PathBuf,
+
+    /// Also look up needed libraries in this report, e.g. of the build root
+    #[arg(long, value_name = "REPORT")]
+    sysroot: Option<PathBuf>,
+
+    /// A plugin loaded into the process, as named in the report (may be repeated)
+    #[arg(long = "with", value_name = "FILE")]
+    plugins: Vec<String>,
+
+    /// Load every shared object of the report in this directory as a plugin (may be repeated)
+    #[arg(long = "plugin-dir", value_name = "DIR")]
+    plugin_dirs: Vec<String>,
+
+    /// The executable, as named in the report
+    executable: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ConflictsFormat::Text)]
+    format: ConflictsFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ConflictsFormat {
+    /// Human readable, one line per conflicting symbol
+    Text,
+    /// The process and its conflicts as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let report = read_report(&args.db)?;
+    let sysroot = args.sysroot.as_deref().map(read_report).transpose()?;
+    let plugin_dirs = args
+        .plugin_dirs
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let mut plugins = args.plugins.iter().map(String::as_str).collect::<Vec<_>>();
+    plugins.extend(report.plugins_in(&plugin_dirs));
+    let conflicts = report
+        .process_conflicts(&args.executable, &plugins, sysroot.as_ref())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{}: the executable or a plugin isn't in the report",
+                    args.db.display()
+                ),
+            )
+        })?;
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        ConflictsFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&conflicts, &palette, &mut writer)?
+        }
+        ConflictsFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &conflicts)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(process: &ProcessConflicts, palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    writeln!(w, "{}", palette.heading(&process.executable))?;
+    for (idx, object) in process.load_order.iter().enumerate() {
+        writeln!(w, "  {:>3}  {object}", idx + 1)?;
+    }
+    for soname in &process.missing {
+        writeln!(
+            w,
+            "       {}",
+            palette.removed(format!("{soname} (not in the reports)"))
+        )?;
+    }
+    for conflict in &process.conflicts {
+        writeln!(
+            w,
+            "  {} from {}, shadowing {}",
+            palette.changed(&conflict.symbol),
+            conflict.winner,
+            conflict.shadowed.join(", ")
+        )?;
+    }
+    for unresolved in &process.unresolved {
+        for symbol in &unresolved.symbols {
+            writeln!(
+                w,
+                "  {} unresolved in {}",
+                palette.removed(symbol),
+                unresolved.filename
+            )?;
+        }
+    }
+    writeln!(
+        w,
+        "{} symbol(s) defined more than once, {} unresolved, in {} object(s)",
+        palette.changed(process.conflicts.len()),
+        palette.removed(
+            process
+                .unresolved
+                .iter()
+                .map(|u| u.symbols.len())
+                .sum::<usize>()
+        ),
+        process.load_order.len()
+    )
+}
@@ -0,0 +1,57 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport convert`: re-encode a report in another format.
+
+use super::{output_format, output_writer, template, write_demangled, write_report, Format};
+use abireport_rs::AbiReport;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Report to convert (format is detected from its contents)
+    input: PathBuf,
+
+    /// Where to write the converted report (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Format to convert to (default: from the output extension, else JSON)
+    #[arg(long, value_enum)]
+    to: Option<Format>,
+
+    /// Render the report through this template (Jinja2 syntax) instead
+    #[arg(long, value_name = "FILE", conflicts_with = "to")]
+    template: Option<PathBuf>,
+
+    /// List the exported C++ symbols of each file below the table, demangled and grouped by
+    /// namespace and class (table output only)
+    #[arg(long)]
+    demangle: bool,
+
+    /// Leave out C++ and Rust mangled symbols, keeping only the extern "C" interface
+    #[arg(long)]
+    c_abi_only: bool,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let mut report = AbiReport::read(BufReader::new(File::open(&args.input)?))?;
+    if args.c_abi_only {
+        report = report.c_abi_only();
+    }
+
+    if let Some(template) = &args.template {
+        let writer = output_writer(args.output.as_deref())?;
+        return template::render_report(template, &report, writer);
+    }
+
+    let format = output_format(args.to, args.output.as_deref(), Format::Json);
+    let writer = output_writer(args.output.as_deref())?;
+    match format == Format::Table && args.demangle {
+        true => write_demangled(&report, writer),
+        false => write_report(&report, format, writer),
+    }
+}
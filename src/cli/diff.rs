@@ -0,0 +1,422 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport diff`: compare two reports.
+
+use super::color::{ColorChoice, Palette};
+use super::{fetch, output_writer, parse_report, read_input, read_report, template, verify};
+use abireport_rs::diff::{
+    CaptureDiff, ChangeKind, DiffFilter, DiffOptions, DiffSummary, ListDiff, ReportDiff,
+    ValueChange,
+};
+use abireport_rs::{AbiReport, SymbolPattern};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The baseline report, a file or an http(s) URL (zstd or gzip compressed or not), then the
+    /// report to compare against it; with --db, just the latter
+    #[arg(value_name = "REPORT", num_args = 1..=2, required = true)]
+    reports: Vec<PathBuf>,
+
+    /// Take this report of the whole repository as the baseline, and only compare the files
+    /// whose contents changed since (as told by their SHA-256), e.g. for nightly monitoring
+    #[arg(long, value_name = "FILE")]
+    db: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
+
+    /// Render the diff through this template (Jinja2 syntax) instead
+    #[arg(long, value_name = "FILE", conflicts_with = "format")]
+    template: Option<PathBuf>,
+
+    /// Recommend libtool version (current:revision:age) and soname changes for each changed
+    /// shared object (text output only)
+    #[arg(long)]
+    advise: bool,
+
+    /// Also compare the symbols the toolchain generates for every binary (_init, _fini,
+    /// __bss_start, _edata, ...)
+    #[arg(long)]
+    include_boilerplate: bool,
+
+    /// Only compare the files of this package (as recorded by `scan --package-map`), e.g. to
+    /// see what a package's ABI did between two of its versions
+    #[arg(long, value_name = "NAME")]
+    package: Option<String>,
+
+    /// Only compare files whose path, name or soname matches this glob, e.g. 'libLLVM*' (may be
+    /// repeated)
+    #[arg(long, value_name = "GLOB")]
+    only: Vec<String>,
+
+    /// Leave out files whose path or name matches this glob (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    exclude_path: Vec<String>,
+
+    /// Only compare symbols matching this glob, e.g. 'LLVM*' (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    symbol_filter: Vec<String>,
+
+    /// Only compare symbols of the C ABI, leaving out C++ and Rust mangled ones
+    #[arg(long)]
+    c_abi_only: bool,
+
+    /// List the changes package by package, each with its own summary (text output only)
+    #[arg(long)]
+    by_package: bool,
+
+    /// Compare this many files in parallel (0: one per CPU)
+    #[arg(short, long, default_value_t = 0)]
+    jobs: usize,
+
+    /// Where to write the diff (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Refuse the baseline unless its file has this SHA-256 (as hex), e.g. to pin the published
+    /// baseline of a release
+    #[arg(long, value_name = "HEX")]
+    baseline_sha256: Option<String>,
+
+    /// Refuse the baseline unless its signature (the baseline with .minisig appended) verifies
+    /// with this public key
+    #[arg(long, value_name = "FILE")]
+    baseline_key: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DiffFormat {
+    /// Human readable, one section per changed file
+    Text,
+    /// The diff as JSON
+    Json,
+    /// Standalone HTML page
+    Html,
+}
+
+impl Args {
+    /// The baseline and the new report.
+    fn reports(&self) -> io::Result<(&Path, &Path)> {
+        match (&self.db, self.reports.as_slice()) {
+            (Some(db), [new]) => Ok((db, new)),
+            (None, [old, new]) => Ok((old, new)),
+            (Some(_), _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "with --db, give only the report to compare against it",
+            )),
+            (None, _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "give the baseline report and the report to compare against it",
+            )),
+        }
+    }
+
+    fn filter(&self) -> io::Result<DiffFilter> {
+        let mut filter = DiffFilter::new();
+        for pattern in &self.only {
+            filter = filter.only(pattern)?;
+        }
+        for pattern in &self.exclude_path {
+            filter = filter.exclude(pattern)?;
+        }
+        for pattern in &self.symbol_filter {
+            filter = filter.symbols(SymbolPattern::glob(pattern)?);
+        }
+        if self.c_abi_only {
+            filter = filter.c_abi_only();
+        }
+        Ok(filter)
+    }
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let (old_path, new_path) = args.reports()?;
+    let data = match &args.baseline_key {
+        Some(key) => {
+            let key = verify::read_public_key(key)?;
+            let signature = verify::signature_path(old_path);
+            verify::read_verified(old_path, &key, &signature)?.0
+        }
+        None => read_input(old_path)?,
+    };
+    if let Some(sha256) = &args.baseline_sha256 {
+        fetch::check_sha256(&data, sha256)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", old_path.display())))?;
+    }
+    let mut old = parse_report(old_path, data)?;
+    let mut new = read_report(new_path)?;
+    if args.db.is_some() {
+        let changed = new.changed_since(&old);
+        eprintln!(
+            "{} file(s) unchanged since {}, comparing {} changed one(s)",
+            changed.unchanged,
+            old_path.display(),
+            changed.new.captures().len()
+        );
+        (old, new) = (changed.baseline, changed.new);
+    }
+    if let Some(package) = &args.package {
+        old = old.package(package);
+        new = new.package(package);
+    }
+    let options = DiffOptions {
+        include_boilerplate: args.include_boilerplate,
+        jobs: args.jobs,
+    };
+    let filter = args.filter()?;
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    let palette = Palette::new(color, args.output.is_some());
+    if args.format == DiffFormat::Text && args.template.is_none() && !args.by_package {
+        // each file's changes are written out as soon as they are computed
+        let mut filenames = HashSet::new();
+        let mut written = Ok(());
+        let summary = ReportDiff::stream(&old, &new, options, &filter, |change| {
+            if written.is_ok() {
+                written = write_capture_diff(&change, &palette, &mut writer);
+            }
+            filenames.insert(change.filename);
+        });
+        written?;
+        write_summary(&summary, &palette, &mut writer)?;
+        if args.advise {
+            write_advice(&old, &new, &filenames, &palette, &mut writer)?;
+        }
+        return writer.flush();
+    }
+
+    let diff = old.diff_filtered(&new, options, &filter);
+    if let Some(template) = &args.template {
+        return template::render_diff(template, &diff, writer);
+    }
+    match args.format {
+        DiffFormat::Text => {
+            write_packages(&diff, &old, &new, &palette, &mut writer)?;
+            if args.advise {
+                let filenames = diff
+                    .changes
+                    .iter()
+                    .map(|change| change.filename.clone())
+                    .collect();
+                write_advice(&old, &new, &filenames, &palette, &mut writer)?;
+            }
+        }
+        DiffFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &diff)?;
+            writeln!(writer)?
+        }
+        DiffFormat::Html => template::html_diff(&diff, &mut writer)?,
+    }
+    writer.flush()
+}
+
+fn write_text(diff: &ReportDiff, palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    for change in &diff.changes {
+        write_capture_diff(change, palette, &mut w)?;
+    }
+    write_summary(&diff.summary(), palette, w)
+}
+
+fn write_summary(summary: &DiffSummary, palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    writeln!(
+        w,
+        "{} added, {} removed, {} modified file(s); {} added, {} removed{} export(s){}",
+        summary.files_added,
+        summary.files_removed,
+        summary.files_modified,
+        palette.added(summary.exports_added),
+        palette.removed(summary.exports_removed),
+        match summary.exports_resized {
+            0 => String::new(),
+            resized => format!(", {} resized", palette.removed(resized)),
+        },
+        match summary.breaking {
+            true => format!(" ({})", palette.removed("breaking")),
+            false => String::new(),
+        }
+    )
+}
+
+/// [write_text] for each package in turn, headed by its versions on either side.
+fn write_packages(
+    diff: &ReportDiff,
+    old: &AbiReport,
+    new: &AbiReport,
+    palette: &Palette,
+    mut w: impl Write,
+) -> io::Result<()> {
+    let versions = |report: &AbiReport, name: &str| {
+        let versions = report
+            .packages()
+            .into_iter()
+            .filter(|package| package.name == name)
+            .filter_map(|package| package.version.as_deref())
+            .collect::<Vec<_>>();
+        match versions.is_empty() {
+            true => "(none)".to_string(),
+            false => versions.join(", "),
+        }
+    };
+    for (idx, (package, diff)) in diff.by_package().iter().enumerate() {
+        if idx > 0 {
+            writeln!(w)?;
+        }
+        let heading = match package {
+            Some(name) => format!("{name} {} -> {}", versions(old, name), versions(new, name)),
+            None => "(no package)".to_string(),
+        };
+        writeln!(w, "{}", palette.heading(heading))?;
+        write_text(diff, palette, &mut w)?;
+    }
+    Ok(())
+}
+
+/// Versioning advice for the shared objects the (possibly filtered) diff lists.
+fn write_advice(
+    old: &AbiReport,
+    new: &AbiReport,
+    filenames: &HashSet<String>,
+    palette: &Palette,
+    mut w: impl Write,
+) -> io::Result<()> {
+    let mut advice = old.version_advice(new);
+    advice.retain(|advice| filenames.contains(&advice.filename));
+    if advice.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "\n{}", palette.heading("Versioning advice"))?;
+    for advice in &advice {
+        match advice.is_followed() {
+            true => writeln!(w, "  {advice}")?,
+            false => writeln!(w, "  {}", palette.removed(advice))?,
+        }
+    }
+    Ok(())
+}
+
+/// One file's changes, as listed by `diff` (and `watch`).
+pub fn write_capture_diff(
+    diff: &CaptureDiff,
+    palette: &Palette,
+    w: &mut impl Write,
+) -> io::Result<()> {
+    let filename = palette.heading(&diff.filename);
+    match diff.change {
+        ChangeKind::Added => writeln!(w, "{} {filename}", palette.added("A"))?,
+        ChangeKind::Removed => writeln!(w, "{} {filename}", palette.removed("D"))?,
+        ChangeKind::Modified => writeln!(w, "{} {filename}", palette.changed("M"))?,
+    }
+
+    write_value(
+        w,
+        palette,
+        "kind",
+        diff.elf_kind
+            .as_ref()
+            .map(|c| (c.old.to_string(), c.new.to_string())),
+    )?;
+    write_value(w, palette, "soname", diff.soname.as_ref().map(optional))?;
+    write_value(w, palette, "rpath", diff.rpath.as_ref().map(optional))?;
+    write_value(w, palette, "runpath", diff.runpath.as_ref().map(optional))?;
+    write_value(
+        w,
+        palette,
+        "constructors",
+        diff.constructors
+            .as_ref()
+            .map(|c| (c.old.to_string(), c.new.to_string())),
+    )?;
+
+    // files that came or went are summarized, rather than listing everything they contain
+    let summarize = diff.change != ChangeKind::Modified;
+    write_value(
+        w,
+        palette,
+        "bind-now",
+        diff.bind_now
+            .as_ref()
+            .map(|c| (c.old.to_string(), c.new.to_string())),
+    )?;
+    write_value(
+        w,
+        palette,
+        "relro",
+        diff.relro
+            .as_ref()
+            .map(|c| (c.old.to_string(), c.new.to_string())),
+    )?;
+    write_list(w, palette, "export", &diff.exports, summarize)?;
+    for alias in &diff.dropped_aliases {
+        let line = format!("- alias {alias} (its canonical name is still exported)");
+        writeln!(w, "  {}", palette.changed(line))?;
+    }
+    for moved in &diff.default_versions {
+        let (old, new) = (&moved.version.old, &moved.version.new);
+        let line = format!("! default version of {}: {old} -> {new}", moved.name);
+        writeln!(w, "  {}", palette.changed(line))?;
+    }
+    for resize in &diff.resized {
+        let (old, new) = (resize.size.old, resize.size.new);
+        let line = format!("~ object {}: {old} -> {new} bytes", resize.symbol);
+        writeln!(w, "  {}", palette.removed(line))?;
+    }
+    write_list(w, palette, "import", &diff.imports, summarize)?;
+    write_list(w, palette, "needed", &diff.needed, summarize)?;
+    Ok(())
+}
+
+fn write_value(
+    w: &mut impl Write,
+    palette: &Palette,
+    what: &str,
+    change: Option<(String, String)>,
+) -> io::Result<()> {
+    match change {
+        Some((old, new)) => writeln!(
+            w,
+            "    {what}: {} -> {}",
+            palette.removed(old),
+            palette.added(new)
+        ),
+        None => Ok(()),
+    }
+}
+
+fn write_list(
+    w: &mut impl Write,
+    palette: &Palette,
+    what: &str,
+    list: &ListDiff<impl fmt::Display>,
+    summarize: bool,
+) -> io::Result<()> {
+    if summarize {
+        if !list.added.is_empty() {
+            let count = palette.added(format!("+{}", list.added.len()));
+            writeln!(w, "    {count} {what}(s)")?;
+        } else if !list.removed.is_empty() {
+            let count = palette.removed(format!("-{}", list.removed.len()));
+            writeln!(w, "    {count} {what}(s)")?;
+        }
+        return Ok(());
+    }
+    for removed in &list.removed {
+        writeln!(w, "  {}", palette.removed(format!("- {what} {removed}")))?;
+    }
+    for added in &list.added {
+        writeln!(w, "  {}", palette.added(format!("+ {what} {added}")))?;
+    }
+    Ok(())
+}
+
+fn optional(change: &ValueChange<Option<String>>) -> (String, String) {
+    let show = |value: &Option<String>| value.as_deref().unwrap_or("(none)").to_string();
+    (show(&change.old), show(&change.new))
+}
@@ -0,0 +1,81 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reading reports from URLs (e.g. the published baseline of the previous release) and
+//! compressed files.
+//!
+//! Downloads go through curl, which is there on any CI runner and brings along the TLS setup,
+//! proxy settings and certificates of the system. Reports may be compressed with zstd or gzip,
+//! which is detected from their contents rather than their name.
+
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::Command;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// The URL a report argument names, if it is an http(s) URL rather than a path.
+pub fn as_url(path: &Path) -> Option<&str> {
+    path.to_str()
+        .filter(|path| path.starts_with("https://") || path.starts_with("http://"))
+}
+
+/// Download a URL, failing on HTTP errors. Redirects are followed, but never from https to
+/// plain http, which would throw away what https vouches for.
+pub fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    let redirects = match url.starts_with("https://") {
+        true => "=https",
+        false => "=https,http",
+    };
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args([
+            "--proto",
+            "=https,http",
+            "--proto-redir",
+            redirects,
+            "--",
+            url,
+        ])
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(err.kind(), "fetching URLs needs curl"),
+            _ => err,
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "could not be fetched: {}",
+            stderr.trim().trim_start_matches("curl: ")
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Fail unless the data has the given SHA-256 (as hex), e.g. the one published alongside.
+pub fn check_sha256(data: &[u8], expected: &str) -> io::Result<()> {
+    let actual = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    match actual.eq_ignore_ascii_case(expected.trim()) {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("has SHA-256 {actual}, not the pinned {}", expected.trim()),
+        )),
+    }
+}
+
+/// Decompress gzip compressed data, passing anything else (including zstd compressed reports,
+/// which the library reads) through.
+pub fn decompress(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !data.starts_with(GZIP_MAGIC) {
+        return Ok(data);
+    }
+    let mut out = vec![];
+    flate2::read::MultiGzDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+    Ok(out)
+}
@@ -0,0 +1,96 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport impact`: what in a repository a library update breaks or forces to be rebuilt.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{RebuildImpact, ReportIndex};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A report of the whole repository (any format)
+    #[arg(long)]
+    db: PathBuf,
+
+    /// The report of the library package as it is in the repository
+    old: PathBuf,
+
+    /// The report of the updated library package
+    new: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ImpactFormat::Text)]
+    format: ImpactFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ImpactFormat {
+    /// Human readable, one entry per affected file and library
+    Text,
+    /// The affected files as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let index = ReportIndex::new(read_report(&args.db)?);
+    let old = read_report(&args.old)?;
+    let new = read_report(&args.new)?;
+    let impacts = index.rebuild_impact(&old, &new);
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        ImpactFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&impacts, &palette, &mut writer)?
+        }
+        ImpactFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &impacts)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(impacts: &[RebuildImpact], palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    for impact in impacts {
+        writeln!(w, "{}", palette.heading(&impact.filename))?;
+        if impact.soname_gone {
+            writeln!(
+                w,
+                "    needs {}, which the update no longer provides",
+                palette.changed(&impact.library)
+            )?;
+        } else {
+            writeln!(w, "    needs {}", impact.library)?;
+        }
+        for symbol in &impact.broken_imports {
+            writeln!(w, "  {}", palette.removed(format!("- import {symbol}")))?;
+        }
+    }
+
+    let files = |pred: fn(&RebuildImpact) -> bool| {
+        let mut files = impacts
+            .iter()
+            .filter(|impact| pred(impact))
+            .map(|impact| &impact.filename)
+            .collect::<Vec<_>>();
+        files.dedup();
+        files.len()
+    };
+    writeln!(
+        w,
+        "{} file(s) affected: {} would break, {} need(s) relinking against a new soname",
+        files(|_| true),
+        palette.removed(files(RebuildImpact::breaks)),
+        palette.changed(files(|impact| !impact.breaks() && impact.soname_gone)),
+    )
+}
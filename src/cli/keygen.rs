@@ -0,0 +1,64 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport keygen`: create a key pair for signing reports.
+
+use abireport_rs::sign::SecretKey;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Where to write the public key
+    #[arg(short, long, default_value = "abireport.pub")]
+    public_key: PathBuf,
+
+    /// Where to write the secret key (unencrypted, keep it safe)
+    #[arg(short, long, default_value = "abireport.key")]
+    secret_key: PathBuf,
+
+    /// Overwrite existing key files
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let secret = SecretKey::generate()?;
+    write_key(&args.secret_key, &secret.to_minisign(), args.force, 0o600)?;
+    write_key(
+        &args.public_key,
+        &secret.public_key().to_minisign(),
+        args.force,
+        0o644,
+    )?;
+    println!(
+        "Key {} written to {} (public) and {} (secret)",
+        secret.public_key().key_id(),
+        args.public_key.display(),
+        args.secret_key.display()
+    );
+    Ok(())
+}
+
+/// Write a new key file with the given permissions. With `force`, an existing file is removed
+/// first rather than overwritten, as that would keep its permissions (and any other links to it).
+fn write_key(path: &Path, contents: &str, force: bool, mode: u32) -> io::Result<()> {
+    let with_path =
+        |err: io::Error| io::Error::new(err.kind(), format!("{}: {err}", path.display()));
+    if force {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(with_path(err)),
+            _ => {}
+        }
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = options.open(path).map_err(with_path)?;
+    file.write_all(contents.as_bytes())
+}
@@ -0,0 +1,62 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport merge`: combine several reports into one.
+
+use super::{output_format, output_writer, read_report, write_report, Format};
+use abireport_rs::{AbiReport, MergePolicy};
+use clap::ValueEnum;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The reports to merge (any format)
+    #[arg(required = true, num_args = 2..)]
+    reports: Vec<PathBuf>,
+
+    /// What to do about a file captured differently by several reports
+    #[arg(long, value_enum, default_value_t = Policy::Error)]
+    on_conflict: Policy,
+
+    /// Where to write the merged report (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Format of the merged report (default: from the output extension, else JSON)
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Policy {
+    /// Fail
+    Error,
+    /// Keep the capture of the file with the latest mtime (else of the report scanned last)
+    PreferNewest,
+    /// Keep all of them, as FILE#N for the Nth report
+    KeepBoth,
+}
+
+impl From<Policy> for MergePolicy {
+    fn from(policy: Policy) -> Self {
+        match policy {
+            Policy::Error => Self::Error,
+            Policy::PreferNewest => Self::PreferNewest,
+            Policy::KeepBoth => Self::KeepBoth,
+        }
+    }
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let reports = args
+        .reports
+        .iter()
+        .map(|path| read_report(path))
+        .collect::<io::Result<Vec<_>>>()?;
+    let merged = AbiReport::merge(reports, args.on_conflict.into())?;
+
+    let format = output_format(args.format, args.output.as_deref(), Format::Json);
+    write_report(&merged, format, output_writer(args.output.as_deref())?)
+}
@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! The abireport subcommands.
+
+use abireport_rs::{is_zstd_path, AbiReport, ReportFormat, ZstdWriter};
+use clap::ValueEnum;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub mod bench;
+#[cfg(feature = "browse")]
+pub mod browse;
+pub mod check;
+pub mod closure;
+pub mod color;
+pub mod conflicts;
+pub mod convert;
+pub mod diff;
+pub mod fetch;
+pub mod impact;
+pub mod keygen;
+pub mod merge;
+pub mod parity;
+pub mod preload;
+pub mod progress;
+pub mod query;
+pub mod resolve;
+pub mod scan;
+pub mod schema;
+pub mod search_dirs;
+#[cfg(unix)]
+pub mod serve;
+pub mod sign;
+pub mod split;
+pub mod stats;
+pub mod template;
+pub mod timings;
+pub mod transition;
+pub mod usage;
+pub mod validate;
+pub mod verify;
+pub mod watch;
+
+/// The report output formats, as selectable on the command line
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned table for humans (can't be read back)
+    Table,
+    /// JSON
+    Json,
+    /// Compact CBOR with interned strings
+    Cbor,
+    /// Streamed JSON Lines, one capture per line
+    Jsonl,
+    /// Streamed length-prefixed CBOR records
+    Records,
+    /// One row per symbol: file,direction,symbol,version,type (can't be read back)
+    Csv,
+    /// Like csv, but tab separated
+    Tsv,
+    /// Standalone, searchable HTML page (can't be read back)
+    Html,
+}
+
+impl Format {
+    /// The serialization format, or None for the human readable and tabular outputs
+    pub fn report_format(self) -> Option<ReportFormat> {
+        match self {
+            Format::Table | Format::Csv | Format::Tsv | Format::Html => None,
+            Format::Json => Some(ReportFormat::Json),
+            Format::Cbor => Some(ReportFormat::Cbor),
+            Format::Jsonl => Some(ReportFormat::JsonLines),
+            Format::Records => Some(ReportFormat::Records),
+        }
+    }
+}
+
+impl From<ReportFormat> for Format {
+    fn from(format: ReportFormat) -> Self {
+        match format {
+            ReportFormat::Json => Format::Json,
+            ReportFormat::Cbor => Format::Cbor,
+            ReportFormat::JsonLines => Format::Jsonl,
+            ReportFormat::Records => Format::Records,
+        }
+    }
+}
+
+/// Read a report in any format, possibly compressed, from a file or an http(s) URL, naming it
+/// in errors.
+pub fn read_report(path: &Path) -> io::Result<AbiReport> {
+    parse_report(path, read_input(path)?)
+}
+
+/// The contents of a file, or of an http(s) URL.
+pub fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    let data = match fetch::as_url(path) {
+        Some(url) => fetch::fetch(url),
+        None => fs::read(path),
+    };
+    data.map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+}
+
+/// Parse a report read with [read_input], decompressing it first if need be.
+pub fn parse_report(path: &Path, data: Vec<u8>) -> io::Result<AbiReport> {
+    fetch::decompress(data)
+        .and_then(|data| AbiReport::read(data.as_slice()))
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+}
+
+/// Open `path` for writing (zstd compressed for `.zst` files), or stdout if there is no path.
+pub fn output_writer(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) if is_zstd_path(path) => Box::new(ZstdWriter::new(File::create(path)?)),
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+/// The format to write, from an explicit choice, else the output file extension, else `default`.
+pub fn output_format(explicit: Option<Format>, path: Option<&Path>, default: Format) -> Format {
+    let from_extension = |path: &Path| {
+        // look through .zst, as with ReportFormat::from_extension()
+        let name = match is_zstd_path(path) {
+            true => Path::new(path.file_stem().unwrap_or_default()),
+            false => path,
+        };
+        match name.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Format::Csv,
+            Some("tsv") => Format::Tsv,
+            Some("html" | "htm") => Format::Html,
+            _ => ReportFormat::from_extension(path).into(),
+        }
+    };
+    explicit
+        .or_else(|| path.map(from_extension))
+        .unwrap_or(default)
+}
+
+/// Write the report table, followed by the exported C++ symbols of each file that has any,
+/// demangled and grouped by namespace and class.
+pub fn write_demangled(report: &AbiReport, mut writer: impl Write) -> io::Result<()> {
+    write!(writer, "{report}")?;
+    for capture in report.captures() {
+        let tree = capture.cpp_exports();
+        if tree.is_empty() {
+            continue;
+        }
+        writeln!(
+            writer,
+            "\nC++ exports of {} ({} of {} symbols):",
+            capture.filename(),
+            tree.len(),
+            capture.dynsym_exports().len()
+        )?;
+        write!(writer, "{tree}")?;
+    }
+    writer.flush()
+}
+
+/// Write out a whole report in the given format.
+pub fn write_report(report: &AbiReport, format: Format, mut writer: impl Write) -> io::Result<()> {
+    match format {
+        Format::Table => write!(writer, "{report}")?,
+        Format::Csv => report.write_symbol_table(&mut writer, ',')?,
+        Format::Tsv => report.write_symbol_table(&mut writer, '\t')?,
+        Format::Html => template::html_report(report, &mut writer)?,
+        Format::Json => {
+            report.write(&mut writer, ReportFormat::Json)?;
+            writeln!(writer)?;
+        }
+        Format::Cbor | Format::Jsonl | Format::Records => {
+            let format = format.report_format().expect("serialized formats");
+            report.write(&mut writer, format)?
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport parity`: compare the exports of a package built for two architectures.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{AbiReport, ArchParity, ParityAllowlist};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report of the package built for one architecture
+    left: PathBuf,
+
+    /// The report of the same package built for another architecture
+    right: PathBuf,
+
+    /// Exports matching this glob may exist on this architecture only, e.g. x86_64='__x86_*'
+    /// (may be repeated)
+    #[arg(long, value_name = "ARCH=GLOB")]
+    allow: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ParityFormat::Text)]
+    format: ParityFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ParityFormat {
+    /// Human readable, one section per library that differs
+    Text,
+    /// The differing libraries as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let left = read_report(&args.left)?;
+    let right = read_report(&args.right)?;
+    let allowed = args
+        .allow
+        .iter()
+        .map(|arg| {
+            arg.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("--allow {arg}: expected ARCH=GLOB"),
+                )
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let parity = left.arch_parity(&right, &ParityAllowlist::new(allowed)?);
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        ParityFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            let arches = [arch_of(&left), arch_of(&right)];
+            write_text(&parity, arches, &palette, &mut writer)?
+        }
+        ParityFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &parity)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+/// The architecture(s) of a report, for labelling its side.
+fn arch_of(report: &AbiReport) -> String {
+    let arches = report
+        .partition_by_arch()
+        .into_keys()
+        .map(|arch| arch.unwrap_or_else(|| "unknown".to_string()))
+        .collect::<Vec<_>>();
+    arches.join("+")
+}
+
+fn write_text(
+    parity: &[ArchParity],
+    [left_arch, right_arch]: [String; 2],
+    palette: &Palette,
+    mut w: impl Write,
+) -> io::Result<()> {
+    for library in parity {
+        writeln!(w, "{}", palette.heading(&library.library))?;
+        let sides = [
+            (&library.left, &left_arch, &library.only_left),
+            (&library.right, &right_arch, &library.only_right),
+        ];
+        for (filename, arch, only) in sides {
+            match filename {
+                Some(filename) if !only.is_empty() => {
+                    writeln!(w, "  only on {arch} ({filename}):")?;
+                    for symbol in only {
+                        writeln!(w, "    {}", palette.changed(&symbol.name))?;
+                    }
+                }
+                Some(_) => {}
+                None => writeln!(w, "  {}", palette.removed(format!("missing on {arch}")))?,
+            }
+        }
+    }
+    writeln!(
+        w,
+        "{} libraries differ between {left_arch} and {right_arch}",
+        parity.len()
+    )
+}
@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport preload`: which imports of a tree a preloaded library would take over.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{parse_elf_with, Interposition, Limits};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A report of the tree the library would be preloaded into (any format)
+    #[arg(long)]
+    db: PathBuf,
+
+    /// Also look up needed libraries in this report, e.g. of the build root
+    #[arg(long, value_name = "REPORT")]
+    sysroot: Option<PathBuf>,
+
+    /// The library to preload (an ELF file)
+    library: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = PreloadFormat::Text)]
+    format: PreloadFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PreloadFormat {
+    /// Human readable, one entry per affected file
+    Text,
+    /// The affected files as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let report = read_report(&args.db)?;
+    let sysroot = args.sysroot.as_deref().map(read_report).transpose()?;
+    let preload = parse_elf_with(&args.library.to_string_lossy(), &Limits::default())?;
+    let impact = report.preload_impact(&preload, sysroot.as_ref());
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        PreloadFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&impact, &palette, &mut writer)?
+        }
+        PreloadFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &impact)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(impact: &[Interposition], palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    for file in impact {
+        writeln!(w, "{}", palette.heading(&file.filename))?;
+        for symbol in &file.symbols {
+            let provider = symbol.provider.as_deref().unwrap_or_default();
+            writeln!(
+                w,
+                "  {} (instead of {provider})",
+                palette.changed(format!("~ {}", symbol))
+            )?;
+        }
+    }
+    let symbols = impact.iter().map(|file| file.symbols.len()).sum::<usize>();
+    writeln!(
+        w,
+        "{} import(s) of {} file(s) interposed",
+        palette.changed(symbols),
+        impact.len()
+    )
+}
@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Opt-in progress reporting for long running scans.
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+const TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] {wide_bar} {pos}/{len} parsed, {msg} (ETA {eta})";
+
+/// Tracks files discovered/parsed/failed, drawing a progress bar on stderr if it is a TTY.
+///
+/// When the bar is hidden, failures and partial captures are still reported on stderr.
+pub struct Progress {
+    bar: ProgressBar,
+    failed: u64,
+    partial: u64,
+}
+
+impl Progress {
+    pub fn new(enabled: bool) -> Self {
+        let bar = if enabled && std::io::stderr().is_terminal() {
+            ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr())
+        } else {
+            ProgressBar::hidden()
+        };
+        bar.set_style(ProgressStyle::with_template(TEMPLATE).expect("valid progress template"));
+        bar.set_message("0 failed");
+
+        Self {
+            bar,
+            failed: 0,
+            partial: 0,
+        }
+    }
+
+    pub fn discovered(&self, total: usize) {
+        self.bar.set_length(total as u64);
+    }
+
+    pub fn parsed(&self) {
+        self.bar.inc(1);
+    }
+
+    /// A file was captured, but only partially.
+    pub fn partial(&mut self, path: impl Display, warnings: &[impl Display]) {
+        self.partial += 1;
+        self.bar.inc(1);
+        self.bar.suspend(|| {
+            for warning in warnings {
+                eprintln!("Partially captured {path}: {warning}");
+            }
+        });
+    }
+
+    pub fn failed(&mut self, path: impl Display, err: impl Display) {
+        self.failed += 1;
+        self.bar.set_message(format!("{} failed", self.failed));
+        self.bar.inc(1);
+        self.bar
+            .suspend(|| eprintln!("Could not capture {path}: {err}"));
+    }
+
+    /// How many files could not be captured, which the caller reports (or fails on).
+    pub fn failures(&self) -> u64 {
+        self.failed
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+        if self.partial > 0 {
+            eprintln!("{} file(s) were only partially captured", self.partial);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport query`: look things up in a report.
+
+use super::{output_writer, read_report};
+use abireport_rs::{SymbolMatch, SymbolPattern};
+use clap::{Subcommand, ValueEnum};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// How many similar names `query exporters` suggests for a symbol nothing exports
+const SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Subcommand)]
+pub enum Query {
+    /// Find exported and imported symbols by name, with the files providing and using them
+    Symbols(SymbolsArgs),
+    /// List the files exporting a symbol, across any number of reports
+    Exporters(ExportersArgs),
+    /// List the oldest Linux kernel each file declares it runs on (in its ABI tag)
+    MinKernel(MinKernelArgs),
+    /// List the packages the files belong to (see `scan --package-map`), with their file counts
+    Packages(PackagesArgs),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PackagesArgs {
+    /// The report to look through
+    report: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct MinKernelArgs {
+    /// The reports to look through
+    #[arg(required = true)]
+    reports: Vec<PathBuf>,
+
+    /// Only list files declaring a newer kernel than this (e.g. a planned baseline, 5.10)
+    #[arg(long, value_name = "VERSION", value_parser = parse_kernel)]
+    above: Option<[u32; 3]>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ExportersArgs {
+    /// The symbol name, optionally with @VERSION (e.g. 'memcpy@GLIBC_2.14')
+    symbol: String,
+
+    /// The reports to search
+    #[arg(required = true)]
+    reports: Vec<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SymbolsArgs {
+    /// Glob (e.g. 'SSL_*') or, with --regex, regular expression matched against symbol names
+    pattern: String,
+
+    /// The report to search
+    report: PathBuf,
+
+    /// Treat the pattern as a regular expression
+    #[arg(long, conflicts_with_all = ["prefix", "substring", "fuzzy"])]
+    regex: bool,
+
+    /// Match the names starting with the pattern
+    #[arg(long, conflicts_with_all = ["substring", "fuzzy"])]
+    prefix: bool,
+
+    /// Match the names containing the pattern
+    #[arg(long, conflicts_with = "fuzzy")]
+    substring: bool,
+
+    /// Match the names a few typos away from the pattern, closest first, e.g. to find what
+    /// 'undefined symbol: g_strv_bulider_add' was meant to be
+    #[arg(long)]
+    fuzzy: bool,
+
+    /// How many typos (inserted, deleted, replaced or swapped characters) --fuzzy forgives;
+    /// by default 1 to 3, growing with the length of the pattern
+    #[arg(long, value_name = "N", requires = "fuzzy")]
+    max_distance: Option<usize>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = QueryFormat::Text)]
+    format: QueryFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum QueryFormat {
+    /// Human readable
+    Text,
+    /// The matches as JSON
+    Json,
+}
+
+pub fn run(query: Query) -> io::Result<()> {
+    match query {
+        Query::Symbols(args) => symbols(args),
+        Query::Exporters(args) => exporters(args),
+        Query::MinKernel(args) => min_kernel(args),
+        Query::Packages(args) => packages(args),
+    }
+}
+
+fn packages(args: PackagesArgs) -> io::Result<()> {
+    let report = read_report(&args.report)?;
+    let mut stdout = io::stdout().lock();
+    for package in report.packages() {
+        let files = report
+            .captures()
+            .iter()
+            .filter(|capture| capture.package() == Some(package))
+            .count();
+        writeln!(stdout, "{package}  {files} file(s)")?;
+    }
+    let unpackaged = report
+        .captures()
+        .iter()
+        .filter(|capture| capture.package().is_none())
+        .count();
+    if unpackaged > 0 {
+        writeln!(stdout, "(no package)  {unpackaged} file(s)")?;
+    }
+    stdout.flush()
+}
+
+/// Files newest kernel first, then in report order.
+fn min_kernel(args: MinKernelArgs) -> io::Result<()> {
+    let mut files = vec![];
+    for path in &args.reports {
+        let report = read_report(path)?;
+        files.extend(report.captures().iter().filter_map(|capture| {
+            let kernel = capture.min_kernel()?;
+            (args.above.is_none_or(|above| kernel > above))
+                .then(|| (kernel, capture.filename().to_string()))
+        }));
+    }
+    files.sort_by_key(|(kernel, _)| Reverse(*kernel));
+
+    let mut stdout = io::stdout().lock();
+    for ([major, minor, subminor], filename) in &files {
+        writeln!(stdout, "{major}.{minor}.{subminor}  {filename}")?;
+    }
+    stdout.flush()
+}
+
+fn parse_kernel(arg: &str) -> io::Result<[u32; 3]> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid kernel version {arg}"),
+        )
+    };
+    let mut version = [0; 3];
+    let mut parts = arg.split('.');
+    for (number, part) in version.iter_mut().zip(parts.by_ref()) {
+        *number = part.parse().map_err(|_| invalid())?;
+    }
+    match parts.next() {
+        Some(_) => Err(invalid()),
+        None => Ok(version),
+    }
+}
+
+fn exporters(args: ExportersArgs) -> io::Result<()> {
+    let (name, version) = match args.symbol.split_once('@') {
+        Some((name, version)) => (name, Some(version.trim_start_matches('@'))),
+        None => (args.symbol.as_str(), None),
+    };
+    let mut stdout = io::stdout().lock();
+    let pattern = SymbolPattern::fuzzy(name, None);
+    let mut exported = false;
+    let mut similar = BTreeSet::new(); // (distance, name) of the exports a typo away
+    for path in &args.reports {
+        let report = read_report(path)?;
+        for capture in report.files_exporting(name, version) {
+            writeln!(stdout, "{}", capture.filename())?;
+            exported = true;
+        }
+        if !exported {
+            similar.extend(
+                report
+                    .find_symbols(&pattern)
+                    .into_iter()
+                    .filter(|found| !found.providers.is_empty() && found.symbol.name != name)
+                    .filter_map(|found| {
+                        let distance = pattern.distance(&found.symbol.name)?;
+                        Some((distance, found.symbol.name))
+                    }),
+            );
+        }
+    }
+    if !exported && !similar.is_empty() {
+        let mut names = similar
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        names.truncate(SUGGESTIONS);
+        eprintln!(
+            "No file exports {}; did you mean {}?",
+            args.symbol,
+            names.join(", ")
+        );
+    }
+    stdout.flush()
+}
+
+fn symbols(args: SymbolsArgs) -> io::Result<()> {
+    let pattern = if args.regex {
+        SymbolPattern::regex(&args.pattern)?
+    } else if args.prefix {
+        SymbolPattern::prefix(&args.pattern)
+    } else if args.substring {
+        SymbolPattern::substring(&args.pattern)
+    } else if args.fuzzy {
+        SymbolPattern::fuzzy(&args.pattern, args.max_distance)
+    } else {
+        SymbolPattern::glob(&args.pattern)?
+    };
+    let matches = read_report(&args.report)?.find_symbols(&pattern);
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        QueryFormat::Text => write_text(&matches, &mut writer)?,
+        QueryFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &matches)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(matches: &[SymbolMatch], mut w: impl Write) -> io::Result<()> {
+    for found in matches {
+        writeln!(w, "{} ({})", found.symbol, found.symbol.kind)?;
+        for (what, files) in [
+            ("provided by", &found.providers),
+            ("used by", &found.consumers),
+        ] {
+            if !files.is_empty() {
+                writeln!(w, "  {what:<12} {}", files.join(", "))?;
+            }
+        }
+    }
+    writeln!(w, "{} matching symbol(s)", matches.len())
+}
@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport resolve`: attribute each import of a report to the library supplying it.
+
+use super::{output_format, output_writer, read_report, write_report, Format};
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Report to annotate (format is detected from its contents)
+    input: PathBuf,
+
+    /// Also look up needed libraries in this report, e.g. of the build root
+    #[arg(long, value_name = "REPORT")]
+    sysroot: Option<PathBuf>,
+
+    /// Where to write the annotated report (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Format to write (default: from the output extension, else JSON)
+    #[arg(long, value_enum)]
+    to: Option<Format>,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let report = read_report(&args.input)?;
+    let sysroot = args.sysroot.as_deref().map(read_report).transpose()?;
+    let report = report.resolve_imports(sysroot.as_ref());
+
+    let format = output_format(args.to, args.output.as_deref(), Format::Json);
+    write_report(&report, format, output_writer(args.output.as_deref())?)
+}
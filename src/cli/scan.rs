@@ -0,0 +1,545 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport scan`: capture the ABI of a set of ELF files.
+
+use super::progress::Progress;
+use super::timings::Timings;
+use super::{
+    output_format, output_writer, read_report, template, write_demangled, write_report, Format,
+};
+use abireport_rs::{
+    AbiCapture, AbiReport, CaptureOrder, CompactReport, ExportPolicy, Limits, PackageMap,
+    PackageRef, ReportFormat, ReportHeader, ScanBuilder, Scanner, StreamFormat, StreamWriter,
+};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Output format (default: from the output extension, else a table)
+    ///
+    /// The streamed formats (jsonl, records) write each capture out as soon as it is parsed.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Render the report through this template (Jinja2 syntax) instead of a format
+    #[arg(long, value_name = "FILE", conflicts_with = "format")]
+    template: Option<PathBuf>,
+
+    /// Where to write serialized reports (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Write one report per package, i.e. per top-level directory of the (single) path
+    /// scanned, into the --output directory, along with an index.json listing them
+    #[arg(
+        long,
+        requires = "output",
+        conflicts_with_all = ["template", "group_by_build_id"]
+    )]
+    per_package: bool,
+
+    /// Where in the output directory each package's report goes, with {package} replaced by
+    /// the package name; the extension picks the format unless --format is given
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = "{package}.json",
+        requires = "per_package"
+    )]
+    layout: String,
+
+    /// Show captures sharing a build-id (binary, debug file, hardlinked copies) as one entry
+    /// (table output only)
+    #[arg(long)]
+    group_by_build_id: bool,
+
+    /// List the exported C++ symbols of each file below the table, demangled and grouped by
+    /// namespace and class (table output only)
+    #[arg(long, conflicts_with = "group_by_build_id")]
+    demangle: bool,
+
+    /// Only capture the dynamic section (sonames, dependencies, rpaths), skipping all symbols
+    #[arg(long)]
+    sonames_only: bool,
+
+    /// Skip files and directories whose path matches this glob (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Don't skip what .abireportignore files in the searched directories exclude
+    #[arg(long)]
+    no_ignore_files: bool,
+
+    /// How to sort the captures in the report (ties go by filename)
+    #[arg(long, value_enum, default_value_t = Order::Filename)]
+    order: Order,
+
+    /// Follow symlinks to files and directories
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Parse this many files in parallel (0: one per CPU)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Which defined symbols count as exports
+    #[arg(long, value_enum, default_value_t = Policy::Default)]
+    export_policy: Policy,
+
+    /// Also capture the defined symbols that aren't exported, listed in a section of their own,
+    /// and the share of .symtab exported (to check visibility annotations)
+    #[arg(long)]
+    audit_hidden: bool,
+
+    /// Also record every entry of the dynamic section, tag and value, with the string of
+    /// string valued ones (to investigate unusual tags later, without the files)
+    #[arg(long)]
+    raw_dynamic: bool,
+
+    /// A report of an earlier scan (with the same options), whose captures are kept for files
+    /// whose contents haven't changed since instead of parsing them again
+    #[arg(long, value_name = "REPORT")]
+    reuse: Option<PathBuf>,
+
+    /// The staging root (DESTDIR) the scanned files are installed into: files under it are
+    /// recorded by their install paths (/usr/lib/...), so scans from other build machines compare
+    #[arg(long, value_name = "DIR", conflicts_with = "per_package")]
+    root: Option<PathBuf>,
+
+    /// Leave out everything depending on the host (input root, mtimes, scan time unless
+    /// SOURCE_DATE_EPOCH is set), so builds on different machines give identical reports
+    #[arg(long)]
+    reproducible: bool,
+
+    /// Record which package each file belongs to, by the first matching `GLOB NAME [VERSION]`
+    /// line of this file
+    #[arg(long, value_name = "FILE")]
+    package_map: Option<PathBuf>,
+
+    /// The package of files no --package-map line matches (or of all files, without one)
+    #[arg(long, value_name = "NAME[@VERSION]", value_parser = PackageRef::parse)]
+    package: Option<PackageRef>,
+
+    /// Only keep symbols matching this glob (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    include_symbols: Vec<String>,
+
+    /// Drop symbols matching this glob (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    exclude_symbols: Vec<String>,
+
+    /// Drop C++ and Rust mangled symbols, keeping only the extern "C" interface
+    #[arg(long)]
+    c_abi_only: bool,
+
+    /// Scan a second time with a different number of jobs, failing unless both scans capture
+    /// exactly the same
+    #[arg(long)]
+    verify_determinism: bool,
+
+    /// Write the report of the files captured even if others could not be (they are listed on
+    /// stderr), instead of failing
+    #[arg(long, conflicts_with = "verify_determinism")]
+    keep_going: bool,
+
+    /// Show a progress bar on stderr (if it is a terminal)
+    #[arg(long)]
+    progress: bool,
+
+    /// Time the capture of each file and list the slowest ones, with their sizes and symbol
+    /// counts, on stderr
+    #[arg(long)]
+    timings: bool,
+
+    /// Skip files larger than this (accepts K/M/G suffixes)
+    #[arg(long, value_parser = parse_size)]
+    max_file_size: Option<u64>,
+
+    /// Skip files with more .dynsym entries than this
+    #[arg(long)]
+    max_dynsyms: Option<usize>,
+
+    /// Give up on a single file after this many seconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Paths to one or more ELF files, or directories to search for them recursively
+    paths: Vec<PathBuf>,
+}
+
+/// CLI spelling of [ExportPolicy]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Policy {
+    /// Global and weak symbols with default visibility
+    Default,
+    /// Also protected symbols
+    IncludeProtected,
+    /// Only global symbols with default visibility (no weak ones)
+    GlobalOnly,
+}
+
+impl From<Policy> for ExportPolicy {
+    fn from(policy: Policy) -> Self {
+        match policy {
+            Policy::Default => Self::Default,
+            Policy::IncludeProtected => Self::IncludeProtected,
+            Policy::GlobalOnly => Self::GlobalOnly,
+        }
+    }
+}
+
+/// CLI spelling of [CaptureOrder]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Order {
+    /// Natural sort order by filename
+    Filename,
+    /// By soname, files without one last
+    Soname,
+    /// By build-id, files without one last
+    BuildId,
+}
+
+impl From<Order> for CaptureOrder {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Filename => Self::Filename,
+            Order::Soname => Self::Soname,
+            Order::BuildId => Self::BuildId,
+        }
+    }
+}
+
+impl Args {
+    fn limits(&self) -> Limits {
+        let defaults = Limits::default();
+        Limits {
+            max_file_size: self.max_file_size.unwrap_or(defaults.max_file_size),
+            max_dynsyms: self.max_dynsyms.unwrap_or(defaults.max_dynsyms),
+            timeout: self.timeout.map(Duration::from_secs),
+            ..defaults
+        }
+    }
+
+    fn package_map(&self) -> io::Result<PackageMap> {
+        let map = match &self.package_map {
+            Some(path) => fs::read_to_string(path)
+                .and_then(|contents| PackageMap::parse(&contents))
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?,
+            None => PackageMap::default(),
+        };
+        Ok(match &self.package {
+            Some(package) => map.with_fallback(package.clone()),
+            None => map,
+        })
+    }
+
+    fn scanner(&self) -> io::Result<Scanner> {
+        let mut builder = ScanBuilder::new()
+            .paths(&self.paths)
+            .ignore_files(!self.no_ignore_files)
+            .order(self.order.into())
+            .follow_symlinks(self.follow_symlinks)
+            .jobs(self.jobs)
+            .export_policy(self.export_policy.into())
+            .audit_hidden(self.audit_hidden)
+            .raw_dynamic(self.raw_dynamic)
+            .c_abi_only(self.c_abi_only)
+            .reproducible(self.reproducible)
+            .limits(self.limits())
+            .sonames_only(self.sonames_only);
+        if let Some(root) = &self.root {
+            builder = builder.root(root);
+        }
+        if let Some(previous) = &self.reuse {
+            builder = builder.reuse(read_report(previous)?);
+        }
+        if self.package_map.is_some() || self.package.is_some() {
+            builder = builder.package_map(self.package_map()?);
+        }
+        for pattern in &self.exclude {
+            builder = builder.exclude(pattern);
+        }
+        for pattern in &self.include_symbols {
+            builder = builder.include_symbols(pattern);
+        }
+        for pattern in &self.exclude_symbols {
+            builder = builder.exclude_symbols(pattern);
+        }
+        builder.build()
+    }
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let scanner = args.scanner()?;
+    let format = output_format(args.format, args.output.as_deref(), Format::Table);
+    let (files, warnings) = scanner.discover_with_warnings();
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+    if args.verify_determinism {
+        // the report written is the one verified rather than that of a third scan
+        let report = scanner.verify_determinism()?;
+        return write_scan(&report, format, &args);
+    }
+
+    let mut capturer = Capturer {
+        scanner,
+        progress: Progress::new(args.progress),
+        timings: Timings::new(args.timings),
+        keep_going: args.keep_going,
+    };
+    capturer.progress.discovered(files.len());
+    let header = capturer.scanner.header(&files);
+    match format {
+        Format::Jsonl if !args.per_package => {
+            capturer.stream(&files, header, StreamFormat::JsonLines, &args)?
+        }
+        Format::Records if !args.per_package => {
+            capturer.stream(&files, header, StreamFormat::Records, &args)?
+        }
+        // written as is, so the symbols can stay interned while the rest of the files are parsed
+        Format::Json | Format::Cbor if !args.per_package && args.template.is_none() => {
+            let report = capturer.capture_compact(&files)?.finish(header);
+            capturer.finish()?;
+            let format = format.report_format().expect("serialized formats");
+            let mut writer = output_writer(args.output.as_deref())?;
+            report.write(&mut writer, format)?;
+            if format == ReportFormat::Json {
+                writeln!(writer)?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        format => {
+            let report = AbiReport::with_header(header, capturer.capture_all(&files));
+            capturer.finish()?;
+            return write_scan(&report, format, &args);
+        }
+    }
+    capturer.finish()
+}
+
+/// Write a scan's report as the arguments say: per package, through a template or in a format.
+fn write_scan(report: &AbiReport, format: Format, args: &Args) -> io::Result<()> {
+    if args.per_package {
+        return write_packages(report, args);
+    }
+    let writer = output_writer(args.output.as_deref())?;
+    if let Some(template) = &args.template {
+        return template::render_report(template, report, writer);
+    }
+    if format == Format::Table && args.group_by_build_id {
+        return write_groups(report, writer);
+    }
+    if format == Format::Table && args.demangle {
+        return write_demangled(report, writer);
+    }
+    if format == Format::Table && args.audit_hidden {
+        return write_hidden(report, writer);
+    }
+    write_report(report, format, writer)
+}
+
+/// Captures files through the scanner, keeping track of progress and timings.
+struct Capturer {
+    scanner: Scanner,
+    progress: Progress,
+    timings: Timings,
+    keep_going: bool, // leave out files that could not be captured rather than fail
+}
+
+impl Capturer {
+    /// Write each capture out as soon as it is parsed, never holding the whole report.
+    fn stream(
+        &mut self,
+        files: &[PathBuf],
+        header: ReportHeader,
+        format: StreamFormat,
+        args: &Args,
+    ) -> io::Result<()> {
+        let writer = output_writer(args.output.as_deref())?;
+        let mut stream = StreamWriter::new(writer, format, &header)?;
+        let mut written = Ok(());
+        self.scanner
+            .capture_each_timed(files, |file, result, elapsed| {
+                self.timings.record(file, &result, elapsed);
+                if let Some(capture) = track(&mut self.progress, file, result) {
+                    if written.is_ok() {
+                        written = stream.write_capture(&capture);
+                    }
+                }
+            });
+        written?;
+        stream.finish().map(|_| ())
+    }
+
+    /// Fails if any file could not be captured, unless told to keep going. Streamed reports
+    /// have been written by then, without those files.
+    fn finish(&mut self) -> io::Result<()> {
+        self.progress.finish();
+        self.timings.finish();
+        match self.progress.failures() {
+            0 => Ok(()),
+            failures if self.keep_going => {
+                eprintln!("{failures} file(s) could not be captured");
+                Ok(())
+            }
+            failures => Err(io::Error::other(format!(
+                "{failures} file(s) could not be captured (--keep-going leaves them out of the \
+                 report instead)"
+            ))),
+        }
+    }
+
+    fn capture_compact(&mut self, files: &[PathBuf]) -> io::Result<CompactReport> {
+        let mut report = CompactReport::new();
+        let mut interned = Ok(());
+        self.scanner
+            .capture_each_timed(files, |file, result, elapsed| {
+                self.timings.record(file, &result, elapsed);
+                if let Some(capture) = track(&mut self.progress, file, result) {
+                    if interned.is_ok() {
+                        interned = report.push(capture);
+                    }
+                }
+            });
+        interned.map(|_| report)
+    }
+
+    fn capture_all(&mut self, files: &[PathBuf]) -> Vec<AbiCapture> {
+        let mut captures = Vec::with_capacity(files.len());
+        self.scanner
+            .capture_each_timed(files, |file, result, elapsed| {
+                self.timings.record(file, &result, elapsed);
+                captures.extend(track(&mut self.progress, file, result));
+            });
+        captures
+    }
+}
+
+/// Report the outcome of a single capture, passing on successful ones.
+fn track(
+    progress: &mut Progress,
+    file: &Path,
+    result: io::Result<AbiCapture>,
+) -> Option<AbiCapture> {
+    match result {
+        Ok(capture) => {
+            if capture.warnings().is_empty() {
+                progress.parsed();
+            } else {
+                progress.partial(file.display(), capture.warnings());
+            }
+            Some(capture)
+        }
+        Err(err) => {
+            progress.failed(file.display(), err);
+            None
+        }
+    }
+}
+
+/// The index.json of a --per-package scan
+#[derive(Serialize)]
+struct PackageIndex<'a> {
+    packages: Vec<IndexEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    name: &'a str,
+    report: String, // relative to the index
+    captures: usize,
+    digest: &'a str, // of the package report's captures
+}
+
+/// Write the report of each package where the layout says, then the index of them all.
+fn write_packages(report: &AbiReport, args: &Args) -> io::Result<()> {
+    let [root] = args.paths.as_slice() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--per-package needs a single directory of packages to scan",
+        ));
+    };
+    let output = args.output.as_deref().expect("required by --per-package");
+    fs::create_dir_all(output)?;
+    let packages = report.partition_by_package(&root.to_string_lossy());
+
+    let mut index = PackageIndex { packages: vec![] };
+    for (name, package) in &packages {
+        let relative = args.layout.replace("{package}", name);
+        let path = output.join(&relative);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let format = output_format(args.format, Some(&path), Format::Json);
+        write_report(package, format, output_writer(Some(&path))?)?;
+        index.packages.push(IndexEntry {
+            name,
+            report: relative,
+            captures: package.captures().len(),
+            digest: &package.header().digest,
+        });
+    }
+    let mut writer = output_writer(Some(&output.join("index.json")))?;
+    serde_json::to_writer_pretty(&mut writer, &index)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Write one summary per build-id, listing all of the paths it was found under.
+fn write_groups(report: &AbiReport, mut writer: impl Write) -> io::Result<()> {
+    for group in report.group_by_build_id() {
+        match group.build_id {
+            Some(build_id) => writeln!(writer, "build-id {build_id}:")?,
+            None => writeln!(writer, "(no build-id):")?,
+        }
+        for path in group.paths() {
+            writeln!(writer, "\t{path}")?;
+        }
+        writeln!(writer, "{}", group.primary())?;
+    }
+    writer.flush()
+}
+
+/// Write the report table, followed by the hidden symbols of each file that has any.
+fn write_hidden(report: &AbiReport, mut writer: impl Write) -> io::Result<()> {
+    write!(writer, "{report}")?;
+    for capture in report.captures() {
+        if capture.hidden_symbols().is_empty() {
+            continue;
+        }
+        writeln!(
+            writer,
+            "\nhidden in {} ({} symbols):",
+            capture.filename(),
+            capture.hidden_symbols().len()
+        )?;
+        for symbol in capture.hidden_symbols() {
+            writeln!(writer, "\t{symbol}")?;
+        }
+    }
+    writer.flush()
+}
+
+/// Parse a byte size with an optional K/M/G (binary) suffix, e.g. "512M".
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (digits, shift) = match s.trim().to_ascii_uppercase() {
+        s if s.ends_with('K') => (s.trim_end_matches('K').to_string(), 10),
+        s if s.ends_with('M') => (s.trim_end_matches('M').to_string(), 20),
+        s if s.ends_with('G') => (s.trim_end_matches('G').to_string(), 30),
+        s => (s, 0),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size {s:?}"))
+}
@@ -0,0 +1,42 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport schema`: the JSON Schema of the report or diff format, for validating documents
+//! and generating bindings elsewhere.
+
+use super::output_writer;
+use abireport_rs::{diff_schema, report_schema};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Which document format to describe
+    #[arg(value_enum, default_value_t = Document::Report)]
+    document: Document,
+
+    /// Where to write the schema (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Document {
+    /// Reports, as written by `scan` in JSON
+    Report,
+    /// Diffs, as written by `diff --format json`
+    Diff,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let schema = match args.document {
+        Document::Report => report_schema(),
+        Document::Diff => diff_schema(),
+    };
+    let mut writer = output_writer(args.output.as_deref())?;
+    serde_json::to_writer_pretty(&mut writer, &schema)?;
+    writeln!(writer)?;
+    writer.flush()
+}
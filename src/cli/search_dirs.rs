@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport search-dirs`: the directories the RPATHs and RUNPATHs of an install root name.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{LibrarySearch, ScanBuilder, SearchDir, SearchDirStatus};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The install root, e.g. a staging root or mounted image
+    root: PathBuf,
+
+    /// A report of the root (any format; default: a scan of it)
+    #[arg(long, value_name = "REPORT")]
+    db: Option<PathBuf>,
+
+    /// Only list the directories missing from the root
+    #[arg(long)]
+    missing: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = SearchDirsFormat::Text)]
+    format: SearchDirsFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SearchDirsFormat {
+    /// Human readable, one entry per directory with the files naming it
+    Text,
+    /// The directories as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let report = match &args.db {
+        Some(db) => read_report(db)?,
+        None => ScanBuilder::new()
+            .path(&args.root)
+            .root(&args.root)
+            .sonames_only(true)
+            .jobs(0)
+            .scan()?,
+    };
+    let mut dirs = LibrarySearch::new(&args.root).search_dirs(&report);
+    if args.missing {
+        dirs.retain(|dir| dir.status == SearchDirStatus::Missing);
+    }
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        SearchDirsFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&dirs, &palette, &mut writer)?
+        }
+        SearchDirsFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &dirs)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(dirs: &[SearchDir], palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    for dir in dirs {
+        let status = match dir.status {
+            SearchDirStatus::Present => dir.status.to_string(),
+            SearchDirStatus::Missing => palette.removed(dir.status).to_string(),
+            _ => palette.changed(dir.status).to_string(),
+        };
+        let rules = dir.rules.iter().map(|rule| rule.to_string());
+        writeln!(
+            w,
+            "{} ({status}, {})",
+            palette.heading(&dir.path),
+            rules.collect::<Vec<_>>().join(", ")
+        )?;
+        for file in &dir.files {
+            writeln!(w, "  {file}")?;
+        }
+    }
+    let missing = dirs
+        .iter()
+        .filter(|dir| dir.status == SearchDirStatus::Missing)
+        .count();
+    writeln!(
+        w,
+        "{} directories, {} missing",
+        dirs.len(),
+        palette.removed(missing)
+    )
+}
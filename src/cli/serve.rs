@@ -0,0 +1,261 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport serve`: answer queries about a report over a unix socket.
+//!
+//! The report is loaded and indexed once, so other services can ask a warm process instead of
+//! spawning the CLI per question. The protocol is JSON Lines: each request is one JSON object
+//! on a line of its own, answered by one line holding either `{"result": ...}` or
+//! `{"error": "..."}`. A request line longer than 4 MiB gets an error and ends the connection.
+//! The socket is only accessible to the user running the server. Requests:
+//!
+//! - `{"query": "who_provides", "soname": "libssl.so.3"}`
+//! - `{"query": "who_provides", "symbol": "SSL_new", "version": "OPENSSL_3.0.0"}` (the version
+//!   is optional)
+//! - `{"query": "who_needs", ...}`, taking the same arguments
+//! - `{"query": "symbols", "pattern": "SSL_*"}` (glob; add `"regex": true` for a regex, or
+//!   `"fuzzy": true` for the names a few typos away, closest first)
+//! - `{"query": "diff", "report": "new.json"}`, diffing the served report against another one.
+//!   Only reports in the directory given with `--diff-dir` (relative to it, or absolute but
+//!   within it) can be named, and never URLs: clients shouldn't get to read arbitrary files or
+//!   make the server fetch things.
+
+use super::{fetch, parse_report, read_report};
+use abireport_rs::{AbiCapture, AbiReport, ReportIndex, SymbolPattern};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{self, Permissions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// The longest request line read, so a client can't make the server buffer without end
+const MAX_REQUEST: u64 = 4 << 20;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report to serve (any format)
+    #[arg(long)]
+    db: PathBuf,
+
+    /// Where to listen
+    #[arg(long, default_value = "abireport.sock")]
+    socket: PathBuf,
+
+    /// The directory holding the reports diff queries may name (without it, they are refused)
+    #[arg(long, value_name = "DIR")]
+    diff_dir: Option<PathBuf>,
+}
+
+/// What the client threads share
+struct Server {
+    index: ReportIndex,
+    diff_dir: Option<PathBuf>, // canonical
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "query", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    WhoProvides(Target),
+    WhoNeeds(Target),
+    Symbols {
+        pattern: String,
+        #[serde(default)]
+        regex: bool,
+        #[serde(default)]
+        fuzzy: bool,
+    },
+    Diff {
+        report: PathBuf,
+    },
+}
+
+/// What a who_* query is about: a soname, or a symbol (optionally of one version only)
+#[derive(Debug, Deserialize)]
+struct Target {
+    soname: Option<String>,
+    symbol: Option<String>,
+    version: Option<String>,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let diff_dir = args
+        .diff_dir
+        .as_deref()
+        .map(|dir| {
+            fs::canonicalize(dir)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", dir.display())))
+        })
+        .transpose()?;
+    let server = Arc::new(Server {
+        index: ReportIndex::new(read_report(&args.db)?),
+        diff_dir,
+    });
+    let listener = bind(&args.socket)?;
+    eprintln!(
+        "Serving {} captures from {} on {}",
+        server.index.report().captures().len(),
+        args.db.display(),
+        args.socket.display()
+    );
+
+    for stream in listener.incoming() {
+        // one failed accept (out of file descriptors, a client giving up) isn't the end of it
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Could not accept a connection: {err}");
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            if let Err(err) = serve_client(&server, stream) {
+                eprintln!("Client connection failed: {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Bind the socket, taking over a stale one left behind by a previous instance, for the
+/// current user only rather than whoever the umask lets in.
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    let listener = match UnixListener::bind(path) {
+        Err(err) if err.kind() == ErrorKind::AddrInUse => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("{} is in use by a running server", path.display()),
+                ));
+            }
+            fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }?;
+    fs::set_permissions(path, Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+fn serve_client(server: &Server, stream: UnixStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.by_ref().take(MAX_REQUEST).read_line(&mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if read as u64 == MAX_REQUEST && !line.ends_with('\n') {
+            let err = format!("requests are limited to {} MiB", MAX_REQUEST >> 20);
+            serde_json::to_writer(&mut writer, &json!({ "error": err }))?;
+            writeln!(writer)?;
+            return Err(io::Error::new(ErrorKind::InvalidData, err));
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match answer(server, &line) {
+            Ok(result) => json!({ "result": result }),
+            Err(err) => json!({ "error": err.to_string() }),
+        };
+        serde_json::to_writer(&mut writer, &response)?;
+        writeln!(writer)?;
+    }
+}
+
+fn answer(server: &Server, line: &str) -> io::Result<Value> {
+    let index = &server.index;
+    let request = serde_json::from_str::<Request>(line)
+        .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+    Ok(match request {
+        Request::WhoProvides(target) => filenames(match target.subject()? {
+            Subject::Soname(soname) => index.soname_providers(soname),
+            Subject::Symbol(name, version) => index.symbol_providers(name, version),
+        }),
+        Request::WhoNeeds(target) => filenames(match target.subject()? {
+            Subject::Soname(soname) => index.soname_dependents(soname),
+            Subject::Symbol(name, version) => index.symbol_consumers(name, version),
+        }),
+        Request::Symbols {
+            pattern,
+            regex,
+            fuzzy,
+        } => {
+            let pattern = match (regex, fuzzy) {
+                (true, _) => SymbolPattern::regex(&pattern)?,
+                (false, true) => SymbolPattern::fuzzy(&pattern, None),
+                (false, false) => SymbolPattern::glob(&pattern)?,
+            };
+            serde_json::to_value(index.report().find_symbols(&pattern))?
+        }
+        Request::Diff { report } => {
+            let new = server.diff_report(&report)?;
+            serde_json::to_value(index.report().diff(&new))?
+        }
+    })
+}
+
+impl Server {
+    /// Read the report a diff query names, if it is a file in the diff directory.
+    fn diff_report(&self, report: &Path) -> io::Result<AbiReport> {
+        let Some(dir) = &self.diff_dir else {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                "diff queries are disabled (see --diff-dir)",
+            ));
+        };
+        if fetch::as_url(report).is_some() {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                "diff queries can only name local files",
+            ));
+        }
+        // canonical, so neither `..` nor symlinks lead out of the directory
+        let path = fs::canonicalize(dir.join(report))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", report.display())))?;
+        if !path.starts_with(dir) {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} is not in the diff directory", report.display()),
+            ));
+        }
+        let data = fs::read(&path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", report.display())))?;
+        parse_report(report, data)
+    }
+}
+
+enum Subject<'a> {
+    Soname(&'a str),
+    Symbol(&'a str, Option<&'a str>),
+}
+
+impl Target {
+    fn subject(&self) -> io::Result<Subject<'_>> {
+        match (&self.soname, &self.symbol) {
+            (Some(soname), None) => Ok(Subject::Soname(soname)),
+            (None, Some(symbol)) => Ok(Subject::Symbol(symbol, self.version.as_deref())),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "expected either a soname or a symbol",
+            )),
+        }
+    }
+}
+
+fn filenames(captures: Vec<&AbiCapture>) -> Value {
+    captures
+        .iter()
+        .map(|capture| capture.filename())
+        .collect::<Vec<_>>()
+        .into()
+}
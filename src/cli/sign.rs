@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport sign`: sign a serialized report, writing a minisign compatible signature.
+
+use abireport_rs::sign::SecretKey;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report (or any other file) to sign
+    input: PathBuf,
+
+    /// The secret key to sign with, see `abireport keygen`
+    #[arg(short, long, default_value = "abireport.key")]
+    secret_key: PathBuf,
+
+    /// Where to write the signature (default: the input with .minisig appended)
+    #[arg(short = 'x', long)]
+    signature: Option<PathBuf>,
+
+    /// Trusted comment to sign along (default: the signing time and file name)
+    #[arg(short, long)]
+    trusted_comment: Option<String>,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let secret =
+        SecretKey::from_minisign(&fs::read_to_string(&args.secret_key)?).map_err(|err| {
+            io::Error::new(err.kind(), format!("{}: {err}", args.secret_key.display()))
+        })?;
+    let data = fs::read(&args.input)?;
+    let trusted_comment = args.trusted_comment.unwrap_or_else(|| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let file_name = args.input.file_name().unwrap_or_default().to_string_lossy();
+        format!("timestamp:{timestamp}\tfile:{file_name}\thashed")
+    });
+
+    let signature = secret.sign(&data, &trusted_comment)?;
+    let path = args
+        .signature
+        .unwrap_or_else(|| super::verify::signature_path(&args.input));
+    fs::write(path, signature.to_minisign())
+}
@@ -0,0 +1,64 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport split`: suggest which files of an install root belong in -devel.
+
+use super::output_writer;
+use abireport_rs::{InstallTree, SplitSuggestion, Subpackage};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The staging root the package was installed into
+    root: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = SplitFormat::Text)]
+    format: SplitFormat,
+
+    /// Where to write the suggestions (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SplitFormat {
+    /// Human readable, grouped by subpackage
+    Text,
+    /// One entry per file as JSON
+    Json,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let suggestions = InstallTree::walk(&args.root)?.suggest_split();
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        SplitFormat::Text => write_text(&suggestions, &mut writer)?,
+        SplitFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &suggestions)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(suggestions: &[SplitSuggestion], mut w: impl Write) -> io::Result<()> {
+    for subpackage in [Subpackage::Runtime, Subpackage::Devel] {
+        let files = suggestions
+            .iter()
+            .filter(|suggestion| suggestion.subpackage == subpackage)
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            continue;
+        }
+        writeln!(w, "{subpackage}:")?;
+        for suggestion in files {
+            writeln!(w, "  {} ({})", suggestion.path, suggestion.reason)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport stats`: summarize a report.
+
+use super::{output_writer, read_report};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report to summarize
+    report: PathBuf,
+
+    /// How many entries to show in each ranking
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = StatsFormat::Text)]
+    format: StatsFormat,
+
+    /// Where to write the summary (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum StatsFormat {
+    /// Human readable
+    Text,
+    /// The statistics as JSON, e.g. for dashboards
+    Json,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let stats = read_report(&args.report)?.stats(args.top);
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        StatsFormat::Text => write!(writer, "{stats}")?,
+        StatsFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &stats)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! User provided output templates.
+//!
+//! Templates use Jinja2 syntax (rendered by minijinja) and see the same data as the JSON output:
+//!
+//! - reports: `header` and `captures`
+//! - diffs: `changes`, `summary` and `breaking`
+//!
+//! Fields the JSON output leaves out when empty (e.g. a capture's `warnings`, or the `exports` of
+//! a diff) are undefined, so use `|default([])` before e.g. `|length`. Templates named `*.html`
+//! or `*.xml` have their output HTML escaped.
+//!
+//! The `html` formats are built-in templates of the same kind.
+
+use abireport_rs::diff::ReportDiff;
+use abireport_rs::AbiReport;
+use minijinja::{context, Environment, UndefinedBehavior, Value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Render the template at `path` for a report.
+pub fn render_report(path: &Path, report: &AbiReport, writer: impl Write) -> io::Result<()> {
+    render(path, report, writer)
+}
+
+/// Render the template at `path` for a diff.
+pub fn render_diff(path: &Path, diff: &ReportDiff, writer: impl Write) -> io::Result<()> {
+    render(path, diff_context(diff), writer)
+}
+
+/// A standalone, searchable page with the symbols and dependencies of every file.
+pub fn html_report(report: &AbiReport, writer: impl Write) -> io::Result<()> {
+    // link up DT_NEEDED entries with the files providing them, both ways
+    let mut providers = BTreeMap::new();
+    let mut consumers = BTreeMap::<_, Vec<_>>::new();
+    for capture in report.captures() {
+        if let Some(soname) = capture.soname() {
+            providers.entry(soname).or_insert(capture.filename());
+        }
+        for needed in capture.needed_deps() {
+            consumers
+                .entry(needed.as_str())
+                .or_default()
+                .push(capture.filename());
+        }
+    }
+    let context = context! {
+        header => report.header(),
+        captures => report.captures(),
+        providers,
+        consumers,
+    };
+    let source = include_str!("templates/report.html");
+    render_source("report.html", source, context, writer)
+}
+
+/// A standalone page highlighting the changes between two reports.
+pub fn html_diff(diff: &ReportDiff, writer: impl Write) -> io::Result<()> {
+    let source = include_str!("templates/diff.html");
+    render_source("diff.html", source, diff_context(diff), writer)
+}
+
+fn diff_context(diff: &ReportDiff) -> Value {
+    context! {
+        changes => &diff.changes,
+        summary => diff.summary(),
+        breaking => diff.is_breaking(),
+    }
+}
+
+fn render(path: &Path, context: impl Serialize, writer: impl Write) -> io::Result<()> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    render_source(&name, &source, context, writer)
+}
+
+fn render_source(
+    name: &str,
+    source: &str,
+    context: impl Serialize,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let mut env = Environment::new();
+    // block tags on lines of their own shouldn't leave blank lines behind
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    // empty fields are left out of the data, so `change.exports.removed` mustn't be an error
+    env.set_undefined_behavior(UndefinedBehavior::Chainable);
+    env.add_template(name, source).map_err(template_error)?;
+
+    let template = env.get_template(name).map_err(template_error)?;
+    let rendered = template.render(context).map_err(template_error)?;
+    writer.write_all(rendered.as_bytes())?;
+    writer.flush()
+}
+
+fn template_error(err: minijinja::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+}
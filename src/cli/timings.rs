@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Opt-in per-file timings of a scan, summarized on stderr to find the slowest inputs.
+
+use abireport_rs::AbiCapture;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How many of the slowest files the summary lists
+const SLOWEST: usize = 10;
+
+/// What it took to capture one file
+struct FileTiming {
+    path: PathBuf,
+    elapsed: Duration,      // reading and parsing
+    size: u64,              // of the file, 0 if unknown
+    symbols: Option<usize>, // imports and exports, None if the capture failed
+}
+
+/// The timings of the files captured so far; does nothing unless enabled.
+pub struct Timings {
+    enabled: bool,
+    files: Vec<FileTiming>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            files: vec![],
+        }
+    }
+
+    pub fn record(&mut self, path: &Path, result: &io::Result<AbiCapture>, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let capture = result.as_ref().ok();
+        let size = capture
+            .and_then(|capture| capture.metadata())
+            .map(|metadata| metadata.size)
+            .or_else(|| fs::metadata(path).ok().map(|metadata| metadata.len()))
+            .unwrap_or_default();
+        self.files.push(FileTiming {
+            path: path.to_path_buf(),
+            elapsed,
+            size,
+            symbols: capture
+                .map(|capture| capture.dynsym_imports().len() + capture.dynsym_exports().len()),
+        });
+    }
+
+    /// Write the totals and the slowest files to stderr. The total is the time spent in
+    /// captures, summed over all jobs, so it exceeds the wall clock time with several.
+    pub fn finish(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let total = self.files.iter().map(|file| file.elapsed).sum::<Duration>();
+        let size = self.files.iter().map(|file| file.size).sum::<u64>();
+        let throughput = match total.is_zero() {
+            true => 0.0,
+            false => mib(size) / total.as_secs_f64(),
+        };
+        eprintln!(
+            "Captured {} file(s), {:.1} MiB, in {:.3}s ({throughput:.1} MiB/s)",
+            self.files.len(),
+            mib(size),
+            total.as_secs_f64()
+        );
+
+        self.files
+            .sort_by_key(|file| std::cmp::Reverse(file.elapsed));
+        if !self.files.is_empty() {
+            eprintln!("Slowest files:");
+        }
+        for file in self.files.iter().take(SLOWEST) {
+            let symbols = match file.symbols {
+                Some(symbols) => format!("{symbols} symbols"),
+                None => "failed".to_string(),
+            };
+            eprintln!(
+                "  {:>8.3}s {:>9.1} MiB {symbols:>15}  {}",
+                file.elapsed.as_secs_f64(),
+                mib(file.size),
+                file.path.display()
+            );
+        }
+    }
+}
+
+fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport transition`: what moving a repository to a new soname of a library takes.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::{parse_elf_with, Limits, ReportIndex, SonameTransition};
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A report of the whole repository (any format)
+    #[arg(long)]
+    db: PathBuf,
+
+    /// The soname being replaced (by default the latest other one of the same library)
+    #[arg(long, value_name = "SONAME")]
+    old_soname: Option<String>,
+
+    /// The new library (an ELF file)
+    library: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = TransitionFormat::Text)]
+    format: TransitionFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TransitionFormat {
+    /// Human readable: the consumers to port, then the rebuild waves
+    Text,
+    /// The transition plan as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let index = ReportIndex::new(read_report(&args.db)?);
+    let library = parse_elf_with(&args.library.to_string_lossy(), &Limits::default())?;
+    let transition = index
+        .soname_transition(&library, args.old_soname.as_deref())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{}: no other soname of {} has consumers, pass --old-soname",
+                    args.db.display(),
+                    args.library.display()
+                ),
+            )
+        })?;
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        TransitionFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&transition, &palette, &mut writer)?
+        }
+        TransitionFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &transition)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(plan: &SonameTransition, palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    writeln!(
+        w,
+        "{}",
+        palette.heading(format!("{} -> {}", plan.old_soname, plan.new_soname))
+    )?;
+    if plan.old_library.is_none() {
+        writeln!(
+            w,
+            "  {} isn't in the report, so no imports are known to break",
+            palette.changed(&plan.old_soname)
+        )?;
+    }
+    for port in &plan.port {
+        writeln!(w, "  port {}", palette.removed(&port.filename))?;
+        for symbol in &port.broken_imports {
+            writeln!(w, "    {}", palette.removed(format!("- import {symbol}")))?;
+        }
+    }
+    for (idx, wave) in plan.waves.iter().enumerate() {
+        writeln!(w, "  wave {}: {}", idx + 1, wave.join(" "))?;
+    }
+    writeln!(
+        w,
+        "{} consumer(s): {} need porting, {} only a rebuild, in {} wave(s)",
+        plan.rebuild.len() + plan.port.len(),
+        palette.removed(plan.port.len()),
+        palette.changed(plan.rebuild.len()),
+        plan.waves.len()
+    )
+}
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport usage`: how many symbols each needed library actually supplies.
+
+use super::color::{ColorChoice, Palette};
+use super::{output_writer, read_report};
+use abireport_rs::DependencyUsage;
+use clap::ValueEnum;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report to look at (any format)
+    report: PathBuf,
+
+    /// Also look up needed libraries in this report, e.g. of the build root
+    #[arg(long, value_name = "REPORT")]
+    sysroot: Option<PathBuf>,
+
+    /// Only list needed libraries supplying no symbols at all
+    #[arg(long)]
+    unused: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = UsageFormat::Text)]
+    format: UsageFormat,
+
+    /// Where to write the results (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum UsageFormat {
+    /// Human readable, grouped by file
+    Text,
+    /// One entry per file and needed library as JSON
+    Json,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    let report = read_report(&args.report)?;
+    let sysroot = args.sysroot.as_deref().map(read_report).transpose()?;
+    let mut usage = report.dependency_usage(sysroot.as_ref());
+    if args.unused {
+        usage.retain(DependencyUsage::is_unused);
+    }
+
+    let mut writer = output_writer(args.output.as_deref())?;
+    match args.format {
+        UsageFormat::Text => {
+            let palette = Palette::new(color, args.output.is_some());
+            write_text(&usage, &palette, &mut writer)?
+        }
+        UsageFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &usage)?;
+            writeln!(writer)?
+        }
+    }
+    writer.flush()
+}
+
+fn write_text(usage: &[DependencyUsage], palette: &Palette, mut w: impl Write) -> io::Result<()> {
+    let mut filename = None;
+    for entry in usage {
+        if filename != Some(&entry.filename) {
+            writeln!(w, "{}", palette.heading(&entry.filename))?;
+            filename = Some(&entry.filename);
+        }
+        match entry.symbols {
+            Some(0) => writeln!(w, "  {:>6}  {}", 0, palette.changed(&entry.library))?,
+            Some(symbols) => writeln!(w, "  {symbols:>6}  {}", entry.library)?,
+            None => writeln!(w, "  {:>6}  {} (not in the report)", "?", entry.library)?,
+        }
+    }
+
+    let unused = usage.iter().filter(|entry| entry.is_unused()).count();
+    writeln!(
+        w,
+        "{} needed libraries supply no symbols",
+        palette.changed(unused)
+    )
+}
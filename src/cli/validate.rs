@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport validate`: check that a file is a report or diff abireport can read.
+//!
+//! Documents are checked by reading them with the types the schemas of `abireport schema` are
+//! derived from, so anything passing here matches the published schema (reports after
+//! migrating them to the current schema version).
+
+use super::schema::Document;
+use super::{fetch, read_input};
+use abireport_rs::diff::ReportDiff;
+use abireport_rs::AbiReport;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The report (any format) or JSON diff to check
+    input: PathBuf,
+
+    /// What the file has to be (by default either)
+    #[arg(long, value_enum)]
+    document: Option<Document>,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let data = fetch::decompress(read_input(&args.input)?)?;
+    let invalid = |err: io::Error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {err}", args.input.display()),
+        )
+    };
+    let report = || AbiReport::read(data.as_slice());
+    let diff = || serde_json::from_slice::<ReportDiff>(&data).map_err(io::Error::from);
+
+    let found = match args.document {
+        Some(Document::Report) => report().map(|_| Document::Report).map_err(invalid)?,
+        Some(Document::Diff) => diff().map(|_| Document::Diff).map_err(invalid)?,
+        // a report carries a header, so its errors are the informative ones
+        None => match (report(), diff()) {
+            (Ok(_), _) => Document::Report,
+            (Err(_), Ok(_)) => Document::Diff,
+            (Err(err), Err(_)) => return Err(invalid(err)),
+        },
+    };
+    match found {
+        Document::Report => println!("{}: valid report", args.input.display()),
+        Document::Diff => println!("{}: valid diff", args.input.display()),
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport verify`: check the signature of a report before trusting it.
+
+use super::read_input;
+use abireport_rs::sign::{PublicKey, Signature};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The signed report (or any other file)
+    input: PathBuf,
+
+    /// The public key file to verify with
+    #[arg(short, long, value_name = "FILE", default_value = "abireport.pub")]
+    public_key: PathBuf,
+
+    /// The signature (default: the input with .minisig appended)
+    #[arg(short = 'x', long)]
+    signature: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> io::Result<()> {
+    let key = read_public_key(&args.public_key)?;
+    let signature = args
+        .signature
+        .unwrap_or_else(|| signature_path(&args.input));
+    let (_, trusted_comment) = read_verified(&args.input, &key, &signature)?;
+    println!("Signature and comment signature verified");
+    println!("Trusted comment: {trusted_comment}");
+    Ok(())
+}
+
+/// Where the signature of a file goes by default: next to it, with `.minisig` appended.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature = OsString::from(path.as_os_str());
+    signature.push(".minisig");
+    PathBuf::from(signature)
+}
+
+pub fn read_public_key(path: &Path) -> io::Result<PublicKey> {
+    PublicKey::from_minisign(&fs::read_to_string(path)?)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+}
+
+/// Read a file (or URL) and check its signature, returning its contents (as verified, so they
+/// can't change in between) and the trusted comment.
+pub fn read_verified(
+    path: &Path,
+    key: &PublicKey,
+    signature: &Path,
+) -> io::Result<(Vec<u8>, String)> {
+    let data = read_input(path)?;
+    let signature = Signature::from_minisign(&String::from_utf8_lossy(&read_input(signature)?))
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", signature.display())))?;
+    let trusted_comment = key
+        .verify(&data, &signature)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?
+        .to_string();
+    Ok((data, trusted_comment))
+}
@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `abireport watch`: re-capture ELF files as they change and print what changed.
+//!
+//! Meant for iterating on a library: leave it running on the build directory and see exports
+//! appear and disappear as you rebuild. Changes are batched until the tree has been quiet for
+//! the debounce period, so a linker writing a file in several steps only shows up once.
+
+use super::color::{ColorChoice, Palette};
+use super::diff::write_capture_diff;
+use abireport_rs::diff::CaptureDiff;
+use abireport_rs::{AbiCapture, ScanBuilder, Scanner};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Skip files and directories whose path matches this glob (may be repeated)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Parse this many files in parallel for the initial capture (0: one per CPU)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Wait for this many milliseconds without changes before re-capturing
+    #[arg(long, default_value_t = 200)]
+    debounce: u64,
+
+    /// ELF files, or directories to watch recursively
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+/// What is being watched, and the captures as of the last change
+struct State {
+    scanner: Scanner,
+    dirs: Vec<PathBuf>,  // watched recursively
+    files: Vec<PathBuf>, // watched through their parent directory, which survives replacing them
+    captures: HashMap<PathBuf, AbiCapture>,
+}
+
+pub fn run(args: Args, color: ColorChoice) -> io::Result<()> {
+    // events name absolute paths, so capture under absolute paths too
+    let paths = args
+        .paths
+        .iter()
+        .map(std::path::absolute)
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut builder = ScanBuilder::new().paths(&paths).jobs(args.jobs);
+    for pattern in &args.exclude {
+        builder = builder.exclude(pattern);
+    }
+    let (dirs, files) = paths.into_iter().partition(|path| path.is_dir());
+    let mut state = State {
+        scanner: builder.build()?,
+        dirs,
+        files,
+        captures: HashMap::new(),
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+    for dir in &state.dirs {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(io::Error::other)?;
+    }
+    for file in &state.files {
+        let parent = file.parent().unwrap_or(Path::new("/"));
+        watcher
+            .watch(parent, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+    }
+
+    let (files, warnings) = state.scanner.discover_with_warnings();
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+    state
+        .scanner
+        .capture_each(&files, |path, result| match result {
+            Ok(capture) => {
+                state.captures.insert(path.to_path_buf(), capture);
+            }
+            Err(err) => eprintln!("{}: {err}", path.display()),
+        });
+    eprintln!("Watching {} ELF file(s) for changes", state.captures.len());
+
+    let palette = Palette::new(color, false);
+    let debounce = Duration::from_millis(args.debounce);
+    while let Ok(event) = receiver.recv() {
+        // collect everything up to the first quiet period
+        let mut changed = BTreeSet::new();
+        let mut event = event;
+        loop {
+            match event {
+                Ok(event) if !event.kind.is_access() => changed.extend(event.paths),
+                Ok(_) => {}
+                Err(err) => eprintln!("Watching failed: {err}"),
+            }
+            match receiver.recv_timeout(debounce) {
+                Ok(next) => event = next,
+                Err(_) => break,
+            }
+        }
+        state.update(changed, &palette, &mut io::stdout().lock())?;
+    }
+    Ok(())
+}
+
+impl State {
+    fn update(
+        &mut self,
+        changed: BTreeSet<PathBuf>,
+        palette: &Palette,
+        w: &mut impl Write,
+    ) -> io::Result<()> {
+        for path in changed {
+            if !path.exists() {
+                self.remove(&path, palette, w)?;
+            } else if path.is_file() && self.watches(&path) {
+                self.recapture(path, palette, w)?;
+            }
+        }
+        w.flush()
+    }
+
+    fn watches(&self, path: &Path) -> bool {
+        self.files.iter().any(|file| file == path)
+            || (self.dirs.iter().any(|dir| path.starts_with(dir)) && self.scanner.wants(path))
+    }
+
+    fn recapture(
+        &mut self,
+        path: PathBuf,
+        palette: &Palette,
+        w: &mut impl Write,
+    ) -> io::Result<()> {
+        let new = match self.scanner.capture(&path) {
+            Ok(new) => new,
+            // most likely still being written; the next change will bring it up again
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                return Ok(());
+            }
+        };
+        let diff = match self.captures.get(&path) {
+            Some(old) => old.diff(&new),
+            None => CaptureDiff::added(&new),
+        };
+        if !diff.is_empty() {
+            write_capture_diff(&diff, palette, w)?;
+        }
+        self.captures.insert(path, new);
+        Ok(())
+    }
+
+    /// Forget a file, or everything under a directory, that went away.
+    fn remove(&mut self, path: &Path, palette: &Palette, w: &mut impl Write) -> io::Result<()> {
+        let mut gone = self
+            .captures
+            .keys()
+            .filter(|file| file.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        gone.sort();
+        for file in gone {
+            let old = self.captures.remove(&file).expect("key was just listed");
+            write_capture_diff(&CaptureDiff::removed(&old), palette, w)?;
+        }
+        Ok(())
+    }
+}
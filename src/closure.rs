@@ -0,0 +1,535 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! The libraries an executable loads, found the way the dynamic linker finds them.
+//!
+//! Each DT_NEEDED entry is looked up in order: as a path if it contains a slash, else in the
+//! DT_RPATH of the needing file and of the files that loaded it (unless the needing file has a
+//! DT_RUNPATH), the DT_RUNPATH of the needing file, the directories of `/etc/ld.so.conf` and
+//! finally the default directories. Candidates of another architecture are skipped, as ld.so
+//! does. `$ORIGIN` is expanded, `$LIB` and `$PLATFORM` are not and their directories are
+//! skipped; LD_LIBRARY_PATH and the ld.so.cache are not looked at.
+//!
+//! Everything is looked up within a sysroot: install paths such as `/usr/lib/libz.so.1` are
+//! joined to it and absolute symlinks are followed within it, so a staging root or a mounted
+//! image resolves like the system it will become.
+//!
+//! For a library not found, other versions of it are suggested: libraries in the ld.so.conf and
+//! default directories, or captures of a report, whose soname has the same stem (`libicuuc`
+//! for `libicuuc.so.74`, `libpython` for `libpython3.12.so.1.0`). That is usually all there is
+//! to a missing library after a soname bump: the dependent needs rebuilding.
+//!
+//! [LibrarySearch::search_dirs] goes the other way, from the files of a report of the sysroot to
+//! every directory their DT_RPATH and DT_RUNPATH name, and whether the sysroot has it. A missing
+//! one is most often a path of the build sandbox (`/build/foo-1.0/.libs`, `/home/builder/...`)
+//! the build system embedded and nothing stripped.
+
+use crate::{natural_sorted, parse_elf_sonames, soname_stem, AbiCapture, AbiReport, Limits};
+use globset::Glob;
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+/// How many symlinks to follow before giving up on a loop
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Where ld.so looks last
+const DEFAULT_DIRS: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+
+/// Which lookup found a library
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SearchRule {
+    Path,        // the DT_NEEDED entry is a path
+    Rpath,       // DT_RPATH of the needing file or one that loaded it
+    Runpath,     // DT_RUNPATH of the needing file
+    LdSoConf,    // a directory of /etc/ld.so.conf
+    DefaultPath, // one of the default directories
+}
+
+impl fmt::Display for SearchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Path => "path",
+            Self::Rpath => "DT_RPATH",
+            Self::Runpath => "DT_RUNPATH",
+            Self::LdSoConf => "ld.so.conf",
+            Self::DefaultPath => "default path",
+        })
+    }
+}
+
+/// An executable and every library it loads
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DependencyClosure {
+    pub root: String,                 // the install path of the executable
+    pub libraries: Vec<ClosureEntry>, // in load order (breadth first), each soname once
+}
+
+/// A library of a closure, where it was found and why
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ClosureEntry {
+    pub soname: String,
+    pub needed_by: String,    // the install path of the first file needing it
+    pub depth: usize,         // 1 for the executable's own DT_NEEDED
+    pub path: Option<String>, // the install path, None if not found
+    pub found_by: Option<SearchRule>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub alternatives: Vec<String>, // if not found, sonames of other versions that are around
+}
+
+impl DependencyClosure {
+    /// The needed sonames no lookup found.
+    pub fn missing(&self) -> impl Iterator<Item = &ClosureEntry> {
+        self.libraries.iter().filter(|entry| entry.path.is_none())
+    }
+
+    /// Also suggest the sonames of `report` for the libraries not found.
+    pub fn suggest_from(&mut self, report: &AbiReport) {
+        for entry in self.libraries.iter_mut().filter(|e| e.path.is_none()) {
+            let sonames = report.captures().iter().filter_map(AbiCapture::soname);
+            let alternatives = entry
+                .alternatives
+                .iter()
+                .map(String::as_str)
+                .chain(sonames.filter(|soname| is_alternative(soname, &entry.soname)));
+            entry.alternatives = natural_sorted(alternatives);
+            entry.alternatives.dedup();
+        }
+    }
+}
+
+/// A directory named by the DT_RPATH or DT_RUNPATH of files, see [LibrarySearch::search_dirs]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SearchDir {
+    pub path: String, // the install path, `$ORIGIN` expanded; as written if it can't be
+    pub status: SearchDirStatus,
+    pub rules: Vec<SearchRule>, // DT_RPATH, DT_RUNPATH or both
+    pub files: Vec<String>,     // naming it, in report order
+}
+
+/// Whether the sysroot has a [SearchDir]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SearchDirStatus {
+    Present,    // a directory within the sysroot (after following symlinks within it)
+    Missing,    // nothing, or no directory, at that path
+    Relative,   // relative to the working directory of the process, so not checked
+    Unexpanded, // uses `$LIB` or `$PLATFORM`, which depend on the machine
+}
+
+impl fmt::Display for SearchDirStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Present => "present",
+            Self::Missing => "missing",
+            Self::Relative => "relative",
+            Self::Unexpanded => "unexpanded",
+        })
+    }
+}
+
+/// Looks libraries up within a sysroot, see the module docs
+#[derive(Clone, Debug)]
+pub struct LibrarySearch {
+    sysroot: PathBuf,
+    conf_dirs: Vec<String>, // from /etc/ld.so.conf, in order
+    limits: Limits,
+}
+
+/// A file of the closure being computed
+struct Loaded {
+    path: String, // install path
+    capture: AbiCapture,
+    loader: Option<usize>, // the file that first needed it
+}
+
+impl LibrarySearch {
+    /// Look libraries up within `sysroot` (`/` for the running system), reading its
+    /// `/etc/ld.so.conf` if there is one.
+    pub fn new(sysroot: impl Into<PathBuf>) -> Self {
+        let mut search = Self {
+            sysroot: sysroot.into(),
+            conf_dirs: vec![],
+            limits: Limits::default(),
+        };
+        let mut conf_dirs = vec![];
+        search.read_conf("/etc/ld.so.conf", &mut conf_dirs, 0);
+        search.conf_dirs = conf_dirs;
+        search
+    }
+
+    /// Hold the files read to these limits.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn sysroot(&self) -> &Path {
+        &self.sysroot
+    }
+
+    /// The closure of `executable`, a file on disk. Within the sysroot its install path is
+    /// where `$ORIGIN` points, elsewhere it's treated as installed where it is.
+    pub fn closure(&self, executable: &Path) -> Result<DependencyClosure> {
+        let root = match executable.strip_prefix(&self.sysroot) {
+            Ok(relative) => format!("/{}", relative.to_string_lossy()),
+            Err(_) => executable.to_string_lossy().into_owned(),
+        };
+        let capture = parse_elf_sonames(&executable.to_string_lossy(), &self.limits)?;
+        let mut loaded = vec![Loaded {
+            path: root.clone(),
+            capture,
+            loader: None,
+        }];
+        let mut libraries = vec![];
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([(0, 0)]);
+        while let Some((idx, depth)) = queue.pop_front() {
+            // older captures only have the sorted list
+            let needed = match loaded[idx].capture.needed_link_order() {
+                [] => loaded[idx].capture.needed_deps(),
+                order => order,
+            }
+            .to_vec();
+            for soname in needed {
+                if !seen.insert(soname.clone()) {
+                    continue;
+                }
+                let found = self.find(&soname, &loaded, idx);
+                let entry = ClosureEntry {
+                    soname: soname.clone(),
+                    needed_by: loaded[idx].path.clone(),
+                    depth: depth + 1,
+                    path: found.as_ref().map(|(path, _, _)| path.clone()),
+                    found_by: found.as_ref().map(|&(_, rule, _)| rule),
+                    alternatives: match found {
+                        Some(_) => vec![],
+                        None => self.alternatives(&soname, &loaded[0].capture),
+                    },
+                };
+                libraries.push(entry);
+                if let Some((path, _, capture)) = found {
+                    queue.push_back((loaded.len(), depth + 1));
+                    loaded.push(Loaded {
+                        path,
+                        capture,
+                        loader: Some(idx),
+                    });
+                }
+            }
+        }
+        Ok(DependencyClosure { root, libraries })
+    }
+
+    /// Every directory the DT_RPATH and DT_RUNPATH of the files of `report`, a scan of the
+    /// sysroot, name, in natural order, and whether the sysroot has it. `$ORIGIN` is expanded to
+    /// the install directory of each file: its filename, less the sysroot if it was scanned
+    /// without `--root` (relative filenames taken as relative to the working directory).
+    pub fn search_dirs(&self, report: &AbiReport) -> Vec<SearchDir> {
+        let mut dirs = BTreeMap::<String, SearchDir>::new();
+        let sysroot = std::path::absolute(&self.sysroot).unwrap_or_else(|_| self.sysroot.clone());
+        for capture in report.captures() {
+            let filename = std::path::absolute(capture.filename())
+                .unwrap_or_else(|_| capture.filename().into());
+            let file = match filename.strip_prefix(&sysroot) {
+                Ok(relative) => format!("/{}", relative.to_string_lossy()),
+                Err(_) => capture.filename().to_string(),
+            };
+            for (path, rule) in [
+                (capture.rpath(), SearchRule::Rpath),
+                (capture.runpath(), SearchRule::Runpath),
+            ] {
+                for dir in path.into_iter().flat_map(|path| path.split(':')) {
+                    if dir.is_empty() {
+                        continue;
+                    }
+                    let path = normalize(&expand_origin(dir, &file));
+                    let entry = dirs.entry(path.clone()).or_insert_with(|| SearchDir {
+                        status: self.dir_status(&path),
+                        path,
+                        rules: vec![],
+                        files: vec![],
+                    });
+                    if !entry.rules.contains(&rule) {
+                        entry.rules.push(rule);
+                        entry.rules.sort_by_key(|rule| *rule != SearchRule::Rpath);
+                    }
+                    if entry.files.last() != Some(&file) {
+                        entry.files.push(file.clone());
+                    }
+                }
+            }
+        }
+        let mut dirs = dirs.into_values().collect::<Vec<_>>();
+        dirs.sort_by(|a, b| a.path.natural_cmp(&b.path));
+        dirs
+    }
+
+    fn dir_status(&self, path: &str) -> SearchDirStatus {
+        if path.contains('$') {
+            SearchDirStatus::Unexpanded
+        } else if !path.starts_with('/') {
+            SearchDirStatus::Relative
+        } else if self.resolve(path).is_some_and(|staged| staged.is_dir()) {
+            SearchDirStatus::Present
+        } else {
+            SearchDirStatus::Missing
+        }
+    }
+
+    /// The install path, rule and capture of the library `soname` as needed by `loaded[idx]`.
+    fn find(
+        &self,
+        soname: &str,
+        loaded: &[Loaded],
+        idx: usize,
+    ) -> Option<(String, SearchRule, AbiCapture)> {
+        let executable = &loaded[0].capture;
+        if soname.contains('/') {
+            return self
+                .candidate(soname, executable)
+                .map(|capture| (soname.to_string(), SearchRule::Path, capture));
+        }
+
+        let needing = &loaded[idx];
+        let mut dirs = vec![];
+        if needing.capture.runpath().is_none() {
+            let mut loader = Some(idx);
+            while let Some(current) = loader {
+                let file = &loaded[current];
+                for dir in search_path(file.capture.rpath(), &file.path) {
+                    dirs.push((dir, SearchRule::Rpath));
+                }
+                loader = file.loader;
+            }
+        }
+        for dir in search_path(needing.capture.runpath(), &needing.path) {
+            dirs.push((dir, SearchRule::Runpath));
+        }
+        dirs.extend(
+            self.conf_dirs
+                .iter()
+                .map(|dir| (dir.clone(), SearchRule::LdSoConf)),
+        );
+        dirs.extend(
+            DEFAULT_DIRS
+                .iter()
+                .map(|dir| (dir.to_string(), SearchRule::DefaultPath)),
+        );
+
+        dirs.into_iter().find_map(|(dir, rule)| {
+            let path = format!("{}/{soname}", dir.trim_end_matches('/'));
+            self.candidate(&path, executable)
+                .map(|capture| (path, rule, capture))
+        })
+    }
+
+    /// The sonames of other versions of `soname` in the ld.so.conf and default directories.
+    fn alternatives(&self, soname: &str, executable: &AbiCapture) -> Vec<String> {
+        let mut alternatives = vec![];
+        for dir in self
+            .conf_dirs
+            .iter()
+            .map(String::as_str)
+            .chain(DEFAULT_DIRS.iter().copied())
+        {
+            let Some(entries) = self
+                .resolve(dir)
+                .and_then(|staged| fs::read_dir(staged).ok())
+            else {
+                continue;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                if soname_stem(&file_name) != soname_stem(soname) {
+                    continue;
+                }
+                let path = format!("{}/{file_name}", dir.trim_end_matches('/'));
+                let found = self
+                    .candidate(&path, executable)
+                    .and_then(|capture| capture.soname().map(str::to_string));
+                alternatives.extend(found.filter(|found| is_alternative(found, soname)));
+            }
+        }
+        let mut alternatives = natural_sorted(alternatives);
+        alternatives.dedup();
+        alternatives
+    }
+
+    /// The library at an install path, if it's there and of the executable's architecture.
+    fn candidate(&self, path: &str, executable: &AbiCapture) -> Option<AbiCapture> {
+        let staged = self.staged(path)?;
+        let capture = parse_elf_sonames(&staged.to_string_lossy(), &self.limits).ok()?;
+        capture.is_same_arch(executable).then_some(capture)
+    }
+
+    /// Where the file at an install path is within the sysroot.
+    fn staged(&self, path: &str) -> Option<PathBuf> {
+        self.resolve(path).filter(|staged| staged.is_file())
+    }
+
+    /// Where an install path is within the sysroot, following symlinks without leaving it.
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let mut pending = path
+            .split('/')
+            .rev()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut resolved = vec![];
+        let mut hops = 0;
+        while let Some(component) = pending.pop() {
+            match component.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    resolved.pop();
+                    continue;
+                }
+                _ => resolved.push(component),
+            }
+            let staged = self.join(&resolved);
+            let metadata = fs::symlink_metadata(&staged).ok()?;
+            if metadata.file_type().is_symlink() {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return None;
+                }
+                let target = fs::read_link(&staged).ok()?;
+                let target = target.to_string_lossy();
+                resolved.pop();
+                if target.starts_with('/') {
+                    resolved.clear();
+                }
+                pending.extend(target.split('/').rev().map(str::to_string));
+            }
+        }
+        Some(self.join(&resolved))
+    }
+
+    fn join(&self, components: &[String]) -> PathBuf {
+        components
+            .iter()
+            .fold(self.sysroot.clone(), |path, component| path.join(component))
+    }
+
+    /// Collect the directories of an ld.so.conf file, following its includes.
+    fn read_conf(&self, path: &str, dirs: &mut Vec<String>, depth: usize) {
+        // includes including themselves are an easy mistake to make
+        if depth > MAX_SYMLINK_HOPS {
+            return;
+        }
+        let Some(contents) = self
+            .staged(path)
+            .and_then(|staged| fs::read_to_string(staged).ok())
+        else {
+            return;
+        };
+        let conf_dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if let Some(pattern) = line.strip_prefix("include") {
+                for include in self.includes(pattern.trim(), conf_dir) {
+                    self.read_conf(&include, dirs, depth + 1);
+                }
+            } else if line.starts_with('/') && !line.starts_with("hwcap") {
+                for dir in line
+                    .split([':', ',', ' ', '\t'])
+                    .filter(|dir| !dir.is_empty())
+                {
+                    dirs.push(dir.to_string());
+                }
+            }
+        }
+    }
+
+    /// The files an include line of an ld.so.conf file matches, in name order. Only the file
+    /// name may have wildcards, as in the usual `include ld.so.conf.d/*.conf`.
+    fn includes(&self, pattern: &str, conf_dir: &str) -> Vec<String> {
+        let pattern = match pattern.starts_with('/') {
+            true => pattern.to_string(),
+            false => format!("{conf_dir}/{pattern}"),
+        };
+        let (dir, name) = pattern.rsplit_once('/').unwrap_or(("", &pattern));
+        let Ok(glob) = Glob::new(name) else {
+            return vec![];
+        };
+        let matcher = glob.compile_matcher();
+        let Some(entries) = self
+            .resolve(dir)
+            .and_then(|staged| fs::read_dir(staged).ok())
+        else {
+            return vec![];
+        };
+        let mut includes = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|file_name| matcher.is_match(file_name))
+            .map(|file_name| format!("{dir}/{file_name}"))
+            .collect::<Vec<_>>();
+        includes.sort();
+        includes
+    }
+}
+
+/// Whether `soname` is of another version of the library `missing`.
+fn is_alternative(soname: &str, missing: &str) -> bool {
+    soname != missing && soname_stem(soname) == soname_stem(missing)
+}
+
+/// The directories of a DT_RPATH or DT_RUNPATH, with `$ORIGIN` expanded to the install
+/// directory of the file it's from.
+fn search_path(path: Option<&str>, file: &str) -> Vec<String> {
+    path.into_iter()
+        .flat_map(|path| path.split(':'))
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| expand_origin(dir, file))
+        .filter(|dir| !dir.contains('$'))
+        .collect()
+}
+
+/// A directory of a DT_RPATH or DT_RUNPATH with `$ORIGIN` expanded to the install directory of
+/// `file`.
+fn expand_origin(dir: &str, file: &str) -> String {
+    let origin = file.rsplit_once('/').map_or(".", |(dir, _)| dir);
+    dir.replace("${ORIGIN}", origin).replace("$ORIGIN", origin)
+}
+
+/// Drop the `.` and `..` components and repeated slashes of an absolute path, as `$ORIGIN/../lib`
+/// leaves them. Relative paths are kept as they are.
+fn normalize(path: &str) -> String {
+    if !path.starts_with('/') {
+        return path.to_string();
+    }
+    let mut components = vec![];
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            _ => components.push(component),
+        }
+    }
+    format!("/{}", components.join("/"))
+}
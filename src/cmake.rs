@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Cross-checking installed CMake package exports against the libraries actually installed.
+//!
+//! The `*Targets-<config>.cmake` files CMake generates tell consumers where each imported
+//! target's library is (`IMPORTED_LOCATION_<CONFIG>`) and what its soname is
+//! (`IMPORTED_SONAME_<CONFIG>`). Both are baked in at build time, so a library renamed or moved
+//! by the packaging (or a soname bump the export wasn't regenerated for) breaks every consumer.
+//!
+//! `${_IMPORT_PREFIX}` and `${PACKAGE_PREFIX_DIR}` are taken to be the prefix the CMake
+//! directory is installed under (`/usr` for `/usr/lib/cmake/Foo`), and
+//! `${CMAKE_CURRENT_LIST_DIR}` the directory of the file. Locations using any other variable
+//! are not checked.
+
+use crate::check::{Check, Finding};
+use crate::tree::{EntryKind, InstallTree, TreeEntry};
+use std::fs;
+
+/// One `IMPORTED_LOCATION` of a `set_target_properties()` call
+#[derive(Debug)]
+struct ImportedLocation {
+    target: String,
+    config: String,         // the property suffix, e.g. "_RELEASE", or empty
+    location: String,       // with the variables substituted
+    soname: Option<String>, // IMPORTED_SONAME of the same configuration
+}
+
+/// The imported locations set in a .cmake file.
+fn imported_locations(contents: &str, file: &TreeEntry) -> Vec<ImportedLocation> {
+    let dir = file.path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let prefix = install_prefix(dir);
+
+    let mut locations = vec![];
+    let mut rest = contents;
+    while let Some(start) = rest.find("set_target_properties(") {
+        rest = &rest[start + "set_target_properties(".len()..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        let args = tokenize(&rest[..end]);
+        rest = &rest[end..];
+
+        let Some((target, args)) = args.split_first() else {
+            continue;
+        };
+        let properties = match args.split_first() {
+            Some((keyword, properties)) if keyword == "PROPERTIES" => properties,
+            _ => continue,
+        };
+        let value = |name: &str| {
+            properties
+                .chunks(2)
+                .find(|pair| pair[0] == name)
+                .and_then(|pair| pair.get(1))
+        };
+        for pair in properties.chunks(2) {
+            let (Some(config), Some(location)) =
+                (pair[0].strip_prefix("IMPORTED_LOCATION"), pair.get(1))
+            else {
+                continue;
+            };
+            let location = location
+                .replace("${_IMPORT_PREFIX}", prefix)
+                .replace("${PACKAGE_PREFIX_DIR}", prefix)
+                .replace("${CMAKE_CURRENT_LIST_DIR}", dir);
+            if location.contains("${") {
+                continue;
+            }
+            locations.push(ImportedLocation {
+                target: target.clone(),
+                config: config.to_string(),
+                location,
+                soname: value(&format!("IMPORTED_SONAME{config}")).cloned(),
+            });
+        }
+    }
+    locations
+}
+
+/// Split CMake arguments on whitespace, keeping quoted arguments together (without quotes).
+fn tokenize(args: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = args.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            tokens.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+/// `/usr` for `/usr/lib/cmake/Foo`, `/usr/lib/x86_64-linux-gnu/cmake/Foo` or
+/// `/usr/share/cmake/Foo`.
+fn install_prefix(dir: &str) -> &str {
+    let Some((base, _)) = dir.split_once("/cmake/").or(dir.rsplit_once("/cmake")) else {
+        return "";
+    };
+    match base.rfind("/lib").max(base.rfind("/share")) {
+        Some(idx) => &base[..idx],
+        None => base,
+    }
+}
+
+/// A finding for every imported location of an installed CMake export that isn't shipped, or
+/// whose soname is not what the export claims.
+pub(crate) fn cmake_imports(tree: &InstallTree) -> Vec<Finding> {
+    let mut findings = vec![];
+    let cmake_files = tree.entries().iter().filter(|entry| {
+        entry.path.ends_with(".cmake")
+            && entry.path.contains("/cmake/")
+            && entry.kind == EntryKind::File
+    });
+    for file in cmake_files {
+        let contents = match fs::read_to_string(tree.staged_path(&file.path)) {
+            Ok(contents) => contents,
+            Err(err) => {
+                let message = format!("could not be read: {err}");
+                findings.push(Finding::new(Check::CMakeImports, &file.path, message));
+                continue;
+            }
+        };
+        for imported in imported_locations(&contents, file) {
+            let property = format!("IMPORTED_LOCATION{}", imported.config);
+            let message = match tree.resolve(&imported.location).map(|entry| &entry.kind) {
+                None => format!(
+                    "{} of {} is {}, which is not shipped",
+                    property, imported.target, imported.location
+                ),
+                Some(EntryKind::Elf { soname, .. })
+                    if imported.soname.is_some() && *soname != imported.soname =>
+                {
+                    format!(
+                        "{} of {} has soname {}, not {}",
+                        imported.location,
+                        imported.target,
+                        soname.as_deref().unwrap_or("(none)"),
+                        imported.soname.as_deref().unwrap_or_default()
+                    )
+                }
+                Some(_) => continue,
+            };
+            findings.push(Finding::new(Check::CMakeImports, &file.path, message));
+        }
+    }
+    findings
+}
@@ -0,0 +1,444 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reports holding their symbol tables in a single arena.
+//!
+//! An [AbiReport] of a whole distro holds millions of [Symbol]s, each with a `String` for its
+//! name (and version, and provider) although most of them repeat across captures: every binary
+//! imports `malloc@GLIBC_2.2.5`. A [CompactReport] interns these strings once, in one buffer,
+//! and keeps each symbol as a few u32 handles into it, cutting the memory a symbol table takes to
+//! about a third. Captures are read through [CompactCapture], whose symbols are [SymbolRef]s
+//! borrowing from the arena, and turned back into owned ones where needed.
+//!
+//! Lookups and diffs work on the arena directly, and serializing copies out one capture at a
+//! time. Checks look across captures in too many ways for that, and run on an owned copy.
+
+#[cfg(feature = "serde")]
+use crate::format::{self, ReportFormat};
+use crate::report::{captures_digest_each, ReportHeader};
+use crate::schema::SCHEMA_VERSION;
+#[cfg(feature = "serde")]
+use crate::stream::{StreamFormat, StreamWriter};
+use crate::{AbiCapture, AbiReport, Check, ElfKind, Finding, Symbol, SymbolKind, SymbolRef};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+#[cfg(feature = "serde")]
+use std::io::{BufRead, Write};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+
+/// A string in the arena; [NONE] for an absent optional one
+type StrId = u32;
+const NONE: StrId = StrId::MAX;
+
+/// Interned strings, stored back to back
+#[derive(Clone, Debug, Default)]
+struct Strings {
+    data: String,
+    ends: Vec<u32>,           // end offset in data, by id
+    ids: HashMap<u64, StrId>, // by hash of the string, the first interned with that hash
+    hasher: RandomState,
+}
+
+impl Strings {
+    fn intern(&mut self, text: &str) -> Result<StrId> {
+        let hash = self.hasher.hash_one(text);
+        if let Some(&id) = self.ids.get(&hash) {
+            // on a hash collision the string is stored twice, which is merely less compact
+            if self.get(id) == text {
+                return Ok(id);
+            }
+        }
+        let id = StrId::try_from(self.ends.len())
+            .ok()
+            .filter(|&id| id != NONE);
+        let end = u32::try_from(self.data.len() + text.len()).ok();
+        let (Some(id), Some(end)) = (id, end) else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "too many symbol strings for a compact report",
+            ));
+        };
+        self.data.push_str(text);
+        self.ends.push(end);
+        self.ids.entry(hash).or_insert(id);
+        Ok(id)
+    }
+
+    fn intern_opt(&mut self, text: Option<&str>) -> Result<StrId> {
+        text.map_or(Ok(NONE), |text| self.intern(text))
+    }
+
+    /// Done interning: drop the index and spare capacity.
+    fn seal(&mut self) {
+        self.ids = HashMap::new();
+        self.data.shrink_to_fit();
+        self.ends.shrink_to_fit();
+    }
+
+    fn get(&self, id: StrId) -> &str {
+        let start = match id {
+            0 => 0,
+            id => self.ends[id as usize - 1] as usize,
+        };
+        &self.data[start..self.ends[id as usize] as usize]
+    }
+
+    fn get_opt(&self, id: StrId) -> Option<&str> {
+        (id != NONE).then(|| self.get(id))
+    }
+}
+
+/// A [Symbol] as handles into the arena
+#[derive(Clone, Copy, Debug)]
+struct CompactSymbol {
+    name: StrId,
+    version: StrId,
+    provider: StrId,
+    size: Option<u64>,
+    kind: SymbolKind,
+    compat: bool,
+}
+
+/// A capture without its symbols, which are ranges of the report's symbol table
+#[derive(Clone, Debug)]
+struct Entry {
+    capture: AbiCapture,
+    imports: Range<u32>,
+    exports: Range<u32>,
+    protected: Range<u32>,
+    hidden: Range<u32>,
+}
+
+/// An [AbiReport] with its symbols interned, see [AbiReport::into_compact]
+#[derive(Clone, Debug)]
+pub struct CompactReport {
+    header: ReportHeader,
+    entries: Vec<Entry>, // in report order
+    symbols: Vec<CompactSymbol>,
+    strings: Strings,
+}
+
+/// A capture of a [CompactReport], mirroring the symbol accessors of [AbiCapture]
+#[derive(Clone, Copy, Debug)]
+pub struct CompactCapture<'a> {
+    report: &'a CompactReport,
+    entry: &'a Entry,
+}
+
+/// The symbols of a [CompactCapture], borrowing from the report
+#[derive(Clone, Debug)]
+pub struct CompactSymbols<'a> {
+    report: &'a CompactReport,
+    symbols: std::slice::Iter<'a, CompactSymbol>,
+}
+
+impl CompactReport {
+    /// An empty report, to push captures to before [Self::finish] sorts and digests them.
+    pub fn new() -> Self {
+        Self {
+            header: ReportHeader::new(),
+            entries: vec![],
+            symbols: vec![],
+            strings: Strings::default(),
+        }
+    }
+
+    /// Intern the symbols of `capture` and add it, unsorted. Fails only past 4 GiB of distinct
+    /// symbol strings.
+    pub fn push(&mut self, mut capture: AbiCapture) -> Result<()> {
+        let imports = self.intern(std::mem::take(&mut capture.dynsym_imports))?;
+        let exports = self.intern(std::mem::take(&mut capture.dynsym_exports))?;
+        let protected = self.intern(std::mem::take(&mut capture.protected_exports))?;
+        let hidden = self.intern(std::mem::take(&mut capture.hidden_symbols))?;
+        self.entries.push(Entry {
+            capture,
+            imports,
+            exports,
+            protected,
+            hidden,
+        });
+        Ok(())
+    }
+
+    /// Sort the captures in the order `header` says and digest them, as [AbiReport::with_header].
+    pub fn finish(mut self, header: ReportHeader) -> Self {
+        self.entries
+            .sort_by(|a, b| header.order.cmp(&a.capture, &b.capture));
+        let digest = captures_digest_each(self.captures().map(|capture| capture.to_capture()));
+        self.header = ReportHeader {
+            schema_version: SCHEMA_VERSION,
+            digest,
+            ..header
+        };
+        self.seal();
+        self
+    }
+
+    fn seal(&mut self) {
+        self.strings.seal();
+        self.symbols.shrink_to_fit();
+    }
+
+    fn intern(&mut self, symbols: Vec<Symbol>) -> Result<Range<u32>> {
+        let too_many = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                "too many symbols for a compact report",
+            )
+        };
+        let start = u32::try_from(self.symbols.len()).map_err(|_| too_many())?;
+        let end = u32::try_from(self.symbols.len() + symbols.len()).map_err(|_| too_many())?;
+        self.symbols.reserve(symbols.len());
+        for symbol in symbols {
+            self.symbols.push(CompactSymbol {
+                name: self.strings.intern(&symbol.name)?,
+                version: self.strings.intern_opt(symbol.version.as_deref())?,
+                provider: self.strings.intern_opt(symbol.provider.as_deref())?,
+                size: symbol.size,
+                kind: symbol.kind,
+                compat: symbol.compat,
+            });
+        }
+        Ok(start..end)
+    }
+
+    pub fn header(&self) -> &ReportHeader {
+        &self.header
+    }
+
+    /// The captures, in report order.
+    pub fn captures(&self) -> impl ExactSizeIterator<Item = CompactCapture<'_>> {
+        self.entries.iter().map(|entry| CompactCapture {
+            report: self,
+            entry,
+        })
+    }
+
+    pub fn capture(&self, index: usize) -> Option<CompactCapture<'_>> {
+        self.entries.get(index).map(|entry| CompactCapture {
+            report: self,
+            entry,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How many symbols the captures hold in all, and how many distinct strings they share.
+    pub fn interned(&self) -> (usize, usize) {
+        (self.symbols.len(), self.strings.ends.len())
+    }
+
+    /// The owned report, copying every symbol back out of the arena.
+    pub fn into_report(self) -> AbiReport {
+        self.to_report()
+    }
+
+    /// An owned copy of the report.
+    pub fn to_report(&self) -> AbiReport {
+        let captures = self
+            .captures()
+            .map(|capture| capture.to_capture())
+            .collect();
+        AbiReport::from_parts(self.header.clone(), captures)
+    }
+
+    /// See [AbiReport::check]. The checks run on an owned copy of the report.
+    pub fn check(&self, checks: &[Check]) -> Vec<Finding> {
+        self.to_report().check(checks)
+    }
+}
+
+impl Default for CompactReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reading and writing the serialized forms, which are those of [AbiReport].
+#[cfg(feature = "serde")]
+impl CompactReport {
+    /// See [AbiReport::verify_digest].
+    pub fn verify_digest(&self) -> bool {
+        captures_digest_each(self.captures().map(|capture| capture.to_capture()))
+            == self.header.digest
+    }
+
+    /// See [AbiReport::write_json].
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// See [AbiReport::write]. Only the compact format needs the whole document at once.
+    pub fn write<W: Write>(&self, writer: W, format: ReportFormat) -> Result<()> {
+        match format {
+            ReportFormat::Json => self.write_json(writer),
+            ReportFormat::Cbor => format::write_cbor(&serde_json::to_value(self)?, writer),
+            ReportFormat::JsonLines => self.write_stream(writer, StreamFormat::JsonLines),
+            ReportFormat::Records => self.write_stream(writer, StreamFormat::Records),
+        }
+    }
+
+    fn write_stream<W: Write>(&self, writer: W, format: StreamFormat) -> Result<()> {
+        let mut stream = StreamWriter::new(writer, format, &self.header)?;
+        for capture in self.captures() {
+            stream.write_capture(&capture.to_capture())?;
+        }
+        stream.finish().map(|_| ())
+    }
+
+    /// See [AbiReport::read]. The report is read whole before it is interned.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self> {
+        AbiReport::read(reader)?.into_compact()
+    }
+}
+
+/// As an [AbiReport], copying each capture out of the arena as it is written
+#[cfg(feature = "serde")]
+impl Serialize for CompactReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        struct Captures<'a>(&'a CompactReport);
+
+        impl Serialize for Captures<'_> {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.0.captures().map(|capture| capture.to_capture()))
+            }
+        }
+
+        let mut report = serializer.serialize_struct("AbiReport", 2)?;
+        report.serialize_field("header", &self.header)?;
+        report.serialize_field("captures", &Captures(self))?;
+        report.end()
+    }
+}
+
+impl AbiReport {
+    /// Intern the symbols of this report into a [CompactReport], which takes much less memory
+    /// for reports of many captures. Fails only past 4 GiB of distinct symbol strings.
+    pub fn into_compact(self) -> Result<CompactReport> {
+        let (header, captures) = self.into_parts();
+        let mut compact = CompactReport::new();
+        for capture in captures {
+            compact.push(capture)?;
+        }
+        // already sorted and digested
+        compact.header = header;
+        compact.seal();
+        Ok(compact)
+    }
+}
+
+impl From<CompactReport> for AbiReport {
+    fn from(report: CompactReport) -> Self {
+        report.into_report()
+    }
+}
+
+impl<'a> CompactCapture<'a> {
+    pub fn filename(&self) -> &'a str {
+        self.entry.capture.filename()
+    }
+
+    pub fn elf_kind(&self) -> ElfKind {
+        self.entry.capture.elf_kind()
+    }
+
+    pub fn soname(&self) -> Option<&'a str> {
+        self.entry.capture.soname()
+    }
+
+    /// See [AbiCapture::dynsym_imports].
+    pub fn dynsym_imports(&self) -> CompactSymbols<'a> {
+        self.symbols(&self.entry.imports)
+    }
+
+    /// See [AbiCapture::dynsym_exports].
+    pub fn dynsym_exports(&self) -> CompactSymbols<'a> {
+        self.symbols(&self.entry.exports)
+    }
+
+    /// See [AbiCapture::protected_exports].
+    pub fn protected_exports(&self) -> CompactSymbols<'a> {
+        self.symbols(&self.entry.protected)
+    }
+
+    /// See [AbiCapture::hidden_symbols].
+    pub fn hidden_symbols(&self) -> CompactSymbols<'a> {
+        self.symbols(&self.entry.hidden)
+    }
+
+    /// Everything else the capture records (dependencies, notes, warnings, ...), as an
+    /// [AbiCapture] with no symbols.
+    pub fn without_symbols(&self) -> &'a AbiCapture {
+        &self.entry.capture
+    }
+
+    /// The owned capture, copying its symbols.
+    pub fn to_capture(&self) -> AbiCapture {
+        let owned = |symbols: CompactSymbols| symbols.map(Symbol::from).collect();
+        AbiCapture {
+            dynsym_imports: owned(self.dynsym_imports()),
+            dynsym_exports: owned(self.dynsym_exports()),
+            protected_exports: owned(self.protected_exports()),
+            hidden_symbols: owned(self.hidden_symbols()),
+            ..self.entry.capture.clone()
+        }
+    }
+
+    fn symbols(&self, range: &Range<u32>) -> CompactSymbols<'a> {
+        CompactSymbols {
+            report: self.report,
+            symbols: self.report.symbols[range.start as usize..range.end as usize].iter(),
+        }
+    }
+}
+
+impl<'a> CompactSymbols<'a> {
+    fn resolve(&self, symbol: &CompactSymbol) -> SymbolRef<'a> {
+        let strings = &self.report.strings;
+        SymbolRef {
+            name: strings.get(symbol.name),
+            version: strings.get_opt(symbol.version),
+            compat: symbol.compat,
+            kind: symbol.kind,
+            provider: strings.get_opt(symbol.provider),
+            size: symbol.size,
+        }
+    }
+}
+
+impl<'a> Iterator for CompactSymbols<'a> {
+    type Item = SymbolRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symbol = self.symbols.next()?;
+        Some(self.resolve(symbol))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.symbols.size_hint()
+    }
+}
+
+impl ExactSizeIterator for CompactSymbols<'_> {}
+
+impl DoubleEndedIterator for CompactSymbols<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let symbol = self.symbols.next_back()?;
+        Some(self.resolve(symbol))
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Flat symbol tables for spreadsheets, dataframes and databases.
+//!
+//! Every imported and exported symbol becomes one row of `file,direction,symbol,version,type`,
+//! which loses the per-file details (sonames, dependencies, ...) but needs no custom parser.
+//! These tables can't be read back into a report.
+
+use crate::{AbiReport, Symbol};
+use std::io::{Result, Write};
+
+const HEADINGS: [&str; 5] = ["file", "direction", "symbol", "version", "type"];
+
+impl AbiReport {
+    /// Write one row per symbol of every capture, separated by `delimiter` (`,` for CSV,
+    /// `\t` for TSV), headed by the column names.
+    ///
+    /// Fields containing the delimiter, quotes or line breaks are quoted as in RFC 4180 (rows
+    /// end in a plain newline though).
+    /// Unversioned symbols have an empty version.
+    pub fn write_symbol_table<W: Write>(&self, mut writer: W, delimiter: char) -> Result<()> {
+        write_row(&mut writer, delimiter, HEADINGS)?;
+        for capture in self.captures() {
+            let symbols = [
+                ("import", capture.dynsym_imports()),
+                ("export", capture.dynsym_exports()),
+            ];
+            for (direction, symbols) in symbols {
+                for Symbol {
+                    name,
+                    version,
+                    kind,
+                    ..
+                } in symbols
+                {
+                    let kind = kind.to_string();
+                    let row = [
+                        capture.filename(),
+                        direction,
+                        name,
+                        version.as_deref().unwrap_or_default(),
+                        &kind,
+                    ];
+                    write_row(&mut writer, delimiter, row)?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn write_row<W: Write>(writer: &mut W, delimiter: char, fields: [&str; 5]) -> Result<()> {
+    for (idx, field) in fields.into_iter().enumerate() {
+        if idx > 0 {
+            write!(writer, "{delimiter}")?;
+        }
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writeln!(writer)
+}
@@ -0,0 +1,197 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Locating detached (split) debug info for stripped binaries.
+//!
+//! Distro binaries ship stripped, with their DWARF split off into separate files. Those are found
+//! the same way gdb finds them:
+//!
+//! 1. via the NT_GNU_BUILD_ID note: `<debug root>/.build-id/ab/cdef....debug`
+//! 2. via .gnu_debuglink, next to the binary, in a `.debug/` subdirectory, or mirrored under
+//!    the debug root. Candidates found this way must match the CRC32 recorded in the debuglink.
+//!
+//! The debuglink comes from the (untrusted) binary, so only a plain file name is accepted: an
+//! absolute path or `..` would lead the search anywhere on the system.
+
+use crate::section::{find_note, section_data_by_name};
+use crate::Limits;
+use elf::endian::{AnyEndian, EndianParse};
+use elf::note::Note;
+use elf::ElfBytes;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+use std::fs::File;
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+use std::io::Read;
+use std::io::{Error, ErrorKind, Result};
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+use std::path::PathBuf;
+use std::path::{Component, Path};
+
+/// Where distros conventionally install detached debug info
+pub const DEFAULT_DEBUG_ROOT: &str = "/usr/lib/debug";
+
+/// The contents of a .gnu_debuglink section
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DebugLink {
+    pub filename: String, // basename of the detached debug file
+    pub crc: u32,         // CRC32 of the entire detached debug file
+}
+
+/// Search configuration for detached debug files
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+#[derive(Clone, Debug)]
+pub struct DebugSearch {
+    pub debug_roots: Vec<PathBuf>,
+    pub limits: Limits, // candidates larger than max_file_size aren't checksummed, nor returned
+}
+
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+impl Default for DebugSearch {
+    fn default() -> Self {
+        Self {
+            debug_roots: vec![PathBuf::from(DEFAULT_DEBUG_ROOT)],
+            limits: Limits::default(),
+        }
+    }
+}
+
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+impl DebugSearch {
+    /// Returns the first existing detached debug file for the binary at `binary_path`.
+    ///
+    /// The build-id lookup is tried first since it is unambiguous; debuglink candidates are
+    /// only accepted if their CRC32 matches.
+    pub fn find(
+        &self,
+        binary_path: &Path,
+        build_id: Option<&str>,
+        debuglink: Option<&DebugLink>,
+    ) -> Option<PathBuf> {
+        if let Some(build_id) = build_id.filter(|id| id.len() > 2) {
+            let (dir, rest) = build_id.split_at(2);
+            for root in &self.debug_roots {
+                let candidate = root
+                    .join(".build-id")
+                    .join(dir)
+                    .join(format!("{rest}.debug"));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+
+        // reports from before debuglinks were checked may still hold anything
+        let debuglink = debuglink.filter(|link| is_plain_file_name(&link.filename))?;
+        let binary_dir = binary_path
+            .canonicalize()
+            .ok()?
+            .parent()
+            .map(Path::to_path_buf)?;
+
+        let mut candidates = vec![
+            binary_dir.join(&debuglink.filename),
+            binary_dir.join(".debug").join(&debuglink.filename),
+        ];
+        for root in &self.debug_roots {
+            // Path::join() would discard the root for an absolute binary_dir
+            let relative = binary_dir.strip_prefix("/").unwrap_or(&binary_dir);
+            candidates.push(root.join(relative).join(&debuglink.filename));
+        }
+
+        candidates.into_iter().find(|candidate| {
+            // never hand back the binary itself (same-name debuglinks exist in the wild)
+            candidate.is_file()
+                && candidate.canonicalize().ok().as_deref()
+                    != binary_path.canonicalize().ok().as_deref()
+                && file_crc32(candidate, &self.limits) == Some(debuglink.crc)
+        })
+    }
+}
+
+/// Extract the NT_GNU_BUILD_ID note as a lowercase hex string.
+///
+/// Section headers are preferred (they are what survives objcopy --only-keep-debug), falling
+/// back to PT_NOTE segments for binaries with stripped section headers. Unreadable notes are
+/// only an error if no build-id could be found elsewhere.
+pub fn parse_build_id(elf_file: &ElfBytes<AnyEndian>, limits: &Limits) -> Result<Option<String>> {
+    find_note(elf_file, limits, |note| match note {
+        Note::GnuBuildId(id) => Some(to_hex(id.0)),
+        _ => None,
+    })
+}
+
+/// Parse the .gnu_debuglink section: NUL terminated filename, padded to 4 bytes, then a CRC32
+/// in the file's byte order.
+pub fn parse_debuglink(
+    elf_file: &ElfBytes<AnyEndian>,
+    limits: &Limits,
+) -> Result<Option<DebugLink>> {
+    let Some(data) = section_data_by_name(elf_file, ".gnu_debuglink", limits)? else {
+        return Ok(None);
+    };
+    let malformed = |what: &str| Error::new(ErrorKind::InvalidData, format!("malformed {what}"));
+
+    let nul = data
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| malformed("filename (not NUL terminated)"))?;
+    let filename = std::str::from_utf8(&data[..nul])
+        .map_err(|_| malformed("filename (not UTF-8)"))?
+        .to_string();
+    if !is_plain_file_name(&filename) {
+        return Err(malformed("filename (not a plain file name)"));
+    }
+    let mut crc_offset = (nul + 4) & !3;
+    let crc = elf_file
+        .ehdr
+        .endianness
+        .parse_u32_at(&mut crc_offset, &data)
+        .map_err(|_| malformed("CRC (truncated)"))?;
+
+    Ok(Some(DebugLink { filename, crc }))
+}
+
+/// A single normal path component, so joining it to a directory stays in that directory.
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains('/')
+}
+
+/// The CRC32 of a file, streamed rather than read whole, unless it is over the size limit.
+#[cfg(all(feature = "fs", feature = "dwarf"))]
+fn file_crc32(path: &Path, limits: &Limits) -> Option<u32> {
+    let file = File::open(path).ok()?;
+    limits.check_file_size(file.metadata().ok()?.len()).ok()?;
+
+    // in case it grows while being read
+    let mut file = file.take(limits.max_file_size.saturating_add(1));
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                hasher.update(&buffer[..read]);
+                total += read as u64;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+    limits.check_file_size(total).ok()?;
+    Some(hasher.finalize())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Demangled C++ exports, grouped by scope.
+//!
+//! A C++ library exports tens of thousands of Itanium mangled names, which say little to a
+//! reviewer as a flat list. Demangled, each name is split into its scopes (namespaces and
+//! classes, which the demangled text doesn't tell apart) and its member, the part after the
+//! last `::` along with the parameters, so a [ScopeTree] lists `llvm::APInt::APInt(unsigned
+//! int, unsigned long, bool)` as the member `APInt(unsigned int, unsigned long, bool)` of
+//! `llvm` > `APInt`. Names that can't be demangled are left out.
+
+use crate::AbiCapture;
+use cpp_demangle::{DemangleOptions, Symbol};
+use natural_sort_rs::NaturalSortable;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Special names about a class as a whole, listed as a member of the class by their label
+const CLASS_DATA: [(&str, &str); 4] = [
+    ("vtable for ", "vtable"),
+    ("VTT for ", "VTT"),
+    ("typeinfo name for ", "typeinfo name"),
+    ("typeinfo for ", "typeinfo"),
+];
+
+/// Special names about another symbol, listed next to it with the prefix kept
+const ABOUT_SYMBOL: [&str; 7] = [
+    "guard variable for ",
+    "TLS init function for ",
+    "TLS wrapper function for ",
+    "non-virtual thunk to ",
+    "virtual thunk to ",
+    "covariant return thunk to ",
+    "transaction clone for ",
+];
+
+/// Type names without a space that aren't classes
+const FUNDAMENTAL: [&str; 12] = [
+    "bool", "char", "char8_t", "char16_t", "char32_t", "wchar_t", "short", "int", "long", "float",
+    "double", "void",
+];
+
+/// The C++ exports of a capture by scope, see [AbiCapture::cpp_exports]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScopeTree {
+    pub members: Vec<String>, // without their scope, in natural order
+    pub scopes: BTreeMap<String, ScopeTree>, // nested namespaces and classes, by name
+}
+
+/// Demangle an Itanium C++ ABI symbol name (`_Z...`) as `c++filt` spells it, None for
+/// anything else.
+pub fn demangle(name: &str) -> Option<String> {
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    let demangled = Symbol::new(name)
+        .ok()?
+        .demangle(&DemangleOptions::default())
+        .ok()?;
+    Some(spell_special(demangled))
+}
+
+/// cpp_demangle writes vtables, VTTs and thunks as `{vtable(T)}`, `{vtt(T)}` and `{virtual
+/// override thunk({offset(-8)}, f())}`: spell them as `vtable for T`, `VTT for T` and
+/// `non-virtual thunk to f()` instead.
+fn spell_special(demangled: String) -> String {
+    let Some(inner) = demangled
+        .strip_prefix('{')
+        .and_then(|inner| inner.strip_suffix(")}"))
+    else {
+        return demangled;
+    };
+    if let Some(class) = inner.strip_prefix("vtable(") {
+        return format!("vtable for {class}");
+    }
+    if let Some(class) = inner.strip_prefix("vtt(") {
+        return format!("VTT for {class}");
+    }
+    let Some(mut rest) = inner.strip_prefix("virtual override thunk(") else {
+        return demangled;
+    };
+    let mut offsets = vec![];
+    while let Some(offset) = rest.strip_prefix('{') {
+        let Some((offset, tail)) = offset.split_once(")}, ") else {
+            return demangled;
+        };
+        offsets.push(offset);
+        rest = tail;
+    }
+    let kind = match offsets.as_slice() {
+        [this] if this.starts_with("virtual") => "virtual thunk to",
+        [_] => "non-virtual thunk to",
+        _ => "covariant return thunk to",
+    };
+    format!("{kind} {rest}")
+}
+
+impl ScopeTree {
+    /// How many members the tree holds, at any depth.
+    pub fn len(&self) -> usize {
+        self.members.len() + self.scopes.values().map(ScopeTree::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty() && self.scopes.is_empty()
+    }
+
+    /// File a demangled name under its scopes.
+    fn insert(&mut self, demangled: &str) {
+        let (scopes, member) = split_scopes(demangled);
+        let mut tree = self;
+        for scope in scopes {
+            tree = tree.scopes.entry(scope.to_string()).or_default();
+        }
+        tree.members.push(member);
+    }
+
+    fn sort(&mut self) {
+        self.members.sort_by(|a, b| a.natural_cmp(b));
+        // the same name in several versions is one member
+        self.members.dedup();
+        self.scopes.values_mut().for_each(ScopeTree::sort);
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = 2 * depth;
+        for member in &self.members {
+            writeln!(f, "{:indent$}{member}", "")?;
+        }
+        for (name, scope) in &self.scopes {
+            writeln!(f, "{:indent$}{name}:: ({})", "", scope.len())?;
+            scope.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per scope (with its member count) and member, indented to sit below a heading.
+impl fmt::Display for ScopeTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 1)
+    }
+}
+
+impl AbiCapture {
+    /// The exported C++ symbols, demangled and grouped by namespace and class.
+    pub fn cpp_exports(&self) -> ScopeTree {
+        let mut tree = ScopeTree::default();
+        for demangled in self
+            .dynsym_exports
+            .iter()
+            .filter_map(|symbol| demangle(&symbol.name))
+        {
+            tree.insert(&demangled);
+        }
+        tree.sort();
+        tree
+    }
+}
+
+/// The scopes of a demangled name and the member within them.
+fn split_scopes(demangled: &str) -> (Vec<&str>, String) {
+    if let Some((class, label)) = CLASS_DATA
+        .iter()
+        .find_map(|(prefix, label)| Some((demangled.strip_prefix(prefix)?, label)))
+    {
+        // the typeinfo of `int` or `void (*)()` isn't about any class: keep it at the top
+        if let Some(scopes) = class_scopes(class) {
+            return (scopes, label.to_string());
+        }
+        return (vec![], demangled.to_string());
+    }
+    // the vtable of a base class used while constructing a derived one, under the derived one
+    if let Some((base, class)) = demangled
+        .strip_prefix("construction vtable for ")
+        .and_then(|types| types.rsplit_once("-in-"))
+    {
+        if let Some(scopes) = class_scopes(class) {
+            return (scopes, format!("construction vtable for {base}"));
+        }
+    }
+    let (prefix, name) = ABOUT_SYMBOL
+        .iter()
+        .find_map(|prefix| Some((*prefix, demangled.strip_prefix(prefix)?)))
+        .unwrap_or(("", demangled));
+    let (scopes, member) = split_name(name);
+    (scopes, format!("{prefix}{member}"))
+}
+
+/// The scopes naming `class`, including itself, or None if it isn't a class (or namespace
+/// scoped) name but e.g. a fundamental or function type.
+fn class_scopes(class: &str) -> Option<Vec<&str>> {
+    let (mut scopes, last) = split_name(class);
+    let base = last.split(['<', '[']).next().unwrap_or_default();
+    if class.len() != scopes.iter().map(|scope| scope.len() + 2).sum::<usize>() + last.len()
+        || base.is_empty()
+        || !base.bytes().all(is_identifier)
+        || (scopes.is_empty() && FUNDAMENTAL.contains(&base))
+    {
+        return None;
+    }
+    scopes.push(last);
+    Some(scopes)
+}
+
+/// Split `[return type ]scope::...::name[(parameters)[ qualifiers]]` into the scopes and the
+/// rest, minding the `::` and spaces within template arguments, parameters, lambdas, ABI tags,
+/// `(anonymous namespace)` and operator names.
+fn split_name(name: &str) -> (Vec<&str>, &str) {
+    let bytes = name.as_bytes();
+    let mut depth = 0usize;
+    let mut start = 0; // of the qualified name, past any return type
+    let mut separators = vec![]; // positions of the top level `::`
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let rest = &name[idx..];
+        let word_start = idx == 0 || !is_identifier(bytes[idx - 1]);
+        if depth == 0 && rest.starts_with("(anonymous namespace)") {
+            idx += "(anonymous namespace)".len();
+            continue;
+        }
+        if depth == 0 && word_start && rest.starts_with("operator") {
+            idx += operator_len(rest);
+            continue;
+        }
+        match bytes[idx] {
+            b'<' | b'[' | b'{' => depth += 1,
+            b'(' if depth == 0 => break, // the parameters
+            b'(' => depth += 1,
+            b'>' | b']' | b'}' | b')' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 && rest.starts_with("::") => {
+                separators.push(idx);
+                idx += 2;
+                continue;
+            }
+            b' ' if depth == 0 => {
+                // what came before was the return type
+                start = idx + 1;
+                separators.clear();
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    let mut scopes = vec![];
+    let mut from = start;
+    for separator in separators {
+        scopes.push(&name[from..separator]);
+        from = separator + 2;
+    }
+    (scopes, &name[from..])
+}
+
+/// The length of an operator name at the start of `text`, up to its parameters: `operator()`,
+/// `operator<<`, `operator new[]`, `operator std::string`, ...
+fn operator_len(text: &str) -> usize {
+    let len = "operator".len();
+    let rest = &text[len..];
+    if rest.starts_with("()") {
+        return len + 2;
+    }
+    let mut depth = 0usize;
+    for (idx, byte) in rest.bytes().enumerate() {
+        match byte {
+            b'(' if depth == 0 && idx > 0 => return len + idx,
+            b'<' if rest.starts_with(' ') => depth += 1, // conversion to a template type
+            b'>' if rest.starts_with(' ') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn is_identifier(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
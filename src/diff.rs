@@ -0,0 +1,921 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Comparing captures and reports.
+//!
+//! Captures are paired up by filename. Exported symbols that disappear (and sonames that
+//! change) are what breaks existing consumers; everything else is informational. Symbols the
+//! toolchain generates for every binary are left out by default (see [DiffOptions]), and a
+//! [DiffFilter] narrows a diff down to some files or symbols.
+
+use crate::symbol::{is_boilerplate, is_mangled};
+use crate::{
+    AbiCapture, AbiReport, CompactCapture, CompactReport, ElfKind, Relro, Symbol, SymbolKind,
+    SymbolPattern,
+};
+use globset::{Glob, GlobMatcher};
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// What a diff compares beyond the ABI proper
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub include_boilerplate: bool, // toolchain generated symbols, see Symbol::is_boilerplate
+    pub jobs: usize, // captures compared in parallel when diffing reports (0: one per CPU)
+}
+
+/// Which files and symbols a diff looks at, to focus a big diff on what is under review.
+///
+/// File patterns are globs matched against each file's path and its name alone (`only` ones
+/// against its soname too), so `libLLVM*` picks out the LLVM libraries wherever they are.
+#[derive(Clone, Debug, Default)]
+pub struct DiffFilter {
+    only: Vec<GlobMatcher>,      // if non-empty, only files matching one of these
+    exclude: Vec<GlobMatcher>,   // files matching any of these are left out
+    symbols: Vec<SymbolPattern>, // if non-empty, only symbols matching one of these
+    c_abi_only: bool,            // leave out C++ and Rust mangled symbols
+}
+
+/// Entries added to and removed from a list (symbols, DT_NEEDED)
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ListDiff<T> {
+    pub added: Vec<T>,   // in the order of the new list
+    pub removed: Vec<T>, // in the order of the old list
+}
+
+impl<T> Default for ListDiff<T> {
+    fn default() -> Self {
+        Self {
+            added: vec![],
+            removed: vec![],
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> ListDiff<T> {
+    pub fn between(old: &[T], new: &[T]) -> Self {
+        Self::between_by(old, new, |item| item.clone())
+    }
+}
+
+impl<T: Clone> ListDiff<T> {
+    /// Entries are matched up by `key`, so e.g. symbols can be compared by name and version
+    /// while ignoring their type.
+    pub fn between_by<K: Eq + Hash>(old: &[T], new: &[T], key: impl Fn(&T) -> K) -> Self {
+        let old_set = old.iter().map(&key).collect::<HashSet<_>>();
+        let new_set = new.iter().map(&key).collect::<HashSet<_>>();
+        Self {
+            added: new
+                .iter()
+                .filter(|s| !old_set.contains(&key(s)))
+                .cloned()
+                .collect(),
+            removed: old
+                .iter()
+                .filter(|s| !new_set.contains(&key(s)))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl<T> ListDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A single value that differs between the old and the new capture
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ValueChange<T> {
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: PartialEq> ValueChange<T> {
+    fn between(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChangeKind {
+    Added,    // only in the new report
+    Removed,  // only in the old report
+    Modified, // in both, but different
+}
+
+/// An exported data object whose size changed. Executables using it get a copy of it,
+/// allocated with the old size (copy relocations), so this breaks them even though the symbol
+/// is still there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SymbolResize {
+    pub symbol: Symbol, // as in the new capture
+    pub size: ValueChange<u64>,
+}
+
+/// A symbol whose default version changed, e.g. from `foo@@VER_1` to `foo@@VER_2`. Existing
+/// binaries keep working (as long as the old version is still there), but anything linked
+/// from now on binds to the new version, so it won't run against the old library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DefaultVersionMove {
+    pub name: String,
+    pub version: ValueChange<String>,
+}
+
+/// How one file changed. Files only present on one side diff against an empty capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CaptureDiff {
+    pub filename: String,
+    pub change: ChangeKind,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub package: Option<String>, // the file's package name, new side first (not a change itself)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub elf_kind: Option<ValueChange<ElfKind>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub soname: Option<ValueChange<Option<String>>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub rpath: Option<ValueChange<Option<String>>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub runpath: Option<ValueChange<Option<String>>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub constructors: Option<ValueChange<u64>>, // see InitFini::constructors, if both sides know
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub bind_now: Option<ValueChange<bool>>, // if both sides know
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub relro: Option<ValueChange<Relro>>, // if both sides know
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sha256: Option<ValueChange<String>>, // contents, if both sides know (not a change itself)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "ListDiff::is_empty")
+    )]
+    pub exports: ListDiff<Symbol>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub dropped_aliases: Vec<Symbol>, // removed, but their canonical name survives (not breaking)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub resized: Vec<SymbolResize>, // exported objects present on both sides
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub default_versions: Vec<DefaultVersionMove>, // not breaking, see DefaultVersionMove
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "ListDiff::is_empty")
+    )]
+    pub imports: ListDiff<Symbol>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "ListDiff::is_empty")
+    )]
+    pub needed: ListDiff<String>,
+}
+
+impl CaptureDiff {
+    /// Whether existing consumers of the old file may break: it is gone, exports were removed
+    /// or resized, or its soname changed.
+    pub fn is_breaking(&self) -> bool {
+        self.change == ChangeKind::Removed
+            || !self.exports.removed.is_empty()
+            || !self.resized.is_empty()
+            || self
+                .soname
+                .as_ref()
+                .is_some_and(|soname| soname.old.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.change == ChangeKind::Modified
+            && self.elf_kind.is_none()
+            && self.soname.is_none()
+            && self.rpath.is_none()
+            && self.runpath.is_none()
+            && self.constructors.is_none()
+            && self.bind_now.is_none()
+            && self.relro.is_none()
+            && self.exports.is_empty()
+            && self.dropped_aliases.is_empty()
+            && self.resized.is_empty()
+            && self.default_versions.is_empty()
+            && self.imports.is_empty()
+            && self.needed.is_empty()
+    }
+}
+
+/// All changes between two reports, in natural filename order (unchanged files are left out)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ReportDiff {
+    pub changes: Vec<CaptureDiff>,
+}
+
+/// Counts over a [ReportDiff]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DiffSummary {
+    pub files_added: usize,
+    pub files_removed: usize,
+    pub files_modified: usize,
+    pub exports_added: usize,
+    pub exports_removed: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exports_resized: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub breaking: bool, // any of the changes is, see CaptureDiff::is_breaking
+}
+
+/// The parts of a new report and a baseline that differ, see [AbiReport::changed_since]
+#[derive(Debug)]
+pub struct ChangedSince {
+    pub baseline: AbiReport, // the files changed or gone since the snapshot
+    pub new: AbiReport,      // the files changed or added since
+    pub unchanged: usize,    // files with the same contents on both sides, left out
+}
+
+/// A pair of captures to compare, as paired up by filename
+enum Pending<'a> {
+    Modified(&'a AbiCapture, &'a AbiCapture),
+    Removed(&'a AbiCapture),
+    Added(&'a AbiCapture),
+}
+
+impl ReportDiff {
+    pub fn between(old: &AbiReport, new: &AbiReport) -> Self {
+        Self::between_with(old, new, DiffOptions::default())
+    }
+
+    pub fn between_with(old: &AbiReport, new: &AbiReport, options: DiffOptions) -> Self {
+        let mut changes = vec![];
+        each_change(old, new, options, |change| changes.push(change));
+        Self { changes }
+    }
+
+    /// Compare the parts of the reports `filter` keeps (see [Self::between_filtered]), handing
+    /// each change to `f` as soon as it is computed rather than collecting them, and count them.
+    ///
+    /// Changes come in natural filename order, as in a [ReportDiff], whatever the number of
+    /// jobs comparing captures in parallel.
+    pub fn stream(
+        old: &AbiReport,
+        new: &AbiReport,
+        options: DiffOptions,
+        filter: &DiffFilter,
+        mut f: impl FnMut(CaptureDiff),
+    ) -> DiffSummary {
+        let mut summary = DiffSummary::default();
+        let mut counted = |change: CaptureDiff| {
+            summary.count(&change);
+            f(change)
+        };
+        if filter.is_empty() {
+            each_change(old, new, options, &mut counted);
+            return summary;
+        }
+
+        let files = old
+            .captures()
+            .iter()
+            .chain(new.captures())
+            .filter(|capture| filter.keeps_file(capture))
+            .map(|capture| capture.filename.as_str())
+            .collect::<HashSet<_>>();
+        let (old, new) = (filter.narrow(old, &files), filter.narrow(new, &files));
+        each_change(&old, &new, options, |change| {
+            // files that came or went only matter for the symbols they bring or take along
+            if !filter.narrows_symbols()
+                || change.change == ChangeKind::Modified
+                || !change.exports.is_empty()
+                || !change.imports.is_empty()
+            {
+                counted(change)
+            }
+        });
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The changes by package name (None for files of no package), each in filename order.
+    pub fn by_package(&self) -> BTreeMap<Option<&str>, ReportDiff> {
+        let mut packages = BTreeMap::<_, ReportDiff>::new();
+        for change in &self.changes {
+            let package = packages.entry(change.package.as_deref()).or_default();
+            package.changes.push(change.clone());
+        }
+        packages
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(CaptureDiff::is_breaking)
+    }
+
+    pub fn summary(&self) -> DiffSummary {
+        let mut summary = DiffSummary::default();
+        for diff in &self.changes {
+            summary.count(diff);
+        }
+        summary
+    }
+}
+
+impl DiffSummary {
+    fn count(&mut self, diff: &CaptureDiff) {
+        match diff.change {
+            ChangeKind::Added => self.files_added += 1,
+            ChangeKind::Removed => self.files_removed += 1,
+            ChangeKind::Modified => self.files_modified += 1,
+        }
+        self.exports_added += diff.exports.added.len();
+        self.exports_removed += diff.exports.removed.len();
+        self.exports_resized += diff.resized.len();
+        self.breaking |= diff.is_breaking();
+    }
+}
+
+/// Pair up the captures of two reports and hand the (non-empty) diffs of each pair to `f`, in
+/// natural filename order, comparing `options.jobs` pairs in parallel.
+fn each_change(old: &AbiReport, new: &AbiReport, options: DiffOptions, f: impl FnMut(CaptureDiff)) {
+    let pending = pending(old.captures().iter(), new.captures().iter());
+    compute_each(&pending, |pair| pair.compute(options), options, f)
+}
+
+/// Compute the diff of each pair, `options.jobs` pairs in parallel, and hand the non-empty ones
+/// to `f` in the order of `pending`.
+fn compute_each<T: Sync>(
+    pending: &[T],
+    compute: impl Fn(&T) -> CaptureDiff + Sync,
+    options: DiffOptions,
+    mut f: impl FnMut(CaptureDiff),
+) {
+    let jobs = match options.jobs {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        jobs => jobs,
+    }
+    .min(pending.len());
+    if jobs <= 1 {
+        pending
+            .iter()
+            .map(&compute)
+            .filter(|diff| !diff.is_empty())
+            .for_each(f);
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    // bounded, so slow consumers (e.g. writing to a pipe) hold the workers back
+    let (sender, receiver) = mpsc::sync_channel(jobs);
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next, compute) = (&next, &compute);
+            scope.spawn(move || loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(pair) = pending.get(idx) else {
+                    break;
+                };
+                if sender.send((idx, compute(pair))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        // pairs complete out of order, hold on to their diffs until it's their turn
+        let mut done = BTreeMap::new();
+        let mut turn = 0;
+        for (idx, diff) in receiver {
+            done.insert(idx, diff);
+            while let Some(diff) = done.remove(&turn) {
+                if !diff.is_empty() {
+                    f(diff);
+                }
+                turn += 1;
+            }
+        }
+    });
+}
+
+/// The pairs of captures to compare, in natural filename order, leaving out unchanged files.
+fn pending<'a, I>(old: I, new: I) -> Vec<Pending<'a>>
+where
+    I: Iterator<Item = &'a AbiCapture> + Clone,
+{
+    let new_by_name = new
+        .clone()
+        .map(|capture| (capture.filename.as_str(), capture))
+        .collect::<HashMap<_, _>>();
+    // a file rebuilt for another architecture is a different file, not a changed one
+    let paired = old
+        .clone()
+        .filter(|old| {
+            new_by_name
+                .get(old.filename.as_str())
+                .is_some_and(|new| old.is_same_arch(new))
+        })
+        .map(|capture| capture.filename.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut pending = old
+        .filter_map(|old| match paired.contains(old.filename.as_str()) {
+            true => {
+                let new = new_by_name[old.filename.as_str()];
+                // the very same file on both sides has nothing to compare
+                (!old.identity().is_same_file(&new.identity()))
+                    .then_some(Pending::Modified(old, new))
+            }
+            false => Some(Pending::Removed(old)),
+        })
+        .chain(
+            new.filter(|new| !paired.contains(new.filename.as_str()))
+                .map(Pending::Added),
+        )
+        .collect::<Vec<_>>();
+    pending.sort_by(|a, b| a.filename().natural_cmp(b.filename()));
+    pending
+}
+
+impl Pending<'_> {
+    fn filename(&self) -> &str {
+        match self {
+            Self::Modified(_, new) | Self::Added(new) => &new.filename,
+            Self::Removed(old) => &old.filename,
+        }
+    }
+
+    fn compute(&self, options: DiffOptions) -> CaptureDiff {
+        match *self {
+            Self::Modified(old, new) => old.diff_with(new, options),
+            Self::Removed(old) => {
+                CaptureDiff::compute(old, &empty_like(old), ChangeKind::Removed, options)
+            }
+            Self::Added(new) => {
+                CaptureDiff::compute(&empty_like(new), new, ChangeKind::Added, options)
+            }
+        }
+    }
+}
+
+impl CaptureDiff {
+    /// A file that appeared, as compared to nothing.
+    pub fn added(new: &AbiCapture) -> Self {
+        Self::compute(
+            &empty_like(new),
+            new,
+            ChangeKind::Added,
+            DiffOptions::default(),
+        )
+    }
+
+    /// A file that disappeared, as compared to nothing.
+    pub fn removed(old: &AbiCapture) -> Self {
+        Self::compute(
+            old,
+            &empty_like(old),
+            ChangeKind::Removed,
+            DiffOptions::default(),
+        )
+    }
+
+    fn compute(
+        old: &AbiCapture,
+        new: &AbiCapture,
+        change: ChangeKind,
+        options: DiffOptions,
+    ) -> Self {
+        let (old, new) = match options.include_boilerplate {
+            true => (Cow::Borrowed(old), Cow::Borrowed(new)),
+            false => (without_boilerplate(old), without_boilerplate(new)),
+        };
+        let (old, new) = (old.as_ref(), new.as_ref());
+        let modified = change == ChangeKind::Modified;
+        let mut exports =
+            ListDiff::between_by(&old.dynsym_exports, &new.dynsym_exports, symbol_key);
+        let (dropped_aliases, removed) = exports
+            .removed
+            .into_iter()
+            .partition(|symbol| old.is_surviving_alias(symbol, new));
+        exports.removed = removed;
+        Self {
+            filename: new.filename.clone(),
+            change,
+            package: new
+                .package()
+                .or(old.package())
+                .map(|package| package.name.clone()),
+            // a file appearing or disappearing doesn't change kind or soname as such
+            elf_kind: ValueChange::between(old.elf_kind, new.elf_kind).filter(|_| modified),
+            soname: ValueChange::between(old.soname.clone(), new.soname.clone())
+                .filter(|_| modified),
+            rpath: ValueChange::between(old.rpath.clone(), new.rpath.clone()).filter(|_| modified),
+            runpath: ValueChange::between(old.runpath.clone(), new.runpath.clone())
+                .filter(|_| modified),
+            constructors: constructor_change(old, new).filter(|_| modified),
+            bind_now: known_change(old.bind_now, new.bind_now).filter(|_| modified),
+            relro: known_change(old.relro, new.relro).filter(|_| modified),
+            sha256: known_change(old.identity().sha256, new.identity().sha256)
+                .filter(|_| modified)
+                .map(|change| ValueChange {
+                    old: change.old.to_string(),
+                    new: change.new.to_string(),
+                }),
+            exports,
+            dropped_aliases,
+            resized: resized_objects(old, new),
+            default_versions: moved_default_versions(old, new),
+            imports: ListDiff::between_by(&old.dynsym_imports, &new.dynsym_imports, symbol_key),
+            needed: ListDiff::between(&old.needed_deps, &new.needed_deps),
+        }
+    }
+}
+
+impl DiffFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only compare the files matching `pattern` (or any of the other `only` patterns).
+    pub fn only(mut self, pattern: &str) -> Result<Self> {
+        self.only.push(file_glob(pattern)?);
+        Ok(self)
+    }
+
+    /// Leave out the files matching `pattern`, even if an `only` pattern matches them too.
+    pub fn exclude(mut self, pattern: &str) -> Result<Self> {
+        self.exclude.push(file_glob(pattern)?);
+        Ok(self)
+    }
+
+    /// Only compare the symbols matching `pattern` (or any of the other symbol patterns).
+    /// Files that came or went are then only listed if they have such symbols.
+    pub fn symbols(mut self, pattern: SymbolPattern) -> Self {
+        self.symbols.push(pattern);
+        self
+    }
+
+    /// Leave out the C++ and Rust mangled symbols ([Symbol::is_mangled]), comparing only the
+    /// `extern "C"` interface.
+    pub fn c_abi_only(mut self) -> Self {
+        self.c_abi_only = true;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty() && !self.narrows_symbols()
+    }
+
+    /// Whether only some of the symbols are compared.
+    fn narrows_symbols(&self) -> bool {
+        !self.symbols.is_empty() || self.c_abi_only
+    }
+
+    /// Whether the file is compared at all.
+    pub fn keeps_file(&self, capture: &AbiCapture) -> bool {
+        let name = Path::new(&capture.filename)
+            .file_name()
+            .map(|name| name.to_string_lossy());
+        let paths = [Some(capture.filename.as_str()), name.as_deref()];
+        let matches = |globs: &[GlobMatcher], names: &[Option<&str>]| {
+            globs
+                .iter()
+                .any(|glob| names.iter().flatten().any(|name| glob.is_match(name)))
+        };
+        let only = match self.only.is_empty() {
+            true => true,
+            false => matches(&self.only, &[paths[0], paths[1], capture.soname()]),
+        };
+        only && !matches(&self.exclude, &paths)
+    }
+
+    /// Whether symbols of this name are compared.
+    pub fn keeps_symbol(&self, name: &str) -> bool {
+        !(self.c_abi_only && is_mangled(name))
+            && (self.symbols.is_empty() || self.symbols.iter().any(|pattern| pattern.matches(name)))
+    }
+
+    /// The files of `report` named in `files`, with only the symbols the filter keeps.
+    fn narrow(&self, report: &AbiReport, files: &HashSet<&str>) -> AbiReport {
+        let captures = report
+            .captures()
+            .iter()
+            .filter(|capture| files.contains(capture.filename.as_str()))
+            .map(|capture| {
+                let mut capture = capture.clone();
+                if self.narrows_symbols() {
+                    capture.retain_symbols(|name| self.keeps_symbol(name));
+                }
+                capture
+            })
+            .collect();
+        AbiReport::with_header(report.header().clone(), captures)
+    }
+}
+
+impl ReportDiff {
+    /// Compare the parts of the reports `filter` keeps. A file kept on either side (say, by
+    /// its new soname) is compared on both.
+    pub fn between_filtered(
+        old: &AbiReport,
+        new: &AbiReport,
+        options: DiffOptions,
+        filter: &DiffFilter,
+    ) -> Self {
+        let mut changes = vec![];
+        Self::stream(old, new, options, filter, |change| changes.push(change));
+        Self { changes }
+    }
+}
+
+fn file_glob(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid pattern {pattern:?}: {err}"),
+            )
+        })
+}
+
+impl AbiCapture {
+    /// Compare this (old) capture to a new one, regardless of their filenames.
+    pub fn diff(&self, new: &AbiCapture) -> CaptureDiff {
+        self.diff_with(new, DiffOptions::default())
+    }
+
+    pub fn diff_with(&self, new: &AbiCapture, options: DiffOptions) -> CaptureDiff {
+        CaptureDiff::compute(self, new, ChangeKind::Modified, options)
+    }
+
+    /// The exports of this (old) capture that the new one no longer provides as they were:
+    /// removed, still there with a different type, or data objects with a different size.
+    /// Toolchain generated symbols never count, and neither do aliases whose canonical name the
+    /// new capture still exports.
+    pub fn broken_exports(&self, new: &AbiCapture) -> Vec<Symbol> {
+        let new_exports = new
+            .dynsym_exports
+            .iter()
+            .map(|symbol| (symbol_key(symbol), symbol))
+            .collect::<HashMap<_, _>>();
+        self.dynsym_exports
+            .iter()
+            .filter(|symbol| !symbol.is_boilerplate() && !self.is_surviving_alias(symbol, new))
+            .filter(|symbol| {
+                new_exports.get(&symbol_key(symbol)).is_none_or(|new| {
+                    new.kind != symbol.kind || object_size_change(symbol, new).is_some()
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl AbiReport {
+    /// Narrow this (new) report and `baseline`, a snapshot of the whole repository, down to the
+    /// files whose contents changed since the snapshot: those whose digest is the same on both
+    /// sides are left out before anything is paired up or copied, so diffing what is left gives
+    /// the diff of the whole at the cost of the changed files. Without digests, files count as
+    /// changed.
+    pub fn changed_since(&self, baseline: &AbiReport) -> ChangedSince {
+        let snapshot = baseline
+            .captures()
+            .iter()
+            .map(AbiCapture::identity)
+            .filter(|identity| identity.sha256.is_some())
+            .collect::<HashSet<_>>();
+        let unchanged = self
+            .captures()
+            .iter()
+            .map(AbiCapture::identity)
+            .filter(|identity| identity.sha256.is_some() && snapshot.contains(identity))
+            .collect::<HashSet<_>>();
+        let changed = |report: &AbiReport| {
+            let captures = report
+                .captures()
+                .iter()
+                .filter(|capture| !unchanged.contains(&capture.identity()))
+                .cloned()
+                .collect();
+            AbiReport::with_header(report.header().clone(), captures)
+        };
+        ChangedSince {
+            baseline: changed(baseline),
+            new: changed(self),
+            unchanged: unchanged.len(),
+        }
+    }
+
+    /// Compare this (old) report to a new one.
+    pub fn diff(&self, new: &AbiReport) -> ReportDiff {
+        ReportDiff::between(self, new)
+    }
+
+    pub fn diff_with(&self, new: &AbiReport, options: DiffOptions) -> ReportDiff {
+        ReportDiff::between_with(self, new, options)
+    }
+
+    pub fn diff_filtered(
+        &self,
+        new: &AbiReport,
+        options: DiffOptions,
+        filter: &DiffFilter,
+    ) -> ReportDiff {
+        ReportDiff::between_filtered(self, new, options, filter)
+    }
+}
+
+impl CompactReport {
+    /// See [AbiReport::diff].
+    pub fn diff(&self, new: &CompactReport) -> ReportDiff {
+        self.diff_with(new, DiffOptions::default())
+    }
+
+    /// See [AbiReport::diff_with]. Captures are paired up without their symbols, which are only
+    /// copied out of the arenas for the pairs being compared.
+    pub fn diff_with(&self, new: &CompactReport, options: DiffOptions) -> ReportDiff {
+        let by_name = |report| {
+            CompactReport::captures(report)
+                .map(|capture| (capture.filename(), capture))
+                .collect::<HashMap<_, _>>()
+        };
+        let (old_by_name, new_by_name) = (by_name(self), by_name(new));
+        let owned = |by_name: &HashMap<&str, CompactCapture>, capture: &AbiCapture| {
+            by_name[capture.filename()].to_capture()
+        };
+
+        let without_symbols = |report| {
+            CompactReport::captures(report)
+                .map(|capture| capture.without_symbols())
+                .collect::<Vec<_>>()
+        };
+        let (old_captures, new_captures) = (without_symbols(self), without_symbols(new));
+        let pending = pending(old_captures.iter().copied(), new_captures.iter().copied());
+        let mut changes = vec![];
+        let compute = |pair: &Pending| match *pair {
+            Pending::Modified(old, new) => {
+                owned(&old_by_name, old).diff_with(&owned(&new_by_name, new), options)
+            }
+            Pending::Removed(old) => Pending::Removed(&owned(&old_by_name, old)).compute(options),
+            Pending::Added(new) => Pending::Added(&owned(&new_by_name, new)).compute(options),
+        };
+        compute_each(&pending, compute, options, |change| changes.push(change));
+        ReportDiff { changes }
+    }
+}
+
+/// A symbol whose version changed is a different symbol to the dynamic linker, so it counts as
+/// removed and added; a changed type alone isn't reported.
+pub(crate) fn symbol_key(symbol: &Symbol) -> (String, Option<String>) {
+    (symbol.name.clone(), symbol.version.clone())
+}
+
+/// The exported data objects whose size changed, in the order of the new capture.
+fn resized_objects(old: &AbiCapture, new: &AbiCapture) -> Vec<SymbolResize> {
+    let old_exports = old
+        .dynsym_exports
+        .iter()
+        .map(|symbol| (symbol_key(symbol), symbol))
+        .collect::<HashMap<_, _>>();
+    new.dynsym_exports
+        .iter()
+        .filter_map(|symbol| {
+            let old = old_exports.get(&symbol_key(symbol))?;
+            Some(SymbolResize {
+                symbol: symbol.clone(),
+                size: object_size_change(old, symbol)?,
+            })
+        })
+        .collect()
+}
+
+/// The exports whose default version changed, in natural order.
+///
+/// Only symbols with a single default version on both sides count: captures from before
+/// compat versions were told apart have every version look like a default one.
+fn moved_default_versions(old: &AbiCapture, new: &AbiCapture) -> Vec<DefaultVersionMove> {
+    let old_defaults = default_versions(old);
+    let mut moves = default_versions(new)
+        .into_iter()
+        .filter_map(
+            |(name, new)| match (old_defaults.get(name)?.as_slice(), &new[..]) {
+                ([old], [new]) if old != new => Some(DefaultVersionMove {
+                    name: name.to_string(),
+                    version: ValueChange {
+                        old: old.to_string(),
+                        new: new.to_string(),
+                    },
+                }),
+                _ => None,
+            },
+        )
+        .collect::<Vec<_>>();
+    moves.sort_by(|a, b| a.name.natural_cmp(&b.name));
+    moves
+}
+
+/// The default versions of each versioned export.
+fn default_versions(capture: &AbiCapture) -> HashMap<&str, Vec<&str>> {
+    let mut versions = HashMap::<&str, Vec<&str>>::new();
+    for symbol in capture.dynsym_exports.iter().filter(|s| !s.compat) {
+        if let Some(version) = &symbol.version {
+            versions.entry(&symbol.name).or_default().push(version);
+        }
+    }
+    versions
+}
+
+/// How a value changed, if both captures recorded it.
+fn known_change<T: PartialEq>(old: Option<T>, new: Option<T>) -> Option<ValueChange<T>> {
+    ValueChange::between(old?, new?)
+}
+
+/// How the number of constructors changed, if both captures recorded it.
+fn constructor_change(old: &AbiCapture, new: &AbiCapture) -> Option<ValueChange<u64>> {
+    let (old, new) = (old.init_fini?, new.init_fini?);
+    ValueChange::between(old.constructors(), new.constructors())
+}
+
+/// How the size of a data object changed, if both sides know it (older captures don't).
+fn object_size_change(old: &Symbol, new: &Symbol) -> Option<ValueChange<u64>> {
+    match (old.kind, new.kind) {
+        (SymbolKind::Object, SymbolKind::Object) => ValueChange::between(old.size?, new.size?),
+        _ => None,
+    }
+}
+
+/// A copy of a capture without the toolchain generated symbols, if it has any.
+fn without_boilerplate(capture: &AbiCapture) -> Cow<'_, AbiCapture> {
+    let symbols = capture.dynsym_exports.iter().chain(&capture.dynsym_imports);
+    if !symbols.into_iter().any(Symbol::is_boilerplate) {
+        return Cow::Borrowed(capture);
+    }
+    let mut capture = capture.clone();
+    capture.retain_symbols(|name| !is_boilerplate(name));
+    Cow::Owned(capture)
+}
+
+/// The other side of a capture that is only present in one report
+pub(crate) fn empty_like(capture: &AbiCapture) -> AbiCapture {
+    AbiCapture::new(capture.filename.clone(), capture.elf_kind)
+}
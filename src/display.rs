@@ -0,0 +1,453 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Human readable output.
+//!
+//! A capture displays as a short summary (`{:#}` adds the full symbol lists), while a report
+//! displays as a table with one aligned row per capture.
+
+use crate::{
+    AbiCapture, AbiReport, Check, ElfKind, FailOn, Finding, Relro, ReportStats, Severity, Symbol,
+    VersionAdvice, VersionBump,
+};
+#[cfg(feature = "fs")]
+use crate::{SplitReason, Subpackage};
+use std::fmt;
+
+/// DATA counts the exported variables among the EXPORTS
+const TABLE_HEADINGS: [&str; 7] = [
+    "FILE", "KIND", "SONAME", "EXPORTS", "DATA", "IMPORTS", "NEEDED",
+];
+
+impl fmt::Display for ElfKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Executable => "executable",
+            Self::SharedObject => "shared object",
+            Self::Unknown => "unknown",
+        })
+    }
+}
+
+impl fmt::Display for Relro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::None => "none",
+            Self::Partial => "partial",
+            Self::Full => "full",
+        })
+    }
+}
+
+impl fmt::Display for AbiCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({})", self.filename, self.elf_kind)?;
+        let mode = self
+            .metadata
+            .as_ref()
+            .map(|metadata| match &metadata.capabilities {
+                Some(capabilities) => format!("{:04o} ({capabilities})", metadata.mode),
+                None => format!("{:04o}", metadata.mode),
+            });
+        let size = self
+            .metadata
+            .as_ref()
+            .map(|metadata| format!("{} bytes", metadata.size));
+        let package = self.package.as_ref().map(|package| package.to_string());
+        let optional = [
+            ("package", package.as_deref()),
+            ("size", size.as_deref()),
+            ("mode", mode.as_deref()),
+            ("soname", self.soname.as_deref()),
+            ("rpath", self.rpath.as_deref()),
+            ("runpath", self.runpath.as_deref()),
+            ("build-id", self.build_id.as_deref()),
+            (
+                "debuglink",
+                self.debuglink.as_ref().map(|link| link.filename.as_str()),
+            ),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                writeln!(f, "  {name:<10} {value}")?;
+            }
+        }
+        if !self.needed_deps.is_empty() {
+            writeln!(f, "  {:<10} {}", "needed", self.needed_deps.join(", "))?;
+        }
+        if let Some(relro) = self.relro {
+            writeln!(f, "  {:<10} {relro}", "relro")?;
+        }
+        if let Some(segments) = self.segments {
+            write!(
+                f,
+                "  {:<10} {} load, aligned to {:#x}",
+                "segments", segments.load, segments.align
+            )?;
+            if segments.writable_exec > 0 {
+                write!(f, ", {} writable and executable", segments.writable_exec)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(coverage) = self.visibility {
+            writeln!(
+                f,
+                "  {:<10} {} of {} defined symbols exported ({:.0}%)",
+                "visibility",
+                coverage.exported,
+                coverage.defined,
+                coverage.ratio() * 100.0
+            )?;
+        }
+        if let Some(tls) = self.thread_local.filter(|tls| tls.size > 0) {
+            let model = match tls.static_tls {
+                true => "static",
+                false => "dynamic",
+            };
+            writeln!(f, "  {:<10} {} bytes ({model})", "tls", tls.size)?;
+        }
+        if let Some(relocations) = self.relocations {
+            writeln!(
+                f,
+                "  {:<10} {} ({} plt, {} relative)",
+                "relocs",
+                relocations.total(),
+                relocations.plt,
+                relocations.relative
+            )?;
+        }
+        if let Some(init_fini) = self.init_fini {
+            if init_fini.constructors() > 0 || init_fini.destructors() > 0 {
+                writeln!(
+                    f,
+                    "  {:<10} {} constructors, {} destructors",
+                    "init/fini",
+                    init_fini.constructors(),
+                    init_fini.destructors()
+                )?;
+            }
+        }
+        let kinds = self.export_kinds();
+        write!(
+            f,
+            "  {:<10} {} symbols ({} functions, {} data",
+            "exports",
+            self.dynsym_exports.len(),
+            kinds.functions,
+            kinds.data
+        )?;
+        if kinds.other > 0 {
+            write!(f, ", {} other", kinds.other)?;
+        }
+        match kinds.version_nodes {
+            0 => writeln!(f, ")")?,
+            nodes => writeln!(f, ", {nodes} version nodes)")?,
+        }
+        if f.alternate() {
+            // broken down by kind, as exported data is the riskier part of the ABI
+            let heading = |symbol: &Symbol| match symbol.kind {
+                _ if symbol.is_version_node() => "version nodes",
+                kind if kind.is_function() => "functions",
+                kind if kind.is_data() => "data",
+                _ => "other",
+            };
+            for group in ["functions", "data", "other", "version nodes"] {
+                let mut symbols = self
+                    .dynsym_exports
+                    .iter()
+                    .filter(|symbol| heading(symbol) == group)
+                    .peekable();
+                if symbols.peek().is_some() {
+                    writeln!(f, "    {group}")?;
+                }
+                for symbol in symbols {
+                    writeln!(f, "      {symbol}")?;
+                }
+            }
+        }
+        if !self.protected_exports.is_empty() {
+            writeln!(
+                f,
+                "  {:<10} {} symbols",
+                "protected",
+                self.protected_exports.len()
+            )?;
+            if f.alternate() {
+                for symbol in &self.protected_exports {
+                    writeln!(f, "    {symbol}")?;
+                }
+            }
+        }
+        if !self.hidden_symbols.is_empty() {
+            writeln!(
+                f,
+                "  {:<10} {} symbols",
+                "hidden",
+                self.hidden_symbols.len()
+            )?;
+            if f.alternate() {
+                for symbol in &self.hidden_symbols {
+                    writeln!(f, "    {symbol}")?;
+                }
+            }
+        }
+        if !self.alias_groups.is_empty() {
+            writeln!(f, "  {:<10} {} groups", "aliases", self.alias_groups.len())?;
+            if f.alternate() {
+                for group in &self.alias_groups {
+                    writeln!(f, "    {} = {}", group.canonical, group.aliases.join(", "))?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "  {:<10} {} symbols",
+            "imports",
+            self.dynsym_imports.len()
+        )?;
+        if f.alternate() {
+            let resolved = self.dynsym_imports.iter().any(|s| s.provider.is_some());
+            if resolved {
+                // broken down by the library supplying them
+                for (provider, imports) in self.imports_by_provider() {
+                    writeln!(f, "    {}", provider.unwrap_or("(unattributed)"))?;
+                    for symbol in imports {
+                        writeln!(f, "      {symbol}")?;
+                    }
+                }
+            } else {
+                for symbol in &self.dynsym_imports {
+                    writeln!(f, "    {symbol}")?;
+                }
+            }
+        }
+        for warning in &self.warnings {
+            writeln!(f, "  warning    {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AbiReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .captures()
+            .iter()
+            .map(|capture| {
+                [
+                    capture.filename.clone(),
+                    capture.elf_kind.to_string(),
+                    capture.soname.clone().unwrap_or_else(|| "-".to_string()),
+                    capture.dynsym_exports.len().to_string(),
+                    capture.export_kinds().data.to_string(),
+                    capture.dynsym_imports.len().to_string(),
+                    capture.needed_deps.join(", "),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut widths = TABLE_HEADINGS.map(|heading| heading.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        write_row(f, &TABLE_HEADINGS, &widths)?;
+        for row in &rows {
+            write_row(f, row, &widths)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReportStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>8}", "files", self.files)?;
+        writeln!(f, "by kind")?;
+        for (kind, count) in &self.by_kind {
+            writeln!(f, "  {kind:<14} {count:>8}")?;
+        }
+        writeln!(f, "by arch")?;
+        for (arch, count) in &self.by_arch {
+            writeln!(f, "  {arch:<14} {count:>8}")?;
+        }
+        writeln!(f, "{:<16} {:>8}", "exports", self.exports)?;
+        writeln!(
+            f,
+            "  {:<14} {:>8}",
+            "functions", self.export_kinds.functions
+        )?;
+        writeln!(f, "  {:<14} {:>8}", "data", self.export_kinds.data)?;
+        if self.export_kinds.other > 0 {
+            writeln!(f, "  {:<14} {:>8}", "other", self.export_kinds.other)?;
+        }
+        if self.export_kinds.version_nodes > 0 {
+            writeln!(
+                f,
+                "  {:<14} {:>8}",
+                "version nodes", self.export_kinds.version_nodes
+            )?;
+        }
+        writeln!(f, "{:<16} {:>8}", "imports", self.imports)?;
+        writeln!(f, "{:<16} {:>8}", "relocations", self.relocations)?;
+        writeln!(f, "  {:<14} {:>8}", "symbolic", self.symbolic_relocations)?;
+
+        writeln!(f, "\nmost imported symbols (files)")?;
+        for count in &self.most_imported {
+            writeln!(f, "  {:>8}  {}", count.files, count.symbol)?;
+        }
+        writeln!(f, "\nlargest export surfaces (exports)")?;
+        for count in &self.largest_exporters {
+            writeln!(f, "  {:>8}  {}", count.exports, count.filename)?;
+        }
+        if !self.most_exported_data.is_empty() {
+            writeln!(f, "\nmost exported data (data/exports)")?;
+            for count in &self.most_exported_data {
+                let counts = format!("{}/{}", count.data, count.exports);
+                writeln!(f, "  {counts:>8}  {}", count.filename)?;
+            }
+        }
+        writeln!(f, "\nmost symbolic relocations (symbolic/total)")?;
+        for count in &self.most_relocated {
+            let counts = format!("{}/{}", count.symbolic, count.total);
+            writeln!(f, "  {counts:>8}  {}", count.filename)?;
+        }
+        if !self.least_hidden.is_empty() {
+            writeln!(f, "\nleast hidden (exported/defined in .symtab)")?;
+            for count in &self.least_hidden {
+                let counts = format!("{}/{}", count.exported, count.defined);
+                let share = count.exported as f64 / count.defined as f64 * 100.0;
+                writeln!(f, "  {counts:>8}  {share:>3.0}%  {}", count.filename)?;
+            }
+        }
+        writeln!(
+            f,
+            "\norphaned sonames (needed by nothing in the report): {}",
+            self.orphaned_sonames.len()
+        )?;
+        for soname in &self.orphaned_sonames {
+            writeln!(f, "  {soname}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write one row of aligned cells, with the counts right aligned and the last cell unpadded.
+fn write_row(
+    f: &mut fmt::Formatter<'_>,
+    cells: &[impl AsRef<str>],
+    widths: &[usize],
+) -> fmt::Result {
+    let last = cells.len() - 1;
+    for (idx, (cell, width)) in cells.iter().zip(widths).enumerate() {
+        let cell = cell.as_ref();
+        match idx {
+            _ if idx == last => writeln!(f, "{cell}")?,
+            3..=5 => write!(f, "{cell:>width$}  ")?,
+            _ => write!(f, "{cell:<width$}  ")?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for VersionBump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Revision => "c:r+1:a",
+            Self::Age => "c+1:0:a+1",
+            Self::Current => "c+1:0:0",
+        })
+    }
+}
+
+impl fmt::Display for VersionAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.bump {
+            VersionBump::Revision => "no exports added or removed",
+            VersionBump::Age => "exports added",
+            VersionBump::Current => "exports removed or changed",
+        };
+        write!(f, "{}: {what}, bump to {}", self.filename, self.bump)?;
+
+        let new_soname = self.new_soname.as_deref().unwrap_or("(none)");
+        match (self.bump, self.is_followed()) {
+            (VersionBump::Current, true) => write!(f, ", soname already changed to {new_soname}"),
+            (VersionBump::Current, false) => match &self.recommended_soname {
+                Some(soname) => write!(f, ", change the soname to {soname}"),
+                None => write!(f, ", change the soname"),
+            },
+            (_, true) => write!(f, ", keep the soname"),
+            (_, false) => write!(
+                f,
+                ", but the soname changed to {new_soname} (no exports were removed)"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+impl fmt::Display for FailOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Never => "never",
+        })
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.id())
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} [{}]: {}",
+            self.filename, self.severity, self.check, self.message
+        )
+    }
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for Subpackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Runtime => "runtime",
+            Self::Devel => "devel",
+        })
+    }
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for SplitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::LinkSymlink => "symlink for linking",
+            Self::StaticArchive => "static archive",
+            Self::LibtoolArchive => "libtool archive",
+            Self::Header => "header",
+            Self::PkgConfig => "pkg-config file",
+            Self::CMakeConfig => "CMake package config",
+            Self::Aclocal => "autoconf macros",
+            Self::SharedLibrary => "shared library",
+            Self::SonameSymlink => "soname symlink",
+            Self::Executable => "executable",
+            Self::Other => "other",
+        })
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Bloom filters over the names a capture exports.
+//!
+//! Looking for the files exporting a symbol across a whole distribution means going through
+//! millions of exports. Each capture carries a small filter of its export names (about ten bits
+//! per name), which rules out all but about 1% of the files not exporting a name without
+//! touching their symbol lists. The hashes are FNV-1a, so filters stay valid across platforms
+//! and releases; captures of older reports have no filter and are always searched.
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Filter bits per name, for about 1% false positives
+const BITS_PER_NAME: usize = 10;
+
+/// Hash functions (bits set) per name, optimal for [BITS_PER_NAME]
+const HASHES: u32 = 7;
+
+/// A bloom filter of export names: a name it doesn't contain is certainly not exported
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ExportFilter {
+    hashes: u32,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    bits: Vec<u8>, // as lowercase hex when serialized
+}
+
+impl ExportFilter {
+    /// A filter of `names`, None if there are none.
+    pub fn new<'a>(names: impl ExactSizeIterator<Item = &'a str>) -> Option<Self> {
+        if names.len() == 0 {
+            return None;
+        }
+        let bytes = (names.len() * BITS_PER_NAME)
+            .div_ceil(8)
+            .next_power_of_two();
+        let mut filter = Self {
+            hashes: HASHES,
+            bits: vec![0; bytes],
+        };
+        for name in names {
+            for bit in filter.bits_of(name) {
+                filter.bits[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        Some(filter)
+    }
+
+    /// Whether `name` may be in the filter (false means it certainly isn't).
+    pub fn may_contain(&self, name: &str) -> bool {
+        // a damaged filter rules nothing out
+        self.bits.is_empty()
+            || self.bits_of(name).all(|bit| {
+                self.bits
+                    .get(bit / 8)
+                    .is_some_and(|b| b & (1 << (bit % 8)) != 0)
+            })
+    }
+
+    /// The bits a name sets: double hashing of the two halves of its FNV-1a hash.
+    fn bits_of(&self, name: &str) -> impl Iterator<Item = usize> {
+        let hash = fnv1a(name.as_bytes());
+        let (h1, h2) = (hash as u32, (hash >> 32) as u32 | 1);
+        let len = self.bits.len() * 8;
+        (0..self.hashes).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) as usize % len)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("odd number of hex digits"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex digit"))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! On-disk report formats.
+//!
+//! JSON is the interchange format, but is far too bulky for repository wide baselines covering
+//! tens of thousands of binaries, where the same symbol and library names recur endlessly.
+//!
+//! The compact format is the very same document encoded as CBOR, with every string replaced by
+//! its index in an explicit string table, so each distinct string is stored once. The indices are
+//! marked with a tag of our own from the first come, first served range (0x616269, "abi"); this
+//! is not the registered stringref scheme (tag 25), whose references are implicit, although
+//! documents written with tag 25 by older versions are still read. Since it mirrors the JSON
+//! document, both formats share the schema migrations.
+//!
+//! Layout: `tag 55799 (self-described CBOR) { "strings": [...], "report": <interned report> }`
+
+use crate::stream::{JSON_LINES_PREFIX, RECORDS_MAGIC};
+use ciborium::Value as Cbor;
+use serde_json::{Map, Number, Value as Json};
+use std::collections::HashMap;
+use std::io::{BufRead, Error, ErrorKind, Read, Result, Write};
+
+/// CBOR "self-described CBOR" tag, doubling as our magic number (0xd9d9f7)
+const SELF_DESCRIBED_TAG: u64 = 55799;
+/// Our string index tag: the tagged integer is an index into the string table
+const STRING_INDEX_TAG: u64 = 0x61_62_69;
+/// The tag older versions (mis)used for string indices, still accepted when reading
+const OLD_STRING_INDEX_TAG: u64 = 25;
+const CBOR_MAGIC: &[u8] = &[0xd9, 0xd9, 0xf7];
+pub(crate) const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The serialization formats a report can be stored in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Cbor,
+    JsonLines, // streamed, see crate::stream
+    Records,   // streamed, see crate::stream
+}
+
+impl ReportFormat {
+    /// Guess the format from a file extension (looking through `.zst`), defaulting to JSON.
+    pub fn from_extension(path: &std::path::Path) -> Self {
+        let path = match is_zstd_path(path) {
+            true => std::path::Path::new(path.file_stem().unwrap_or_default()),
+            false => path,
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("cbor" | "bin") => Self::Cbor,
+            Some("jsonl") => Self::JsonLines,
+            Some("records") => Self::Records,
+            _ => Self::Json,
+        }
+    }
+
+    /// Sniff the format from the start of a buffered reader without consuming anything.
+    pub fn detect<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let buf = reader.fill_buf()?;
+        if buf.starts_with(CBOR_MAGIC) {
+            Ok(Self::Cbor)
+        } else if buf.starts_with(RECORDS_MAGIC) {
+            Ok(Self::Records)
+        } else if buf.starts_with(JSON_LINES_PREFIX) {
+            Ok(Self::JsonLines)
+        } else {
+            Ok(Self::Json)
+        }
+    }
+}
+
+/// Whether a file name asks for zstd compression, e.g. `report.json.zst`.
+pub fn is_zstd_path(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "zst")
+}
+
+/// Write a report document in the compact format.
+pub fn write_cbor<W: Write>(document: &Json, writer: W) -> Result<()> {
+    let mut interner = Interner::default();
+    let report = interner.intern(document);
+
+    let compact = Cbor::Tag(
+        SELF_DESCRIBED_TAG,
+        Box::new(Cbor::Map(vec![
+            (
+                Cbor::Text("strings".into()),
+                Cbor::Array(interner.strings.into_iter().map(Cbor::Text).collect()),
+            ),
+            (Cbor::Text("report".into()), report),
+        ])),
+    );
+
+    ciborium::into_writer(&compact, writer).map_err(|e| Error::other(e.to_string()))
+}
+
+/// Read a compact format report back into the equivalent JSON document.
+pub fn read_cbor<R: Read>(reader: R) -> Result<Json> {
+    let compact: Cbor = ciborium::from_reader(reader)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+    let Cbor::Tag(SELF_DESCRIBED_TAG, compact) = compact else {
+        return Err(invalid("missing self-described CBOR tag"));
+    };
+    let Cbor::Map(entries) = *compact else {
+        return Err(invalid("expected a map at the top level"));
+    };
+
+    let mut strings = None;
+    let mut report = None;
+    for (key, value) in entries {
+        match (key.as_text(), value) {
+            (Some("strings"), Cbor::Array(table)) => {
+                strings = Some(
+                    table
+                        .into_iter()
+                        .map(|s| {
+                            s.into_text()
+                                .map_err(|_| invalid("non-text string table entry"))
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            (Some("report"), value) => report = Some(value),
+            _ => {} // tolerate unknown top-level entries
+        }
+    }
+
+    let strings = strings.ok_or_else(|| invalid("missing string table"))?;
+    let report = report.ok_or_else(|| invalid("missing report"))?;
+    resolve(&report, &strings)
+}
+
+#[derive(Default)]
+struct Interner {
+    strings: Vec<String>,
+    index: HashMap<String, u64>,
+}
+
+impl Interner {
+    fn intern_str(&mut self, s: &str) -> Cbor {
+        let idx = match self.index.get(s) {
+            Some(&idx) => idx,
+            None => {
+                let idx = self.strings.len() as u64;
+                self.strings.push(s.to_string());
+                self.index.insert(s.to_string(), idx);
+                idx
+            }
+        };
+        Cbor::Tag(STRING_INDEX_TAG, Box::new(Cbor::Integer(idx.into())))
+    }
+
+    fn intern(&mut self, value: &Json) -> Cbor {
+        match value {
+            Json::Null => Cbor::Null,
+            Json::Bool(b) => Cbor::Bool(*b),
+            Json::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    Cbor::Integer(u.into())
+                } else if let Some(i) = n.as_i64() {
+                    Cbor::Integer(i.into())
+                } else {
+                    Cbor::Float(n.as_f64().unwrap_or_default())
+                }
+            }
+            Json::String(s) => self.intern_str(s),
+            Json::Array(items) => Cbor::Array(items.iter().map(|v| self.intern(v)).collect()),
+            Json::Object(map) => Cbor::Map(
+                map.iter()
+                    .map(|(k, v)| (self.intern_str(k), self.intern(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn resolve(value: &Cbor, strings: &[String]) -> Result<Json> {
+    Ok(match value {
+        Cbor::Null => Json::Null,
+        Cbor::Bool(b) => Json::Bool(*b),
+        Cbor::Integer(i) => {
+            let i = i128::from(*i);
+            if let Ok(u) = u64::try_from(i) {
+                Json::Number(u.into())
+            } else {
+                Json::Number(
+                    i64::try_from(i)
+                        .map_err(|_| invalid("integer out of range"))?
+                        .into(),
+                )
+            }
+        }
+        Cbor::Float(f) => Number::from_f64(*f).map(Json::Number).unwrap_or(Json::Null),
+        Cbor::Text(s) => Json::String(s.clone()),
+        Cbor::Tag(STRING_INDEX_TAG | OLD_STRING_INDEX_TAG, _) => {
+            Json::String(resolve_str(value, strings)?)
+        }
+        Cbor::Tag(_, inner) => resolve(inner, strings)?,
+        Cbor::Array(items) => Json::Array(
+            items
+                .iter()
+                .map(|v| resolve(v, strings))
+                .collect::<Result<_>>()?,
+        ),
+        Cbor::Map(entries) => {
+            let mut map = Map::with_capacity(entries.len());
+            for (k, v) in entries {
+                map.insert(resolve_str(k, strings)?, resolve(v, strings)?);
+            }
+            Json::Object(map)
+        }
+        Cbor::Bytes(_) => return Err(invalid("unexpected byte string")),
+        _ => return Err(invalid("unexpected CBOR value")),
+    })
+}
+
+fn resolve_str(value: &Cbor, strings: &[String]) -> Result<String> {
+    match value {
+        Cbor::Text(s) => Ok(s.clone()),
+        Cbor::Tag(STRING_INDEX_TAG | OLD_STRING_INDEX_TAG, idx) => {
+            let idx = idx
+                .as_integer()
+                .and_then(|i| usize::try_from(i128::from(i)).ok())
+                .ok_or_else(|| invalid("malformed string reference"))?;
+            strings
+                .get(idx)
+                .cloned()
+                .ok_or_else(|| invalid("dangling string reference"))
+        }
+        _ => Err(invalid("expected a string")),
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("malformed compact report: {msg}"),
+    )
+}
@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! `.abireportignore` files: excludes that live in the tree they apply to.
+//!
+//! A build can drop one next to its test fixtures, sample binaries or vendored code instead of
+//! every scan having to pass `--exclude` for them. The syntax is that of `.gitignore`: `#`
+//! comments, `!` to re-include, a trailing `/` to only match directories, and patterns
+//! containing a `/` anchored to the directory of the file (others match at any depth). `*`
+//! doesn't cross directories, `**` does. A file applies to its directory and everything below,
+//! the rules of deeper files take precedence, and within a file the last matching rule wins.
+
+use globset::{GlobBuilder, GlobMatcher};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// The name of the ignore file looked for in every searched directory
+pub(crate) const IGNORE_FILE: &str = ".abireportignore";
+
+#[derive(Debug)]
+struct Rule {
+    matcher: GlobMatcher, // against the path relative to the ignore file's directory
+    negated: bool,        // `!pattern`: keep what an earlier rule skipped
+    dir_only: bool,       // `pattern/`
+}
+
+/// The rules of one ignore file
+#[derive(Debug)]
+pub(crate) struct IgnoreFile {
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    /// The ignore file of a directory, if it has one.
+    pub(crate) fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(IGNORE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::new(err.kind(), format!("{}: {err}", path.display()))),
+        };
+        let mut rules = vec![];
+        for (idx, line) in contents.lines().enumerate() {
+            match parse_rule(line) {
+                Ok(rule) => rules.extend(rule),
+                Err(err) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{}:{}: {err}", path.display(), idx + 1),
+                    ))
+                }
+            }
+        }
+        Ok(Some(Self {
+            dir: dir.to_path_buf(),
+            rules,
+        }))
+    }
+
+    /// Some(true) if the last rule matching the path skips it, Some(false) if it keeps it, None
+    /// if no rule matches.
+    fn verdict(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(relative))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Whether the ignore files in effect (outermost first) skip a path.
+pub(crate) fn is_ignored(ignores: &[IgnoreFile], path: &Path, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .find_map(|ignore| ignore.verdict(path, is_dir))
+        .unwrap_or(false)
+}
+
+fn parse_rule(line: &str) -> std::result::Result<Option<Rule>, globset::Error> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    // `\#` and `\!` are left to the glob, which takes them literally
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        None => (false, line),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{pattern}"),
+    };
+    let matcher = GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher();
+    Ok(Some(Rule {
+        matcher,
+        negated,
+        dir_only,
+    }))
+}
@@ -0,0 +1,272 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Which binaries of a whole repository a library update affects.
+//!
+//! A dependent breaks if it imports an export the update removes (or changes the type of), and
+//! has to be rebuilt if the soname it links against goes away. This is the question behind
+//! planning a distro transition: what needs rebuilding, and what would break if it weren't.
+//!
+//! A soname transition is planned from the new library alone: every consumer of the old soname
+//! needs a rebuild, those importing symbols the new library no longer provides need porting
+//! first. Consumers are rebuilt in waves, each only needing the consumers of earlier waves, so
+//! libraries are relinked before the files linking against them.
+
+use crate::diff::empty_like;
+use crate::{soname_stem, AbiCapture, AbiReport, NaturalSortable, ReportIndex, Symbol};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A dependent of a changed library, and how the change affects it
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RebuildImpact {
+    pub filename: String,            // the affected file in the repository
+    pub library: String,             // the DT_NEEDED entry naming the changed library
+    pub broken_imports: Vec<Symbol>, // imports the new library no longer provides as they were
+    pub soname_gone: bool,           // no file in the update provides `library` any more
+}
+
+impl RebuildImpact {
+    /// Whether the file would fail to load (or misbehave) against the new library.
+    pub fn breaks(&self) -> bool {
+        !self.broken_imports.is_empty()
+    }
+}
+
+/// The plan for moving a repository from one soname of a library to a new one
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SonameTransition {
+    pub old_soname: String,
+    pub new_soname: String,
+    pub old_library: Option<String>, // the file providing the old soname, if in the repository
+    pub rebuild: Vec<String>,        // consumers a rebuild against the new library suffices for
+    pub port: Vec<RebuildImpact>,    // consumers importing symbols the new library lacks
+    pub waves: Vec<Vec<String>>,     // all consumers, in the order to rebuild them
+}
+
+impl ReportIndex {
+    /// Plan the transition of the consumers of `old_soname` to `new`, a capture of the new
+    /// library. Without `old_soname`, it's the naturally greatest other soname of this report
+    /// with the same stem (`libicuuc.so.74` for `libicuuc.so.75`) having consumers; None if
+    /// there is none.
+    ///
+    /// Consumers are files of this report needing the old soname, of the new library's
+    /// architecture, in report order. Whether their imports survive is decided as in
+    /// [ReportIndex::rebuild_impact], so without the old library in the report nothing is
+    /// known to break.
+    pub fn soname_transition(
+        &self,
+        new: &AbiCapture,
+        old_soname: Option<&str>,
+    ) -> Option<SonameTransition> {
+        let new_soname = needed_name(new);
+        let old_soname = match old_soname {
+            Some(old_soname) => old_soname,
+            None => self
+                .report()
+                .captures()
+                .iter()
+                .filter_map(AbiCapture::soname)
+                .filter(|soname| {
+                    *soname != new_soname
+                        && soname_stem(soname) == soname_stem(new_soname)
+                        && !self.soname_dependents(soname).is_empty()
+                })
+                .max_by(|a, b| a.natural_cmp(b))?,
+        };
+        let old_library = self
+            .soname_providers(old_soname)
+            .into_iter()
+            .find(|library| library.is_same_arch(new));
+        let broken = old_library.map_or_else(Vec::new, |old| old.broken_exports(new));
+
+        let consumers = self
+            .soname_dependents(old_soname)
+            .into_iter()
+            .filter(|consumer| consumer.is_same_arch(new))
+            .collect::<Vec<_>>();
+        let mut rebuild = vec![];
+        let mut port = vec![];
+        for consumer in &consumers {
+            let broken_imports = consumer
+                .dynsym_imports
+                .iter()
+                .filter(|import| breaks(import, &broken, new))
+                .cloned()
+                .collect::<Vec<_>>();
+            match broken_imports.is_empty() {
+                true => rebuild.push(consumer.filename.clone()),
+                false => port.push(RebuildImpact {
+                    filename: consumer.filename.clone(),
+                    library: old_soname.to_string(),
+                    broken_imports,
+                    soname_gone: true,
+                }),
+            }
+        }
+
+        Some(SonameTransition {
+            old_soname: old_soname.to_string(),
+            new_soname: new_soname.to_string(),
+            old_library: old_library.map(|library| library.filename.clone()),
+            rebuild,
+            port,
+            waves: rebuild_waves(&consumers),
+        })
+    }
+
+    /// The files of this (repository) report affected by going from the `old` to the `new`
+    /// build of a library package, in report order (then by library).
+    ///
+    /// Libraries are paired up by filename, as in [AbiReport::diff]; a library missing from
+    /// `new` breaks everything importing from it. Files that are part of the update itself are
+    /// left out, as they are rebuilt along with it.
+    pub fn rebuild_impact(&self, old: &AbiReport, new: &AbiReport) -> Vec<RebuildImpact> {
+        let new_by_name = new
+            .captures()
+            .iter()
+            .map(|capture| (capture.filename.as_str(), capture))
+            .collect::<HashMap<_, _>>();
+        let new_sonames = new
+            .captures()
+            .iter()
+            .map(needed_name)
+            .collect::<HashSet<_>>();
+        // the update's own files are rebuilt with it
+        let updated = old
+            .captures()
+            .iter()
+            .chain(new.captures())
+            .map(AbiCapture::filename)
+            .collect::<HashSet<_>>();
+
+        let mut impacts = vec![];
+        for old_library in old.captures() {
+            let removed;
+            let new_library = match new_by_name.get(old_library.filename.as_str()) {
+                Some(new_library) if new_library.is_same_arch(old_library) => *new_library,
+                _ => {
+                    removed = empty_like(old_library);
+                    &removed
+                }
+            };
+            let library = needed_name(old_library);
+            let soname_gone = !new_sonames.contains(library);
+            let broken = old_library.broken_exports(new_library);
+            if broken.is_empty() && !soname_gone {
+                continue;
+            }
+
+            let dependents = self.soname_dependents(library);
+            for dependent in dependents
+                .iter()
+                .filter(|d| !updated.contains(d.filename()) && d.is_same_arch(old_library))
+            {
+                let broken_imports = dependent
+                    .dynsym_imports
+                    .iter()
+                    .filter(|import| breaks(import, &broken, new_library))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if broken_imports.is_empty() && !soname_gone {
+                    continue;
+                }
+                impacts.push(RebuildImpact {
+                    filename: dependent.filename.clone(),
+                    library: library.to_string(),
+                    broken_imports,
+                    soname_gone,
+                });
+            }
+        }
+
+        let order = self
+            .report()
+            .captures()
+            .iter()
+            .enumerate()
+            .map(|(idx, capture)| (capture.filename.as_str(), idx))
+            .collect::<HashMap<_, _>>();
+        impacts.sort_by(|a, b| {
+            (order[a.filename.as_str()], &a.library).cmp(&(order[b.filename.as_str()], &b.library))
+        });
+        impacts
+    }
+}
+
+/// Order files into waves, each file only needing (the sonames of) files of earlier waves, in
+/// report order within a wave. Files needing each other end up together in the last wave.
+fn rebuild_waves(files: &[&AbiCapture]) -> Vec<Vec<String>> {
+    let provided = files
+        .iter()
+        .enumerate()
+        .map(|(idx, file)| (needed_name(file), idx))
+        .collect::<HashMap<_, _>>();
+    // for each file, the other files it needs
+    let mut needs = files
+        .iter()
+        .enumerate()
+        .map(|(idx, file)| {
+            file.needed_deps()
+                .iter()
+                .filter_map(|soname| provided.get(soname.as_str()).copied())
+                .filter(|&dep| dep != idx)
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut waves = vec![];
+    let mut pending = (0..files.len()).collect::<Vec<_>>();
+    while !pending.is_empty() {
+        let (ready, blocked) = pending
+            .iter()
+            .partition::<Vec<_>, _>(|&&idx| needs[idx].is_empty());
+        let wave = match ready.is_empty() {
+            true => blocked.clone(),
+            false => ready,
+        };
+        for need in &mut needs {
+            need.retain(|dep| !wave.contains(dep));
+        }
+        pending.retain(|idx| !wave.contains(idx));
+        waves.push(
+            wave.iter()
+                .map(|&idx| files[idx].filename.clone())
+                .collect(),
+        );
+    }
+    waves
+}
+
+/// What dependents list in DT_NEEDED to get this library: its soname, else its file name.
+fn needed_name(library: &AbiCapture) -> &str {
+    library.soname().unwrap_or_else(|| {
+        let filename = library.filename();
+        filename.rsplit('/').next().unwrap_or(filename)
+    })
+}
+
+/// Whether an import is satisfied by one of the `broken` exports only.
+///
+/// A versioned import names exactly one export. An unversioned one binds to whichever version
+/// the library offers, so it only breaks if the new library has no version of the same type.
+fn breaks(import: &Symbol, broken: &[Symbol], new_library: &AbiCapture) -> bool {
+    let mut broken = broken.iter().filter(|export| export.name == import.name);
+    match &import.version {
+        Some(_) => broken.any(|export| export.version == import.version),
+        None => broken.any(|export| {
+            !new_library
+                .dynsym_exports
+                .iter()
+                .any(|new| new.name == export.name && new.kind == export.kind)
+        }),
+    }
+}
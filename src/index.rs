@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Indexed lookups over a report.
+//!
+//! Building the index costs a pass over every symbol of every capture, after which "who
+//! provides/needs X" are hash lookups. Worth it for long-running consumers answering many
+//! queries; one-off lookups can just scan the report.
+
+use crate::{AbiCapture, AbiReport};
+use std::collections::HashMap;
+
+/// A report plus lookup tables from sonames and symbol names to the captures involved
+#[derive(Debug)]
+pub struct ReportIndex {
+    report: AbiReport,
+    sonames: HashMap<String, Vec<usize>>, // DT_SONAME -> captures
+    needed: HashMap<String, Vec<usize>>,  // DT_NEEDED -> captures
+    exports: HashMap<String, Vec<(usize, Option<String>)>>, // name -> (capture, version)
+    imports: HashMap<String, Vec<(usize, Option<String>)>>, // name -> (capture, version)
+}
+
+impl ReportIndex {
+    pub fn new(report: AbiReport) -> Self {
+        let mut sonames = HashMap::<_, Vec<_>>::new();
+        let mut needed = HashMap::<_, Vec<_>>::new();
+        let mut exports = HashMap::<_, Vec<_>>::new();
+        let mut imports = HashMap::<_, Vec<_>>::new();
+        for (idx, capture) in report.captures().iter().enumerate() {
+            if let Some(soname) = capture.soname() {
+                sonames.entry(soname.to_string()).or_default().push(idx);
+            }
+            for dep in capture.needed_deps() {
+                needed.entry(dep.clone()).or_default().push(idx);
+            }
+            for symbol in capture.dynsym_exports() {
+                let entry = exports.entry(symbol.name.clone()).or_default();
+                entry.push((idx, symbol.version.clone()));
+            }
+            for symbol in capture.dynsym_imports() {
+                let entry = imports.entry(symbol.name.clone()).or_default();
+                entry.push((idx, symbol.version.clone()));
+            }
+        }
+        Self {
+            report,
+            sonames,
+            needed,
+            exports,
+            imports,
+        }
+    }
+
+    pub fn report(&self) -> &AbiReport {
+        &self.report
+    }
+
+    /// The captures with this DT_SONAME, in report order.
+    pub fn soname_providers(&self, soname: &str) -> Vec<&AbiCapture> {
+        self.captures(self.sonames.get(soname).map_or(&[], Vec::as_slice))
+    }
+
+    /// The captures with a DT_NEEDED entry for this soname, in report order.
+    pub fn soname_dependents(&self, soname: &str) -> Vec<&AbiCapture> {
+        self.captures(self.needed.get(soname).map_or(&[], Vec::as_slice))
+    }
+
+    /// The captures exporting this symbol (in any version if `version` is None).
+    pub fn symbol_providers(&self, name: &str, version: Option<&str>) -> Vec<&AbiCapture> {
+        self.symbol_captures(&self.exports, name, version)
+    }
+
+    /// The captures importing this symbol (in any version if `version` is None).
+    pub fn symbol_consumers(&self, name: &str, version: Option<&str>) -> Vec<&AbiCapture> {
+        self.symbol_captures(&self.imports, name, version)
+    }
+
+    fn symbol_captures(
+        &self,
+        table: &HashMap<String, Vec<(usize, Option<String>)>>,
+        name: &str,
+        version: Option<&str>,
+    ) -> Vec<&AbiCapture> {
+        let mut indices = table
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|(_, v)| version.is_none() || v.as_deref() == version)
+            .map(|(idx, _)| *idx)
+            .collect::<Vec<_>>();
+        // a file may carry several versions of the same symbol
+        indices.dedup();
+        self.captures(&indices)
+    }
+
+    fn captures(&self, indices: &[usize]) -> Vec<&AbiCapture> {
+        let captures = self.report.captures();
+        indices.iter().map(|&idx| &captures[idx]).collect()
+    }
+}
@@ -0,0 +1,1269 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Incremental inspection of a single ELF file.
+//!
+//! Only the ELF and program headers are parsed up front; the dynamic section, the .dynsym and
+//! the notes are each parsed the first time they are asked for (and then kept). Callers that
+//! only want to know the kind or the soname of a file never pay for symbol extraction.
+
+use crate::abitag::{parse_abi_tag, AbiTag};
+use crate::alias::{alias_groups, AliasCandidate, AliasGroup};
+use crate::backend::assemble;
+#[cfg(feature = "fs")]
+use crate::backend::read_file;
+use crate::debuginfo::{parse_build_id, parse_debuglink, DebugLink};
+use crate::limits::Deadline;
+use crate::symbol::{is_boilerplate, Symbol, SymbolKind, SymbolRef};
+use crate::{
+    natural_sorted, AbiCapture, AbiCaptureRef, CaptureWarning, ElfKind, Limits, ObjectBackend,
+    ParseOptions,
+};
+use elf::abi::{
+    DF_1_NODELETE, DF_1_NOW, DF_1_PIE, DF_BIND_NOW, DF_STATIC_TLS, DT_AUDIT, DT_BIND_NOW,
+    DT_CONFIG, DT_DEPAUDIT, DT_FINI, DT_FINI_ARRAYSZ, DT_FLAGS, DT_FLAGS_1, DT_INIT,
+    DT_INIT_ARRAYSZ, DT_JMPREL, DT_NEEDED, DT_NULL, DT_PLTREL, DT_PLTRELSZ, DT_REL, DT_RELA,
+    DT_RELACOUNT, DT_RELAENT, DT_RELASZ, DT_RELCOUNT, DT_RELENT, DT_RELSZ, DT_RPATH, DT_RUNPATH,
+    DT_SONAME, DT_STRSZ, DT_STRTAB, EM_386, EM_AARCH64, EM_ARM, EM_MIPS, EM_PPC, EM_PPC64,
+    EM_RISCV, EM_S390, EM_X86_64, ET_DYN, ET_EXEC, PF_W, PF_X, PT_GNU_RELRO, PT_INTERP, PT_LOAD,
+    PT_TLS, STB_GLOBAL, STB_LOCAL, STB_WEAK, STT_FILE, STT_FUNC, STT_GNU_IFUNC, STT_OBJECT,
+    STT_TLS, STV_DEFAULT, STV_PROTECTED, VER_FLG_BASE,
+};
+use elf::dynamic::DynamicTable;
+use elf::endian::AnyEndian;
+use elf::file::Class;
+use elf::gnu_symver::SymbolVersionTable;
+use elf::string_table::StringTable;
+use elf::to_str::{d_tag_to_str, e_machine_to_string};
+use elf::{CommonElfData, ElfBytes, ParseError};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Result};
+
+pub(crate) const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+
+// filters, which elf.rs has no constants for
+const DT_AUXILIARY: i64 = 0x7fff_fffd;
+const DT_FILTER: i64 = 0x7fff_ffff;
+
+/// Dynamic tags whose value is an offset into the dynamic string table
+const STRING_TAGS: &[i64] = &[
+    DT_NEEDED,
+    DT_SONAME,
+    DT_RPATH,
+    DT_RUNPATH,
+    DT_AUXILIARY,
+    DT_FILTER,
+    DT_CONFIG,
+    DT_DEPAUDIT,
+    DT_AUDIT,
+];
+
+/// What the dynamic section says about a file
+#[derive(Clone, Debug, Default)]
+pub struct DynamicInfo {
+    pub needed: Vec<String>,               // DT_NEEDED, natural sorted
+    pub needed_order: Vec<String>,         // DT_NEEDED, in link order
+    pub rpath: Option<String>,             // DT_RPATH
+    pub runpath: Option<String>,           // DT_RUNPATH
+    pub soname: Option<String>,            // DT_SONAME
+    pub init_fini: Option<InitFini>,       // None without a dynamic section
+    pub relocations: Option<Relocations>,  // None without a dynamic section
+    pub bind_now: Option<bool>, // DT_BIND_NOW or a NOW flag; None without a dynamic section
+    pub relro: Option<Relro>,   // None without a dynamic section
+    pub thread_local: Option<ThreadLocal>, // None without a dynamic section
+    pub nodelete: Option<bool>, // DF_1_NODELETE; None without a dynamic section
+    pub segments: Option<Segments>, // None without program headers
+    pub raw: Vec<DynamicEntry>, // every entry, only when capturing them raw
+}
+
+/// An entry of the dynamic section as it is, for tags no other field covers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DynamicEntry {
+    pub tag: String, // the DT_* name, or the number in hex for tags elf.rs doesn't know
+    pub value: u64,  // d_val (or d_ptr)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub string: Option<String>, // what the value points to, for string valued tags
+}
+
+/// How much of a file's relocated data is made read-only after relocation (RELRO).
+///
+/// With a PT_GNU_RELRO segment, the GOT entries of data references are protected, but the PLT
+/// part of the GOT stays writable for lazy binding. Only when the file is bound immediately
+/// (BIND_NOW) is all of it protected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Relro {
+    None,    // no PT_GNU_RELRO
+    Partial, // PT_GNU_RELRO, lazily bound
+    Full,    // PT_GNU_RELRO and BIND_NOW
+}
+
+/// What the program headers say about how a file is mapped into memory.
+///
+/// A segment both writable and executable breaks W^X: whatever gets written into it can be run.
+/// The alignment of the loadable segments bounds the page size a file can be mapped with, so
+/// files linked for 4 KiB pages don't load on kernels with 16 KiB or 64 KiB pages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Segments {
+    pub load: u32,          // PT_LOAD segments
+    pub writable_exec: u32, // PT_LOAD segments with both PF_W and PF_X
+    pub relro: bool,        // a PT_GNU_RELRO segment
+    pub align: u64,         // the smallest PT_LOAD p_align, 0 without PT_LOAD
+}
+
+/// The thread-local storage of a file, and how it is accessed.
+///
+/// Code built with the initial-exec TLS model (and the linker then setting DF_STATIC_TLS)
+/// expects its TLS block at a fixed offset from the thread pointer, i.e. in the static TLS area
+/// allocated for every thread at startup. That works for whatever is loaded at startup; a
+/// library dlopen()ed later has to fit into what little surplus the area has.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ThreadLocal {
+    pub size: u64,        // of the per-thread block (PT_TLS p_memsz), 0 without PT_TLS
+    pub static_tls: bool, // DF_STATIC_TLS: accessed with the initial-exec (or local-exec) model
+}
+
+/// What runs when a file is loaded and unloaded, according to its dynamic section.
+///
+/// Every entry of the init array is a static constructor (or a function marked
+/// `__attribute__((constructor))`) run before anything else in the file can be used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct InitFini {
+    pub init_array: u64, // DT_INIT_ARRAYSZ entries
+    pub fini_array: u64, // DT_FINI_ARRAYSZ entries
+    pub init: bool,      // DT_INIT (the legacy _init function)
+    pub fini: bool,      // DT_FINI (the legacy _fini function)
+}
+
+impl InitFini {
+    /// The functions run when the file is loaded.
+    pub fn constructors(&self) -> u64 {
+        self.init_array + u64::from(self.init)
+    }
+
+    /// The functions run when the file is unloaded.
+    pub fn destructors(&self) -> u64 {
+        self.fini_array + u64::from(self.fini)
+    }
+}
+
+/// The visible .dynsym entries of a file
+#[derive(Clone, Debug, Default)]
+pub struct DynamicSymbols {
+    pub imports: Vec<Symbol>,     // undefined symbols, natural sorted
+    pub exports: Vec<Symbol>,     // defined and visible symbols, natural sorted
+    pub protected: Vec<Symbol>,   // STV_PROTECTED definitions, whatever the ExportPolicy
+    pub hidden: Vec<Symbol>,      // defined but not visible, only when auditing visibility
+    pub aliases: Vec<AliasGroup>, // exports sharing a definition
+    pub visibility: Option<VisibilityCoverage>, // from .symtab, only when auditing visibility
+}
+
+/// How much of what a file defines it exports, from its .symtab (so only for unstripped files).
+/// Libraries built without `-fvisibility=hidden` or a version script export nearly everything
+/// they define, internals included, which all becomes ABI. Linking turns hidden definitions
+/// into local ones just like static ones, so static functions count as defined too: the share
+/// exported is a measure to compare and track, it never quite reaches 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct VisibilityCoverage {
+    pub defined: u64, // functions and objects, hidden, static or exported, but not the toolchain's
+    pub exported: u64, // the ones among them that are dynamically visible
+}
+
+impl VisibilityCoverage {
+    /// The share of the defined symbols that are exported, from 0 to 1 (0 if nothing is).
+    pub fn ratio(&self) -> f64 {
+        match self.defined {
+            0 => 0.0,
+            defined => self.exported as f64 / defined as f64,
+        }
+    }
+}
+
+/// Which defined .dynsym entries count as exports
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportPolicy {
+    /// Global and weak symbols with default visibility
+    #[default]
+    Default,
+    /// Also STV_PROTECTED symbols, which are dynamically visible but can't be interposed (they
+    /// are captured separately as well, see [AbiCapture::protected_exports])
+    IncludeProtected,
+    /// Only global symbols with default visibility, leaving out weak (overridable) definitions
+    GlobalOnly,
+}
+
+impl ExportPolicy {
+    fn exports(self, dynsym: &elf::symbol::Symbol) -> bool {
+        // st_vis() returns > 0 if flags other than STB_GLOBAL or STB_WEAK are set
+        match self {
+            Self::Default => dynsym.st_vis() == STV_DEFAULT,
+            Self::IncludeProtected => matches!(dynsym.st_vis(), STV_DEFAULT | STV_PROTECTED),
+            Self::GlobalOnly => dynsym.st_vis() == STV_DEFAULT && dynsym.st_bind() == STB_GLOBAL,
+        }
+    }
+}
+
+/// How many dynamic relocations the dynamic linker has to process for a file.
+///
+/// Relative relocations are cheap (an addition each); all others look up a symbol, which is
+/// what makes up most of the relocation cost at startup. PLT relocations are only processed on
+/// first call, unless the file is bound immediately (BIND_NOW).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Relocations {
+    pub plt: u64,      // DT_JMPREL entries (DT_PLTRELSZ)
+    pub dynamic: u64,  // DT_RELA/DT_REL entries other than the PLT ones
+    pub relative: u64, // DT_RELACOUNT/DT_RELCOUNT, the relative ones among the dynamic ones
+}
+
+impl Relocations {
+    pub fn total(&self) -> u64 {
+        self.plt + self.dynamic
+    }
+
+    /// The relocations that need a symbol lookup.
+    pub fn symbolic(&self) -> u64 {
+        self.total() - self.relative.min(self.dynamic)
+    }
+}
+
+/// The notes and debug info pointers of a file
+#[derive(Clone, Debug, Default)]
+struct Notes {
+    build_id: Option<String>,
+    debuglink: Option<DebugLink>,
+    abi_tag: Option<AbiTag>,
+}
+
+/// An opened ELF file, parsed lazily.
+///
+/// The file data is held in memory for as long as the inspector lives.
+pub struct ElfInspector {
+    filename: String,
+    data: Vec<u8>,
+    limits: Limits,
+    deadline: Deadline, // started when the file was opened
+    export_policy: ExportPolicy,
+    audit_hidden: bool, // keep the defined symbols that aren't visible
+    raw_dynamic: bool,  // keep every dynamic entry
+    kind: ElfKind,
+    arch: String,
+    warnings: Vec<CaptureWarning>,
+    dynamic: Option<DynamicInfo>,    // parsed on first use
+    symbols: Option<DynamicSymbols>, // parsed on first use
+    notes: Option<Notes>,            // parsed on first use
+}
+
+impl ElfInspector {
+    /// Read the file and parse its headers, within `limits`.
+    #[cfg(feature = "fs")]
+    pub fn open(file_name: &str, limits: &Limits) -> Result<Self> {
+        // TODO: which error type might be useful here...?
+        let deadline = limits.deadline();
+        let data = read_file(file_name, limits)?;
+        Self::inspect(file_name, data, limits, deadline)
+    }
+
+    /// Parse the headers of a file already in memory, within `limits`.
+    ///
+    /// `file_name` only labels the capture; nothing is read from the filesystem.
+    pub fn from_bytes(file_name: &str, data: Vec<u8>, limits: &Limits) -> Result<Self> {
+        let deadline = limits.deadline();
+        limits.check_file_size(data.len() as u64)?;
+        Self::inspect(file_name, data, limits, deadline)
+    }
+
+    fn inspect(
+        file_name: &str,
+        mut data: Vec<u8>,
+        limits: &Limits,
+        deadline: Deadline,
+    ) -> Result<Self> {
+        let mut warnings = vec![];
+
+        // A truncated file usually loses its section header table first (it sits at the very
+        // end), which makes elf.rs refuse the whole file. Retry with the section headers
+        // disregarded, so whatever the program headers still describe can be captured.
+        if let Err(err) = ElfBytes::<AnyEndian>::minimal_parse(&data) {
+            let stripped = strip_section_headers(&mut data)
+                && ElfBytes::<AnyEndian>::minimal_parse(&data).is_ok();
+            if !stripped {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Could not parse {file_name:?} as ELF data: {err}"),
+                ));
+            }
+            warnings.push(CaptureWarning::SectionHeaders {
+                error: err.to_string(),
+            });
+        }
+
+        let elf_file = parse(&data);
+        let kind = elf_kind(&elf_file);
+        let arch = arch_name(&elf_file);
+        Ok(Self {
+            filename: file_name.to_string(),
+            data,
+            limits: limits.clone(),
+            deadline,
+            export_policy: ExportPolicy::default(),
+            audit_hidden: false,
+            raw_dynamic: false,
+            kind,
+            arch,
+            warnings,
+            dynamic: None,
+            symbols: None,
+            notes: None,
+        })
+    }
+
+    /// Decide which symbols count as exports (this only affects symbols not parsed yet).
+    pub fn with_export_policy(mut self, export_policy: ExportPolicy) -> Self {
+        self.export_policy = export_policy;
+        self
+    }
+
+    /// Also keep the defined .dynsym entries that aren't exported (hidden, internal or left out
+    /// by the export policy), and measure the share of .symtab exported, to check visibility
+    /// annotations against. Like the export policy, this only affects symbols not parsed yet.
+    pub fn with_hidden_audit(mut self, audit_hidden: bool) -> Self {
+        self.audit_hidden = audit_hidden;
+        self
+    }
+
+    /// Also keep every entry of the dynamic section, see [AbiCapture::dynamic_entries]. This
+    /// only affects the dynamic section if it isn't parsed yet.
+    pub fn with_raw_dynamic(mut self, raw_dynamic: bool) -> Self {
+        self.raw_dynamic = raw_dynamic;
+        self
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Whether this is an executable (PIE included) or a shared object, decided up front.
+    pub fn kind(&self) -> ElfKind {
+        self.kind
+    }
+
+    /// The target architecture (e_machine), e.g. "x86_64" or "aarch64".
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    /// Problems encountered so far; these only grow as more of the file is parsed.
+    pub fn warnings(&self) -> &[CaptureWarning] {
+        &self.warnings
+    }
+
+    pub fn dynamic(&mut self) -> &DynamicInfo {
+        let dynamic = match self.dynamic.take() {
+            Some(dynamic) => dynamic,
+            None => {
+                let elf_file = parse(&self.data);
+                let mut warnings = vec![];
+                let tables = find_dynamic_data(&elf_file, &self.data, &mut warnings);
+                let (needed, rpath, runpath, soname) =
+                    parse_dynamic_section(&tables, &mut warnings);
+                let init_fini = parse_init_fini(&tables, elf_file.ehdr.class);
+                let relocations = parse_relocations(&tables, elf_file.ehdr.class);
+                let bind_now = tables.dynamic.as_ref().map(is_bind_now);
+                let relro = bind_now.map(|bind_now| relro(&elf_file, bind_now));
+                let thread_local = tables
+                    .dynamic
+                    .as_ref()
+                    .map(|dynamic| thread_local(&elf_file, dynamic));
+                let nodelete = tables
+                    .dynamic
+                    .as_ref()
+                    .map(|dynamic| has_flag(dynamic, DT_FLAGS_1, DF_1_NODELETE));
+                let raw = match self.raw_dynamic {
+                    true => parse_raw_dynamic(&tables),
+                    false => vec![],
+                };
+                let segments = segments(&elf_file);
+                self.warn(warnings);
+                DynamicInfo {
+                    needed: natural_sorted(needed.iter().cloned()),
+                    needed_order: needed,
+                    rpath,
+                    runpath,
+                    soname,
+                    init_fini,
+                    relocations,
+                    bind_now,
+                    relro,
+                    thread_local,
+                    nodelete,
+                    segments,
+                    raw,
+                }
+            }
+        };
+        self.dynamic.insert(dynamic)
+    }
+
+    pub fn soname(&mut self) -> Option<&str> {
+        self.dynamic().soname.as_deref()
+    }
+
+    /// The imported and exported symbols, failing if the .dynsym exceeds the limits.
+    pub fn symbols(&mut self) -> Result<&DynamicSymbols> {
+        let symbols = match self.symbols.take() {
+            Some(symbols) => symbols,
+            None => {
+                let elf_file = parse(&self.data);
+                let mut warnings = vec![];
+                let tables = find_dynamic_data(&elf_file, &self.data, &mut warnings);
+                let versions = elf_file.symbol_version_table().unwrap_or_else(|err| {
+                    warnings.push(CaptureWarning::SymbolVersions {
+                        error: err.to_string(),
+                    });
+                    None
+                });
+                let mut symbols = parse_dynsyms_section(
+                    &tables,
+                    versions.as_ref(),
+                    &self.data,
+                    &self.limits,
+                    self.deadline,
+                    self.options(),
+                    &mut warnings,
+                )?;
+                if self.audit_hidden {
+                    symbols.visibility =
+                        visibility_coverage(&elf_file, self.deadline, &mut warnings)?;
+                }
+                self.warn(warnings);
+                symbols
+            }
+        };
+        Ok(self.symbols.insert(symbols))
+    }
+
+    pub fn build_id(&mut self) -> Option<&str> {
+        self.notes().build_id.as_deref()
+    }
+
+    pub fn debuglink(&mut self) -> Option<&DebugLink> {
+        self.notes().debuglink.as_ref()
+    }
+
+    pub fn abi_tag(&mut self) -> Option<AbiTag> {
+        self.notes().abi_tag
+    }
+
+    /// Parse everything into a full capture.
+    pub fn capture(mut self) -> Result<AbiCapture> {
+        self.symbols()?;
+        self.deadline.check()?;
+        self.notes();
+        self.deadline.check()?;
+        Ok(self.into_capture())
+    }
+
+    /// Parse everything into a capture whose symbols borrow their names and versions from the
+    /// file data rather than copying them, see [AbiCaptureRef]. The symbols are parsed anew,
+    /// whether [Self::symbols] parsed them before or not.
+    pub fn capture_ref(&mut self) -> Result<AbiCaptureRef<'_>> {
+        self.dynamic();
+        self.notes();
+        self.deadline.check()?;
+
+        let elf_file = parse(&self.data);
+        let mut warnings = vec![];
+        let tables = find_dynamic_data(&elf_file, &self.data, &mut warnings);
+        let versions = elf_file.symbol_version_table().unwrap_or_else(|err| {
+            warnings.push(CaptureWarning::SymbolVersions {
+                error: err.to_string(),
+            });
+            None
+        });
+        let symbols = parse_dynsym_refs(
+            &tables,
+            versions.as_ref(),
+            &self.data,
+            &self.limits,
+            self.deadline,
+            self.options(),
+            &mut warnings,
+        )?;
+        let visibility = match self.audit_hidden {
+            true => visibility_coverage(&elf_file, self.deadline, &mut warnings)?,
+            false => None,
+        };
+        self.deadline.check()?;
+        // not through warn(), as the symbols hold on to the data
+        merge_warnings(&mut self.warnings, warnings);
+
+        let notes = self.notes.clone().unwrap_or_default();
+        let rest = DynamicSymbols {
+            aliases: symbols.aliases,
+            visibility,
+            ..DynamicSymbols::default()
+        };
+        let dynamic = self.dynamic.clone().unwrap_or_default();
+        let capture = AbiCapture {
+            build_id: notes.build_id,
+            debuglink: notes.debuglink,
+            abi_tag: notes.abi_tag,
+            warnings: self.warnings.clone(),
+            ..assemble(
+                self.filename.clone(),
+                self.kind,
+                self.arch.clone(),
+                dynamic,
+                rest,
+            )
+        };
+        Ok(AbiCaptureRef::new(
+            capture,
+            symbols.imports,
+            symbols.exports,
+            symbols.protected,
+            symbols.hidden,
+        ))
+    }
+
+    /// Capture just the dynamic section, see [crate::parse_elf_sonames].
+    pub fn capture_sonames(mut self) -> AbiCapture {
+        self.dynamic();
+        self.into_capture()
+    }
+
+    /// Assemble a capture from whatever has been parsed so far.
+    fn into_capture(mut self) -> AbiCapture {
+        let dynamic = self.dynamic().clone();
+        let symbols = self.symbols.take().unwrap_or_default();
+        let notes = self.notes.take().unwrap_or_default();
+
+        AbiCapture {
+            build_id: notes.build_id,
+            debuglink: notes.debuglink,
+            abi_tag: notes.abi_tag,
+            warnings: self.warnings,
+            ..assemble(self.filename, self.kind, self.arch, dynamic, symbols)
+        }
+    }
+
+    fn notes(&mut self) -> &Notes {
+        let notes = match self.notes.take() {
+            Some(notes) => notes,
+            None => {
+                let elf_file = parse(&self.data);
+                let mut warnings = vec![];
+                let build_id = parse_build_id(&elf_file, &self.limits).unwrap_or_else(|err| {
+                    warnings.push(CaptureWarning::BuildId {
+                        error: err.to_string(),
+                    });
+                    None
+                });
+                let debuglink = parse_debuglink(&elf_file, &self.limits).unwrap_or_else(|err| {
+                    warnings.push(CaptureWarning::DebugLink {
+                        error: err.to_string(),
+                    });
+                    None
+                });
+                // the notes the ABI tag is in are the build-id's, already warned about
+                let abi_tag = parse_abi_tag(&elf_file, &self.limits).ok().flatten();
+                self.warn(warnings);
+                Notes {
+                    build_id,
+                    debuglink,
+                    abi_tag,
+                }
+            }
+        };
+        self.notes.insert(notes)
+    }
+
+    /// The export policy and capture options this inspector was configured with, for the symbol
+    /// table parsers.
+    fn options(&self) -> ParseOptions {
+        ParseOptions {
+            export_policy: self.export_policy,
+            audit_hidden: self.audit_hidden,
+            raw_dynamic: self.raw_dynamic,
+        }
+    }
+
+    /// Record new warnings, skipping any already seen (the dynamic and symbol parts share the
+    /// lookup of the dynamic tables, so they run into the same problems).
+    fn warn(&mut self, warnings: Vec<CaptureWarning>) {
+        merge_warnings(&mut self.warnings, warnings);
+    }
+}
+
+fn merge_warnings(warnings: &mut Vec<CaptureWarning>, new: Vec<CaptureWarning>) {
+    for warning in new {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+}
+
+impl ObjectBackend for ElfInspector {
+    fn detect(header: &[u8]) -> bool {
+        header.starts_with(ELF_MAGIC)
+    }
+
+    fn parse(
+        file_name: &str,
+        data: Vec<u8>,
+        limits: &Limits,
+        options: ParseOptions,
+    ) -> Result<Self> {
+        Ok(Self::from_bytes(file_name, data, limits)?
+            .with_export_policy(options.export_policy)
+            .with_hidden_audit(options.audit_hidden)
+            .with_raw_dynamic(options.raw_dynamic))
+    }
+
+    fn classify(&self) -> ElfKind {
+        self.kind
+    }
+
+    fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    fn deps(&mut self) -> &DynamicInfo {
+        self.dynamic()
+    }
+
+    fn symbols(&mut self) -> Result<&DynamicSymbols> {
+        ElfInspector::symbols(self)
+    }
+
+    fn capture(self: Box<Self>) -> Result<AbiCapture> {
+        ElfInspector::capture(*self)
+    }
+
+    fn capture_sonames(self: Box<Self>) -> AbiCapture {
+        ElfInspector::capture_sonames(*self)
+    }
+
+    /// Filters the symbols before copying them, see [ElfInspector::capture_ref].
+    fn capture_filtered(mut self: Box<Self>, keep: &dyn Fn(&str) -> bool) -> Result<AbiCapture> {
+        let mut capture = self.capture_ref()?;
+        capture.retain_symbols(keep);
+        Ok(capture.into_owned())
+    }
+}
+
+/// Re-parse the (already validated) headers; this is cheap, as elf.rs parses lazily too.
+fn parse(data: &[u8]) -> ElfBytes<'_, AnyEndian> {
+    ElfBytes::minimal_parse(data).expect("validated by ElfInspector::inspect")
+}
+
+/// The customary (uname -m style) name of the target architecture, falling back to elf.rs'
+/// EM_* names for the less common ones.
+fn arch_name(elf_file: &ElfBytes<AnyEndian>) -> String {
+    let is_64 = elf_file.ehdr.class == Class::ELF64;
+    let name = match elf_file.ehdr.e_machine {
+        EM_X86_64 if is_64 => "x86_64",
+        EM_X86_64 => "x32",
+        EM_386 => "i686",
+        EM_AARCH64 => "aarch64",
+        EM_ARM => "arm",
+        EM_RISCV if is_64 => "riscv64",
+        EM_RISCV => "riscv32",
+        EM_PPC64 => "ppc64",
+        EM_PPC => "ppc",
+        EM_S390 if is_64 => "s390x",
+        EM_S390 => "s390",
+        EM_MIPS if is_64 => "mips64",
+        EM_MIPS => "mips",
+        e_machine => return e_machine_to_string(e_machine).to_lowercase(),
+    };
+    name.to_string()
+}
+
+fn elf_kind(elf_file: &ElfBytes<AnyEndian>) -> ElfKind {
+    match elf_file.ehdr.e_type {
+        ET_EXEC => ElfKind::Executable,
+        ET_DYN if is_pie(elf_file) => ElfKind::Executable,
+        ET_DYN => ElfKind::SharedObject,
+        _ => ElfKind::Unknown,
+    }
+}
+
+/// PIE executables are ET_DYN too, but unlike (most) shared objects they ask for an interpreter.
+///
+/// Some libraries (libc, libcap) are runnable and have one as well, so those are told apart by
+/// DF_1_PIE, or for older linkers that don't set it, by the lack of a DT_SONAME. Only the
+/// dynamic tags are looked at, never the strings.
+fn is_pie(elf_file: &ElfBytes<AnyEndian>) -> bool {
+    let has_interp = elf_file
+        .segments()
+        .is_some_and(|phdrs| phdrs.iter().any(|phdr| phdr.p_type == PT_INTERP));
+    if !has_interp {
+        return false;
+    }
+
+    let Ok(Some(dynamic)) = elf_file.dynamic() else {
+        return true;
+    };
+    let mut has_soname = false;
+    for entry in dynamic.iter() {
+        match entry.d_tag {
+            DT_FLAGS_1 if entry.d_val() & DF_1_PIE as u64 != 0 => return true,
+            DT_SONAME => has_soname = true,
+            _ => {}
+        }
+    }
+    !has_soname
+}
+
+/// Whether the dynamic linker resolves all symbols at load time rather than on first use.
+fn is_bind_now(dynamic: &DynamicTable<AnyEndian>) -> bool {
+    dynamic.iter().any(|entry| match entry.d_tag {
+        DT_BIND_NOW => true,
+        DT_FLAGS => entry.d_val() & DF_BIND_NOW as u64 != 0,
+        DT_FLAGS_1 => entry.d_val() & DF_1_NOW as u64 != 0,
+        _ => false,
+    })
+}
+
+fn thread_local(elf_file: &ElfBytes<AnyEndian>, dynamic: &DynamicTable<AnyEndian>) -> ThreadLocal {
+    let size = elf_file
+        .segments()
+        .and_then(|phdrs| phdrs.iter().find(|phdr| phdr.p_type == PT_TLS))
+        .map_or(0, |phdr| phdr.p_memsz);
+    ThreadLocal {
+        size,
+        static_tls: has_flag(dynamic, DT_FLAGS, DF_STATIC_TLS),
+    }
+}
+
+/// Whether the DT_FLAGS or DT_FLAGS_1 (`tag`) entry has `flag` set.
+fn has_flag(dynamic: &DynamicTable<AnyEndian>, tag: i64, flag: i64) -> bool {
+    dynamic
+        .iter()
+        .any(|entry| entry.d_tag == tag && entry.d_val() & flag as u64 != 0)
+}
+
+fn segments(elf_file: &ElfBytes<AnyEndian>) -> Option<Segments> {
+    let phdrs = elf_file.segments()?;
+    let mut segments = Segments::default();
+    for phdr in phdrs.iter() {
+        match phdr.p_type {
+            PT_LOAD => {
+                segments.load += 1;
+                if phdr.p_flags & PF_W != 0 && phdr.p_flags & PF_X != 0 {
+                    segments.writable_exec += 1;
+                }
+                segments.align = match segments.align {
+                    0 => phdr.p_align,
+                    align => align.min(phdr.p_align),
+                };
+            }
+            PT_GNU_RELRO => segments.relro = true,
+            _ => {}
+        }
+    }
+    Some(segments)
+}
+
+fn relro(elf_file: &ElfBytes<AnyEndian>, bind_now: bool) -> Relro {
+    let has_relro = elf_file
+        .segments()
+        .is_some_and(|phdrs| phdrs.iter().any(|phdr| phdr.p_type == PT_GNU_RELRO));
+    match (has_relro, bind_now) {
+        (false, _) => Relro::None,
+        (true, false) => Relro::Partial,
+        (true, true) => Relro::Full,
+    }
+}
+
+/// Zero out the section header table fields of the ELF header, so the file parses from its
+/// program headers alone. Returns false if the header itself is too short to patch.
+fn strip_section_headers(file_data: &mut [u8]) -> bool {
+    // (e_shoff, e_shoff width, e_shnum) offsets per EI_CLASS
+    let (shoff, width, shnum) = match file_data.get(elf::abi::EI_CLASS) {
+        Some(&elf::abi::ELFCLASS32) => (0x20, 4, 0x30),
+        Some(&elf::abi::ELFCLASS64) => (0x28, 8, 0x3c),
+        _ => return false,
+    };
+    // e_shstrndx directly follows e_shnum
+    if file_data.len() < shnum + 4 {
+        return false;
+    }
+    file_data[shoff..shoff + width].fill(0);
+    file_data[shnum..shnum + 4].fill(0);
+    true
+}
+
+/// Count the defined and exported functions and objects of .symtab, None if there is none.
+/// Whatever the toolchain adds (the CRT startup files' locals, reserved `_X`/`__x` names) is
+/// left out, as no visibility annotation could hide it anyway.
+fn visibility_coverage(
+    elf_file: &ElfBytes<AnyEndian>,
+    deadline: Deadline,
+    warnings: &mut Vec<CaptureWarning>,
+) -> Result<Option<VisibilityCoverage>> {
+    let (symtab, strtab) = match elf_file.symbol_table() {
+        Ok(Some(tables)) => tables,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            warnings.push(CaptureWarning::SymbolTable {
+                error: err.to_string(),
+            });
+            return Ok(None);
+        }
+    };
+    let mut coverage = VisibilityCoverage {
+        defined: 0,
+        exported: 0,
+    };
+    // locals follow the STT_FILE entry of the source file they come from
+    let mut in_crt_file = false;
+    for (idx, symbol) in symtab.iter().enumerate().skip(1) {
+        if idx % 4096 == 0 {
+            deadline.check()?;
+        }
+        let name = strtab.get(symbol.st_name as usize).unwrap_or_default();
+        let kind = symbol.st_symtype();
+        if kind == STT_FILE {
+            in_crt_file = name.starts_with("crt");
+            continue;
+        }
+        let bind = symbol.st_bind();
+        let reserved = name.starts_with("__")
+            || name.starts_with('_') && name[1..].starts_with(|c: char| c.is_ascii_uppercase());
+        if symbol.is_undefined()
+            || !matches!(kind, STT_FUNC | STT_OBJECT | STT_TLS | STT_GNU_IFUNC)
+            || name.is_empty()
+            || reserved
+            || is_boilerplate(name)
+            || (bind == STB_LOCAL && in_crt_file)
+        {
+            continue;
+        }
+        coverage.defined += 1;
+        if bind != STB_LOCAL && matches!(symbol.st_vis(), STV_DEFAULT | STV_PROTECTED) {
+            coverage.exported += 1;
+        }
+    }
+    Ok(Some(coverage))
+}
+
+/// Like [ElfBytes::find_common_data], but only for the parts we use, and without letting one
+/// broken section (or an unrelated one, like .symtab) take the others down with it.
+fn find_dynamic_data<'data>(
+    elf_file: &ElfBytes<'data, AnyEndian>,
+    file_data: &'data [u8],
+    warnings: &mut Vec<CaptureWarning>,
+) -> CommonElfData<'data, AnyEndian> {
+    let mut result = CommonElfData::default();
+
+    match elf_file.dynamic_symbol_table() {
+        Ok(Some((dynsyms, dynsyms_strs))) => {
+            result.dynsyms = Some(dynsyms);
+            result.dynsyms_strs = Some(dynsyms_strs);
+        }
+        Ok(None) => {}
+        Err(err) => warnings.push(CaptureWarning::DynamicSymbols {
+            error: err.to_string(),
+        }),
+    }
+
+    // (this falls back to PT_DYNAMIC by itself if there are no section headers)
+    match elf_file.dynamic() {
+        Ok(dynamic) => result.dynamic = dynamic,
+        Err(err) => warnings.push(CaptureWarning::Dynamic {
+            error: err.to_string(),
+        }),
+    }
+
+    // Without section headers there is no .dynstr either, so use the DT_STRTAB it points to
+    if result.dynsyms_strs.is_none() && elf_file.section_headers().is_none() {
+        if let Some(dynamic) = &result.dynamic {
+            result.dynsyms_strs = dynamic_strtab(elf_file, dynamic, file_data);
+        }
+    }
+
+    result
+}
+
+/// Locate the dynamic string table through DT_STRTAB/DT_STRSZ and the program headers.
+fn dynamic_strtab<'data>(
+    elf_file: &ElfBytes<'data, AnyEndian>,
+    dynamic: &DynamicTable<'data, AnyEndian>,
+    file_data: &'data [u8],
+) -> Option<StringTable<'data>> {
+    let addr = dynamic.iter().find(|d| d.d_tag == DT_STRTAB)?.d_ptr();
+    let size = dynamic.iter().find(|d| d.d_tag == DT_STRSZ)?.d_val();
+
+    // DT_STRTAB is a virtual address, so map it back to a file offset via its PT_LOAD segment
+    let load = elf_file.segments()?.iter().find(|phdr| {
+        phdr.p_type == PT_LOAD && addr >= phdr.p_vaddr && addr - phdr.p_vaddr < phdr.p_filesz
+    })?;
+    let start = usize::try_from(load.p_offset.checked_add(addr - load.p_vaddr)?).ok()?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    file_data.get(start..end).map(StringTable::new)
+}
+
+fn parse_dynsyms_section(
+    common_elf_data: &CommonElfData<AnyEndian>,
+    versions: Option<&SymbolVersionTable<AnyEndian>>,
+    file_data: &[u8],
+    limits: &Limits,
+    deadline: Deadline,
+    options: ParseOptions,
+    warnings: &mut Vec<CaptureWarning>,
+) -> Result<DynamicSymbols> {
+    let symbols = parse_dynsym_refs(
+        common_elf_data,
+        versions,
+        file_data,
+        limits,
+        deadline,
+        options,
+        warnings,
+    )?;
+    let owned = |symbols: Vec<SymbolRef>| symbols.iter().map(SymbolRef::to_symbol).collect();
+    Ok(DynamicSymbols {
+        imports: owned(symbols.imports),
+        exports: owned(symbols.exports),
+        protected: owned(symbols.protected),
+        hidden: owned(symbols.hidden),
+        aliases: symbols.aliases,
+        visibility: None,
+    })
+}
+
+/// The visible .dynsym entries of a file, borrowing their names and versions from it
+#[derive(Debug, Default)]
+struct SymbolRefs<'data> {
+    imports: Vec<SymbolRef<'data>>, // natural sorted, as all of them
+    exports: Vec<SymbolRef<'data>>, // see DynamicSymbols for which is which
+    protected: Vec<SymbolRef<'data>>,
+    hidden: Vec<SymbolRef<'data>>,
+    aliases: Vec<AliasGroup>,
+}
+
+fn parse_dynsym_refs<'data>(
+    common_elf_data: &CommonElfData<'data, AnyEndian>,
+    versions: Option<&SymbolVersionTable<'data, AnyEndian>>,
+    file_data: &'data [u8],
+    limits: &Limits,
+    deadline: Deadline,
+    options: ParseOptions,
+    warnings: &mut Vec<CaptureWarning>,
+) -> Result<SymbolRefs<'data>> {
+    // detached debug files (and fully static executables) have no .dynsym to speak of
+    let (Some(dynsyms), Some(strtab)) = (
+        common_elf_data.dynsyms.as_ref(),
+        common_elf_data.dynsyms_strs.as_ref(),
+    ) else {
+        return Ok(SymbolRefs::default());
+    };
+    limits.check_dynsyms(dynsyms.len())?;
+
+    let mut symbols = SymbolRefs::default();
+    let mut definitions = Vec::new(); // (address, size, weak) of each export, for alias_groups()
+
+    // entry 0 is always the reserved undefined symbol (STN_UNDEF), so skip it
+    for (idx, dynsym) in dynsyms.iter().enumerate().skip(1) {
+        if idx % 4096 == 0 {
+            deadline.check()?;
+        }
+
+        // find the type of each symbol (imported or exported)
+        // each dynsym entry has a string table entry associated with it
+        let ds = match strtab.get(dynsym.st_name as usize) {
+            Ok(name) => name,
+            Err(err) => {
+                warnings.push(CaptureWarning::SymbolName {
+                    index: idx,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let imported = dynsym.is_undefined();
+        // TODO: build our own, more discerning visibility function here (cf. clearlinux's abireport tool)
+        let exported = !dynsym.is_undefined() && options.export_policy.exports(&dynsym);
+        // part of the ABI as well, but they don't play well with copy relocations
+        let is_protected = !dynsym.is_undefined() && dynsym.st_vis() == STV_PROTECTED;
+
+        // defined but not visible: not part of the ABI, only kept when auditing visibility
+        let is_hidden = !imported && !exported && !is_protected;
+        if is_hidden && (!options.audit_hidden || ds.is_empty()) {
+            continue;
+        }
+
+        let mut symbol = SymbolRef {
+            name: ds,
+            version: None,
+            compat: false,
+            kind: SymbolKind::from_st_type(dynsym.st_symtype()),
+            provider: None,
+            size: None,
+        };
+        // the size of a data object is part of the ABI (see CaptureDiff::resized)
+        if !imported && matches!(symbol.kind, SymbolKind::Function | SymbolKind::Object) {
+            symbol.size = Some(dynsym.st_size);
+        }
+        match symbol_version(versions, file_data, idx, imported) {
+            Ok(Some((version, hidden))) => {
+                symbol.version = Some(version);
+                symbol.compat = hidden;
+            }
+            Ok(None) => {}
+            Err(err) => warnings.push(CaptureWarning::SymbolVersion {
+                index: idx,
+                error: err.to_string(),
+            }),
+        }
+
+        if imported {
+            // we import (= rely on) undefined symbols (currenly the only constraint)
+            symbols.imports.push(symbol);
+        } else if is_hidden {
+            symbols.hidden.push(symbol);
+        } else {
+            if is_protected {
+                symbols.protected.push(symbol);
+            }
+            // only export defined and visible symbols (see ExportPolicy)
+            if exported {
+                if matches!(
+                    symbol.kind,
+                    SymbolKind::Function | SymbolKind::Object | SymbolKind::Ifunc
+                ) {
+                    let weak = dynsym.st_bind() == STB_WEAK;
+                    definitions.push((
+                        dynsym.st_value,
+                        dynsym.st_size,
+                        weak,
+                        symbols.exports.len(),
+                    ));
+                }
+                symbols.exports.push(symbol);
+            }
+        }
+    }
+
+    symbols.aliases =
+        alias_groups(
+            definitions
+                .into_iter()
+                .map(|(address, size, weak, idx)| AliasCandidate {
+                    address,
+                    size,
+                    name: symbols.exports[idx].name,
+                    weak,
+                }),
+        );
+    symbols.imports.sort_by(SymbolRef::natural_cmp);
+    symbols.exports.sort_by(SymbolRef::natural_cmp);
+    symbols.protected.sort_by(SymbolRef::natural_cmp);
+    symbols.hidden.sort_by(SymbolRef::natural_cmp);
+    Ok(symbols)
+}
+
+/// The GNU symbol version of the .dynsym entry at `idx`, if it has one, and whether it is
+/// hidden, i.e. not the default version of the symbol (`foo@VER` rather than `foo@@VER`).
+///
+/// Imports are looked up in the version requirements (.gnu.version_r), exports in the version
+/// definitions (.gnu.version_d). The base definition (the soname itself) is not a version.
+///
+/// The name is one of `file_data`'s, like those of the symbols.
+fn symbol_version<'data>(
+    versions: Option<&SymbolVersionTable<'data, AnyEndian>>,
+    file_data: &'data [u8],
+    idx: usize,
+    imported: bool,
+) -> std::result::Result<Option<(&'data str, bool)>, ParseError> {
+    let Some(versions) = versions else {
+        return Ok(None);
+    };
+    if imported {
+        return Ok(versions
+            .get_requirement(idx)?
+            .and_then(|requirement| in_file(file_data, requirement.name))
+            .map(|name| (name, false)));
+    }
+    match versions.get_definition(idx)? {
+        Some(definition) if definition.flags & VER_FLG_BASE == 0 => {
+            let mut names = definition.names;
+            let name = names.next().transpose()?;
+            Ok(name
+                .and_then(|name| in_file(file_data, name))
+                .map(|name| (name, definition.hidden)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `text` (a string of `file_data`) as borrowed from `file_data`. elf.rs hands version names
+/// out borrowed from its version table rather than from the file, so they are looked up again.
+/// elf.rs parses in place, so they are always found.
+fn in_file<'data>(file_data: &'data [u8], text: &str) -> Option<&'data str> {
+    let start = (text.as_ptr() as usize).wrapping_sub(file_data.as_ptr() as usize);
+    let end = start.checked_add(text.len())?;
+    std::str::from_utf8(file_data.get(start..end)?).ok()
+}
+
+fn parse_dynamic_section(
+    common_elf_data: &CommonElfData<AnyEndian>,
+    warnings: &mut Vec<CaptureWarning>,
+) -> (
+    Vec<String>,    // dt_needed, in link order
+    Option<String>, // dt_rpath
+    Option<String>, // dt_runpath
+    Option<String>, // dt_soname
+) {
+    // default values if everything goes to shit
+    let mut dt_needed = vec![];
+    let mut dt_rpath = None;
+    let mut dt_runpath = None;
+    let mut dt_soname = None;
+
+    if let Some(dynamic) = &common_elf_data.dynamic {
+        if let Some(dynsyms_strs) = &common_elf_data.dynsyms_strs {
+            for entry in dynamic.iter() {
+                if !matches!(entry.d_tag, DT_NEEDED | DT_RPATH | DT_RUNPATH | DT_SONAME) {
+                    continue;
+                }
+                let tag = entry.d_tag;
+                let value = match dynsyms_strs.get(entry.d_val() as usize) {
+                    Ok(value) => value.to_string(),
+                    Err(err) => {
+                        warnings.push(CaptureWarning::DynamicEntry {
+                            tag,
+                            error: err.to_string(),
+                        });
+                        continue;
+                    }
+                };
+                match tag {
+                    DT_NEEDED => dt_needed.push(value),
+                    DT_RPATH => dt_rpath = Some(value),
+                    DT_RUNPATH => dt_runpath = Some(value),
+                    _ => dt_soname = Some(value),
+                }
+            }
+        }
+    }
+    (dt_needed, dt_rpath, dt_runpath, dt_soname)
+}
+
+/// Every entry of the dynamic section up to the DT_NULL ending it, with the strings of the
+/// string valued ones (where they can be read).
+fn parse_raw_dynamic(common_elf_data: &CommonElfData<AnyEndian>) -> Vec<DynamicEntry> {
+    let Some(dynamic) = &common_elf_data.dynamic else {
+        return vec![];
+    };
+    dynamic
+        .iter()
+        .take_while(|entry| entry.d_tag != DT_NULL)
+        .map(|entry| {
+            let tag = entry.d_tag;
+            let value = entry.d_val();
+            DynamicEntry {
+                tag: d_tag_to_str(tag).map_or_else(|| format!("{tag:#x}"), str::to_string),
+                value,
+                string: STRING_TAGS
+                    .contains(&tag)
+                    .then_some(common_elf_data.dynsyms_strs.as_ref())
+                    .flatten()
+                    .and_then(|strs| strs.get(value as usize).ok())
+                    .map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// Count the init and fini array entries (pointers, so 4 or 8 bytes each).
+fn parse_init_fini(common_elf_data: &CommonElfData<AnyEndian>, class: Class) -> Option<InitFini> {
+    let dynamic = common_elf_data.dynamic.as_ref()?;
+    let pointer_size = match class {
+        Class::ELF32 => 4,
+        Class::ELF64 => 8,
+    };
+    let mut init_fini = InitFini::default();
+    for entry in dynamic.iter() {
+        match entry.d_tag {
+            DT_INIT_ARRAYSZ => init_fini.init_array = entry.d_val() / pointer_size,
+            DT_FINI_ARRAYSZ => init_fini.fini_array = entry.d_val() / pointer_size,
+            DT_INIT => init_fini.init = true,
+            DT_FINI => init_fini.fini = true,
+            _ => {}
+        }
+    }
+    Some(init_fini)
+}
+
+/// Count the dynamic relocations by the sizes of their tables.
+fn parse_relocations(
+    common_elf_data: &CommonElfData<AnyEndian>,
+    class: Class,
+) -> Option<Relocations> {
+    let dynamic = common_elf_data.dynamic.as_ref()?;
+    // Elf{32,64}_Rela and Elf{32,64}_Rel, unless DT_RELAENT/DT_RELENT say otherwise
+    let (mut rela_size, mut rel_size) = match class {
+        Class::ELF32 => (12, 8),
+        Class::ELF64 => (24, 16),
+    };
+    let (mut rela, mut rel, mut jmprel) = ((0, 0), (0, 0), (0, 0)); // (address, size)
+    let mut plt_is_rel = false;
+    let mut relative = 0;
+    for entry in dynamic.iter() {
+        match entry.d_tag {
+            DT_RELA => rela.0 = entry.d_ptr(),
+            DT_RELASZ => rela.1 = entry.d_val(),
+            DT_RELAENT => rela_size = entry.d_val(),
+            DT_REL => rel.0 = entry.d_ptr(),
+            DT_RELSZ => rel.1 = entry.d_val(),
+            DT_RELENT => rel_size = entry.d_val(),
+            DT_JMPREL => jmprel.0 = entry.d_ptr(),
+            DT_PLTRELSZ => jmprel.1 = entry.d_val(),
+            DT_PLTREL => plt_is_rel = entry.d_val() == DT_REL as u64,
+            DT_RELACOUNT | DT_RELCOUNT => relative += entry.d_val(),
+            _ => {}
+        }
+    }
+
+    // some linkers make the PLT relocations part of the dynamic ones, so don't count them twice
+    let (plt_table, plt_entry_size) = match plt_is_rel {
+        true => (&mut rel, rel_size),
+        false => (&mut rela, rela_size),
+    };
+    let (start, end) = (plt_table.0, plt_table.0.saturating_add(plt_table.1));
+    if jmprel.1 > 0 && (start..end).contains(&jmprel.0) {
+        plt_table.1 = plt_table.1.saturating_sub(jmprel.1);
+    }
+
+    let count = |size: u64, entry_size: u64| size.checked_div(entry_size).unwrap_or(0);
+    Some(Relocations {
+        plt: count(jmprel.1, plt_entry_size),
+        dynamic: count(rela.1, rela_size) + count(rel.1, rel_size),
+        relative,
+    })
+}
@@ -0,0 +1,214 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Symbols defined by more than one object of a process.
+//!
+//! The dynamic linker loads an executable's dependencies breadth first, each file's DT_NEEDED
+//! entries in link order, and looks symbols up in that order: the first object defining a
+//! symbol provides it to everyone. A second definition goes unused, which is harmless until the
+//! two differ, as when a plugin drags in a library exporting the same names as one already
+//! loaded. Plugins given along with the executable are loaded after its dependencies (with
+//! theirs), as if dlopen()ed with RTLD_GLOBAL. [AbiReport::plugins_in] picks them from plugin
+//! directories such as `/usr/lib/gstreamer-1.0`.
+//!
+//! Imports no object of the process defines are listed as unresolved: for a plugin that means
+//! dlopen() fails (or, with lazy binding, the process aborts on first use). Libraries missing
+//! from the reports leave the imports they'd provide unresolved too.
+//!
+//! Libraries are looked up by soname in the report, then in the sysroot report, as in
+//! [AbiReport::resolve_imports]. Definitions of different versions of a symbol don't conflict,
+//! and toolchain boilerplate (`_init`, `_edata`, ...) is left out as every object defines it.
+
+use crate::resolve::Libraries;
+use crate::{AbiCapture, AbiReport, ElfKind, Symbol};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::Path;
+
+/// The objects of a process and the symbols more than one of them defines
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ProcessConflicts {
+    pub executable: String,
+    pub load_order: Vec<String>, // the files of the process, in lookup order
+    pub missing: Vec<String>,    // needed sonames not found in the reports
+    pub conflicts: Vec<SymbolConflict>,
+    pub unresolved: Vec<UnresolvedImports>, // in lookup order
+}
+
+/// A symbol defined by more than one object of a process
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SymbolConflict {
+    pub symbol: Symbol,        // the definition that wins
+    pub winner: String,        // the file providing it
+    pub shadowed: Vec<String>, // the other files defining it, in lookup order
+}
+
+/// The imports of one object of a process no object defines
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct UnresolvedImports {
+    pub filename: String,
+    pub symbols: Vec<Symbol>,
+}
+
+impl AbiReport {
+    /// The shared objects of this report within any of `dirs` (or their subdirectories), in
+    /// report order: the modules a host application dlopen()s from its plugin directories.
+    pub fn plugins_in(&self, dirs: &[&str]) -> Vec<&str> {
+        self.captures()
+            .iter()
+            .filter(|capture| capture.elf_kind() == ElfKind::SharedObject)
+            .map(AbiCapture::filename)
+            .filter(|filename| dirs.iter().any(|dir| Path::new(filename).starts_with(dir)))
+            .collect()
+    }
+
+    /// The symbols defined more than once in the process of `executable` (a file of this
+    /// report) with `plugins` (also files of this report) loaded, in natural symbol order.
+    /// None if any of them isn't in the report.
+    pub fn process_conflicts(
+        &self,
+        executable: &str,
+        plugins: &[&str],
+        sysroot: Option<&AbiReport>,
+    ) -> Option<ProcessConflicts> {
+        let find = |filename: &str| {
+            self.captures()
+                .iter()
+                .find(|capture| capture.filename() == filename)
+        };
+        let executable = find(executable)?;
+        let plugins = plugins
+            .iter()
+            .map(|plugin| find(plugin))
+            .collect::<Option<Vec<_>>>()?;
+        let (objects, missing) = load_order(executable, &plugins, &Libraries::new(self, sysroot));
+
+        let mut definitions = Definitions::new();
+        for (idx, object) in objects.iter().enumerate() {
+            for export in object.dynsym_exports() {
+                if !export.is_boilerplate() {
+                    let entry = definitions
+                        .entry((export.name.as_str(), export.version.as_deref()))
+                        .or_default();
+                    entry.push((idx, export));
+                }
+            }
+        }
+        // an unversioned definition competes with every version of the name
+        let unversioned = definitions
+            .iter()
+            .filter(|((_, version), _)| version.is_none())
+            .map(|(&(name, _), defs)| (name, defs.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let mut conflicts = vec![];
+        for (&(name, version), defs) in &definitions {
+            let mut defs = defs.clone();
+            if version.is_some() {
+                defs.extend(unversioned.get(name).into_iter().flatten());
+                defs.sort_by_key(|&(idx, _)| idx);
+            }
+            let mut defs = defs.into_iter();
+            let Some((winner, symbol)) = defs.next() else {
+                continue;
+            };
+            let shadowed = defs
+                .map(|(idx, _)| objects[idx].filename().to_string())
+                .collect::<Vec<_>>();
+            if !shadowed.is_empty() {
+                conflicts.push(SymbolConflict {
+                    symbol: symbol.clone(),
+                    winner: objects[winner].filename().to_string(),
+                    shadowed,
+                });
+            }
+        }
+        conflicts.sort_by(|a, b| a.symbol.natural_cmp(&b.symbol));
+
+        let unresolved = objects
+            .iter()
+            .filter_map(|object| {
+                let symbols = object
+                    .dynsym_imports()
+                    .iter()
+                    .filter(|import| !import.is_boilerplate() && !defined(&definitions, import))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!symbols.is_empty()).then(|| UnresolvedImports {
+                    filename: object.filename().to_string(),
+                    symbols,
+                })
+            })
+            .collect();
+
+        Some(ProcessConflicts {
+            executable: executable.filename().to_string(),
+            load_order: objects.iter().map(|o| o.filename().to_string()).collect(),
+            missing,
+            conflicts,
+            unresolved,
+        })
+    }
+}
+
+/// (name, version) -> the definitions, as (position in the lookup order, export)
+type Definitions<'a> = BTreeMap<(&'a str, Option<&'a str>), Vec<(usize, &'a Symbol)>>;
+
+/// Whether an object of the process defines `import`. A versioned import binds to that version
+/// or an unversioned definition, an unversioned one to any definition of the name.
+fn defined(definitions: &Definitions, import: &Symbol) -> bool {
+    let name = import.name.as_str();
+    match import.version.as_deref() {
+        Some(version) => {
+            definitions.contains_key(&(name, Some(version)))
+                || definitions.contains_key(&(name, None))
+        }
+        None => definitions
+            .range((name, None)..)
+            .next()
+            .is_some_and(|(&(defined, _), _)| defined == name),
+    }
+}
+
+/// The objects of the process in lookup order, and the needed sonames not found.
+fn load_order<'a>(
+    executable: &'a AbiCapture,
+    plugins: &[&'a AbiCapture],
+    libraries: &Libraries<'a>,
+) -> (Vec<&'a AbiCapture>, Vec<String>) {
+    let mut objects = vec![];
+    let mut missing = vec![];
+    let mut seen = HashSet::new();
+    for root in std::iter::once(executable).chain(plugins.iter().copied()) {
+        if !seen.insert(root.filename()) {
+            continue;
+        }
+        let mut queue = VecDeque::from([root]);
+        while let Some(object) = queue.pop_front() {
+            objects.push(object);
+            // older reports only have the sorted list
+            let needed = match object.needed_link_order() {
+                [] => object.needed_deps(),
+                order => order,
+            };
+            for soname in needed {
+                match libraries.find(soname, executable) {
+                    Some(library) if seen.insert(library.filename()) => queue.push_back(library),
+                    Some(_) => {}
+                    None if !missing.contains(soname) => missing.push(soname.clone()),
+                    None => {}
+                }
+            }
+        }
+    }
+    (objects, missing)
+}
@@ -20,177 +20,688 @@
 
 #![allow(dead_code)] // TODO
 
-use elf::abi::{DT_NEEDED, DT_RPATH, DT_RUNPATH, DT_SONAME};
-use elf::endian::AnyEndian;
-use elf::{CommonElfData, ElfBytes};
+mod abitag;
+mod advice;
+mod alias;
+mod backend;
+#[cfg(feature = "budgets")]
+mod budget;
+mod bundled;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod capture_ref;
+mod check;
+#[cfg(feature = "fs")]
+mod closure;
+#[cfg(feature = "fs")]
+mod cmake;
+mod compact;
+mod csv;
+pub mod debuginfo;
+#[cfg(feature = "demangle")]
+mod demangle;
+pub mod diff;
+mod display;
+mod filter;
+#[cfg(feature = "serde")]
+mod format;
+#[cfg(feature = "fs")]
+mod ignore;
+mod impact;
+mod index;
+mod inspector;
+mod interpose;
+#[cfg(feature = "fs")]
+mod libtool;
+mod limits;
+#[cfg(feature = "macho")]
+mod macho;
+mod merge;
+mod metadata;
+mod package;
+mod parity;
+#[cfg(feature = "pe")]
+mod pe;
+#[cfg(feature = "fs")]
+mod pkgconfig;
+mod preload;
+mod query;
+mod report;
+mod resolve;
+#[cfg(feature = "fs")]
+mod scan;
+mod schema;
+mod section;
+mod shadow;
+#[cfg(feature = "sign")]
+pub mod sign;
+#[cfg(feature = "fs")]
+mod split;
+mod stats;
+#[cfg(feature = "serde")]
+mod stream;
+mod symbol;
+#[cfg(feature = "fs")]
+mod tree;
+#[cfg(feature = "waivers")]
+mod waiver;
+mod warning;
+#[cfg(feature = "zstd")]
+mod zstd;
+
+use debuginfo::DebugLink;
 use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use std::io::Result;
 
-#[derive(Debug)]
-enum ElfKind {
+pub use abitag::AbiTag;
+pub use advice::{VersionAdvice, VersionBump};
+pub use alias::AliasGroup;
+#[cfg(feature = "fs")]
+pub use backend::open_object;
+pub use backend::{is_object, open_object_bytes, ObjectBackend, ParseOptions, HEADER_LEN};
+#[cfg(feature = "budgets")]
+pub use budget::{Budget, ExportBudgets};
+pub use capture_ref::AbiCaptureRef;
+pub use check::{Check, FailOn, FailPolicy, Finding, Fix, Severity};
+#[cfg(feature = "fs")]
+pub use closure::{
+    ClosureEntry, DependencyClosure, LibrarySearch, SearchDir, SearchDirStatus, SearchRule,
+};
+pub use compact::{CompactCapture, CompactReport, CompactSymbols};
+#[cfg(feature = "demangle")]
+pub use demangle::{demangle, ScopeTree};
+pub use filter::ExportFilter;
+#[cfg(feature = "serde")]
+pub use format::{is_zstd_path, ReportFormat};
+pub use impact::{RebuildImpact, SonameTransition};
+pub use index::ReportIndex;
+pub use inspector::{
+    DynamicEntry, DynamicInfo, DynamicSymbols, ElfInspector, ExportPolicy, InitFini, Relocations,
+    Relro, Segments, ThreadLocal, VisibilityCoverage,
+};
+pub use interpose::{ProcessConflicts, SymbolConflict, UnresolvedImports};
+pub use limits::{Limits, HAS_CLOCK};
+#[cfg(all(feature = "macho", feature = "fs"))]
+pub use macho::parse_macho;
+#[cfg(feature = "macho")]
+pub use macho::{is_macho, parse_macho_bytes};
+pub use merge::MergePolicy;
+pub use metadata::{CaptureIdentity, FileMetadata};
+pub use package::{PackageMap, PackageRef};
+pub use parity::{ArchParity, ParityAllowlist};
+#[cfg(all(feature = "pe", feature = "fs"))]
+pub use pe::parse_pe;
+#[cfg(feature = "pe")]
+pub use pe::{is_pe, parse_pe_bytes};
+pub use preload::Interposition;
+pub use query::{SymbolMatch, SymbolPattern};
+pub use report::{AbiReport, BuildIdGroup, CaptureOrder, ReportHeader};
+pub use resolve::DependencyUsage;
+#[cfg(feature = "fs")]
+pub use scan::{scan_iter, ScanBuilder, ScanIter, Scanner};
+pub use schema::SCHEMA_VERSION;
+#[cfg(feature = "schema")]
+pub use schema::{diff_schema, report_schema};
+pub use shadow::{LibcNames, LIBC_NAMES};
+#[cfg(feature = "fs")]
+pub use split::{SplitReason, SplitSuggestion, Subpackage};
+pub use stats::{ExportCount, RelocationCount, ReportStats, SymbolCount, VisibilityCount};
+#[cfg(feature = "serde")]
+pub use stream::{StreamFormat, StreamReader, StreamWriter};
+pub use symbol::{ExportKinds, Symbol, SymbolKind, SymbolRef};
+#[cfg(feature = "fs")]
+pub use tree::{EntryKind, InstallTree, TreeEntry};
+#[cfg(feature = "waivers")]
+pub use waiver::{Waived, Waiver, Waivers};
+pub use warning::{CaptureWarning, ScanWarning};
+#[cfg(feature = "zstd")]
+pub use zstd::ZstdWriter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ElfKind {
     Executable,
     SharedObject,
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct AbiCapture {
-    elf_kind: ElfKind,           // This seems useful to know
-    filename: String,            // Stuff that needs to can instantiate this as a Pathbuf
-    dynsym_imports: Vec<String>, // the string version of symbols (deliberately unversioned for now)
+    elf_kind: ElfKind, // This seems useful to know
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    arch: Option<String>, // e_machine, e.g. "x86_64" (not recorded by older reports)
+    filename: String,  // Stuff that needs to can instantiate this as a Pathbuf
+    dynsym_imports: Vec<Symbol>, // undefined symbols, with their version requirement if any
     //    dynsym_imports_hash: ,
-    dynsym_exports: Vec<String>, // the string version of symbols (deliberately unversioned for now)
-    //    dynsym_exports_hash: ,
+    dynsym_exports: Vec<Symbol>, // defined and visible symbols, with their version if any
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    export_filter: Option<ExportFilter>, // of the export names (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    protected_exports: Vec<Symbol>, // STV_PROTECTED definitions (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    hidden_symbols: Vec<Symbol>, // defined but not visible, only recorded by visibility audits
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    visibility: Option<VisibilityCoverage>, // exported share of .symtab, only recorded by visibility audits
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    alias_groups: Vec<AliasGroup>, // exports sharing a definition (not recorded by older reports)
     manual_deps: Vec<String>, // deps added manually by a packager (could be useful?)
     needed_deps: Vec<String>, // dynamically linked at build time (via DT_NEEDED)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    needed_link_order: Vec<String>, // needed_deps as linked (not recorded by older reports)
     optional_deps: Vec<String>, // dynamically linked and opened at runtime (via dlopen() calls)
     rpath: Option<String>, // DT_RPATH if available (needs to be analysed _after_ any patchelf manipulation)
     runpath: Option<String>, // DT_RUNPATH if available (needs to be analysed _after_ any patchelf manipulation)
     soname: Option<String>,  // DT_SONAME if available (this will be empty for executables)
+    build_id: Option<String>, // NT_GNU_BUILD_ID as lowercase hex if available
+    debuglink: Option<DebugLink>, // .gnu_debuglink if the debug info was split off
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    abi_tag: Option<AbiTag>, // NT_GNU_ABI_TAG, the oldest OS version (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    init_fini: Option<InitFini>, // constructor and destructor counts (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    relocations: Option<Relocations>, // dynamic relocation counts (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    bind_now: Option<bool>, // resolved at load time (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    relro: Option<Relro>, // PT_GNU_RELRO coverage (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    thread_local: Option<ThreadLocal>, // TLS block and model (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    nodelete: Option<bool>, // never unloaded (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    segments: Option<Segments>, // program header summary (not recorded by older reports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    dynamic_entries: Vec<DynamicEntry>, // the whole dynamic section, only if asked for
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    warnings: Vec<CaptureWarning>, // what couldn't be captured from a damaged file
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    metadata: Option<FileMetadata>, // size, mode, mtime, capabilities and digest, if scanned from disk
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    package: Option<PackageRef>, // the package the file belongs to, if scanned with a package map
 }
 
-/// All the info we need for ABI parsing purposes.
-pub fn parse_elf(file_name: &str) -> Result<AbiCapture> {
-    // TODO: which error type might be useful here...?
-
-    let path = std::path::PathBuf::from(file_name);
-    let file_data = std::fs::read(path).expect("Could not read file {file_name:?}.");
-
-    // We want to be able to skip around in the file
-    let file_slice = file_data.as_slice();
-    let elf_file = ElfBytes::<AnyEndian>::minimal_parse(file_slice)
-        .expect("Could not parse {file_name:?} as ELF data.");
-
-    // Find the common ELF sections (we want .dynsym and .dynstr)
-    let common_elf_data = elf_file
-        .find_common_data()
-        .expect("ELF section headers (shdrs) of {file_name:?} should parse.");
-
-    let (ds_imports, ds_exports) = parse_dynsyms_section(&common_elf_data);
-    let (dt_needed, dt_rpath, dt_runpath, dt_soname) = parse_dynamic_section(&common_elf_data);
-
-    Ok(AbiCapture {
-        elf_kind: ElfKind::Unknown,
-        filename: file_name.to_string(),
-        dynsym_imports: ds_imports,
-        dynsym_exports: ds_exports,
-        manual_deps: vec!["Not implemented".to_string()],
-        needed_deps: dt_needed,
-        optional_deps: vec!["Not implemented".to_string()],
-        rpath: dt_rpath,
-        runpath: dt_runpath,
-        soname: dt_soname,
-    })
-}
+impl AbiCapture {
+    /// A capture with nothing in it but its filename and kind, for synthesizing captures (e.g.
+    /// in tests or when importing from other tools). Fill it in with the `with_*` methods.
+    pub fn new(filename: impl Into<String>, elf_kind: ElfKind) -> Self {
+        Self {
+            elf_kind,
+            arch: None,
+            filename: filename.into(),
+            dynsym_imports: vec![],
+            dynsym_exports: vec![],
+            export_filter: None,
+            protected_exports: vec![],
+            hidden_symbols: vec![],
+            visibility: None,
+            alias_groups: vec![],
+            manual_deps: vec!["Not implemented".to_string()],
+            needed_deps: vec![],
+            needed_link_order: vec![],
+            optional_deps: vec!["Not implemented".to_string()],
+            rpath: None,
+            runpath: None,
+            soname: None,
+            build_id: None,
+            debuglink: None,
+            abi_tag: None,
+            init_fini: None,
+            relocations: None,
+            bind_now: None,
+            relro: None,
+            thread_local: None,
+            segments: None,
+            nodelete: None,
+            dynamic_entries: vec![],
+            warnings: vec![],
+            metadata: None,
+            package: None,
+        }
+    }
+
+    /// Set the imported symbols (kept in natural sort order, like parsed captures).
+    pub fn with_imports(mut self, imports: impl IntoIterator<Item = impl Into<Symbol>>) -> Self {
+        self.dynsym_imports = sorted_symbols(imports);
+        self
+    }
+
+    /// Set the exported symbols (kept in natural sort order, like parsed captures).
+    pub fn with_exports(mut self, exports: impl IntoIterator<Item = impl Into<Symbol>>) -> Self {
+        self.dynsym_exports = sorted_symbols(exports);
+        self.export_filter = export_filter(&self.dynsym_exports);
+        self
+    }
+
+    /// Set the STV_PROTECTED definitions (kept in natural sort order, like parsed captures).
+    pub fn with_protected_exports(
+        mut self,
+        protected: impl IntoIterator<Item = impl Into<Symbol>>,
+    ) -> Self {
+        self.protected_exports = sorted_symbols(protected);
+        self
+    }
+
+    /// Set the defined but not visible symbols (kept in natural sort order, like parsed
+    /// captures).
+    pub fn with_hidden_symbols(
+        mut self,
+        hidden: impl IntoIterator<Item = impl Into<Symbol>>,
+    ) -> Self {
+        self.hidden_symbols = sorted_symbols(hidden);
+        self
+    }
+
+    /// Set the groups of exports sharing a definition.
+    pub fn with_alias_groups(mut self, groups: impl IntoIterator<Item = AliasGroup>) -> Self {
+        self.alias_groups = groups.into_iter().collect();
+        self
+    }
+
+    /// Set the DT_NEEDED entries in link order (also kept in natural sort order, like parsed
+    /// captures).
+    pub fn with_needed(mut self, needed: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.needed_link_order = needed.into_iter().map(Into::into).collect();
+        self.needed_deps = natural_sorted(self.needed_link_order.iter().cloned());
+        self
+    }
+
+    pub fn with_arch(mut self, arch: impl Into<String>) -> Self {
+        self.arch = Some(arch.into());
+        self
+    }
+
+    pub fn with_soname(mut self, soname: impl Into<String>) -> Self {
+        self.soname = Some(soname.into());
+        self
+    }
+
+    pub fn with_rpath(mut self, rpath: impl Into<String>) -> Self {
+        self.rpath = Some(rpath.into());
+        self
+    }
+
+    pub fn with_runpath(mut self, runpath: impl Into<String>) -> Self {
+        self.runpath = Some(runpath.into());
+        self
+    }
+
+    pub fn with_build_id(mut self, build_id: impl Into<String>) -> Self {
+        self.build_id = Some(build_id.into());
+        self
+    }
+
+    pub fn with_debuglink(mut self, debuglink: DebugLink) -> Self {
+        self.debuglink = Some(debuglink);
+        self
+    }
+
+    pub fn with_init_fini(mut self, init_fini: InitFini) -> Self {
+        self.init_fini = Some(init_fini);
+        self
+    }
 
-fn parse_dynsyms_section(common_elf_data: &CommonElfData<AnyEndian>) -> (Vec<String>, Vec<String>) {
-    let (dynsyms, strtab) = (
-        common_elf_data.dynsyms.as_ref().unwrap(),
-        common_elf_data.dynsyms_strs.as_ref().unwrap(),
-    );
-
-    // The fields that will eventually be moved into an ABI struct as the return value
-    let mut abi_imports: Vec<String> = Vec::new();
-    let mut abi_exports: Vec<String> = Vec::new();
-
-    for dynsym in dynsyms.iter() {
-        // find the type of each symbol (imported or exported)
-        // each dynsym entry has a string table entry associated with it
-        let ds = strtab
-            .get(dynsym.st_name.try_into().unwrap())
-            .unwrap()
-            .to_string();
-
-        let imported = dynsym.is_undefined();
-        // st_vis() returns > 0 if flags other than STB_GLOBAL or STB_WEAK are set
-        // TODO: build our own, more discerning visibility function here (cf. clearlinux's abireport tool)
-        let exported = !dynsym.is_undefined() && dynsym.st_vis() == 0;
-
-        // Not sure this is the most elegant way, but...
-        if imported {
-            // we import (= rely on) undefined symbols (currenly the only constraint)
-            // println!("\t\tImporting {:?}: (st_symtype(): {:?}, st_bind(): {:?}, st_vis(): {:?})",
-            //     ds, dynsym.st_symtype(), dynsym.st_bind(), dynsym.st_vis());
-            abi_imports.push(ds);
-
-        // this implicitly matches !is_undefined()
-        } else if exported {
-            // only export defined and visible symbols for now (= global or weak/overridable)
-            // println!("\t\tExporting {:?}: (st_symtype(): {:?}, st_bind(): {:?}, st_vis(): {:?})",
-            //     ds, dynsym.st_symtype(), dynsym.st_bind(), dynsym.st_vis());
-            abi_exports.push(ds);
-        } else {
-            // defined but not visible, only printed for completeness sake for now
-            println!(
-                "\t\tIgnoring {:?}: (st_symtype(): {:?}, st_bind(): {:?}, st_vis(): {:?})",
-                ds,
-                dynsym.st_symtype(),
-                dynsym.st_bind(),
-                dynsym.st_vis()
-            );
+    pub fn with_relocations(mut self, relocations: Relocations) -> Self {
+        self.relocations = Some(relocations);
+        self
+    }
+
+    pub fn with_bind_now(mut self, bind_now: bool) -> Self {
+        self.bind_now = Some(bind_now);
+        self
+    }
+
+    pub fn with_relro(mut self, relro: Relro) -> Self {
+        self.relro = Some(relro);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: FileMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Set the package the file belongs to.
+    pub fn with_package(mut self, package: PackageRef) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    pub fn elf_kind(&self) -> ElfKind {
+        self.elf_kind
+    }
+
+    /// The target architecture, e.g. "x86_64" or "aarch64".
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// Whether the two files can link against each other as far as architecture goes.
+    /// Captures of unknown architecture (from older reports) are taken to match anything.
+    pub fn is_same_arch(&self, other: &AbiCapture) -> bool {
+        match (self.arch(), other.arch()) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
         }
     }
 
-    abi_imports.sort_by(|a, b| a.natural_cmp(b));
-    abi_exports.sort_by(|a, b| a.natural_cmp(b));
-    (abi_imports, abi_exports)
-}
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Undefined .dynsym entries, i.e. what this file relies on others to provide.
+    pub fn dynsym_imports(&self) -> &[Symbol] {
+        &self.dynsym_imports
+    }
+
+    /// Defined and visible .dynsym entries, i.e. what this file provides.
+    pub fn dynsym_exports(&self) -> &[Symbol] {
+        &self.dynsym_exports
+    }
+
+    /// How many of the exports are functions and how many data.
+    pub fn export_kinds(&self) -> ExportKinds {
+        ExportKinds::count(&self.dynsym_exports)
+    }
+
+    /// Whether this file may export a symbol named `name`: false if its export filter rules the
+    /// name out, true otherwise (or if it has no filter).
+    pub fn may_export(&self, name: &str) -> bool {
+        self.export_filter
+            .as_ref()
+            .is_none_or(|filter| filter.may_contain(name))
+    }
+
+    /// Defined STV_PROTECTED .dynsym entries: visible, but bound to this file's own definition
+    /// from within it. They are listed among [Self::dynsym_exports] as well only with
+    /// [ExportPolicy::IncludeProtected].
+    pub fn protected_exports(&self) -> &[Symbol] {
+        &self.protected_exports
+    }
+
+    /// Defined .dynsym entries that aren't exported (hidden or internal visibility, or left out
+    /// by the [ExportPolicy]). Only captured when auditing visibility, see
+    /// [ElfInspector::with_hidden_audit]; empty otherwise.
+    pub fn hidden_symbols(&self) -> &[Symbol] {
+        &self.hidden_symbols
+    }
+
+    /// How much of what the file defines it exports, measured on its .symtab when auditing
+    /// visibility (see [ElfInspector::with_hidden_audit]). None for stripped files.
+    pub fn visibility(&self) -> Option<VisibilityCoverage> {
+        self.visibility
+    }
+
+    /// The exports sharing their address and size with other exports, grouped by definition.
+    pub fn alias_groups(&self) -> &[AliasGroup] {
+        &self.alias_groups
+    }
+
+    pub fn manual_deps(&self) -> &[String] {
+        &self.manual_deps
+    }
+
+    pub fn needed_deps(&self) -> &[String] {
+        &self.needed_deps
+    }
+
+    /// The DT_NEEDED entries in the order they were linked, which is the order the dynamic
+    /// linker loads and searches them in; empty in captures from older reports.
+    pub fn needed_link_order(&self) -> &[String] {
+        &self.needed_link_order
+    }
+
+    pub fn optional_deps(&self) -> &[String] {
+        &self.optional_deps
+    }
+
+    pub fn rpath(&self) -> Option<&str> {
+        self.rpath.as_deref()
+    }
+
+    pub fn runpath(&self) -> Option<&str> {
+        self.runpath.as_deref()
+    }
+
+    pub fn soname(&self) -> Option<&str> {
+        self.soname.as_deref()
+    }
+
+    pub fn build_id(&self) -> Option<&str> {
+        self.build_id.as_deref()
+    }
+
+    pub fn debuglink(&self) -> Option<&DebugLink> {
+        self.debuglink.as_ref()
+    }
+
+    pub fn abi_tag(&self) -> Option<AbiTag> {
+        self.abi_tag
+    }
+
+    /// The oldest Linux kernel the file declares it runs on (in its ABI tag), if any.
+    pub fn min_kernel(&self) -> Option<[u32; 3]> {
+        self.abi_tag?.min_kernel()
+    }
+
+    /// The init/fini array sizes and DT_INIT/DT_FINI, if the file has a dynamic section.
+    pub fn init_fini(&self) -> Option<&InitFini> {
+        self.init_fini.as_ref()
+    }
+
+    /// The dynamic relocation counts, if the file has a dynamic section.
+    pub fn relocations(&self) -> Option<&Relocations> {
+        self.relocations.as_ref()
+    }
+
+    /// Whether the file asks for all symbols to be bound at load time, if it has a dynamic
+    /// section.
+    pub fn bind_now(&self) -> Option<bool> {
+        self.bind_now
+    }
+
+    /// How much of the relocated data is read-only, if the file has a dynamic section.
+    pub fn relro(&self) -> Option<Relro> {
+        self.relro
+    }
+
+    /// The file's TLS block and whether it needs static TLS, if it has a dynamic section.
+    pub fn thread_local(&self) -> Option<ThreadLocal> {
+        self.thread_local
+    }
+
+    /// Whether the file is marked to never be unloaded (DF_1_NODELETE), if it has a dynamic
+    /// section.
+    pub fn nodelete(&self) -> Option<bool> {
+        self.nodelete
+    }
 
-fn parse_dynamic_section(
-    common_elf_data: &CommonElfData<AnyEndian>,
-) -> (
-    Vec<String>,    // dt_needed
-    Option<String>, // dt_rpath
-    Option<String>, // dt_runpath
-    Option<String>, // dt_soname
-) {
-    // default values if everything goes to shit
-    let mut dt_needed = vec![];
-    let mut dt_rpath = None;
-    let mut dt_runpath = None;
-    let mut dt_soname = None;
-
-    if let Some(dynamic) = &common_elf_data.dynamic {
-        if let Some(dynsyms_strs) = &common_elf_data.dynsyms_strs {
-            for entry in dynamic.iter() {
-                match entry.d_tag {
-                    DT_NEEDED => dt_needed.push(
-                        dynsyms_strs
-                            .get(entry.d_val().try_into().unwrap())
-                            .unwrap()
-                            .to_string(),
-                    ),
-                    DT_RPATH => {
-                        dt_rpath = Some(
-                            dynsyms_strs
-                                .get(entry.d_val().try_into().unwrap())
-                                .unwrap()
-                                .to_string(),
-                        );
-                    }
-                    DT_RUNPATH => {
-                        dt_runpath = Some(
-                            dynsyms_strs
-                                .get(entry.d_val().try_into().unwrap())
-                                .unwrap()
-                                .to_string(),
-                        );
-                    }
-                    DT_SONAME => {
-                        dt_soname = Some(
-                            dynsyms_strs
-                                .get(entry.d_val().try_into().unwrap())
-                                .unwrap()
-                                .to_string(),
-                        );
-                    }
-                    _ => {}
-                }
-            }
-            // we want this in natural sort order
-            dt_needed.sort_by(|a, b| a.natural_cmp(b));
+    /// The number, permissions and alignment of the loadable segments and whether there is a
+    /// RELRO segment, for ELF files (with program headers).
+    pub fn segments(&self) -> Option<Segments> {
+        self.segments
+    }
+
+    /// Every entry of the dynamic section, if the file was scanned with them kept (see
+    /// [ElfInspector::with_raw_dynamic]), else empty.
+    pub fn dynamic_entries(&self) -> &[DynamicEntry] {
+        &self.dynamic_entries
+    }
+
+    /// The file's size, mode, mtime and capabilities; None unless the capture was scanned from
+    /// disk.
+    pub fn metadata(&self) -> Option<&FileMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// The package the file belongs to, see [PackageMap].
+    pub fn package(&self) -> Option<&PackageRef> {
+        self.package.as_ref()
+    }
+
+    /// The path and the digest of the contents of the captured file, which tell whether two
+    /// captures are of the same file.
+    pub fn identity(&self) -> CaptureIdentity<'_> {
+        CaptureIdentity {
+            filename: &self.filename,
+            sha256: self
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.sha256.as_deref()),
         }
     }
-    (dt_needed, dt_rpath, dt_runpath, dt_soname)
+
+    /// Drop the imported and exported symbols whose name `keep` says no to.
+    pub(crate) fn retain_symbols(&mut self, keep: impl Fn(&str) -> bool) {
+        self.dynsym_imports.retain(|symbol| keep(&symbol.name));
+        self.dynsym_exports.retain(|symbol| keep(&symbol.name));
+        self.export_filter = export_filter(&self.dynsym_exports);
+        self.protected_exports.retain(|symbol| keep(&symbol.name));
+        self.hidden_symbols.retain(|symbol| keep(&symbol.name));
+        self.alias_groups = std::mem::take(&mut self.alias_groups)
+            .into_iter()
+            .filter_map(|group| group.retain(&keep))
+            .collect();
+    }
+
+    /// Locate the detached debug file for this capture, if one is installed.
+    ///
+    /// Deep (DWARF based) analysis of stripped binaries needs this to find the type info.
+    #[cfg(all(feature = "fs", feature = "dwarf"))]
+    pub fn find_debug_file(&self, search: &debuginfo::DebugSearch) -> Option<std::path::PathBuf> {
+        search.find(
+            std::path::Path::new(&self.filename),
+            self.build_id.as_deref(),
+            self.debuglink.as_ref(),
+        )
+    }
+
+    /// Problems encountered while capturing; a capture with warnings is incomplete.
+    pub fn warnings(&self) -> &[CaptureWarning] {
+        &self.warnings
+    }
+}
+
+fn natural_sorted(items: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let mut items = items.into_iter().map(Into::into).collect::<Vec<String>>();
+    items.sort_by(|a, b| a.natural_cmp(b));
+    items
+}
+
+/// A soname without its version: up to `.so`, and without a dotted version right before it.
+fn soname_stem(soname: &str) -> &str {
+    let stem = match soname.find(".so") {
+        Some(end) if matches!(soname.as_bytes().get(end + 3), None | Some(b'.')) => &soname[..end],
+        _ => return soname,
+    };
+    let unversioned = stem.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match !unversioned.is_empty() && stem[unversioned.len()..].contains('.') {
+        true => unversioned.trim_end_matches('-'),
+        false => stem,
+    }
+}
+
+fn export_filter(exports: &[Symbol]) -> Option<ExportFilter> {
+    ExportFilter::new(exports.iter().map(|symbol| symbol.name.as_str()))
+}
+
+fn sorted_symbols(symbols: impl IntoIterator<Item = impl Into<Symbol>>) -> Vec<Symbol> {
+    let mut symbols = symbols.into_iter().map(Into::into).collect::<Vec<Symbol>>();
+    symbols.sort_by(Symbol::natural_cmp);
+    symbols
+}
+
+/// All the info we need for ABI parsing purposes.
+#[cfg(feature = "fs")]
+pub fn parse_elf(file_name: &str) -> Result<AbiCapture> {
+    parse_elf_with(file_name, &Limits::default())
+}
+
+/// [parse_elf], guarded by explicit resource limits.
+#[cfg(feature = "fs")]
+pub fn parse_elf_with(file_name: &str, limits: &Limits) -> Result<AbiCapture> {
+    ElfInspector::open(file_name, limits)?.capture()
+}
+
+/// A quick capture of just the dynamic section (soname, DT_NEEDED, rpath/runpath).
+///
+/// The .dynsym is never iterated and the notes are never read, so the capture carries no
+/// symbols and no build-id or debuglink. This is much faster on big trees, for when only "which
+/// libraries does this provide/need" matters.
+#[cfg(feature = "fs")]
+pub fn parse_elf_sonames(file_name: &str, limits: &Limits) -> Result<AbiCapture> {
+    Ok(ElfInspector::open(file_name, limits)?.capture_sonames())
+}
+
+/// [parse_elf_with] on a file already in memory, e.g. one dropped into a web page.
+///
+/// `file_name` only labels the capture. This is the entry point for builds without the `fs`
+/// feature, such as `wasm32-unknown-unknown`.
+pub fn parse_elf_bytes(file_name: &str, data: &[u8], limits: &Limits) -> Result<AbiCapture> {
+    ElfInspector::from_bytes(file_name, data.to_vec(), limits)?.capture()
 }
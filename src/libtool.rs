@@ -0,0 +1,45 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Spotting installed libtool archives.
+//!
+//! `.la` files record the full link line of a library (`dependency_libs`) and make libtool pull
+//! all of it into every consumer, overlinking them against libraries they don't use and breaking
+//! them when one of those goes away. Most distros delete them; the only real user left is
+//! libltdl, for loading plugins.
+
+use crate::check::{Check, Finding};
+use crate::tree::{EntryKind, InstallTree};
+use std::fs;
+
+/// The `dependency_libs` of a .la file, split into arguments.
+fn dependency_libs(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("dependency_libs="))
+        .flat_map(|value| {
+            value
+                .trim_matches(|c| c == '\'' || c == '"')
+                .split_whitespace()
+        })
+        .collect()
+}
+
+/// A finding for every installed .la file.
+pub(crate) fn libtool_archives(tree: &InstallTree) -> Vec<Finding> {
+    tree.entries()
+        .iter()
+        .filter(|entry| entry.path.ends_with(".la") && entry.kind == EntryKind::File)
+        .map(|entry| {
+            let message = match fs::read_to_string(tree.staged_path(&entry.path)) {
+                Ok(contents) => match dependency_libs(&contents).as_slice() {
+                    [] => "libtool archive".to_string(),
+                    libs => format!("libtool archive, dependency_libs: {}", libs.join(" ")),
+                },
+                Err(err) => format!("libtool archive (could not be read: {err})"),
+            };
+            Finding::new(Check::LibtoolArchive, &entry.path, message)
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Resource guards for parsing untrusted ELF files.
+//!
+//! Scanned trees may contain pathological or outright malicious files; these limits keep a
+//! single bad file from blowing up memory or hanging an automated pipeline.
+
+use std::io::{Error, ErrorKind, Result};
+use std::time::{Duration, Instant};
+
+/// Whether the target has a clock; `Instant::now()` and `SystemTime::now()` panic on
+/// `wasm32-unknown-unknown`, so timeouts are ignored and scan times left at 0 there.
+pub const HAS_CLOCK: bool = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+#[derive(Clone, Debug)]
+pub struct Limits {
+    pub max_file_size: u64,    // largest file we're willing to read into memory
+    pub max_section_size: u64, // largest (decompressed) section we're willing to allocate
+    pub max_dynsyms: usize,    // most .dynsym entries we're willing to iterate
+    pub timeout: Option<Duration>, // wall clock budget for parsing a single file (needs HAS_CLOCK)
+}
+
+impl Default for Limits {
+    /// Generous enough for anything a distro actually ships (libLLVM & co included)
+    fn default() -> Self {
+        Self {
+            max_file_size: 2 * 1024 * 1024 * 1024,
+            max_section_size: 512 * 1024 * 1024,
+            max_dynsyms: 1_000_000,
+            timeout: None,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, for trusted inputs.
+    pub fn unlimited() -> Self {
+        Self {
+            max_file_size: u64::MAX,
+            max_section_size: u64::MAX,
+            max_dynsyms: usize::MAX,
+            timeout: None,
+        }
+    }
+
+    pub(crate) fn deadline(&self) -> Deadline {
+        let timeout = self.timeout.filter(|_| HAS_CLOCK);
+        Deadline(timeout.map(|timeout| Instant::now() + timeout))
+    }
+
+    pub(crate) fn check_file_size(&self, size: u64) -> Result<()> {
+        check("file", size, self.max_file_size)
+    }
+
+    pub(crate) fn check_section_size(&self, size: u64) -> Result<()> {
+        check("section", size, self.max_section_size)
+    }
+
+    pub(crate) fn check_dynsyms(&self, count: usize) -> Result<()> {
+        if count > self.max_dynsyms {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{count} .dynsym entries exceed the limit of {}",
+                    self.max_dynsyms
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Cooperative per-file timeout, checked at points where parsing can take a while.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deadline(Option<Instant>);
+
+impl Deadline {
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.0 {
+            Some(deadline) if Instant::now() > deadline => Err(Error::new(
+                ErrorKind::TimedOut,
+                "parsing exceeded the per-file timeout",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check(what: &str, size: u64, limit: u64) -> Result<()> {
+    if size > limit {
+        return Err(Error::new(
+            ErrorKind::FileTooLarge,
+            format!("{what} size of {size} bytes exceeds the limit of {limit} bytes"),
+        ));
+    }
+    Ok(())
+}
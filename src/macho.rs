@@ -0,0 +1,423 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Mach-O dylibs and executables, for the macOS artifacts of cross-platform projects.
+//!
+//! Mach-O maps onto captures closely enough for reports and diffs to work unchanged: the install
+//! name (LC_ID_DYLIB) takes the place of the soname, the LC_LOAD_DYLIB commands (and their weak,
+//! re-export, lazy and upward variants) that of DT_NEEDED, and LC_RPATH that of DT_RUNPATH.
+//! Symbols come from the LC_SYMTAB symbol table: external definitions are exports, external
+//! undefined symbols imports, attributed to the library their two-level namespace ordinal
+//! names. Names keep their leading underscore, and types and sizes aren't known. Entries whose
+//! name isn't UTF-8 are skipped, each with a [CaptureWarning::SymbolName].
+//!
+//! Universal (fat) files are captured from their first slice.
+
+use crate::backend::assemble;
+#[cfg(feature = "fs")]
+use crate::backend::read_file;
+use crate::limits::Deadline;
+use crate::{natural_sorted, sorted_symbols};
+use crate::{
+    AbiCapture, CaptureWarning, DynamicInfo, DynamicSymbols, ElfKind, Limits, ObjectBackend,
+    ParseOptions, Symbol,
+};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+
+const MH_EXECUTE: u32 = 0x2;
+const MH_DYLIB: u32 = 0x6;
+const MH_BUNDLE: u32 = 0x8;
+const MH_TWOLEVEL: u32 = 0x80;
+
+const LC_SYMTAB: u32 = 0x2;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_ID_DYLIB: u32 = 0xd;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+const LC_RPATH: u32 = 0x8000_001c;
+const LC_REEXPORT_DYLIB: u32 = 0x8000_001f;
+const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+const LC_LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+
+const N_STAB: u8 = 0xe0;
+const N_PEXT: u8 = 0x10;
+const N_TYPE: u8 = 0x0e;
+const N_EXT: u8 = 0x01;
+const N_UNDF: u8 = 0x0;
+
+/// Whether `data` starts like a Mach-O file, thin or universal.
+pub fn is_macho(data: &[u8]) -> bool {
+    match read_u32(data, 0, true) {
+        // Java class files start with 0xcafebabe as well, followed by a version of 45 or more
+        Some(FAT_MAGIC | FAT_MAGIC_64) => {
+            read_u32(data, 4, true).is_some_and(|count| (1..45).contains(&count))
+        }
+        Some(_) => MachO::parse(data).is_some(),
+        None => false,
+    }
+}
+
+/// Read and capture a Mach-O file, within `limits`.
+#[cfg(feature = "fs")]
+pub fn parse_macho(file_name: &str, limits: &Limits) -> Result<AbiCapture> {
+    let data = read_file(file_name, limits)?;
+    let file = MachOFile::parse(file_name, data, limits, ParseOptions::default())?;
+    Box::new(file).capture()
+}
+
+/// [parse_macho] on a file already in memory.
+pub fn parse_macho_bytes(file_name: &str, data: &[u8], limits: &Limits) -> Result<AbiCapture> {
+    let file = MachOFile::parse(file_name, data.to_vec(), limits, ParseOptions::default())?;
+    Box::new(file).capture()
+}
+
+/// A Mach-O file: the load commands are parsed up front, the symbol table when asked for.
+pub struct MachOFile {
+    filename: String,
+    data: Vec<u8>,
+    slice: Range<usize>, // the thin file within `data`
+    limits: Limits,
+    deadline: Deadline, // started when the file was parsed
+    kind: ElfKind,
+    arch: String,
+    libraries: Vec<String>, // the dylib commands in order, which the library ordinals refer to
+    dynamic: DynamicInfo,
+    symtab: Option<[u32; 4]>, // symoff, nsyms, stroff, strsize of LC_SYMTAB
+    symbols: Option<DynamicSymbols>, // parsed on first use
+    warnings: Vec<CaptureWarning>, // about the symbols skipped while parsing them
+}
+
+impl MachOFile {
+    fn file(&self) -> MachO<'_> {
+        MachO::parse(&self.data[self.slice.clone()]).expect("validated by MachOFile::parse")
+    }
+
+    fn invalid(file_name: &str, what: &str) -> Error {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not parse {file_name:?} as Mach-O data: {what}"),
+        )
+    }
+}
+
+impl ObjectBackend for MachOFile {
+    fn detect(header: &[u8]) -> bool {
+        is_macho(header)
+    }
+
+    fn parse(file_name: &str, data: Vec<u8>, limits: &Limits, _: ParseOptions) -> Result<Self> {
+        let deadline = limits.deadline();
+        limits.check_file_size(data.len() as u64)?;
+        let invalid = |what: &str| Self::invalid(file_name, what);
+        let slice = thin_slice(&data).ok_or_else(|| invalid("bad universal header"))?;
+        let file = MachO::parse(&data[slice.clone()]).ok_or_else(|| invalid("bad header"))?;
+
+        let mut libraries = vec![];
+        let mut install_name = None;
+        let mut rpaths = vec![];
+        let mut symtab = None;
+        for (cmd, command) in file
+            .load_commands()
+            .ok_or_else(|| invalid("bad load commands"))?
+        {
+            match cmd {
+                LC_ID_DYLIB => install_name = file.lc_str(command),
+                LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB
+                | LC_LOAD_UPWARD_DYLIB => libraries.push(
+                    file.lc_str(command)
+                        .ok_or_else(|| invalid("bad dylib name"))?,
+                ),
+                LC_RPATH => rpaths.extend(file.lc_str(command)),
+                LC_SYMTAB => {
+                    symtab = Some(
+                        file.symtab(command)
+                            .ok_or_else(|| invalid("bad symbol table"))?,
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        let kind = match file.filetype {
+            MH_EXECUTE => ElfKind::Executable,
+            MH_DYLIB | MH_BUNDLE => ElfKind::SharedObject,
+            _ => ElfKind::Unknown,
+        };
+        let dynamic = DynamicInfo {
+            needed: natural_sorted(libraries.iter().cloned()),
+            needed_order: libraries.clone(),
+            runpath: (!rpaths.is_empty()).then(|| rpaths.join(":")),
+            soname: install_name,
+            ..DynamicInfo::default()
+        };
+        Ok(Self {
+            filename: file_name.to_string(),
+            kind,
+            arch: arch_name(file.cputype),
+            data,
+            slice,
+            limits: limits.clone(),
+            deadline,
+            libraries,
+            dynamic,
+            symtab,
+            symbols: None,
+            warnings: vec![],
+        })
+    }
+
+    fn classify(&self) -> ElfKind {
+        self.kind
+    }
+
+    fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    fn deps(&mut self) -> &DynamicInfo {
+        &self.dynamic
+    }
+
+    fn symbols(&mut self) -> Result<&DynamicSymbols> {
+        let symbols = match self.symbols.take() {
+            Some(symbols) => symbols,
+            None => match self.symtab {
+                Some(symtab) => {
+                    let mut warnings = vec![];
+                    let (imports, exports) = self
+                        .file()
+                        .symbols(
+                            symtab,
+                            &self.libraries,
+                            &self.limits,
+                            self.deadline,
+                            &mut warnings,
+                        )
+                        .ok_or_else(|| Self::invalid(&self.filename, "bad symbol table"))??;
+                    self.warnings = warnings;
+                    DynamicSymbols {
+                        imports: sorted_symbols(imports),
+                        exports: sorted_symbols(exports),
+                        ..DynamicSymbols::default()
+                    }
+                }
+                None => DynamicSymbols::default(),
+            },
+        };
+        Ok(self.symbols.insert(symbols))
+    }
+
+    fn capture(mut self: Box<Self>) -> Result<AbiCapture> {
+        self.symbols()?;
+        self.deadline.check()?;
+        Ok(self.capture_sonames())
+    }
+
+    fn capture_sonames(self: Box<Self>) -> AbiCapture {
+        let this = *self;
+        let symbols = this.symbols.unwrap_or_default();
+        AbiCapture {
+            warnings: this.warnings,
+            ..assemble(this.filename, this.kind, this.arch, this.dynamic, symbols)
+        }
+    }
+}
+
+/// The range of the first slice of a universal file, or of the file itself.
+fn thin_slice(data: &[u8]) -> Option<Range<usize>> {
+    let magic = read_u32(data, 0, true)?;
+    let is_64 = match magic {
+        FAT_MAGIC => false,
+        FAT_MAGIC_64 => true,
+        _ => return Some(0..data.len()),
+    };
+    if read_u32(data, 4, true)? == 0 {
+        return None;
+    }
+    // fat_arch: cputype, cpusubtype, offset, size, align (offset and size are 64 bit in fat_arch_64)
+    let (offset, size) = match is_64 {
+        true => (read_u64(data, 8 + 8, true)?, read_u64(data, 8 + 16, true)?),
+        false => (
+            u64::from(read_u32(data, 8 + 8, true)?),
+            u64::from(read_u32(data, 8 + 12, true)?),
+        ),
+    };
+    let start = usize::try_from(offset).ok()?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    (end <= data.len()).then_some(start..end)
+}
+
+/// A parsed mach_header
+struct MachO<'data> {
+    data: &'data [u8],
+    big_endian: bool,
+    is_64: bool,
+    cputype: u32,
+    filetype: u32,
+    ncmds: u32,
+    flags: u32,
+}
+
+impl<'data> MachO<'data> {
+    fn parse(data: &'data [u8]) -> Option<Self> {
+        let (big_endian, is_64) = match (read_u32(data, 0, false)?, read_u32(data, 0, true)?) {
+            (MH_MAGIC, _) => (false, false),
+            (MH_MAGIC_64, _) => (false, true),
+            (_, MH_MAGIC) => (true, false),
+            (_, MH_MAGIC_64) => (true, true),
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            big_endian,
+            is_64,
+            cputype: read_u32(data, 4, big_endian)?,
+            filetype: read_u32(data, 12, big_endian)?,
+            ncmds: read_u32(data, 16, big_endian)?,
+            flags: read_u32(data, 24, big_endian)?,
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        read_u32(self.data, offset, self.big_endian)
+    }
+
+    /// The (cmd, bytes) of each load command.
+    fn load_commands(&self) -> Option<Vec<(u32, &'data [u8])>> {
+        let mut offset = if self.is_64 { 32 } else { 28 };
+        let mut commands = vec![];
+        for _ in 0..self.ncmds {
+            let cmd = self.u32(offset)?;
+            let size = usize::try_from(self.u32(offset + 4)?).ok()?;
+            if size < 8 {
+                return None;
+            }
+            commands.push((cmd, self.data.get(offset..offset.checked_add(size)?)?));
+            offset += size;
+        }
+        Some(commands)
+    }
+
+    /// The string a dylib, rpath or similar command points to (an lc_str, right after cmdsize).
+    fn lc_str(&self, command: &[u8]) -> Option<String> {
+        let offset = usize::try_from(read_u32(command, 8, self.big_endian)?).ok()?;
+        let bytes = command.get(offset..)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8(bytes[..end].to_vec()).ok()
+    }
+
+    /// The symoff, nsyms, stroff and strsize of an LC_SYMTAB command.
+    fn symtab(&self, command: &[u8]) -> Option<[u32; 4]> {
+        let word = |idx: usize| read_u32(command, 8 + 4 * idx, self.big_endian);
+        Some([word(0)?, word(1)?, word(2)?, word(3)?])
+    }
+
+    /// The (imports, exports) of the symbol table an LC_SYMTAB command points to.
+    fn symbols(
+        &self,
+        symtab: [u32; 4],
+        needed: &[String],
+        limits: &Limits,
+        deadline: Deadline,
+        warnings: &mut Vec<CaptureWarning>,
+    ) -> Option<Result<(Vec<Symbol>, Vec<Symbol>)>> {
+        let [symoff, nsyms, stroff, strsize] = symtab;
+        if let Err(err) = limits.check_dynsyms(nsyms as usize) {
+            return Some(Err(err));
+        }
+        let start = usize::try_from(stroff).ok()?;
+        let strtab = self
+            .data
+            .get(start..start.checked_add(usize::try_from(strsize).ok()?)?)?;
+        let entry_size = if self.is_64 { 16 } else { 12 };
+        let two_level = self.flags & MH_TWOLEVEL != 0;
+
+        let (mut imports, mut exports) = (vec![], vec![]);
+        for idx in 0..nsyms as usize {
+            if idx % 4096 == 0 {
+                if let Err(err) = deadline.check() {
+                    return Some(Err(err));
+                }
+            }
+            // nlist(_64): n_strx, n_type, n_sect, n_desc, n_value
+            let entry = (symoff as usize).checked_add(idx.checked_mul(entry_size)?)?;
+            let n_strx = usize::try_from(self.u32(entry)?).ok()?;
+            let n_type = *self.data.get(entry + 4)?;
+            let n_desc = read_u16(self.data, entry + 6, self.big_endian)?;
+            if n_type & N_STAB != 0 || n_type & N_EXT == 0 {
+                continue;
+            }
+            let name = strtab.get(n_strx..)?;
+            let end = name.iter().position(|&b| b == 0)?;
+            let name = match std::str::from_utf8(&name[..end]) {
+                Ok(name) => name,
+                Err(err) => {
+                    warnings.push(CaptureWarning::SymbolName {
+                        index: idx,
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+            if name.is_empty() {
+                continue;
+            }
+
+            let mut symbol = Symbol::new(name);
+            if n_type & N_TYPE == N_UNDF {
+                let ordinal = usize::from(n_desc >> 8);
+                if two_level && (1..=needed.len()).contains(&ordinal) {
+                    symbol.provider = Some(needed[ordinal - 1].clone());
+                }
+                imports.push(symbol);
+            } else if n_type & N_PEXT == 0 {
+                exports.push(symbol);
+            }
+        }
+        Some(Ok((imports, exports)))
+    }
+}
+
+/// The customary (uname -m style) name of a Mach-O CPU type.
+fn arch_name(cputype: u32) -> String {
+    match cputype {
+        0x7 => "i386".to_string(),
+        0x0100_0007 => "x86_64".to_string(),
+        0xc => "arm".to_string(),
+        0x0100_000c => "arm64".to_string(),
+        0x0200_000c => "arm64_32".to_string(),
+        0x12 => "ppc".to_string(),
+        0x0100_0012 => "ppc64".to_string(),
+        cputype => format!("cputype {cputype:#x}"),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u16::from_be_bytes(bytes),
+        false => u16::from_le_bytes(bytes),
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u32::from_be_bytes(bytes),
+        false => u32::from_le_bytes(bytes),
+    })
+}
+
+fn read_u64(data: &[u8], offset: usize, big_endian: bool) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u64::from_be_bytes(bytes),
+        false => u64::from_le_bytes(bytes),
+    })
+}
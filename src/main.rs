@@ -23,30 +23,119 @@
 // use elf::note::Note;
 // use elf::note::NoteGnuBuildId;
 // use elf::section::SectionHeader;
-use abireport_rs::parse_elf;
-use std::env;
-use std::fs;
+use clap::{Parser, Subcommand};
+
+mod cli;
+
+/// Capture the dynamic linking ABI of ELF executables and shared objects
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// When to color the output
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: cli::color::ColorChoice,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Capture the ABI of one or more ELF files
+    Scan(cli::scan::Args),
+    /// Convert a report between the JSON and compact binary formats
+    Convert(cli::convert::Args),
+    /// Combine several reports into one
+    Merge(cli::merge::Args),
+    /// Compare two reports
+    Diff(cli::diff::Args),
+    /// Look for packaging mistakes in a report, e.g. duplicate sonames
+    Check(cli::check::Args),
+    /// Compare the exports of a package built for two architectures
+    Parity(cli::parity::Args),
+    /// List what in a repository a library update breaks or forces to be rebuilt
+    Impact(cli::impact::Args),
+    /// Plan moving a repository to a new soname of a library: what to port, what to rebuild when
+    Transition(cli::transition::Args),
+    /// List which imports of a tree preloading a library would interpose
+    Preload(cli::preload::Args),
+    /// List the symbols defined by more than one object loaded into a process
+    Conflicts(cli::conflicts::Args),
+    /// Show every library an executable loads and where the dynamic linker finds it
+    Closure(cli::closure::Args),
+    /// List the directories the RPATHs and RUNPATHs of an install root name, and which are missing
+    SearchDirs(cli::search_dirs::Args),
+    /// Record which needed library supplies each import of a report
+    Resolve(cli::resolve::Args),
+    /// Suggest which files of an install root belong in a -devel subpackage
+    Split(cli::split::Args),
+    /// Summarize a report: counts by kind and arch, most imported symbols, orphaned sonames
+    Stats(cli::stats::Args),
+    /// Look things up in a report
+    #[command(subcommand)]
+    Query(cli::query::Query),
+    /// Count the symbols each needed library of each file supplies
+    Usage(cli::usage::Args),
+    /// Create a key pair for signing reports
+    Keygen(cli::keygen::Args),
+    /// Sign a report (minisign compatible)
+    Sign(cli::sign::Args),
+    /// Verify the signature of a report
+    Verify(cli::verify::Args),
+    /// Print the JSON Schema of reports or diffs
+    Schema(cli::schema::Args),
+    /// Check that a file is a valid report or diff
+    Validate(cli::validate::Args),
+    /// Answer who-provides/who-needs/diff queries about a report over a unix socket
+    #[cfg(unix)]
+    Serve(cli::serve::Args),
+    /// Re-capture ELF files as they change, printing what changed in their ABI
+    Watch(cli::watch::Args),
+    /// Explore a report interactively
+    #[cfg(feature = "browse")]
+    Browse(cli::browse::Args),
+    /// Time end-to-end scans of a corpus directory
+    #[command(hide = true)]
+    Bench(cli::bench::Args),
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
 
-    let files = &args[1..];
+    let result = match cli.command {
+        Command::Scan(args) => cli::scan::run(args),
+        Command::Convert(args) => cli::convert::run(args),
+        Command::Merge(args) => cli::merge::run(args),
+        Command::Diff(args) => cli::diff::run(args, cli.color),
+        Command::Check(args) => cli::check::run(args, cli.color),
+        Command::Parity(args) => cli::parity::run(args, cli.color),
+        Command::Impact(args) => cli::impact::run(args, cli.color),
+        Command::Transition(args) => cli::transition::run(args, cli.color),
+        Command::Preload(args) => cli::preload::run(args, cli.color),
+        Command::Conflicts(args) => cli::conflicts::run(args, cli.color),
+        Command::Closure(args) => cli::closure::run(args, cli.color),
+        Command::SearchDirs(args) => cli::search_dirs::run(args, cli.color),
+        Command::Resolve(args) => cli::resolve::run(args),
+        Command::Split(args) => cli::split::run(args),
+        Command::Stats(args) => cli::stats::run(args),
+        Command::Query(query) => cli::query::run(query),
+        Command::Usage(args) => cli::usage::run(args, cli.color),
+        Command::Keygen(args) => cli::keygen::run(args),
+        Command::Sign(args) => cli::sign::run(args),
+        Command::Verify(args) => cli::verify::run(args),
+        Command::Schema(args) => cli::schema::run(args),
+        Command::Validate(args) => cli::validate::run(args),
+        #[cfg(unix)]
+        Command::Serve(args) => cli::serve::run(args),
+        Command::Watch(args) => cli::watch::run(args, cli.color),
+        #[cfg(feature = "browse")]
+        Command::Browse(args) => cli::browse::run(args),
+        Command::Bench(args) => cli::bench::run(args),
+    };
 
-    if !files.is_empty() {
-        for file in files {
-            // Instantiating as symlink_metadata ensures that symlinks aren't followed
-            let metadata = fs::symlink_metadata(file)
-                .expect("{file} could not be parsed as symlink_metadata.");
-            if !metadata.is_dir() && !metadata.is_symlink() {
-                if let Some(abi_capture) =
-                    Some(parse_elf(file).expect("{file} is not an ELF format file."))
-                {
-                    println!("{:#?}", abi_capture);
-                }
-            } else {
-                println!("{file} is either a directory or a symlink. Skipping.")
-            }
-        }
+    if let Err(err) = result {
+        eprintln!("abireport: {err}");
+        std::process::exit(1);
     }
 }
 
@@ -0,0 +1,121 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Merging several reports into one.
+//!
+//! Reports of separate packages (or of separate scans of one tree) merge cleanly. The same
+//! filename turning up in two reports, typically two builds of the same package, is a conflict,
+//! which a [MergePolicy] resolves deterministically rather than by whichever capture came last.
+//! Captures of the same file (the same [CaptureIdentity](crate::CaptureIdentity), or identical
+//! captures) are never a conflict: the first of them is kept.
+
+use crate::{AbiCapture, AbiReport, ReportHeader};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+/// What to do about captures of the same filename in several of the reports merged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Fail the merge, naming the filename
+    #[default]
+    Error,
+    /// Keep the capture of the newest file: the latest mtime (captures without one counting as
+    /// the oldest), else the capture of the report scanned last, else the one of the later
+    /// report
+    PreferNewest,
+    /// Keep all of them, each filename qualified with the position of its report among the
+    /// merged ones (`/usr/lib/libfoo.so.1#2` for the second report)
+    KeepBoth,
+}
+
+impl AbiReport {
+    /// Merge `reports` into one report, resolving captures of the same filename by `policy`.
+    ///
+    /// The merged report is sorted in the first report's order. It only records an input root
+    /// all reports agree on, and is sonames-only if any of the reports is.
+    pub fn merge(
+        reports: impl IntoIterator<Item = AbiReport>,
+        policy: MergePolicy,
+    ) -> Result<Self> {
+        let mut header = None::<ReportHeader>;
+        let mut input_roots = vec![];
+        let mut sonames_only = false;
+        // every capture with the position and scan time of its report, by filename
+        let mut by_filename = HashMap::<String, Vec<(usize, u64, AbiCapture)>>::new();
+        let mut filenames = vec![];
+
+        for (position, report) in reports.into_iter().enumerate() {
+            let (report_header, captures) = report.into_parts();
+            input_roots.push(report_header.input_root.clone());
+            sonames_only |= report_header.sonames_only;
+            for capture in captures {
+                let copies = by_filename.entry(capture.filename.clone()).or_default();
+                if copies.is_empty() {
+                    filenames.push(capture.filename.clone());
+                }
+                let same_file = |copy: &AbiCapture| {
+                    *copy == capture || copy.identity().is_same_file(&capture.identity())
+                };
+                if !copies.iter().any(|(_, _, copy)| same_file(copy)) {
+                    copies.push((position + 1, report_header.scan_time, capture));
+                }
+            }
+            header.get_or_insert(report_header);
+        }
+
+        let mut captures = vec![];
+        for filename in filenames {
+            let mut copies = by_filename.remove(&filename).unwrap_or_default();
+            if copies.len() == 1 {
+                captures.extend(copies.pop().map(|(_, _, capture)| capture));
+                continue;
+            }
+            match policy {
+                MergePolicy::Error => {
+                    let positions = copies
+                        .iter()
+                        .map(|(position, _, _)| position.to_string())
+                        .collect::<Vec<_>>();
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "{filename} differs between reports {}",
+                            positions.join(", ")
+                        ),
+                    ));
+                }
+                MergePolicy::PreferNewest => {
+                    let newest = copies
+                        .into_iter()
+                        .max_by_key(|(position, scan_time, capture)| {
+                            let mtime = capture.metadata().and_then(|metadata| metadata.mtime);
+                            (mtime, *scan_time, *position)
+                        });
+                    captures.extend(newest.map(|(_, _, capture)| capture));
+                }
+                MergePolicy::KeepBoth => {
+                    for (position, _, mut capture) in copies {
+                        capture.filename = format!("{filename}#{position}");
+                        captures.push(capture);
+                    }
+                }
+            }
+        }
+
+        let header = header.unwrap_or_default();
+        let input_root = match input_roots.iter().all(|root| *root == input_roots[0]) {
+            true => header.input_root.clone(),
+            false => None,
+        };
+        Ok(Self::with_header(
+            ReportHeader {
+                input_root,
+                sonames_only,
+                order: header.order,
+                ..ReportHeader::new()
+            },
+            captures,
+        ))
+    }
+}
@@ -0,0 +1,247 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Filesystem metadata of captured files.
+//!
+//! Not part of the ABI, but context for it: a setuid binary or one with file capabilities runs
+//! with privileges, so how it finds its libraries matters a lot more, and the size and mtime tell
+//! whether two reports were taken of the same files without reading them again. File
+//! capabilities (the `security.capability` xattr) are only read with the `xattr` feature.
+//!
+//! Scans also record a SHA-256 of each file's contents. Together with its path, that identifies
+//! a capture across reports ([CaptureIdentity]): the same identity is the same file, whatever
+//! else differs between the reports it is in.
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fs")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "fs")]
+use std::io::Result;
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::time::UNIX_EPOCH;
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+
+/// What the filesystem says about a captured file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct FileMetadata {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size: u64, // in bytes
+    pub mode: u32, // permission bits, including setuid/setgid/sticky
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub mtime: Option<u64>, // last modification, in seconds since the epoch
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub capabilities: Option<String>, // file capabilities in getcap(8) syntax, e.g. "cap_net_raw=ep"
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sha256: Option<String>, // of the contents, as hex (not recorded by older reports)
+}
+
+/// What identifies a captured file across reports: where it is and what is in it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CaptureIdentity<'a> {
+    pub filename: &'a str,
+    pub sha256: Option<&'a str>, // None if the capture didn't record its contents
+}
+
+impl CaptureIdentity<'_> {
+    /// Whether both identities are known to be of the same file: the same path with the same
+    /// contents. Without digests, nothing is known to be the same.
+    pub fn is_same_file(&self, other: &CaptureIdentity) -> bool {
+        self.sha256.is_some() && self == other
+    }
+}
+
+impl FileMetadata {
+    pub fn is_setuid(&self) -> bool {
+        self.mode & S_ISUID != 0
+    }
+
+    pub fn is_setgid(&self) -> bool {
+        self.mode & S_ISGID != 0
+    }
+
+    /// Whether the file runs with privileges its caller may not have.
+    pub fn is_privileged(&self) -> bool {
+        self.is_setuid() || self.is_setgid() || self.capabilities.is_some()
+    }
+
+    /// Read the metadata of a file on disk. Without unix permissions, the mode is 0.
+    #[cfg(feature = "fs")]
+    pub fn read(path: &Path) -> Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        #[cfg(unix)]
+        let mode = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o7777;
+        #[cfg(not(unix))]
+        let mode = 0;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_secs());
+        Ok(Self {
+            size: metadata.len(),
+            mode,
+            mtime,
+            capabilities: read_capabilities(path),
+            sha256: None,
+        })
+    }
+
+    /// Record the digest of the file's contents, as read.
+    #[cfg(feature = "fs")]
+    pub fn with_contents(mut self, data: &[u8]) -> Self {
+        self.sha256 = Some(format!("{:x}", Sha256::digest(data)));
+        self
+    }
+}
+
+#[cfg(all(feature = "fs", not(all(feature = "xattr", target_os = "linux"))))]
+fn read_capabilities(_path: &Path) -> Option<String> {
+    None
+}
+
+/// The `security.capability` xattr, if the file has one.
+#[cfg(all(feature = "fs", feature = "xattr", target_os = "linux"))]
+fn read_capabilities(path: &Path) -> Option<String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // VFS_CAP_U32_3 plus the root id, the largest revision
+    let mut data = [0u8; 24];
+    // SAFETY: both strings are NUL terminated, and the buffer size is passed along
+    let len = unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            c"security.capability".as_ptr(),
+            data.as_mut_ptr().cast(),
+            data.len(),
+        )
+    };
+    let len = usize::try_from(len).ok()?;
+    decode_capabilities(&data[..len])
+}
+
+/// The capabilities, by bit
+#[cfg(all(feature = "fs", feature = "xattr", target_os = "linux"))]
+const CAPABILITY_NAMES: [&str; 41] = [
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+/// Render `struct vfs_cap_data` like getcap(8): one clause per distinct set of flags, with the
+/// capabilities having them, e.g. `cap_net_admin,cap_net_raw=ep` or `cap_net_raw=p cap_setuid+i`.
+/// The effective flag is a single bit, raising every permitted and inheritable capability.
+#[cfg(all(feature = "fs", feature = "xattr", target_os = "linux"))]
+fn decode_capabilities(data: &[u8]) -> Option<String> {
+    const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x000001;
+    const VFS_CAP_REVISION_MASK: u32 = 0xFF000000;
+    const VFS_CAP_REVISION_1: u32 = 0x01000000;
+
+    let word = |idx: usize| {
+        let bytes = data.get(idx * 4..idx * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let magic = word(0)?;
+    // revision 1 has one 32 bit set, later ones two
+    let sets = match magic & VFS_CAP_REVISION_MASK {
+        VFS_CAP_REVISION_1 => 1,
+        _ => 2,
+    };
+    let (mut permitted, mut inheritable) = (0u64, 0u64);
+    for set in 0..sets {
+        permitted |= u64::from(word(1 + set * 2)?) << (32 * set);
+        inheritable |= u64::from(word(2 + set * 2)?) << (32 * set);
+    }
+
+    let effective = magic & VFS_CAP_FLAGS_EFFECTIVE != 0;
+    // the capabilities of each combination of flags, in the order of their first capability
+    let mut clauses: Vec<(String, Vec<String>)> = vec![];
+    for bit in (0..64).filter(|bit| (permitted | inheritable) & (1 << bit) != 0) {
+        let mut flags = String::new();
+        if effective {
+            flags.push('e');
+        }
+        if inheritable & (1 << bit) != 0 {
+            flags.push('i');
+        }
+        if permitted & (1 << bit) != 0 {
+            flags.push('p');
+        }
+        let name = match CAPABILITY_NAMES.get(bit) {
+            Some(name) => name.to_string(),
+            None => format!("cap_{bit}"),
+        };
+        match clauses.iter_mut().find(|(other, _)| *other == flags) {
+            Some((_, names)) => names.push(name),
+            None => clauses.push((flags, vec![name])),
+        }
+    }
+    // getcap writes the clauses after the first with `+`
+    let clauses = clauses
+        .iter()
+        .enumerate()
+        .map(|(idx, (flags, names))| {
+            let op = if idx == 0 { '=' } else { '+' };
+            format!("{}{op}{flags}", names.join(","))
+        })
+        .collect::<Vec<_>>();
+    (!clauses.is_empty()).then(|| clauses.join(" "))
+}
@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Which package each captured file belongs to.
+//!
+//! Reports of a whole repository answer questions per file, but packagers think in packages:
+//! what did zlib's ABI do between 1.3 and 1.3.1? Captures can carry the name and version of
+//! their package, assigned while scanning from a [PackageMap]: one `GLOB NAME [VERSION]` rule
+//! per line, `#` comments, with the first rule whose glob matches a file's path winning, e.g.
+//!
+//! ```text
+//! # glob                         package   version
+//! **/usr/lib/libz.so*            zlib      1.3.1
+//! **/usr/lib/libzstd.so*         zstd      1.5.6
+//! ```
+//!
+//! [AbiReport::package] then narrows a report down to one package, so that diffing two
+//! reports' narrowings compares one package's versions.
+
+use crate::AbiReport;
+use globset::{Glob, GlobMatcher};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+
+/// The package a file belongs to
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PackageRef {
+    pub name: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub version: Option<String>,
+}
+
+impl PackageRef {
+    pub fn new(name: impl Into<String>, version: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            version,
+        }
+    }
+
+    /// Parse `name` or `name@version`.
+    pub fn parse(package: &str) -> Result<Self> {
+        let (name, version) = match package.split_once('@') {
+            Some((name, version)) => (name, Some(version.to_string())),
+            None => (package, None),
+        };
+        if name.is_empty() || version.as_deref() == Some("") {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid package {package:?} (expected NAME or NAME@VERSION)"),
+            ));
+        }
+        Ok(Self::new(name, version))
+    }
+}
+
+/// `name` or `name@version`, as accepted by [PackageRef::parse]
+impl fmt::Display for PackageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{version}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Rules assigning files to packages by their path
+#[derive(Clone, Debug, Default)]
+pub struct PackageMap {
+    rules: Vec<(GlobMatcher, PackageRef)>,
+    fallback: Option<PackageRef>, // for files no rule matches
+}
+
+impl PackageMap {
+    /// Parse a mapping file, see the module docs.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut map = Self::default();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let invalid = |message: String| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("line {}: {message}", idx + 1),
+                )
+            };
+            let (pattern, package) = match fields[..] {
+                [] => continue,
+                [pattern, name] => (pattern, PackageRef::new(name, None)),
+                [pattern, name, version] => (pattern, PackageRef::new(name, Some(version.into()))),
+                _ => return Err(invalid("expected GLOB NAME [VERSION]".to_string())),
+            };
+            let glob = Glob::new(pattern)
+                .map_err(|err| invalid(format!("invalid pattern {pattern:?}: {err}")))?;
+            map.rules.push((glob.compile_matcher(), package));
+        }
+        Ok(map)
+    }
+
+    /// Assign files no rule matches to `package`.
+    pub fn with_fallback(mut self, package: PackageRef) -> Self {
+        self.fallback = Some(package);
+        self
+    }
+
+    /// The package of the file at `path`, if any.
+    pub fn lookup(&self, path: &str) -> Option<&PackageRef> {
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(path))
+            .map(|(_, package)| package)
+            .or(self.fallback.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.fallback.is_none()
+    }
+}
+
+impl AbiReport {
+    /// The captures of the package named `name` (of any version), with this report's header.
+    pub fn package(&self, name: &str) -> AbiReport {
+        let captures = self
+            .captures()
+            .iter()
+            .filter(|capture| {
+                capture
+                    .package()
+                    .is_some_and(|package| package.name == name)
+            })
+            .cloned()
+            .collect();
+        Self::with_header(self.header().clone(), captures)
+    }
+
+    /// The packages the captures belong to, sorted and without duplicates.
+    pub fn packages(&self) -> Vec<&PackageRef> {
+        let packages = self
+            .captures()
+            .iter()
+            .filter_map(|capture| capture.package())
+            .collect::<BTreeSet<_>>();
+        packages.into_iter().collect()
+    }
+}
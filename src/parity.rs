@@ -0,0 +1,140 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Comparing the exports of one package built for two architectures.
+//!
+//! Libraries are paired up by soname (else file name), as their paths usually differ between
+//! architectures (`/usr/lib64` vs `/usr/lib`, multiarch triplets). Exports are compared by name
+//! only: symbol versions legitimately differ, e.g. glibc's base version is GLIBC_2.2.5 on x86_64
+//! but GLIBC_2.17 on aarch64. Exports that are expected on one architecture only (intrinsics,
+//! assembly fast paths) can be allowed per architecture with glob patterns.
+
+use crate::{AbiCapture, AbiReport, ElfKind, Symbol};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error, ErrorKind, Result};
+
+/// Exports allowed to exist on one architecture only, by architecture
+#[derive(Clone, Debug, Default)]
+pub struct ParityAllowlist {
+    by_arch: HashMap<String, GlobSet>,
+}
+
+impl ParityAllowlist {
+    /// Allow the exports matching each glob pattern (e.g. `__x86_*`) on the architecture it is
+    /// paired with only.
+    pub fn new<'a>(patterns: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        let mut builders = HashMap::<&str, GlobSetBuilder>::new();
+        for (arch, pattern) in patterns {
+            let glob =
+                Glob::new(pattern).map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+            builders
+                .entry(arch)
+                .or_insert_with(GlobSetBuilder::new)
+                .add(glob);
+        }
+        let by_arch = builders
+            .into_iter()
+            .map(|(arch, builder)| {
+                let set = builder
+                    .build()
+                    .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+                Ok((arch.to_string(), set))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { by_arch })
+    }
+
+    fn allows(&self, arch: Option<&str>, name: &str) -> bool {
+        arch.and_then(|arch| self.by_arch.get(arch))
+            .is_some_and(|set| set.is_match(name))
+    }
+}
+
+/// One library whose exports differ between the two builds
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ArchParity {
+    pub library: String,         // soname, else file name
+    pub left: Option<String>,    // the file in the first report, None if it has none
+    pub right: Option<String>,   // the file in the second report, None if it has none
+    pub only_left: Vec<Symbol>,  // exports of the first build only, less the allowed ones
+    pub only_right: Vec<Symbol>, // exports of the second build only, less the allowed ones
+}
+
+impl AbiReport {
+    /// Compare the exports of the shared objects in this report with those of the same
+    /// package built for another architecture, returning the libraries that differ in library
+    /// order.
+    pub fn arch_parity(&self, other: &AbiReport, allowed: &ParityAllowlist) -> Vec<ArchParity> {
+        let mut libraries = BTreeMap::<&str, (Option<&AbiCapture>, Option<&AbiCapture>)>::new();
+        for capture in self.captures().iter().filter(|c| is_library(c)) {
+            libraries.entry(library_name(capture)).or_default().0 = Some(capture);
+        }
+        for capture in other.captures().iter().filter(|c| is_library(c)) {
+            libraries.entry(library_name(capture)).or_default().1 = Some(capture);
+        }
+
+        libraries
+            .into_iter()
+            .filter_map(|(library, (left, right))| {
+                let parity = ArchParity {
+                    library: library.to_string(),
+                    left: left.map(|capture| capture.filename().to_string()),
+                    right: right.map(|capture| capture.filename().to_string()),
+                    only_left: exports_only_in(left, right, allowed),
+                    only_right: exports_only_in(right, left, allowed),
+                };
+                let differs = parity.left.is_none()
+                    || parity.right.is_none()
+                    || !parity.only_left.is_empty()
+                    || !parity.only_right.is_empty();
+                differs.then_some(parity)
+            })
+            .collect()
+    }
+}
+
+fn is_library(capture: &AbiCapture) -> bool {
+    capture.elf_kind() == ElfKind::SharedObject
+}
+
+fn library_name(library: &AbiCapture) -> &str {
+    library.soname().unwrap_or_else(|| {
+        let filename = library.filename();
+        filename.rsplit('/').next().unwrap_or(filename)
+    })
+}
+
+/// The exports of `this` not in `that`, unless allowed on the architecture of `this`. Nothing
+/// if either is missing: the whole library is.
+fn exports_only_in(
+    this: Option<&AbiCapture>,
+    that: Option<&AbiCapture>,
+    allowed: &ParityAllowlist,
+) -> Vec<Symbol> {
+    let (Some(this), Some(that)) = (this, that) else {
+        return vec![];
+    };
+    let names = that
+        .dynsym_exports()
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect::<HashSet<_>>();
+    let mut only = this
+        .dynsym_exports()
+        .iter()
+        .filter(|symbol| !names.contains(symbol.name.as_str()))
+        .filter(|symbol| !allowed.allows(this.arch(), &symbol.name))
+        .cloned()
+        .collect::<Vec<_>>();
+    // several versions of one name would otherwise repeat it
+    only.dedup_by(|a, b| a.name == b.name);
+    only
+}
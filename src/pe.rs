@@ -0,0 +1,450 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! PE/COFF DLLs and executables, e.g. the Windows artifacts of a mingw cross toolchain.
+//!
+//! The export directory names the DLL, which takes the place of the soname, and lists its named
+//! exports; exports pointing into an executable section are functions, the others data (and
+//! forwarded exports are of unknown type). Exports with only an ordinal have no name to track
+//! and are left out. The import descriptors (and delay load ones) take the place of DT_NEEDED,
+//! with each import attributed to the DLL it is imported from. Imports by ordinal are left out,
+//! and so are exports whose name isn't UTF-8, each with a [CaptureWarning::SymbolName].
+
+use crate::backend::assemble;
+#[cfg(feature = "fs")]
+use crate::backend::read_file;
+use crate::limits::Deadline;
+use crate::{natural_sorted, sorted_symbols};
+use crate::{
+    AbiCapture, CaptureWarning, DynamicInfo, DynamicSymbols, ElfKind, Limits, ObjectBackend,
+    ParseOptions, Symbol, SymbolKind,
+};
+use std::io::{Error, ErrorKind, Result};
+
+const IMAGE_FILE_EXECUTABLE_IMAGE: u16 = 0x0002;
+const IMAGE_FILE_DLL: u16 = 0x2000;
+const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10b;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20b;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
+const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+const IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT: usize = 13;
+
+/// Whether `data` starts like a PE file: an MZ stub pointing to a PE signature.
+pub fn is_pe(data: &[u8]) -> bool {
+    data.starts_with(b"MZ")
+        && read_u32(data, 0x3c)
+            .and_then(|offset| data.get(usize::try_from(offset).ok()?..)?.get(..4))
+            .is_some_and(|signature| signature == b"PE\0\0")
+}
+
+/// Read and capture a PE file, within `limits`.
+#[cfg(feature = "fs")]
+pub fn parse_pe(file_name: &str, limits: &Limits) -> Result<AbiCapture> {
+    let data = read_file(file_name, limits)?;
+    let file = PeFile::parse(file_name, data, limits, ParseOptions::default())?;
+    Box::new(file).capture()
+}
+
+/// [parse_pe] on a file already in memory.
+pub fn parse_pe_bytes(file_name: &str, data: &[u8], limits: &Limits) -> Result<AbiCapture> {
+    let file = PeFile::parse(file_name, data.to_vec(), limits, ParseOptions::default())?;
+    Box::new(file).capture()
+}
+
+/// A PE file: the headers and import tables are parsed up front, the exports when asked for.
+pub struct PeFile {
+    filename: String,
+    data: Vec<u8>,
+    limits: Limits,
+    deadline: Deadline, // started when the file was parsed
+    kind: ElfKind,
+    arch: String,
+    imports: Vec<(String, Vec<String>)>, // the names imported from each DLL
+    dynamic: DynamicInfo,
+    symbols: Option<DynamicSymbols>, // parsed on first use
+    warnings: Vec<CaptureWarning>,   // about the exports skipped while parsing them
+}
+
+impl PeFile {
+    fn file(&self) -> Pe<'_> {
+        Pe::parse(&self.data).expect("validated by PeFile::parse")
+    }
+
+    fn invalid(file_name: &str, what: &str) -> Error {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not parse {file_name:?} as PE data: {what}"),
+        )
+    }
+}
+
+impl ObjectBackend for PeFile {
+    fn detect(header: &[u8]) -> bool {
+        is_pe(header)
+    }
+
+    fn parse(file_name: &str, data: Vec<u8>, limits: &Limits, _: ParseOptions) -> Result<Self> {
+        let deadline = limits.deadline();
+        limits.check_file_size(data.len() as u64)?;
+        let invalid = |what: &str| Self::invalid(file_name, what);
+        let file = Pe::parse(&data).ok_or_else(|| invalid("bad headers"))?;
+
+        let kind = if file.characteristics & IMAGE_FILE_DLL != 0 {
+            ElfKind::SharedObject
+        } else if file.characteristics & IMAGE_FILE_EXECUTABLE_IMAGE != 0 {
+            ElfKind::Executable
+        } else {
+            ElfKind::Unknown
+        };
+        let imports = file.imports().ok_or_else(|| invalid("bad import table"))?;
+        let dynamic = DynamicInfo {
+            needed: natural_sorted(imports.iter().map(|(dll, _)| dll.clone())),
+            needed_order: imports.iter().map(|(dll, _)| dll.clone()).collect(),
+            soname: file.dll_name().ok_or_else(|| invalid("bad export table"))?,
+            ..DynamicInfo::default()
+        };
+        let arch = arch_name(file.machine);
+        Ok(Self {
+            filename: file_name.to_string(),
+            data,
+            limits: limits.clone(),
+            deadline,
+            kind,
+            arch,
+            imports,
+            dynamic,
+            symbols: None,
+            warnings: vec![],
+        })
+    }
+
+    fn classify(&self) -> ElfKind {
+        self.kind
+    }
+
+    fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    fn deps(&mut self) -> &DynamicInfo {
+        &self.dynamic
+    }
+
+    fn symbols(&mut self) -> Result<&DynamicSymbols> {
+        let symbols = match self.symbols.take() {
+            Some(symbols) => symbols,
+            None => {
+                let imports = self
+                    .imports
+                    .iter()
+                    .flat_map(|(dll, names)| {
+                        names.iter().map(|name| {
+                            let mut symbol = Symbol::new(name.as_str());
+                            symbol.provider = Some(dll.clone());
+                            symbol
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                self.limits.check_dynsyms(imports.len())?;
+                let mut warnings = vec![];
+                let exports = self
+                    .file()
+                    .exports(&self.limits, self.deadline, &mut warnings)
+                    .ok_or_else(|| Self::invalid(&self.filename, "bad export table"))??;
+                self.warnings = warnings;
+                DynamicSymbols {
+                    imports: sorted_symbols(imports),
+                    exports: sorted_symbols(exports),
+                    ..DynamicSymbols::default()
+                }
+            }
+        };
+        Ok(self.symbols.insert(symbols))
+    }
+
+    fn capture(mut self: Box<Self>) -> Result<AbiCapture> {
+        self.symbols()?;
+        self.deadline.check()?;
+        Ok(self.capture_sonames())
+    }
+
+    fn capture_sonames(self: Box<Self>) -> AbiCapture {
+        let this = *self;
+        let symbols = this.symbols.unwrap_or_default();
+        AbiCapture {
+            warnings: this.warnings,
+            ..assemble(this.filename, this.kind, this.arch, this.dynamic, symbols)
+        }
+    }
+}
+
+/// A section header: where a range of RVAs lives in the file
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+    characteristics: u32,
+}
+
+/// The parsed COFF and optional headers
+struct Pe<'data> {
+    data: &'data [u8],
+    is_64: bool,
+    machine: u16,
+    characteristics: u16,
+    directories: Vec<(u32, u32)>, // (rva, size) of each data directory
+    sections: Vec<Section>,
+}
+
+impl<'data> Pe<'data> {
+    fn parse(data: &'data [u8]) -> Option<Self> {
+        if !is_pe(data) {
+            return None;
+        }
+        // COFF header: Machine, NumberOfSections, TimeDateStamp, PointerToSymbolTable,
+        // NumberOfSymbols, SizeOfOptionalHeader, Characteristics
+        let coff = usize::try_from(read_u32(data, 0x3c)?).ok()? + 4;
+        let machine = read_u16(data, coff)?;
+        let nsections = read_u16(data, coff + 2)?;
+        let optional_size = usize::from(read_u16(data, coff + 16)?);
+        let characteristics = read_u16(data, coff + 18)?;
+
+        let optional = coff + 20;
+        let (is_64, ndirs_offset) = match read_u16(data, optional)? {
+            IMAGE_NT_OPTIONAL_HDR32_MAGIC => (false, 92),
+            IMAGE_NT_OPTIONAL_HDR64_MAGIC => (true, 108),
+            _ => return None,
+        };
+        let ndirs = read_u32(data, optional + ndirs_offset)?.min(16) as usize;
+        let directories = (0..ndirs)
+            .map(|idx| {
+                let entry = optional + ndirs_offset + 4 + 8 * idx;
+                Some((read_u32(data, entry)?, read_u32(data, entry + 4)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let sections = (0..usize::from(nsections))
+            .map(|idx| {
+                let header = optional + optional_size + 40 * idx;
+                Some(Section {
+                    virtual_size: read_u32(data, header + 8)?,
+                    virtual_address: read_u32(data, header + 12)?,
+                    raw_size: read_u32(data, header + 16)?,
+                    raw_offset: read_u32(data, header + 20)?,
+                    characteristics: read_u32(data, header + 36)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            data,
+            is_64,
+            machine,
+            characteristics,
+            directories,
+            sections,
+        })
+    }
+
+    /// The section an RVA falls into.
+    fn section(&self, rva: u32) -> Option<&Section> {
+        self.sections.iter().find(|section| {
+            let size = section.virtual_size.max(section.raw_size);
+            (section.virtual_address..section.virtual_address.saturating_add(size)).contains(&rva)
+        })
+    }
+
+    /// The file offset of an RVA.
+    fn offset(&self, rva: u32) -> Option<usize> {
+        let section = self.section(rva)?;
+        let offset = (rva - section.virtual_address).checked_add(section.raw_offset)?;
+        usize::try_from(offset).ok()
+    }
+
+    fn u32_at(&self, rva: u32) -> Option<u32> {
+        read_u32(self.data, self.offset(rva)?)
+    }
+
+    /// The NUL terminated string at an RVA.
+    fn str_at(&self, rva: u32) -> Option<String> {
+        String::from_utf8(self.bytes_at(rva)?.to_vec()).ok()
+    }
+
+    /// The bytes of the NUL terminated string at an RVA.
+    fn bytes_at(&self, rva: u32) -> Option<&[u8]> {
+        let bytes = self.data.get(self.offset(rva)?..)?;
+        let end = bytes.iter().position(|&b| b == 0)?;
+        Some(&bytes[..end])
+    }
+
+    /// The (rva, size) of a data directory, if present.
+    fn directory(&self, idx: usize) -> Option<(u32, u32)> {
+        self.directories
+            .get(idx)
+            .copied()
+            .filter(|&(rva, _)| rva != 0)
+    }
+
+    /// The DLL name of the export directory, if there is one.
+    fn dll_name(&self) -> Option<Option<String>> {
+        let Some((dir, _)) = self.directory(IMAGE_DIRECTORY_ENTRY_EXPORT) else {
+            return Some(None);
+        };
+        // IMAGE_EXPORT_DIRECTORY: Characteristics, TimeDateStamp, MajorVersion, MinorVersion,
+        // Name, ...
+        Some(Some(self.str_at(self.u32_at(dir.checked_add(12)?)?)?))
+    }
+
+    /// The named exports of the export directory.
+    fn exports(
+        &self,
+        limits: &Limits,
+        deadline: Deadline,
+        warnings: &mut Vec<CaptureWarning>,
+    ) -> Option<Result<Vec<Symbol>>> {
+        let Some((dir, dir_size)) = self.directory(IMAGE_DIRECTORY_ENTRY_EXPORT) else {
+            return Some(Ok(vec![]));
+        };
+        // IMAGE_EXPORT_DIRECTORY: ..., Name, Base, NumberOfFunctions, NumberOfNames,
+        // AddressOfFunctions, AddressOfNames, AddressOfNameOrdinals
+        let field = |offset: u32| self.u32_at(dir.checked_add(offset)?);
+        let (nfunctions, nnames) = (field(20)?, field(24)?);
+        let (functions, names, ordinals) = (field(28)?, field(32)?, field(36)?);
+        if let Err(err) = limits.check_dynsyms(nnames as usize) {
+            return Some(Err(err));
+        }
+
+        let mut exports = vec![];
+        for idx in 0..nnames {
+            if idx % 4096 == 0 {
+                if let Err(err) = deadline.check() {
+                    return Some(Err(err));
+                }
+            }
+            let name = self.bytes_at(self.u32_at(names.checked_add(4 * idx)?)?)?;
+            let symbol_name = match std::str::from_utf8(name) {
+                Ok(name) => name,
+                Err(err) => {
+                    warnings.push(CaptureWarning::SymbolName {
+                        index: idx as usize,
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let ordinal = read_u16(self.data, self.offset(ordinals.checked_add(2 * idx)?)?)?;
+            if u32::from(ordinal) >= nfunctions {
+                return None;
+            }
+            let address = self.u32_at(functions.checked_add(4 * u32::from(ordinal))?)?;
+            // an address inside the export directory is a forwarder string ("OTHER.name")
+            let kind = if (dir..dir.saturating_add(dir_size)).contains(&address) {
+                SymbolKind::Unknown
+            } else {
+                match self.section(address) {
+                    Some(section) if section.characteristics & IMAGE_SCN_MEM_EXECUTE != 0 => {
+                        SymbolKind::Function
+                    }
+                    Some(_) => SymbolKind::Object,
+                    None => SymbolKind::Unknown,
+                }
+            };
+            exports.push(Symbol::new(symbol_name).with_kind(kind));
+        }
+        Some(Ok(exports))
+    }
+
+    /// The DLLs of the import descriptors, then the delay load ones, with the names imported
+    /// from each.
+    fn imports(&self) -> Option<Vec<(String, Vec<String>)>> {
+        let mut imports = vec![];
+        // IMAGE_IMPORT_DESCRIPTOR: OriginalFirstThunk, TimeDateStamp, ForwarderChain, Name,
+        // FirstThunk
+        if let Some((dir, _)) = self.directory(IMAGE_DIRECTORY_ENTRY_IMPORT) {
+            let mut descriptor = dir;
+            loop {
+                let name = self.u32_at(descriptor.checked_add(12)?)?;
+                if name == 0 {
+                    break;
+                }
+                let lookup = match self.u32_at(descriptor)? {
+                    0 => self.u32_at(descriptor.checked_add(16)?)?,
+                    lookup => lookup,
+                };
+                imports.push((self.str_at(name)?, self.thunk_names(lookup)?));
+                descriptor = descriptor.checked_add(20)?;
+            }
+        }
+        // ImgDelayDescr: Attributes, DllNameRVA, ModuleHandleRVA, ImportAddressTableRVA,
+        // ImportNameTableRVA, ...
+        if let Some((dir, _)) = self.directory(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT) {
+            let mut descriptor = dir;
+            loop {
+                let name = self.u32_at(descriptor.checked_add(4)?)?;
+                if name == 0 {
+                    break;
+                }
+                let lookup = self.u32_at(descriptor.checked_add(16)?)?;
+                imports.push((self.str_at(name)?, self.thunk_names(lookup)?));
+                descriptor = descriptor.checked_add(32)?;
+            }
+        }
+        Some(imports)
+    }
+
+    /// The names of an import lookup table, skipping imports by ordinal.
+    fn thunk_names(&self, table: u32) -> Option<Vec<String>> {
+        let (entry_size, ordinal_flag) = match self.is_64 {
+            true => (8, 1 << 63),
+            false => (4, 1 << 31),
+        };
+        let (mut names, mut entry) = (vec![], table);
+        loop {
+            let offset = self.offset(entry)?;
+            let thunk = match self.is_64 {
+                true => read_u64(self.data, offset)?,
+                false => u64::from(read_u32(self.data, offset)?),
+            };
+            if thunk == 0 {
+                break;
+            }
+            if thunk & ordinal_flag == 0 {
+                // IMAGE_IMPORT_BY_NAME: Hint, then the name
+                names.push(self.str_at(u32::try_from(thunk).ok()?.checked_add(2)?)?);
+            }
+            entry = entry.checked_add(entry_size)?;
+        }
+        Some(names)
+    }
+}
+
+/// The customary (uname -m style) name of a COFF machine type.
+fn arch_name(machine: u16) -> String {
+    match machine {
+        0x14c => "i386".to_string(),
+        0x8664 => "x86_64".to_string(),
+        0x1c0 | 0x1c4 => "arm".to_string(),
+        0xaa64 => "aarch64".to_string(),
+        0x5064 => "riscv64".to_string(),
+        machine => format!("machine {machine:#x}"),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+    Some(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
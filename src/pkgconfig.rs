@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Cross-checking installed pkg-config files against the libraries actually installed.
+//!
+//! Every `-lname` in `Libs:` has to be satisfiable by a `libname.so` (or `libname.a`) the package
+//! ships, else linking with `pkg-config --libs` fails, or picks up some other library. System
+//! libraries the toolchain provides (libm, libpthread, ...) are left alone, as are
+//! `Libs.private`, which only matter for static linking.
+
+use crate::check::{Check, Finding};
+use crate::tree::{EntryKind, InstallTree};
+use crate::ElfKind;
+use std::collections::HashMap;
+use std::fs;
+
+/// Libraries that come with the C/C++ toolchain rather than with a package
+const SYSTEM_LIBRARIES: &[&str] = &[
+    "c", "m", "dl", "rt", "pthread", "util", "resolv", "stdc++", "gcc_s", "atomic",
+];
+
+/// The variables and fields of a .pc file, with variables expanded in field values
+#[derive(Debug, Default)]
+struct PkgConfig {
+    fields: HashMap<String, String>,
+}
+
+impl PkgConfig {
+    fn parse(contents: &str) -> Self {
+        let mut variables = HashMap::new();
+        let mut fields = HashMap::new();
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let field = line.find(':');
+            let variable = line.find('=');
+            match (field, variable) {
+                (Some(colon), equals) if equals.is_none_or(|equals| colon < equals) => {
+                    let value = expand(line[colon + 1..].trim(), &variables);
+                    fields.insert(line[..colon].trim().to_string(), value);
+                }
+                (_, Some(equals)) => {
+                    let value = expand(line[equals + 1..].trim(), &variables);
+                    variables.insert(line[..equals].trim().to_string(), value);
+                }
+                _ => {}
+            }
+        }
+        Self { fields }
+    }
+
+    /// The `-l` arguments of `Libs:`, without the `-l`.
+    fn libs(&self) -> Vec<&str> {
+        self.fields
+            .get("Libs")
+            .map(|libs| {
+                libs.split_whitespace()
+                    .filter_map(|arg| arg.strip_prefix("-l"))
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Substitute `${name}` references to the variables defined so far.
+fn expand(value: &str, variables: &HashMap<String, String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(len) => {
+                let name = &rest[start + 2..start + len];
+                expanded.push_str(variables.get(name).map(String::as_str).unwrap_or_default());
+                rest = &rest[start + len + 1..];
+            }
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// A finding for every `-l` of an installed .pc file that nothing in the tree satisfies.
+pub(crate) fn pkgconfig_libs(tree: &InstallTree) -> Vec<Finding> {
+    // file names, and whether they lead to an actual file
+    let mut shipped = HashMap::<&str, bool>::new();
+    for entry in tree.entries() {
+        let resolves = tree.resolve(&entry.path).is_some();
+        *shipped.entry(entry.file_name()).or_default() |= resolves;
+    }
+    let mut sonames = tree
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.kind {
+            EntryKind::Elf {
+                elf_kind: ElfKind::SharedObject,
+                soname: Some(soname),
+            } => Some(soname.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    sonames.sort();
+    sonames.dedup();
+
+    let mut findings = vec![];
+    let pc_files = tree
+        .entries()
+        .iter()
+        .filter(|entry| entry.path.ends_with(".pc") && entry.kind == EntryKind::File);
+    for pc_file in pc_files {
+        let contents = match fs::read_to_string(tree.staged_path(&pc_file.path)) {
+            Ok(contents) => contents,
+            Err(err) => {
+                let message = format!("could not be read: {err}");
+                findings.push(Finding::new(Check::PkgConfig, &pc_file.path, message));
+                continue;
+            }
+        };
+        for name in PkgConfig::parse(&contents).libs() {
+            let candidates = match name.strip_prefix(':') {
+                Some(file_name) => vec![file_name.to_string()],
+                None if SYSTEM_LIBRARIES.contains(&name) => continue,
+                None => vec![format!("lib{name}.so"), format!("lib{name}.a")],
+            };
+            let found = |resolves| {
+                candidates
+                    .iter()
+                    .find(|candidate| shipped.get(candidate.as_str()) == Some(&resolves))
+            };
+            let message = if found(true).is_some() {
+                continue;
+            } else if let Some(dangling) = found(false) {
+                format!("Libs has -l{name}, but {dangling} is a dangling symlink")
+            } else if sonames.is_empty() {
+                format!("Libs has -l{name}, but the package ships no such library")
+            } else {
+                format!(
+                    "Libs has -l{name}, but the package ships no such library (it ships {})",
+                    sonames.join(", ")
+                )
+            };
+            findings.push(Finding::new(Check::PkgConfig, &pc_file.path, message));
+        }
+    }
+    findings
+}
@@ -0,0 +1,79 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! What preloading a library (LD_PRELOAD) would interpose.
+//!
+//! A preloaded library comes first in the lookup scope of every process, so each of its exports
+//! takes over the imports of that name in the executable and all of its libraries. Imports are
+//! attributed to the libraries they bind to without the preload as in
+//! [AbiReport::resolve_imports]; only those some library provides count, as imports nothing
+//! provides would merely become satisfied.
+
+use crate::{AbiCapture, AbiReport, Symbol};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The imports of one file a preloaded library would take over
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Interposition {
+    pub filename: String,
+    pub symbols: Vec<Symbol>, // the imports, with the library they bind to without the preload
+}
+
+impl AbiReport {
+    /// The files of this report whose imports `preload` would interpose, in report order.
+    /// Libraries are looked up in this report first and then in `sysroot`, as in
+    /// [AbiReport::resolve_imports].
+    pub fn preload_impact(
+        &self,
+        preload: &AbiCapture,
+        sysroot: Option<&AbiReport>,
+    ) -> Vec<Interposition> {
+        let providers = self.import_providers(sysroot);
+        let mut impact = vec![];
+        for (capture, providers) in self.captures().iter().zip(providers) {
+            if capture.filename() == preload.filename() || !capture.is_same_arch(preload) {
+                continue;
+            }
+            let symbols = capture
+                .dynsym_imports()
+                .iter()
+                .zip(providers)
+                .filter_map(|(import, provider)| {
+                    // binding to the preload already isn't a change
+                    let provider =
+                        provider.filter(|provider| preload.soname() != Some(provider.as_str()))?;
+                    let interposed = preload
+                        .dynsym_exports()
+                        .iter()
+                        .any(|export| interposes(export, import));
+                    interposed.then(|| Symbol {
+                        provider: Some(provider),
+                        ..import.clone()
+                    })
+                })
+                .collect::<Vec<_>>();
+            if !symbols.is_empty() {
+                impact.push(Interposition {
+                    filename: capture.filename().to_string(),
+                    symbols,
+                });
+            }
+        }
+        impact
+    }
+}
+
+/// Whether an export of the preload binds an import. Preload shims rarely version their
+/// symbols, and an unversioned definition satisfies any version of a reference.
+fn interposes(export: &Symbol, import: &Symbol) -> bool {
+    export.name == import.name
+        && (export.version.is_none()
+            || import.version.is_none()
+            || export.version == import.version)
+}
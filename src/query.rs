@@ -0,0 +1,245 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Looking up symbols in a report.
+//!
+//! Symbols are matched by name (a glob without wildcards is an exact match), or approximately
+//! to forgive typos: by prefix, substring or a small edit distance. Each match lists the files
+//! providing it (exports) and the files using it (imports).
+
+use crate::{AbiCapture, AbiReport, CompactCapture, CompactReport, Symbol, SymbolRef};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+/// What symbol names to look for
+#[derive(Clone, Debug)]
+pub enum SymbolPattern {
+    Glob(GlobMatcher),    // shell style, e.g. `SSL_*`
+    Regex(Regex),         // unanchored, e.g. `^SSL_(CTX_)?new`
+    Prefix(String),       // names starting with it
+    Substring(String),    // names containing it
+    Fuzzy(String, usize), // names within this edit distance of it, see [edit_distance]
+}
+
+/// A symbol found in a report, with the files on either side of it
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SymbolMatch {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub symbol: Symbol, // the kind is as exported, else as imported
+    pub providers: Vec<String>, // files exporting it, in report order
+    pub consumers: Vec<String>, // files importing it, in report order
+}
+
+impl SymbolPattern {
+    pub fn glob(pattern: &str) -> Result<Self> {
+        let glob = Glob::new(pattern).map_err(|err| invalid_pattern(pattern, err))?;
+        Ok(Self::Glob(glob.compile_matcher()))
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|err| invalid_pattern(pattern, err))?;
+        Ok(Self::Regex(regex))
+    }
+
+    pub fn prefix(prefix: &str) -> Self {
+        Self::Prefix(prefix.to_string())
+    }
+
+    pub fn substring(substring: &str) -> Self {
+        Self::Substring(substring.to_string())
+    }
+
+    /// Names at most `max_distance` edits (insertions, deletions, substitutions or swaps of
+    /// adjacent characters) away from `name`. Without a distance, it grows with the length of
+    /// the name: 1 up to 4 characters, 2 up to 12 and 3 beyond.
+    pub fn fuzzy(name: &str, max_distance: Option<usize>) -> Self {
+        let max_distance = max_distance.unwrap_or(match name.chars().count() {
+            0..=4 => 1,
+            5..=12 => 2,
+            _ => 3,
+        });
+        Self::Fuzzy(name.to_string(), max_distance)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.distance(name).is_some()
+    }
+
+    /// How far `name` is from the pattern, if it matches at all: the edit distance for fuzzy
+    /// patterns, 0 for any other match.
+    pub fn distance(&self, name: &str) -> Option<usize> {
+        match self {
+            Self::Glob(glob) => glob.is_match(name).then_some(0),
+            Self::Regex(regex) => regex.is_match(name).then_some(0),
+            Self::Prefix(prefix) => name.starts_with(prefix.as_str()).then_some(0),
+            Self::Substring(substring) => name.contains(substring.as_str()).then_some(0),
+            Self::Fuzzy(target, max_distance) => edit_distance(target, name, *max_distance),
+        }
+    }
+}
+
+/// The optimal string alignment distance between `a` and `b` (Levenshtein's, with swapped
+/// adjacent characters counting as one edit, as in `bulider`), if it is at most `max`.
+///
+/// Most names of a report are far off, so this gives up as soon as their lengths, or a whole
+/// row of the table, show the distance exceeds `max`.
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    // the last three rows of the table
+    let mut before = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        if current.iter().min().is_some_and(|&min| min > max) {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+impl AbiReport {
+    /// The captures exporting this symbol (in any version if `version` is None), in report
+    /// order. Captures whose export filter rules the name out are skipped without looking at
+    /// their exports, which makes this cheap enough to run over many big reports.
+    pub fn files_exporting(&self, name: &str, version: Option<&str>) -> Vec<&AbiCapture> {
+        self.captures()
+            .iter()
+            .filter(|capture| capture.may_export(name))
+            .filter(|capture| {
+                capture.dynsym_exports().iter().any(|symbol| {
+                    symbol.name == name
+                        && version.is_none_or(|v| symbol.version.as_deref() == Some(v))
+                })
+            })
+            .collect()
+    }
+
+    /// All exported or imported symbols whose name matches, in natural order (closest first
+    /// for fuzzy patterns).
+    ///
+    /// Symbols are told apart by name and version, so `foo@V1` and `foo@V2` are separate matches.
+    pub fn find_symbols(&self, pattern: &SymbolPattern) -> Vec<SymbolMatch> {
+        let mut matches = Matches::new(pattern);
+        for capture in self.captures() {
+            let filename = capture.filename();
+            let exports = capture.dynsym_exports().iter().map(Symbol::to_ref);
+            let imports = capture.dynsym_imports().iter().map(Symbol::to_ref);
+            matches.add(filename, exports, true);
+            matches.add(filename, imports, false);
+        }
+        matches.into_sorted()
+    }
+}
+
+impl CompactReport {
+    /// See [AbiReport::files_exporting].
+    pub fn files_exporting(&self, name: &str, version: Option<&str>) -> Vec<CompactCapture<'_>> {
+        self.captures()
+            .filter(|capture| capture.without_symbols().may_export(name))
+            .filter(|capture| {
+                capture.dynsym_exports().any(|symbol| {
+                    symbol.name == name && version.is_none_or(|v| symbol.version == Some(v))
+                })
+            })
+            .collect()
+    }
+
+    /// See [AbiReport::find_symbols].
+    pub fn find_symbols(&self, pattern: &SymbolPattern) -> Vec<SymbolMatch> {
+        let mut matches = Matches::new(pattern);
+        for capture in self.captures() {
+            matches.add(capture.filename(), capture.dynsym_exports(), true);
+            matches.add(capture.filename(), capture.dynsym_imports(), false);
+        }
+        matches.into_sorted()
+    }
+}
+
+/// The symbols matching a pattern so far, by name and version
+struct Matches<'a, 'p> {
+    pattern: &'p SymbolPattern,
+    found: HashMap<(&'a str, Option<&'a str>), SymbolMatch>,
+}
+
+impl<'a, 'p> Matches<'a, 'p> {
+    fn new(pattern: &'p SymbolPattern) -> Self {
+        Self {
+            pattern,
+            found: HashMap::new(),
+        }
+    }
+
+    fn add(
+        &mut self,
+        filename: &str,
+        symbols: impl Iterator<Item = SymbolRef<'a>>,
+        exported: bool,
+    ) {
+        for symbol in symbols.filter(|s| self.pattern.matches(s.name)) {
+            let entry = self
+                .found
+                .entry((symbol.name, symbol.version))
+                .or_insert_with(|| SymbolMatch {
+                    symbol: Symbol {
+                        provider: None,
+                        ..symbol.to_symbol()
+                    },
+                    providers: vec![],
+                    consumers: vec![],
+                });
+            if exported {
+                // what the definition says beats what an importer assumed
+                entry.symbol.kind = symbol.kind;
+                entry.symbol.size = symbol.size;
+                entry.providers.push(filename.to_string());
+            } else {
+                entry.consumers.push(filename.to_string());
+            }
+        }
+    }
+
+    fn into_sorted(self) -> Vec<SymbolMatch> {
+        let mut matches = self
+            .found
+            .into_values()
+            .map(|found| (self.pattern.distance(&found.symbol.name), found))
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a_distance, a), (b_distance, b)| {
+            a_distance
+                .cmp(b_distance)
+                .then_with(|| a.symbol.natural_cmp(&b.symbol))
+        });
+        matches.into_iter().map(|(_, found)| found).collect()
+    }
+}
+
+fn invalid_pattern(pattern: &str, err: impl std::fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("invalid pattern {pattern:?}: {err}"),
+    )
+}
@@ -0,0 +1,437 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! The AbiReport is the collection of AbiCaptures from the build artefacts under analysis.
+//!
+//! Captures are kept in natural sort order by filename, so the index of a capture is stable
+//! for a given set of inputs (see the crate level docs for why that matters). Reports can be
+//! ordered by soname or build-id instead (see [CaptureOrder]); the order is recorded in the
+//! header, and ties always go by filename, so the order stays deterministic either way.
+
+#[cfg(feature = "serde")]
+use crate::format::{self, ReportFormat};
+#[cfg(feature = "serde")]
+use crate::schema;
+use crate::schema::SCHEMA_VERSION;
+#[cfg(feature = "serde")]
+use crate::stream::{StreamFormat, StreamReader, StreamWriter};
+use crate::symbol::is_mangled;
+use crate::{AbiCapture, HAS_CLOCK};
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "serde")]
+use std::io::{BufRead, Read, Result, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata carried by serialized reports, so consumers can validate and reproduce comparisons.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ReportHeader {
+    pub abireport_version: String, // version of the crate that produced the report
+    pub schema_version: u32,       // layout version of the serialized report
+    pub scan_time: u64,            // seconds since the UNIX epoch
+    pub input_root: Option<String>, // common root of the scanned inputs, if known
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub sonames_only: bool, // symbols, build-ids and debuglinks were deliberately not captured
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "CaptureOrder::is_default")
+    )]
+    pub order: CaptureOrder, // how the captures are sorted
+    pub digest: String,            // sha256 over the serialized captures
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AbiReport {
+    header: ReportHeader,
+    captures: Vec<AbiCapture>, // sorted as the header says, natural by filename by default
+}
+
+/// How the captures of a report are sorted. Whatever the key, captures with equal (or no)
+/// keys are sorted naturally by filename, and captures without the key come last.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum CaptureOrder {
+    /// Natural sort order by filename, e.g. `libfoo.so.2` before `libfoo.so.10`
+    #[default]
+    Filename,
+    /// By DT_SONAME, keeping the files providing one library together
+    Soname,
+    /// By build-id, keeping a binary and its debug file (and hardlinked copies) together
+    BuildId,
+}
+
+/// A set of captures that share a build-id, i.e. one logical binary found under several paths.
+///
+/// This is typically a binary, its detached debug file and any hardlinked copies of it.
+/// Captures without a build-id are never clustered and end up in a group of their own.
+#[derive(Debug)]
+pub struct BuildIdGroup<'a> {
+    pub build_id: Option<&'a str>,
+    pub captures: Vec<&'a AbiCapture>, // in report order
+}
+
+impl ReportHeader {
+    /// A header for a scan starting now, with no digest yet.
+    ///
+    /// For reproducible builds, `SOURCE_DATE_EPOCH` (if set) is taken as the scan time, so the
+    /// same inputs give byte-identical reports.
+    pub fn new() -> Self {
+        let scan_time = match (source_date_epoch(), HAS_CLOCK) {
+            (Some(epoch), _) => epoch,
+            (None, true) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            (None, false) => 0,
+        };
+
+        Self {
+            abireport_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: SCHEMA_VERSION,
+            scan_time,
+            input_root: None,
+            sonames_only: false,
+            order: CaptureOrder::default(),
+            digest: String::new(),
+        }
+    }
+}
+
+/// `SOURCE_DATE_EPOCH`, the timestamp reproducible builds agree on, if set.
+pub(crate) fn source_date_epoch() -> Option<u64> {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+}
+
+impl Default for ReportHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AbiReport {
+    pub fn new(captures: Vec<AbiCapture>) -> Self {
+        Self::with_header(ReportHeader::new(), captures)
+    }
+
+    /// Assemble a report from an existing header (e.g. one read from a stream).
+    ///
+    /// The captures are sorted in the header's order and the digest recomputed; everything else
+    /// is kept as is.
+    pub fn with_header(header: ReportHeader, mut captures: Vec<AbiCapture>) -> Self {
+        captures.sort_by(|a, b| header.order.cmp(a, b));
+        Self {
+            header: ReportHeader {
+                schema_version: SCHEMA_VERSION,
+                digest: captures_digest(&captures),
+                ..header
+            },
+            captures,
+        }
+    }
+
+    /// Sort the captures in another order (which changes the digest).
+    pub fn with_order(self, order: CaptureOrder) -> Self {
+        let (header, captures) = self.into_parts();
+        Self::with_header(ReportHeader { order, ..header }, captures)
+    }
+
+    /// Record the common root of the scanned inputs in the header.
+    pub fn with_input_root(mut self, input_root: impl Into<String>) -> Self {
+        self.header.input_root = Some(input_root.into());
+        self
+    }
+
+    pub fn header(&self) -> &ReportHeader {
+        &self.header
+    }
+
+    pub fn captures(&self) -> &[AbiCapture] {
+        &self.captures
+    }
+
+    pub(crate) fn into_parts(self) -> (ReportHeader, Vec<AbiCapture>) {
+        (self.header, self.captures)
+    }
+
+    /// Reassemble a report from the parts of one, which are already sorted and digested.
+    pub(crate) fn from_parts(header: ReportHeader, captures: Vec<AbiCapture>) -> Self {
+        Self { header, captures }
+    }
+
+    /// Cluster the captures in this report by build-id.
+    ///
+    /// Groups are returned in the order their first member appears in the report.
+    pub fn group_by_build_id(&self) -> Vec<BuildIdGroup<'_>> {
+        let mut groups: Vec<BuildIdGroup<'_>> = Vec::new();
+        let mut group_index: HashMap<&str, usize> = HashMap::new();
+
+        for capture in &self.captures {
+            let build_id = capture.build_id.as_deref();
+            match build_id.and_then(|id| group_index.get(id)) {
+                Some(&idx) => groups[idx].captures.push(capture),
+                None => {
+                    if let Some(id) = build_id {
+                        group_index.insert(id, groups.len());
+                    }
+                    groups.push(BuildIdGroup {
+                        build_id,
+                        captures: vec![capture],
+                    });
+                }
+            }
+        }
+
+        groups
+    }
+
+    /// The report with only the C ABI of each capture, leaving out the C++ and Rust mangled
+    /// symbols ([crate::Symbol::is_mangled]), e.g. to audit the `extern "C"` plugin interface
+    /// of a mixed-language library.
+    pub fn c_abi_only(&self) -> AbiReport {
+        let captures = self
+            .captures
+            .iter()
+            .map(|capture| {
+                let mut capture = capture.clone();
+                capture.retain_symbols(|name| !is_mangled(name));
+                capture
+            })
+            .collect();
+        Self::with_header(self.header.clone(), captures)
+    }
+
+    /// Split the report into one report per architecture (None collecting the captures of
+    /// unknown architecture), each with this report's header.
+    ///
+    /// A file can only ever link against files of its own architecture, so e.g. a multilib
+    /// tree is best looked at one partition at a time.
+    pub fn partition_by_arch(&self) -> BTreeMap<Option<String>, AbiReport> {
+        let mut partitions = BTreeMap::<_, Vec<_>>::new();
+        for capture in &self.captures {
+            let arch = capture.arch().map(str::to_string);
+            partitions.entry(arch).or_default().push(capture.clone());
+        }
+        partitions
+            .into_iter()
+            .map(|(arch, captures)| (arch, Self::with_header(self.header.clone(), captures)))
+            .collect()
+    }
+
+    /// Split the report of a tree laid out as `<root>/<package>/...` (e.g.
+    /// `packages/zlib/usr/lib/libz.so.1`) into one report per package, each with this report's
+    /// header but its package directory as input root. Captures outside of any package
+    /// directory are left out.
+    pub fn partition_by_package(&self, root: &str) -> BTreeMap<String, AbiReport> {
+        let mut partitions = BTreeMap::<_, Vec<_>>::new();
+        for capture in &self.captures {
+            let Ok(relative) = Path::new(&capture.filename).strip_prefix(root) else {
+                continue;
+            };
+            let mut components = relative.components();
+            if let (Some(package), Some(_)) = (components.next(), components.next()) {
+                let package = package.as_os_str().to_string_lossy().into_owned();
+                partitions.entry(package).or_default().push(capture.clone());
+            }
+        }
+        partitions
+            .into_iter()
+            .map(|(package, captures)| {
+                let input_root = Path::new(root).join(&package);
+                let header = ReportHeader {
+                    input_root: Some(input_root.to_string_lossy().into_owned()),
+                    ..self.header.clone()
+                };
+                (package, Self::with_header(header, captures))
+            })
+            .collect()
+    }
+}
+
+/// Reading and writing the serialized forms.
+#[cfg(feature = "serde")]
+impl AbiReport {
+    /// Check that the captures still match the digest recorded in the header.
+    pub fn verify_digest(&self) -> bool {
+        captures_digest(&self.captures) == self.header.digest
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Read a JSON report, migrating it from older schema versions as needed.
+    pub fn read_json<R: Read>(reader: R) -> Result<Self> {
+        Self::from_document(serde_json::from_reader(reader)?)
+    }
+
+    /// Write the report in the given format.
+    pub fn write<W: Write>(&self, writer: W, format: ReportFormat) -> Result<()> {
+        match format {
+            ReportFormat::Json => self.write_json(writer),
+            ReportFormat::Cbor => format::write_cbor(&serde_json::to_value(self)?, writer),
+            ReportFormat::JsonLines => self.write_stream(writer, StreamFormat::JsonLines),
+            ReportFormat::Records => self.write_stream(writer, StreamFormat::Records),
+        }
+    }
+
+    fn write_stream<W: Write>(&self, writer: W, format: StreamFormat) -> Result<()> {
+        let mut stream = StreamWriter::new(writer, format, &self.header)?;
+        for capture in &self.captures {
+            stream.write_capture(capture)?;
+        }
+        stream.finish().map(|_| ())
+    }
+
+    /// Read a report in any supported format, detected from its contents, and possibly zstd
+    /// compressed (which needs the `zstd` feature).
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self> {
+        if reader.fill_buf()?.starts_with(format::ZSTD_MAGIC) {
+            #[cfg(feature = "zstd")]
+            return Self::read(crate::zstd::decompress(reader)?.as_slice());
+            #[cfg(not(feature = "zstd"))]
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "zstd compressed report, but built without the zstd feature",
+            ));
+        }
+        match ReportFormat::detect(&mut reader)? {
+            ReportFormat::Json => Self::read_json(reader),
+            ReportFormat::Cbor => Self::from_document(format::read_cbor(reader)?),
+            ReportFormat::JsonLines => Self::read_stream(reader, StreamFormat::JsonLines),
+            ReportFormat::Records => Self::read_stream(reader, StreamFormat::Records),
+        }
+    }
+
+    fn read_stream<R: BufRead>(reader: R, format: StreamFormat) -> Result<Self> {
+        let mut stream = StreamReader::new(reader, format)?;
+        let captures = stream.by_ref().collect::<Result<Vec<_>>>()?;
+        Ok(Self::with_header(stream.header().clone(), captures))
+    }
+
+    /// Migrations may change how captures serialize, so the digest of a migrated report is
+    /// recomputed; verify older reports before migrating them if that matters.
+    fn from_document(mut document: serde_json::Value) -> Result<Self> {
+        let original_version = schema::migrate(&mut document)?;
+        let mut report: Self = serde_json::from_value(document)?;
+        if original_version != SCHEMA_VERSION {
+            report.header.digest = captures_digest(&report.captures);
+        }
+        Ok(report)
+    }
+}
+
+impl Default for AbiReport {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl BuildIdGroup<'_> {
+    /// The capture that best represents the group.
+    ///
+    /// Detached debug files have their .dynsym stripped to NOBITS, so the member with the most
+    /// dynamic symbols is the actual binary (ties go to the first in report order).
+    pub fn primary(&self) -> &AbiCapture {
+        let symbol_count = |c: &AbiCapture| c.dynsym_exports.len() + c.dynsym_imports.len();
+        self.captures
+            .iter()
+            .copied()
+            .reduce(|best, c| {
+                if symbol_count(c) > symbol_count(best) {
+                    c
+                } else {
+                    best
+                }
+            })
+            .expect("BuildIdGroups are never empty")
+    }
+
+    /// All the paths this logical binary was found under, in report order.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.captures.iter().map(|c| c.filename.as_str())
+    }
+}
+
+impl CaptureOrder {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Compare two captures by this order, then by filename.
+    pub(crate) fn cmp(self, a: &AbiCapture, b: &AbiCapture) -> Ordering {
+        let by_key = match self {
+            Self::Filename => Ordering::Equal,
+            Self::Soname => missing_last(a.soname.as_ref(), b.soname.as_ref()),
+            Self::BuildId => missing_last(a.build_id.as_ref(), b.build_id.as_ref()),
+        };
+        // natural order considers e.g. `lib01` and `lib1` equal, byte order doesn't
+        by_key
+            .then_with(|| a.filename.natural_cmp(&b.filename))
+            .then_with(|| a.filename.cmp(&b.filename))
+    }
+}
+
+/// Natural order of the present keys, followed by the missing ones.
+fn missing_last(a: Option<&String>, b: Option<&String>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.natural_cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The digest covers the compact JSON serialization of the (sorted) captures only, so two
+/// reports of the same inputs agree on it regardless of when or where they were taken.
+#[cfg(feature = "serde")]
+fn captures_digest(captures: &[AbiCapture]) -> String {
+    let serialized = serde_json::to_vec(captures).expect("AbiCaptures always serialize");
+    format!("{:x}", Sha256::digest(serialized))
+}
+
+/// Without serde there is no serialized form to digest (nor to store the digest in).
+#[cfg(not(feature = "serde"))]
+fn captures_digest(_captures: &[AbiCapture]) -> String {
+    String::new()
+}
+
+/// [captures_digest] of captures serialized one at a time, for those not held in a slice.
+#[cfg(feature = "serde")]
+pub(crate) fn captures_digest_each(captures: impl Iterator<Item = AbiCapture>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update("[");
+    for (index, capture) in captures.enumerate() {
+        if index > 0 {
+            hasher.update(",");
+        }
+        hasher.update(serde_json::to_vec(&capture).expect("AbiCaptures always serialize"));
+    }
+    hasher.update("]");
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(not(feature = "serde"))]
+pub(crate) fn captures_digest_each(_captures: impl Iterator<Item = AbiCapture>) -> String {
+    String::new()
+}
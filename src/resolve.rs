@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Attributing imports to the libraries expected to supply them.
+//!
+//! Each import is attributed to the first DT_NEEDED entry of its file, in link order as the
+//! dynamic linker looks them up, whose library (found by soname among the report's captures, else
+//! those of a sysroot report) exports it. Only direct dependencies count, and rpaths and search
+//! paths are not looked at: sonames are assumed to be unique per architecture. Imports nothing
+//! provides, e.g. weak references like `__gmon_start__` or symbols of libraries missing from the
+//! reports, stay unattributed.
+
+use crate::{AbiCapture, AbiReport, Symbol};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// How much a file relies on one of its DT_NEEDED entries
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DependencyUsage {
+    pub filename: String,
+    pub library: String,        // the DT_NEEDED entry
+    pub symbols: Option<usize>, // imports it satisfies, None if the library isn't in the reports
+}
+
+impl DependencyUsage {
+    /// Whether the file could do without the library (as far as symbols go; the library's
+    /// constructors may still matter).
+    pub fn is_unused(&self) -> bool {
+        self.symbols == Some(0)
+    }
+}
+
+/// What a library exports, for lookups by name and version or by name only
+struct Exports<'a> {
+    versioned: HashSet<(&'a str, Option<&'a str>)>,
+    names: HashSet<&'a str>,
+}
+
+impl<'a> Exports<'a> {
+    fn of(library: &'a AbiCapture) -> Self {
+        let exports = library.dynsym_exports();
+        Self {
+            versioned: exports
+                .iter()
+                .map(|symbol| (symbol.name.as_str(), symbol.version.as_deref()))
+                .collect(),
+            names: exports.iter().map(|symbol| symbol.name.as_str()).collect(),
+        }
+    }
+
+    /// A versioned import needs exactly that version, an unversioned one takes any.
+    fn provide(&self, import: &Symbol) -> bool {
+        match import.version.as_deref() {
+            Some(version) => self
+                .versioned
+                .contains(&(import.name.as_str(), Some(version))),
+            None => self.names.contains(import.name.as_str()),
+        }
+    }
+}
+
+impl AbiReport {
+    /// Record the providing DT_NEEDED entry in every import, looking libraries up in this
+    /// report first and then in `sysroot` (e.g. a report of the build root).
+    pub fn resolve_imports(self, sysroot: Option<&AbiReport>) -> Self {
+        let providers = self.import_providers(sysroot);
+        let (header, mut captures) = self.into_parts();
+        for (capture, providers) in captures.iter_mut().zip(providers) {
+            for (import, provider) in capture.dynsym_imports.iter_mut().zip(providers) {
+                import.provider = provider;
+            }
+        }
+        Self::with_header(header, captures)
+    }
+
+    /// The provider of every import of every capture, in report order.
+    pub(crate) fn import_providers(&self, sysroot: Option<&AbiReport>) -> Vec<Vec<Option<String>>> {
+        let libraries = Libraries::new(self, sysroot);
+        self.captures()
+            .iter()
+            .map(|capture| {
+                let needed = libraries.needed(capture);
+                capture
+                    .dynsym_imports()
+                    .iter()
+                    .map(|import| provider(&needed, import).map(str::to_string))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// How many imports each DT_NEEDED entry of each capture satisfies, in report and then
+    /// DT_NEEDED order. Libraries are looked up as in [AbiReport::resolve_imports].
+    pub fn dependency_usage(&self, sysroot: Option<&AbiReport>) -> Vec<DependencyUsage> {
+        let libraries = Libraries::new(self, sysroot);
+        let mut usage = vec![];
+        for capture in self.captures() {
+            let needed = libraries.needed(capture);
+            let mut counts = vec![0; needed.len()];
+            for import in capture.dynsym_imports() {
+                if let Some(idx) = needed
+                    .iter()
+                    .position(|(_, exports)| exports.is_some_and(|exports| exports.provide(import)))
+                {
+                    counts[idx] += 1;
+                }
+            }
+            usage.extend(
+                needed
+                    .iter()
+                    .zip(counts)
+                    .map(|((library, exports), count)| DependencyUsage {
+                        filename: capture.filename().to_string(),
+                        library: library.to_string(),
+                        symbols: exports.map(|_| count),
+                    }),
+            );
+        }
+        usage
+    }
+}
+
+/// The libraries imports can be looked up in, by soname
+pub(crate) struct Libraries<'a> {
+    by_soname: HashMap<&'a str, Vec<(&'a AbiCapture, Exports<'a>)>>,
+}
+
+impl<'a> Libraries<'a> {
+    pub(crate) fn new(report: &'a AbiReport, sysroot: Option<&'a AbiReport>) -> Self {
+        let mut by_soname = HashMap::<&str, Vec<_>>::new();
+        let candidates = report
+            .captures()
+            .iter()
+            .chain(sysroot.into_iter().flat_map(AbiReport::captures));
+        for library in candidates {
+            if let Some(soname) = library.soname() {
+                let entry = by_soname.entry(soname).or_default();
+                entry.push((library, Exports::of(library)));
+            }
+        }
+        Self { by_soname }
+    }
+
+    /// The library of `dependent`'s architecture a DT_NEEDED entry names, if it is in the
+    /// reports.
+    pub(crate) fn find(&self, soname: &str, dependent: &AbiCapture) -> Option<&'a AbiCapture> {
+        self.entry(soname, dependent).map(|(library, _)| *library)
+    }
+
+    fn entry(
+        &self,
+        soname: &str,
+        dependent: &AbiCapture,
+    ) -> Option<&(&'a AbiCapture, Exports<'a>)> {
+        self.by_soname.get(soname).and_then(|libraries| {
+            libraries
+                .iter()
+                .find(|(library, _)| dependent.is_same_arch(library))
+        })
+    }
+
+    /// Whether the library a DT_NEEDED entry of `dependent` names, or one it needs in turn,
+    /// exports any of `imports` by name, so that dropping the entry could change what they bind
+    /// to.
+    pub(crate) fn provides_any(
+        &self,
+        soname: &str,
+        dependent: &AbiCapture,
+        imports: &[Symbol],
+    ) -> bool {
+        let mut seen = HashSet::from([soname]);
+        let mut queue = vec![soname];
+        while let Some(soname) = queue.pop() {
+            let Some((library, exports)) = self.entry(soname, dependent) else {
+                continue;
+            };
+            // by name only: an unversioned definition still interposes when it comes first
+            if imports
+                .iter()
+                .any(|import| exports.names.contains(import.name.as_str()))
+            {
+                return true;
+            }
+            for needed in library.needed_deps() {
+                if seen.insert(needed) {
+                    queue.push(needed);
+                }
+            }
+        }
+        false
+    }
+
+    /// The DT_NEEDED entries of a capture with the exports of the library each one names,
+    /// or None if it isn't in the reports.
+    fn needed<'c>(&self, capture: &'c AbiCapture) -> Vec<(&'c str, Option<&Exports<'a>>)> {
+        // older reports only have the sorted list
+        let needed = match capture.needed_link_order() {
+            [] => capture.needed_deps(),
+            order => order,
+        };
+        needed
+            .iter()
+            .map(|soname| {
+                let exports = self.entry(soname, capture).map(|(_, exports)| exports);
+                (soname.as_str(), exports)
+            })
+            .collect()
+    }
+}
+
+/// The first needed library exporting the import.
+fn provider<'c>(needed: &[(&'c str, Option<&Exports>)], import: &Symbol) -> Option<&'c str> {
+    needed
+        .iter()
+        .find(|(_, exports)| exports.is_some_and(|exports| exports.provide(import)))
+        .map(|(soname, _)| *soname)
+}
+
+impl AbiCapture {
+    /// The imports grouped by the DT_NEEDED entry providing them, in DT_NEEDED order (empty
+    /// groups included), followed by the unattributed ones under None, if there are any.
+    pub fn imports_by_provider(&self) -> Vec<(Option<&str>, Vec<&Symbol>)> {
+        // older reports only have the sorted list
+        let needed = match self.needed_link_order() {
+            [] => self.needed_deps(),
+            order => order,
+        };
+        let mut groups = needed
+            .iter()
+            .map(|soname| (Some(soname.as_str()), vec![]))
+            .chain([(None, vec![])])
+            .collect::<Vec<_>>();
+        for import in self.dynsym_imports() {
+            let provider = import.provider.as_deref();
+            match groups.iter_mut().find(|(group, _)| *group == provider) {
+                Some((_, imports)) => imports.push(import),
+                // only if the capture was edited after resolving
+                None => groups.push((provider, vec![import])),
+            }
+        }
+        groups.retain(|(provider, imports)| provider.is_some() || !imports.is_empty());
+        groups
+    }
+}
@@ -0,0 +1,930 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Scanning trees of files into an AbiReport.
+//!
+//! This is everything `abireport scan` does short of writing the output, so that library
+//! consumers (e.g. boulder) get the very same behaviour without shelling out:
+//!
+//! ```no_run
+//! use abireport_rs::ScanBuilder;
+//!
+//! let report = ScanBuilder::new()
+//!     .path("/usr/lib")
+//!     .exclude("*.debug")
+//!     .jobs(0)
+//!     .scan()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [scan_iter] hands the captures out one at a time instead, for consumers that filter or store
+//! them as they come. With the `async` feature, `scan_async()` does the same as `scan()` from
+//! within a tokio runtime.
+//!
+//! Re-scanning a tree that barely changed needn't parse it all again: given the previous report
+//! ([ScanBuilder::reuse]), files whose contents hash the same as when they were last captured
+//! keep their previous capture.
+
+use crate::backend::{self, has_object_magic};
+use crate::ignore::{is_ignored, IgnoreFile, IGNORE_FILE};
+use crate::inspector::ELF_MAGIC;
+use crate::report::source_date_epoch;
+use crate::symbol::is_mangled;
+use crate::{
+    open_object_bytes, AbiCapture, AbiReport, CaptureOrder, CompactReport, ExportPolicy,
+    FileMetadata, Limits, ObjectBackend, PackageMap, ParseOptions, ReportHeader, ScanWarning,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+#[cfg(feature = "async")]
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use tokio::task;
+
+/// Configures a [Scanner].
+///
+/// Glob patterns are only compiled (and so only rejected) by [ScanBuilder::build].
+#[derive(Clone, Debug)]
+pub struct ScanBuilder {
+    paths: Vec<PathBuf>,          // files, or directories to search recursively
+    excludes: Vec<String>,        // globs matched against full paths
+    follow_symlinks: bool,        // whether to descend into/capture through symlinks
+    jobs: usize,                  // files parsed in parallel (0: one per CPU)
+    export_policy: ExportPolicy,  // which symbols count as exports
+    audit_hidden: bool,           // also capture defined symbols that aren't exported
+    raw_dynamic: bool,            // also capture every dynamic section entry
+    symbol_includes: Vec<String>, // if non-empty, only symbols matching one of these are kept
+    symbol_excludes: Vec<String>, // symbols matching any of these are dropped
+    c_abi_only: bool,             // drop C++ and Rust mangled symbols
+    limits: Limits,               // per-file resource limits
+    sonames_only: bool,           // see crate::parse_elf_sonames
+    ignore_files: bool,           // whether to honour .abireportignore files
+    order: CaptureOrder,          // how the report's captures are sorted
+    previous: Vec<AbiCapture>,    // captures to keep for files that haven't changed since
+    packages: PackageMap,         // which package each file belongs to
+    root: Option<PathBuf>,        // staging root the filenames are recorded relative to
+    reproducible: bool,           // leave out whatever depends on the host
+}
+
+impl Default for ScanBuilder {
+    fn default() -> Self {
+        Self {
+            paths: vec![],
+            excludes: vec![],
+            follow_symlinks: false,
+            jobs: 1,
+            export_policy: ExportPolicy::default(),
+            audit_hidden: false,
+            raw_dynamic: false,
+            symbol_includes: vec![],
+            symbol_excludes: vec![],
+            c_abi_only: false,
+            limits: Limits::default(),
+            sonames_only: false,
+            ignore_files: true,
+            order: CaptureOrder::default(),
+            previous: vec![],
+            packages: PackageMap::default(),
+            root: None,
+            reproducible: false,
+        }
+    }
+}
+
+impl ScanBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, or a directory to search recursively for ELF files.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    pub fn paths(mut self, paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skip files (and whole directories) whose path matches this glob, e.g. `**/*.debug`.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.excludes.push(pattern.into());
+        self
+    }
+
+    /// Skip what the `.abireportignore` files (gitignore syntax) of the searched directories
+    /// exclude. Files named explicitly are captured regardless. On by default.
+    pub fn ignore_files(mut self, ignore_files: bool) -> Self {
+        self.ignore_files = ignore_files;
+        self
+    }
+
+    /// Follow symlinks to files and directories, capturing each real file once (under the
+    /// first path it was found by). Off by default.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Parse this many files in parallel; 0 means one per available CPU.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    pub fn export_policy(mut self, export_policy: ExportPolicy) -> Self {
+        self.export_policy = export_policy;
+        self
+    }
+
+    /// Also capture the defined symbols that aren't exported ([AbiCapture::hidden_symbols]) and
+    /// measure [AbiCapture::visibility], to check visibility annotations. Off by default.
+    pub fn audit_hidden(mut self, audit_hidden: bool) -> Self {
+        self.audit_hidden = audit_hidden;
+        self
+    }
+
+    /// Also capture every entry of the dynamic section ([AbiCapture::dynamic_entries]), to look
+    /// into unusual tags without the files at hand. Off by default.
+    pub fn raw_dynamic(mut self, raw_dynamic: bool) -> Self {
+        self.raw_dynamic = raw_dynamic;
+        self
+    }
+
+    /// Only keep symbols matching this glob (or any other included one), e.g. `gtk_*`.
+    pub fn include_symbols(mut self, pattern: impl Into<String>) -> Self {
+        self.symbol_includes.push(pattern.into());
+        self
+    }
+
+    /// Drop symbols matching this glob, e.g. `_ZN*` or `__bss_start`.
+    pub fn exclude_symbols(mut self, pattern: impl Into<String>) -> Self {
+        self.symbol_excludes.push(pattern.into());
+        self
+    }
+
+    /// Drop the C++ and Rust mangled symbols ([crate::Symbol::is_mangled]), keeping only the
+    /// `extern "C"` interface of mixed-language libraries. Off by default.
+    pub fn c_abi_only(mut self, c_abi_only: bool) -> Self {
+        self.c_abi_only = c_abi_only;
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Only capture the dynamic section of each file, see [crate::parse_elf_sonames].
+    pub fn sonames_only(mut self, sonames_only: bool) -> Self {
+        self.sonames_only = sonames_only;
+        self
+    }
+
+    /// Sort the report's captures by this key rather than by filename.
+    pub fn order(mut self, order: CaptureOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Keep the captures of `previous` (a report of an earlier scan) for the files whose path
+    /// and contents are still the same, see [AbiCapture::identity], instead of parsing them
+    /// again. Only their metadata is read anew.
+    ///
+    /// The previous scan should have had the same options, as kept captures are used as they
+    /// are: if it filtered symbols differently, so will the report.
+    pub fn reuse(mut self, previous: AbiReport) -> Self {
+        let (_, captures) = previous.into_parts();
+        self.previous.extend(captures);
+        self
+    }
+
+    /// Record which package each file belongs to, looked up by its path in `packages`.
+    pub fn package_map(mut self, packages: PackageMap) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    /// Treat the inputs as a staging root (e.g. a `DESTDIR` like `/build/install`) and record
+    /// each file under it by its install path, `/usr/lib/libfoo.so.1` rather than
+    /// `/build/install/usr/lib/libfoo.so.1`, so reports from different build machines compare.
+    /// Files outside of the root keep their path. Reused captures and package map rules are
+    /// matched against the install paths too.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Leave out what depends on the host rather than on the files: the input root, file
+    /// mtimes, and the scan time (which is `SOURCE_DATE_EPOCH`, or 0 without it). Two builds of
+    /// the same package then give byte-identical reports, provided their files are recorded
+    /// under the same names (see [ScanBuilder::root]). Off by default.
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    pub fn build(self) -> Result<Scanner> {
+        let jobs = match self.jobs {
+            0 => thread::available_parallelism().map_or(1, usize::from),
+            jobs => jobs,
+        };
+        Ok(Scanner {
+            excludes: glob_set(&self.excludes)?,
+            symbol_includes: match self.symbol_includes.is_empty() {
+                true => None,
+                false => Some(glob_set(&self.symbol_includes)?),
+            },
+            symbol_excludes: glob_set(&self.symbol_excludes)?,
+            c_abi_only: self.c_abi_only,
+            paths: self.paths,
+            follow_symlinks: self.follow_symlinks,
+            jobs,
+            export_policy: self.export_policy,
+            audit_hidden: self.audit_hidden,
+            raw_dynamic: self.raw_dynamic,
+            limits: self.limits,
+            sonames_only: self.sonames_only,
+            ignore_files: self.ignore_files,
+            order: self.order,
+            previous: self
+                .previous
+                .into_iter()
+                .filter(|capture| capture.identity().sha256.is_some())
+                .map(|capture| (capture.filename.clone(), capture))
+                .collect(),
+            packages: self.packages,
+            root: self.root,
+            reproducible: self.reproducible,
+        })
+    }
+
+    /// Build and run the scan in one go, failing if any of the files can't be captured.
+    ///
+    /// Use [Scanner::capture_each] to carry on past failures instead.
+    pub fn scan(self) -> Result<AbiReport> {
+        self.build()?.scan()
+    }
+}
+
+/// A configured scan, see [ScanBuilder].
+#[derive(Debug)]
+pub struct Scanner {
+    paths: Vec<PathBuf>,
+    excludes: GlobSet,
+    follow_symlinks: bool,
+    jobs: usize,
+    export_policy: ExportPolicy,
+    audit_hidden: bool,
+    raw_dynamic: bool,
+    symbol_includes: Option<GlobSet>,
+    symbol_excludes: GlobSet,
+    c_abi_only: bool,
+    limits: Limits,
+    sonames_only: bool,
+    ignore_files: bool,
+    order: CaptureOrder,
+    previous: HashMap<String, AbiCapture>, // by filename, only those with a digest
+    packages: PackageMap,
+    root: Option<PathBuf>,
+    reproducible: bool,
+}
+
+impl Scanner {
+    /// Expand the input paths into the list of files to capture.
+    ///
+    /// Files named explicitly are always captured (and fail loudly if they aren't ELF), while
+    /// directories are searched recursively for files starting with the magic of a known format
+    /// (see [crate::ObjectBackend]). Unless following symlinks, they are skipped, so each file is
+    /// only captured once under its real name. Paths that can't be inspected are skipped, as are
+    /// broken ignore files (whose rules then don't apply); see [Self::discover_with_warnings]
+    /// to hear about them.
+    pub fn discover(&self) -> Vec<PathBuf> {
+        self.discover_with_warnings().0
+    }
+
+    /// [Self::discover], along with a warning for each path skipped as it couldn't be looked at.
+    pub fn discover_with_warnings(&self) -> (Vec<PathBuf>, Vec<ScanWarning>) {
+        let mut walk = Walk {
+            scanner: self,
+            files: vec![],
+            seen: HashSet::new(),
+            ignores: vec![],
+            warnings: vec![],
+        };
+        for path in &self.paths {
+            if self.excludes.is_match(path) {
+                continue;
+            }
+            // Instantiating as symlink_metadata ensures that symlinks aren't followed
+            let metadata = match self.follow_symlinks {
+                true => fs::metadata(path),
+                false => fs::symlink_metadata(path),
+            };
+            match metadata {
+                Ok(metadata) if metadata.is_dir() => walk.dir(path),
+                Ok(metadata) if metadata.is_file() => walk.file(path.clone()),
+                Ok(_) => walk
+                    .warnings
+                    .push(ScanWarning::NotAFile { path: path.clone() }),
+                Err(err) => walk.warnings.push(ScanWarning::Uninspectable {
+                    path: path.clone(),
+                    error: err.to_string(),
+                }),
+            }
+        }
+        (walk.files, walk.warnings)
+    }
+
+    /// Whether a file turning up in one of the searched directories would be captured: neither
+    /// it nor a directory it is in is excluded or ignored, and it starts with the magic of a known
+    /// format.
+    pub fn wants(&self, path: &Path) -> bool {
+        !path.ancestors().any(|path| self.excludes.is_match(path))
+            && !self.ignored(path)
+            && has_object_magic(path)
+    }
+
+    /// Whether the ignore files between the searched directory a file is in and the file skip
+    /// it, or a directory on the way.
+    fn ignored(&self, path: &Path) -> bool {
+        let root = self
+            .paths
+            .iter()
+            .filter(|root| path.starts_with(root) && path != root.as_path())
+            .max_by_key(|root| root.components().count());
+        let (true, Some(root)) = (self.ignore_files, root) else {
+            return false;
+        };
+        let mut dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root))
+            .collect::<Vec<_>>();
+        dirs.reverse();
+
+        // each directory's ignore file applies to what is in it
+        let mut ignores = vec![];
+        let entries = dirs.iter().skip(1).copied().chain([path]);
+        for (dir, entry) in dirs.iter().zip(entries) {
+            ignores.extend(IgnoreFile::load(dir).ok().flatten());
+            if is_ignored(&ignores, entry, entry != path) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A header for a report (or stream) of the given files.
+    pub fn header(&self, files: &[PathBuf]) -> ReportHeader {
+        let mut header = ReportHeader::new();
+        header.input_root = common_root(files).map(|dir| match &self.root {
+            // common_root() canonicalizes, so the staging root has to be as well
+            Some(root) => {
+                let root = root.canonicalize().unwrap_or_else(|_| root.clone());
+                install_path(&root, &dir)
+            }
+            None => dir.to_string_lossy().into_owned(),
+        });
+        header.sonames_only = self.sonames_only;
+        header.order = self.order;
+        if self.reproducible {
+            header.scan_time = source_date_epoch().unwrap_or(0);
+            header.input_root = None;
+        }
+        header
+    }
+
+    /// Capture a single file with the configured limits, export policy and symbol filters.
+    pub fn capture(&self, path: &Path) -> Result<AbiCapture> {
+        let file_name = path.to_string_lossy();
+        let data = backend::read_file(&file_name, &self.limits)?;
+        self.capture_data(&file_name, data)
+    }
+
+    /// The filename a file is recorded under: its install path when scanning a staging root
+    /// (see [ScanBuilder::root]), its path otherwise.
+    pub fn recorded_name(&self, path: &Path) -> String {
+        match &self.root {
+            Some(root) => install_path(root, path),
+            None => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Capture a file read into `data`, unless it is unchanged since the previous scan.
+    fn capture_data(&self, file_name: &str, data: Vec<u8>) -> Result<AbiCapture> {
+        let recorded = self.recorded_name(Path::new(file_name));
+        // the file was just read, so failing to stat it is a race with something removing it,
+        // not worth failing over
+        let metadata = FileMetadata::read(Path::new(file_name))
+            .ok()
+            .map(|metadata| metadata.with_contents(&data))
+            .map(|metadata| match self.reproducible {
+                true => FileMetadata {
+                    mtime: None,
+                    ..metadata
+                },
+                false => metadata,
+            });
+        let previous = self.previous.get(&recorded).filter(|previous| {
+            let sha256 = metadata.as_ref().and_then(|m| m.sha256.as_deref());
+            previous.identity().sha256 == sha256
+        });
+        let capture = match previous {
+            Some(previous) => previous.clone(),
+            None => {
+                let object =
+                    open_object_bytes(file_name, data, &self.limits, self.parse_options())?;
+                self.capture_object(object)?
+            }
+        };
+        let mut capture = match metadata {
+            Some(metadata) => capture.with_metadata(metadata),
+            None => capture,
+        };
+        // looked up anew for reused captures too, as the map may have changed since
+        capture.package = self.packages.lookup(&recorded).cloned();
+        capture.filename = recorded;
+        Ok(capture)
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            export_policy: self.export_policy,
+            audit_hidden: self.audit_hidden,
+            raw_dynamic: self.raw_dynamic,
+        }
+    }
+
+    fn capture_object(&self, object: Box<dyn ObjectBackend>) -> Result<AbiCapture> {
+        if self.sonames_only {
+            return Ok(object.capture_sonames());
+        }
+
+        object.capture_filtered(&|name| {
+            self.symbol_includes
+                .as_ref()
+                .is_none_or(|includes| includes.is_match(name))
+                && !self.symbol_excludes.is_match(name)
+                && !(self.c_abi_only && is_mangled(name))
+        })
+    }
+
+    /// Capture each of `files`, using the configured number of jobs.
+    ///
+    /// `f` is called on the calling thread, once per file, in the order of `files` however many
+    /// jobs run, so what is made of the captures (e.g. a stream) doesn't depend on timing.
+    pub fn capture_each(&self, files: &[PathBuf], mut f: impl FnMut(&Path, Result<AbiCapture>)) {
+        self.capture_each_timed(files, |path, result, _| f(path, result))
+    }
+
+    /// Like [Scanner::capture_each], also passing on how long reading and parsing each file
+    /// took (on whichever job it ran), to find the inputs slowing a scan down.
+    pub fn capture_each_timed(
+        &self,
+        files: &[PathBuf],
+        mut f: impl FnMut(&Path, Result<AbiCapture>, Duration),
+    ) {
+        self.capture_each_while(self.jobs, files, |path, result, elapsed| {
+            f(path, result, elapsed);
+            true
+        })
+    }
+
+    /// The capture of a file and how long it took.
+    fn capture_timed(&self, path: &Path) -> (Result<AbiCapture>, Duration) {
+        let start = Instant::now();
+        let result = self.capture(path);
+        (result, start.elapsed())
+    }
+
+    /// Like [Scanner::capture_each_timed] with `jobs` jobs, stopping early once `f` returns
+    /// false.
+    fn capture_each_while(
+        &self,
+        jobs: usize,
+        files: &[PathBuf],
+        mut f: impl FnMut(&Path, Result<AbiCapture>, Duration) -> bool,
+    ) {
+        let jobs = jobs.min(files.len());
+        if jobs <= 1 {
+            for file in files {
+                let (result, elapsed) = self.capture_timed(file);
+                if !f(file, result, elapsed) {
+                    return;
+                }
+            }
+            return;
+        }
+
+        let next = AtomicUsize::new(0);
+        // bounded, so slow consumers (e.g. writing to a pipe) hold the workers back
+        let (sender, receiver) = mpsc::sync_channel(jobs);
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(file) = files.get(idx) else {
+                        break;
+                    };
+                    if sender.send((idx, self.capture_timed(file))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            // captures complete out of order, hold on to them until it's their turn
+            let mut done = BTreeMap::new();
+            let mut turn = 0;
+            // hanging up (by leaving the loop) stops the workers at their next file
+            'receive: for (idx, result) in receiver {
+                done.insert(idx, result);
+                while let Some((result, elapsed)) = done.remove(&turn) {
+                    if !f(&files[turn], result, elapsed) {
+                        break 'receive;
+                    }
+                    turn += 1;
+                }
+            }
+        });
+    }
+
+    /// Discover and capture everything, failing on the first file that can't be captured.
+    pub fn scan(&self) -> Result<AbiReport> {
+        self.scan_with(self.jobs)
+    }
+
+    /// Scan twice, in parallel and with a single job, and fail unless both give the same
+    /// captures. Reports are meant to be reproducible whatever the number of CPUs; this catches
+    /// it when they aren't (or when parsing a file isn't, e.g. as it hits the timeout).
+    pub fn verify_determinism(&self) -> Result<AbiReport> {
+        let jobs = match self.jobs {
+            1 => thread::available_parallelism()
+                .map_or(2, usize::from)
+                .max(2),
+            jobs => jobs,
+        };
+        let parallel = self.scan_with(jobs)?;
+        let single = self.scan_with(1)?;
+        if parallel.header().digest == single.header().digest {
+            return Ok(parallel);
+        }
+
+        let differing = parallel
+            .captures()
+            .iter()
+            .zip(single.captures())
+            .find(|(a, b)| a != b)
+            .map(|(a, _)| format!("starting at {}", a.filename()))
+            .unwrap_or_else(|| {
+                format!(
+                    "with {} and {} captures",
+                    parallel.captures().len(),
+                    single.captures().len()
+                )
+            });
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("scans with {jobs} jobs and with 1 job differ, {differing}"),
+        ))
+    }
+
+    /// Scan into a [CompactReport], interning the symbols of each capture as it is made, so
+    /// that a large tree's worth of owned symbol tables never piles up in memory.
+    pub fn scan_compact(&self) -> Result<CompactReport> {
+        let files = self.discover();
+        let mut report = CompactReport::new();
+        let mut failure = None;
+        self.capture_each_while(self.jobs, &files, |path, result, _| {
+            if let Err(err) = result.and_then(|capture| report.push(capture)) {
+                failure.get_or_insert_with(|| with_path(err, path));
+            }
+            true
+        });
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(report.finish(self.header(&files))),
+        }
+    }
+
+    fn scan_with(&self, jobs: usize) -> Result<AbiReport> {
+        let files = self.discover();
+        let mut captures = Vec::with_capacity(files.len());
+        let mut failure = None;
+        self.capture_each_while(jobs, &files, |path, result, _| {
+            match result {
+                Ok(capture) => captures.push(capture),
+                Err(err) => {
+                    failure.get_or_insert_with(|| with_path(err, path));
+                }
+            }
+            true
+        });
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(AbiReport::with_header(self.header(&files), captures)),
+        }
+    }
+}
+
+/// Captures of a scan, handed out as they are made, see [scan_iter]
+///
+/// Dropping it stops the scan after the files being parsed at the time.
+pub struct ScanIter {
+    receiver: Option<mpsc::Receiver<Result<AbiCapture>>>,
+    coordinator: Option<thread::JoinHandle<()>>,
+}
+
+/// Scan `paths` as `options` say (adding to its paths), one capture at a time.
+///
+/// Captures come in the order [Scanner::discover] finds the files (a report would sort them),
+/// with at most `jobs` of them parsed ahead of the consumer, so captures can be filtered or piped
+/// to storage without a whole tree's worth of them in memory. A file that can't be captured is an
+/// error item (naming the file) and the scan goes on; invalid options are a single error item.
+pub fn scan_iter(
+    paths: impl IntoIterator<Item = impl Into<PathBuf>>,
+    options: ScanBuilder,
+) -> ScanIter {
+    match options.paths(paths).build() {
+        Ok(scanner) => scanner.into_iter(),
+        Err(err) => {
+            let (sender, receiver) = mpsc::sync_channel(1);
+            let _ = sender.send(Err(err));
+            ScanIter {
+                receiver: Some(receiver),
+                coordinator: None,
+            }
+        }
+    }
+}
+
+impl IntoIterator for Scanner {
+    type Item = Result<AbiCapture>;
+    type IntoIter = ScanIter;
+
+    /// Discover and capture everything in the background, see [scan_iter].
+    fn into_iter(self) -> ScanIter {
+        let (sender, receiver) = mpsc::sync_channel(self.jobs.max(1));
+        let coordinator = thread::spawn(move || {
+            let files = self.discover();
+            self.capture_each_while(self.jobs, &files, |path, result, _| {
+                sender
+                    .send(result.map_err(|err| with_path(err, path)))
+                    .is_ok()
+            });
+        });
+        ScanIter {
+            receiver: Some(receiver),
+            coordinator: Some(coordinator),
+        }
+    }
+}
+
+impl Iterator for ScanIter {
+    type Item = Result<AbiCapture>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.as_ref()?.recv().ok()
+    }
+}
+
+impl Drop for ScanIter {
+    fn drop(&mut self) {
+        // hang up first, or a scan blocked on handing out the next capture never ends
+        self.receiver.take();
+        if let Some(coordinator) = self.coordinator.take() {
+            let _ = coordinator.join();
+        }
+    }
+}
+
+/// Prefix an error with the file it's about.
+fn with_path(err: Error, path: &Path) -> Error {
+    Error::new(err.kind(), format!("{}: {err}", path.display()))
+}
+
+#[cfg(feature = "async")]
+impl ScanBuilder {
+    /// [ScanBuilder::scan] for async callers, see [Scanner::scan_async].
+    pub async fn scan_async(self) -> Result<AbiReport> {
+        Arc::new(self.build()?).scan_async().await
+    }
+}
+
+#[cfg(feature = "async")]
+impl Scanner {
+    /// [Scanner::scan] without blocking the async runtime (tokio, which must be running).
+    ///
+    /// Files are read with async I/O while up to `jobs` of them are parsed on the blocking
+    /// pool, so slow storage (network filesystems, container layers being streamed in) overlaps
+    /// with parsing instead of stalling it. Fails on the first file that can't be captured.
+    pub async fn scan_async(self: Arc<Self>) -> Result<AbiReport> {
+        let scanner = Arc::clone(&self);
+        let (files, header) = task::spawn_blocking(move || {
+            let files = scanner.discover();
+            let header = scanner.header(&files);
+            (files, header)
+        })
+        .await?;
+
+        let mut captures = Vec::with_capacity(files.len());
+        let mut queue = files.into_iter();
+        let mut pending = task::JoinSet::new();
+        loop {
+            while pending.len() < self.jobs {
+                let Some(path) = queue.next() else {
+                    break;
+                };
+                pending.spawn(Arc::clone(&self).capture_async(path));
+            }
+            match pending.join_next().await {
+                Some(result) => captures.push(result??),
+                None => break,
+            }
+        }
+        Ok(AbiReport::with_header(header, captures))
+    }
+
+    async fn capture_async(self: Arc<Self>, path: PathBuf) -> Result<AbiCapture> {
+        let file_name = path.to_string_lossy().into_owned();
+        let result = match read_file(&path, &self.limits).await {
+            Ok(data) => task::spawn_blocking(move || self.capture_data(&file_name, data))
+                .await
+                .unwrap_or_else(|err| Err(err.into())),
+            Err(err) => Err(err),
+        };
+        result.map_err(|err| with_path(err, &path))
+    }
+}
+
+/// The async counterpart of the read in [crate::open_object], held to the same limits.
+#[cfg(feature = "async")]
+async fn read_file(path: &Path, limits: &Limits) -> Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let file = tokio::fs::File::open(path).await?;
+    let file_size = file.metadata().await?.len();
+    limits.check_file_size(file_size)?;
+    let mut data = Vec::with_capacity(file_size as usize);
+    file.take(limits.max_file_size)
+        .read_to_end(&mut data)
+        .await?;
+    Ok(data)
+}
+
+/// Recursive directory search state
+struct Walk<'a> {
+    scanner: &'a Scanner,
+    files: Vec<PathBuf>,
+    seen: HashSet<PathBuf>, // canonical paths, to break symlink loops and dupes when following
+    ignores: Vec<IgnoreFile>, // those of the directories being searched, outermost first
+    warnings: Vec<ScanWarning>,
+}
+
+impl Walk<'_> {
+    fn dir(&mut self, dir: &Path) {
+        if self.scanner.follow_symlinks && !self.first_visit(dir) {
+            return;
+        }
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.warnings.push(ScanWarning::UnreadableDir {
+                    path: dir.to_path_buf(),
+                    error: err.to_string(),
+                });
+                return;
+            }
+        };
+
+        let mut entries = entries.filter_map(|e| e.ok()).collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.file_name());
+
+        let ignore = match self.scanner.ignore_files {
+            true => IgnoreFile::load(dir).unwrap_or_else(|err| {
+                self.warnings.push(ScanWarning::IgnoreFile {
+                    path: dir.join(IGNORE_FILE),
+                    error: err.to_string(),
+                });
+                None
+            }),
+            false => None,
+        };
+        let has_ignore = ignore.is_some();
+        self.ignores.extend(ignore);
+
+        for entry in entries {
+            let path = entry.path();
+            if self.scanner.excludes.is_match(&path) {
+                continue;
+            }
+            let file_type = match entry.file_type() {
+                Ok(file_type) if file_type.is_symlink() && self.scanner.follow_symlinks => {
+                    fs::metadata(&path).map(|metadata| metadata.file_type())
+                }
+                file_type => file_type,
+            };
+            let Ok(file_type) = file_type else {
+                continue;
+            };
+            if is_ignored(&self.ignores, &path, file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                self.dir(&path);
+            } else if file_type.is_file() && has_object_magic(&path) {
+                self.file(path);
+            }
+        }
+
+        if has_ignore {
+            self.ignores.pop();
+        }
+    }
+
+    fn file(&mut self, path: PathBuf) {
+        if !self.scanner.follow_symlinks || self.first_visit(&path) {
+            self.files.push(path);
+        }
+    }
+
+    fn first_visit(&mut self, path: &Path) -> bool {
+        match path.canonicalize() {
+            Ok(canonical) => self.seen.insert(canonical),
+            Err(_) => true,
+        }
+    }
+}
+
+pub(crate) fn has_elf_magic(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && &magic == ELF_MAGIC
+}
+
+/// Where `path` ends up installed when `root` is the staging root it is under: the rest of the
+/// path, made absolute. Paths outside of the root are kept as they are.
+/// Relative and absolute spellings of the same location match.
+fn install_path(root: &Path, path: &Path) -> String {
+    let relative = path
+        .strip_prefix(root)
+        .map(Path::to_path_buf)
+        .ok()
+        .or_else(|| {
+            let root = std::path::absolute(root).ok()?;
+            let path = std::path::absolute(path).ok()?;
+            path.strip_prefix(root).ok().map(Path::to_path_buf)
+        });
+    match relative {
+        Some(relative) => Path::new("/").join(relative).to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// The deepest directory containing all of the given input files.
+fn common_root(files: &[PathBuf]) -> Option<PathBuf> {
+    let mut dirs = files.iter().filter_map(|file| {
+        file.canonicalize()
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+    });
+
+    let first = dirs.next()?;
+    Some(dirs.fold(first, |root, dir| {
+        root.ancestors()
+            .find(|ancestor| dir.starts_with(ancestor))
+            .map(Path::to_path_buf)
+            .unwrap_or(root)
+    }))
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid pattern {pattern:?}: {err}"),
+            )
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))
+}
@@ -0,0 +1,128 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Versioning of the serialized report layout.
+//!
+//! Stored baseline reports have to survive crate upgrades, so the rules are:
+//!
+//! - Additive changes (new fields) do NOT bump [SCHEMA_VERSION]. Unknown fields are ignored
+//!   when reading, and new fields must deserialize from their absence (`Option` or
+//!   `#[serde(default)]`), so old and new readers can both consume the document.
+//! - Anything else (renames, removals, changed representations) bumps [SCHEMA_VERSION] and
+//!   adds a migration below, which rewrites the older layout into the next one before the
+//!   document is handed to serde.
+//!
+//! Reports from a newer schema than we know about are rejected rather than misread.
+//!
+//! With the `schema` feature, the layout of reports and diffs is also published as JSON
+//! Schemas, derived from the Rust types so they can't drift from what is actually written.
+
+#[cfg(feature = "serde")]
+use serde_json::Value;
+#[cfg(feature = "serde")]
+use std::io::{Error, ErrorKind, Result};
+
+/// Layout version of serialized reports
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// The JSON Schema of reports of the current [SCHEMA_VERSION], as written in JSON.
+#[cfg(feature = "schema")]
+pub fn report_schema() -> Value {
+    schemars::schema_for!(crate::AbiReport).to_value()
+}
+
+/// The JSON Schema of report diffs, as written in JSON.
+#[cfg(feature = "schema")]
+pub fn diff_schema() -> Value {
+    schemars::schema_for!(crate::diff::ReportDiff).to_value()
+}
+
+#[cfg(feature = "serde")]
+type Migration = fn(&mut Value) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a report from schema version `n + 1` to `n + 2`.
+#[cfg(feature = "serde")]
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Upgrade a raw report document in place to the current [SCHEMA_VERSION].
+///
+/// Returns the schema version the document was originally written with.
+#[cfg(feature = "serde")]
+pub fn migrate(report: &mut Value) -> Result<u32> {
+    let version = report
+        .pointer("/header/schema_version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "report has no schema_version"))?;
+
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("report schema version {version} is not supported (current: {SCHEMA_VERSION})"),
+        ));
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(report)?;
+    }
+    report["header"]["schema_version"] = SCHEMA_VERSION.into();
+
+    Ok(version)
+}
+
+/// v2 serializes enum values in snake_case ("SharedObject" -> "shared_object").
+#[cfg(feature = "serde")]
+fn migrate_v1_to_v2(report: &mut Value) -> Result<()> {
+    let Some(captures) = report.get_mut("captures").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for capture in captures {
+        if let Some(Value::String(kind)) = capture.get_mut("elf_kind") {
+            *kind = to_snake_case(kind);
+        }
+    }
+
+    Ok(())
+}
+
+/// v3 captures symbols as objects (name, version, type) rather than bare names, and no longer
+/// captures the nameless null symbol as an import.
+#[cfg(feature = "serde")]
+fn migrate_v2_to_v3(report: &mut Value) -> Result<()> {
+    let Some(captures) = report.get_mut("captures").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for capture in captures {
+        for field in ["dynsym_imports", "dynsym_exports"] {
+            let Some(symbols) = capture.get_mut(field).and_then(Value::as_array_mut) else {
+                continue;
+            };
+            symbols.retain(|symbol| symbol.as_str() != Some(""));
+            for symbol in symbols {
+                if let Value::String(name) = symbol {
+                    *symbol = serde_json::json!({ "name": name });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
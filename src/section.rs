@@ -0,0 +1,241 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Section data access that transparently undoes section compression.
+//!
+//! Distro binaries built with `--compress-debug-sections` (or with compressed notes) carry
+//! SHF_COMPRESSED sections, which elf.rs hands back as raw compressed bytes plus a
+//! compression header. Anything we read beyond .dynsym/.dynamic (DWARF, notes, debuglinks)
+//! should go through these helpers so it doesn't have to care.
+//!
+//! Both the standard gABI format (ELFCOMPRESS_ZLIB / ELFCOMPRESS_ZSTD) and the legacy GNU
+//! `.zdebug_*` format ("ZLIB" magic + big endian size) are supported.
+
+use crate::Limits;
+use elf::abi::{ELFCOMPRESS_ZLIB, ELFCOMPRESS_ZSTD, PT_NOTE, SHT_NOTE};
+use elf::endian::AnyEndian;
+use elf::note::{Note, NoteIterator};
+use elf::section::SectionHeader;
+use elf::ElfBytes;
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind, Result};
+
+/// Magic prefix of legacy GNU compressed `.zdebug_*` sections
+const ZDEBUG_MAGIC: &[u8; 4] = b"ZLIB";
+
+/// Returns the (decompressed if need be) contents of the section described by `shdr`.
+///
+/// Uncompressed sections are borrowed straight from the file data. Compressed sections are only
+/// decompressed if their advertised size is within `limits`.
+pub fn section_data<'data>(
+    elf_file: &ElfBytes<'data, AnyEndian>,
+    shdr: &SectionHeader,
+    limits: &Limits,
+) -> Result<Cow<'data, [u8]>> {
+    let (data, chdr) = elf_file.section_data(shdr).map_err(invalid_data)?;
+
+    match chdr {
+        None => Ok(Cow::Borrowed(data)),
+        Some(chdr) => {
+            limits.check_section_size(chdr.ch_size)?;
+            let expected_size: usize = chdr.ch_size.try_into().map_err(invalid_data)?;
+            let decompressed = match chdr.ch_type {
+                ELFCOMPRESS_ZLIB => inflate(data, expected_size)?,
+                ELFCOMPRESS_ZSTD => unzstd(data, expected_size)?,
+                other => {
+                    return Err(Error::new(
+                        ErrorKind::Unsupported,
+                        format!("unsupported section compression type {other:#x}"),
+                    ))
+                }
+            };
+            Ok(Cow::Owned(decompressed))
+        }
+    }
+}
+
+/// Looks up a section by name and returns its (decompressed if need be) contents.
+///
+/// For `.debug_*` sections, the legacy `.zdebug_*` spelling is tried as a fallback.
+pub fn section_data_by_name<'data>(
+    elf_file: &ElfBytes<'data, AnyEndian>,
+    name: &str,
+    limits: &Limits,
+) -> Result<Option<Cow<'data, [u8]>>> {
+    if let Some(shdr) = elf_file
+        .section_header_by_name(name)
+        .map_err(invalid_data)?
+    {
+        return section_data(elf_file, &shdr, limits).map(Some);
+    }
+
+    if let Some(suffix) = name.strip_prefix(".debug_") {
+        let zname = format!(".zdebug_{suffix}");
+        if let Some(shdr) = elf_file
+            .section_header_by_name(&zname)
+            .map_err(invalid_data)?
+        {
+            let (data, _) = elf_file.section_data(&shdr).map_err(invalid_data)?;
+            return zdebug_data(data, limits).map(|d| Some(Cow::Owned(d)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the notes in an SHT_NOTE section, decompressing the section first if need be.
+///
+/// elf.rs' own `section_data_as_notes()` would happily try to parse compressed bytes as notes,
+/// so the note data is handed back as a buffer to be walked with [iter_notes].
+pub fn section_notes<'data>(
+    elf_file: &ElfBytes<'data, AnyEndian>,
+    shdr: &SectionHeader,
+    limits: &Limits,
+) -> Result<Cow<'data, [u8]>> {
+    if shdr.sh_type != SHT_NOTE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("section type {:#x} is not SHT_NOTE", shdr.sh_type),
+        ));
+    }
+    section_data(elf_file, shdr, limits)
+}
+
+/// Iterate over the notes in note section data previously returned by [section_notes].
+pub fn iter_notes<'buf>(
+    elf_file: &ElfBytes<'_, AnyEndian>,
+    shdr: &SectionHeader,
+    data: &'buf [u8],
+) -> NoteIterator<'buf, AnyEndian> {
+    NoteIterator::new(
+        elf_file.ehdr.endianness,
+        elf_file.ehdr.class,
+        shdr.sh_addralign as usize,
+        data,
+    )
+}
+
+/// The first note `pick` takes, looking through the SHT_NOTE sections, then the PT_NOTE
+/// segments. Unreadable notes are only an error if no note was taken elsewhere.
+pub fn find_note<T>(
+    elf_file: &ElfBytes<AnyEndian>,
+    limits: &Limits,
+    pick: impl Fn(Note) -> Option<T>,
+) -> Result<Option<T>> {
+    let mut first_error = None;
+
+    if let Some(shdrs) = elf_file.section_headers() {
+        for shdr in shdrs.iter().filter(|shdr| shdr.sh_type == SHT_NOTE) {
+            let data = match section_notes(elf_file, &shdr, limits) {
+                Ok(data) => data,
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                    continue;
+                }
+            };
+            if let Some(found) = iter_notes(elf_file, &shdr, &data).find_map(&pick) {
+                return Ok(Some(found));
+            }
+        }
+    }
+
+    for phdr in elf_file.segments().into_iter().flatten() {
+        if phdr.p_type != PT_NOTE {
+            continue;
+        }
+        let notes = match elf_file.segment_data_as_notes(&phdr) {
+            Ok(notes) => notes,
+            Err(err) => {
+                first_error.get_or_insert(Error::new(ErrorKind::InvalidData, err.to_string()));
+                continue;
+            }
+        };
+        if let Some(found) = notes.into_iter().find_map(&pick) {
+            return Ok(Some(found));
+        }
+    }
+
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(None),
+    }
+}
+
+/// Decode a legacy GNU `.zdebug_*` section: "ZLIB", u64 big endian size, zlib stream.
+fn zdebug_data(data: &[u8], limits: &Limits) -> Result<Vec<u8>> {
+    match (data.get(..4), data.get(4..12)) {
+        (Some(magic), Some(size)) if magic == ZDEBUG_MAGIC => {
+            let size = u64::from_be_bytes(size.try_into().expect("slice is 8 bytes long"));
+            limits.check_section_size(size)?;
+            inflate(&data[12..], size.try_into().map_err(invalid_data)?)
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            "missing ZLIB magic in .zdebug section",
+        )),
+    }
+}
+
+// Decompression stops one byte past the advertised size, so a lying header can't be used to
+// make us inflate far more than the limits allowed for.
+
+#[cfg(feature = "dwarf")]
+fn inflate(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut out = Vec::with_capacity(expected_size);
+    flate2::read::ZlibDecoder::new(data)
+        .take(expected_size as u64 + 1)
+        .read_to_end(&mut out)?;
+    check_size(out, expected_size)
+}
+
+#[cfg(feature = "dwarf")]
+fn unzstd(data: &[u8], expected_size: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut out = Vec::with_capacity(expected_size);
+    ruzstd::decoding::StreamingDecoder::new(data)
+        .map_err(invalid_data)?
+        .take(expected_size as u64 + 1)
+        .read_to_end(&mut out)?;
+    check_size(out, expected_size)
+}
+
+#[cfg(not(feature = "dwarf"))]
+fn inflate(_data: &[u8], _expected_size: usize) -> Result<Vec<u8>> {
+    Err(decompression_disabled())
+}
+
+#[cfg(not(feature = "dwarf"))]
+fn unzstd(_data: &[u8], _expected_size: usize) -> Result<Vec<u8>> {
+    Err(decompression_disabled())
+}
+
+#[cfg(not(feature = "dwarf"))]
+fn decompression_disabled() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "compressed sections can't be read without the `dwarf` feature",
+    )
+}
+
+/// The compression header tells us how big the section is meant to be, so hold it to that.
+fn check_size(out: Vec<u8>, expected_size: usize) -> Result<Vec<u8>> {
+    if out.len() == expected_size {
+        Ok(out)
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "decompressed section is {} bytes, header says {expected_size}",
+                out.len()
+            ),
+        ))
+    }
+}
+
+fn invalid_data<E: std::error::Error + Send + Sync + 'static>(err: E) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,515 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Spotting libraries whose exports shadow libc and libm.
+//!
+//! The dynamic linker binds every unversioned reference to the first definition it finds, in
+//! load order, so a library exporting its own `free`, `pow` or `basename` takes over that name
+//! for the whole process: everyone calling it gets the library's version, with whatever
+//! semantics that has. Such interposition is rarely meant; usually a helper missed a `static` or
+//! a version script. The `libc-shadow` check flags shared objects exporting well-known libc and
+//! libm names ([LIBC_NAMES]) and leaves out the libraries whose job it is to interpose on them
+//! (glibc's own, allocators, sanitizer runtimes).
+//!
+//! [LibcNames] takes more names to flag, e.g. those of another C library or of a platform
+//! library every process loads. Exports with a symbol version are left out: they only bind
+//! references to that version, never glibc's.
+
+use crate::check::{Check, Finding};
+use crate::{AbiCapture, AbiReport, ElfKind};
+use std::collections::BTreeSet;
+
+/// The libc and libm names flagged by default: functions and variables of glibc's
+/// `libc.so.6` and `libm.so.6` that programs commonly use
+pub const LIBC_NAMES: &[&str] = &[
+    "_exit",
+    "abort",
+    "abs",
+    "accept",
+    "access",
+    "acos",
+    "acosh",
+    "aligned_alloc",
+    "asctime",
+    "asin",
+    "asinh",
+    "asprintf",
+    "atan",
+    "atan2",
+    "atanh",
+    "atof",
+    "atoi",
+    "atol",
+    "atoll",
+    "basename",
+    "bcmp",
+    "bcopy",
+    "bind",
+    "bsearch",
+    "bzero",
+    "calloc",
+    "cbrt",
+    "ceil",
+    "ceilf",
+    "chdir",
+    "chmod",
+    "chown",
+    "clearenv",
+    "clearerr",
+    "clock",
+    "clock_gettime",
+    "close",
+    "closedir",
+    "closelog",
+    "connect",
+    "copysign",
+    "cos",
+    "cosf",
+    "cosh",
+    "ctime",
+    "difftime",
+    "dirname",
+    "div",
+    "dlclose",
+    "dlerror",
+    "dlopen",
+    "dlsym",
+    "dprintf",
+    "drand48",
+    "dup",
+    "dup2",
+    "environ",
+    "erand48",
+    "erf",
+    "erfc",
+    "err",
+    "errno",
+    "error",
+    "errx",
+    "execv",
+    "execve",
+    "execvp",
+    "exit",
+    "exp",
+    "exp2",
+    "expf",
+    "explicit_bzero",
+    "expm1",
+    "fabs",
+    "fabsf",
+    "fclose",
+    "fdim",
+    "fdopen",
+    "feof",
+    "ferror",
+    "fflush",
+    "fgetc",
+    "fgetpos",
+    "fgets",
+    "fileno",
+    "finite",
+    "floor",
+    "floorf",
+    "fma",
+    "fmax",
+    "fmin",
+    "fmod",
+    "fmodf",
+    "fnmatch",
+    "fopen",
+    "fork",
+    "fprintf",
+    "fputc",
+    "fputs",
+    "fread",
+    "free",
+    "freeaddrinfo",
+    "freopen",
+    "frexp",
+    "fscanf",
+    "fseek",
+    "fsetpos",
+    "fstat",
+    "ftell",
+    "ftruncate",
+    "fwrite",
+    "gai_strerror",
+    "gamma",
+    "getaddrinfo",
+    "getc",
+    "getchar",
+    "getcwd",
+    "getdelim",
+    "getegid",
+    "getenv",
+    "geteuid",
+    "getgid",
+    "gethostname",
+    "getline",
+    "getopt",
+    "getopt_long",
+    "getpid",
+    "getppid",
+    "gettimeofday",
+    "getuid",
+    "glob",
+    "globfree",
+    "gmtime",
+    "hypot",
+    "iconv",
+    "iconv_close",
+    "iconv_open",
+    "ilogb",
+    "index",
+    "isalnum",
+    "isalpha",
+    "isdigit",
+    "isinf",
+    "islower",
+    "isnan",
+    "isprint",
+    "ispunct",
+    "isspace",
+    "isupper",
+    "isxdigit",
+    "j0",
+    "j1",
+    "jn",
+    "kill",
+    "labs",
+    "ldexp",
+    "ldiv",
+    "lgamma",
+    "link",
+    "listen",
+    "llabs",
+    "llrint",
+    "llround",
+    "localtime",
+    "log",
+    "log10",
+    "log1p",
+    "log2",
+    "logb",
+    "logf",
+    "longjmp",
+    "lrand48",
+    "lrint",
+    "lround",
+    "lseek",
+    "lstat",
+    "malloc",
+    "malloc_usable_size",
+    "memalign",
+    "memchr",
+    "memcmp",
+    "memcpy",
+    "memmem",
+    "memmove",
+    "mempcpy",
+    "memrchr",
+    "memset",
+    "mkdir",
+    "mktime",
+    "mmap",
+    "modf",
+    "mprotect",
+    "mrand48",
+    "munmap",
+    "nan",
+    "nanosleep",
+    "nearbyint",
+    "on_exit",
+    "open",
+    "opendir",
+    "openlog",
+    "optarg",
+    "opterr",
+    "optind",
+    "optopt",
+    "perror",
+    "pipe",
+    "poll",
+    "posix_memalign",
+    "pow",
+    "powf",
+    "pread",
+    "printf",
+    "pthread_create",
+    "pthread_join",
+    "pthread_mutex_lock",
+    "pthread_mutex_unlock",
+    "pthread_once",
+    "putc",
+    "putchar",
+    "putenv",
+    "puts",
+    "pvalloc",
+    "pwrite",
+    "qsort",
+    "raise",
+    "rand",
+    "rand_r",
+    "random",
+    "read",
+    "readdir",
+    "readlink",
+    "realloc",
+    "reallocarray",
+    "realpath",
+    "recv",
+    "recvfrom",
+    "regcomp",
+    "regerror",
+    "regexec",
+    "regfree",
+    "remainder",
+    "remove",
+    "remquo",
+    "rename",
+    "rewind",
+    "rewinddir",
+    "rindex",
+    "rint",
+    "rmdir",
+    "round",
+    "roundf",
+    "scalbn",
+    "scandir",
+    "scanf",
+    "secure_getenv",
+    "select",
+    "send",
+    "sendto",
+    "setbuf",
+    "setenv",
+    "setjmp",
+    "setvbuf",
+    "sigaction",
+    "siglongjmp",
+    "signal",
+    "sin",
+    "sinf",
+    "sinh",
+    "sleep",
+    "snprintf",
+    "socket",
+    "sprintf",
+    "sqrt",
+    "sqrtf",
+    "srand",
+    "srand48",
+    "srandom",
+    "sscanf",
+    "stat",
+    "stderr",
+    "stdin",
+    "stdout",
+    "stpcpy",
+    "stpncpy",
+    "strcasecmp",
+    "strcasestr",
+    "strcat",
+    "strchr",
+    "strchrnul",
+    "strcmp",
+    "strcoll",
+    "strcpy",
+    "strcspn",
+    "strdup",
+    "strerror",
+    "strerror_r",
+    "strftime",
+    "strlen",
+    "strncasecmp",
+    "strncat",
+    "strncmp",
+    "strncpy",
+    "strndup",
+    "strnlen",
+    "strpbrk",
+    "strptime",
+    "strrchr",
+    "strsep",
+    "strsignal",
+    "strspn",
+    "strstr",
+    "strtod",
+    "strtof",
+    "strtok",
+    "strtok_r",
+    "strtol",
+    "strtold",
+    "strtoll",
+    "strtoul",
+    "strtoull",
+    "strverscmp",
+    "strxfrm",
+    "symlink",
+    "syslog",
+    "system",
+    "tan",
+    "tanf",
+    "tanh",
+    "tgamma",
+    "time",
+    "tmpfile",
+    "tmpnam",
+    "tolower",
+    "toupper",
+    "trunc",
+    "truncate",
+    "umask",
+    "uname",
+    "ungetc",
+    "unlink",
+    "unsetenv",
+    "usleep",
+    "valloc",
+    "vasprintf",
+    "verr",
+    "verrx",
+    "vfprintf",
+    "vfscanf",
+    "vprintf",
+    "vscanf",
+    "vsnprintf",
+    "vsprintf",
+    "vsscanf",
+    "vwarn",
+    "vwarnx",
+    "wait",
+    "waitpid",
+    "warn",
+    "warnx",
+    "write",
+    "y0",
+    "y1",
+    "yn",
+];
+
+/// Soname prefixes of the libraries meant to interpose on libc: glibc's own, allocators and
+/// sanitizer or preload runtimes
+const INTERPOSERS: [&str; 27] = [
+    "libc.so",
+    "libm.so",
+    "ld-linux",
+    "libc_malloc_debug.so",
+    "libmemusage.so",
+    "libpcprofile.so",
+    "libpthread.so",
+    "libdl.so",
+    "librt.so",
+    "libresolv.so",
+    "libutil.so",
+    "libanl.so",
+    "libmvec.so",
+    "libBrokenLocale.so",
+    "libjemalloc.so",
+    "libtcmalloc",
+    "libmimalloc.so",
+    "libsnmalloc",
+    "librpmalloc.so",
+    "libasan.so",
+    "libtsan.so",
+    "liblsan.so",
+    "libubsan.so",
+    "libhwasan.so",
+    "libclang_rt.",
+    "libfakeroot",
+    "libeatmydata.so",
+];
+
+/// The names the `libc-shadow` check flags exports of
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibcNames {
+    names: BTreeSet<String>,
+}
+
+impl Default for LibcNames {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl LibcNames {
+    /// The names of [LIBC_NAMES].
+    pub fn builtin() -> Self {
+        Self {
+            names: LIBC_NAMES.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Also flag exports named `name`.
+    pub fn add(&mut self, name: impl Into<String>) {
+        self.names.insert(name.into());
+    }
+
+    /// Also flag the names listed in `contents`, one per line, ignoring blank lines and `#`
+    /// comments.
+    pub fn extend_from_list(&mut self, contents: &str) {
+        let names = contents
+            .lines()
+            .map(|line| line.split_once('#').map_or(line, |(name, _)| name).trim())
+            .filter(|name| !name.is_empty());
+        self.names.extend(names.map(str::to_string));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Run the `libc-shadow` check with these names: one finding per shared object exporting
+    /// any of them, unversioned.
+    pub fn check(&self, report: &AbiReport) -> Vec<Finding> {
+        let mut findings = vec![];
+        for capture in report.captures() {
+            if capture.elf_kind() != ElfKind::SharedObject || is_interposer(capture) {
+                continue;
+            }
+            let mut shadowed = capture
+                .dynsym_exports()
+                .iter()
+                .filter(|symbol| symbol.version.is_none() && self.contains(&symbol.name))
+                .map(|symbol| symbol.name.clone())
+                .collect::<Vec<_>>();
+            if shadowed.is_empty() {
+                continue;
+            }
+            shadowed.sort();
+            shadowed.dedup();
+            let message = format!(
+                "exports {} libc/libm name(s), taking them over for the whole process: {}",
+                shadowed.len(),
+                shadowed.join(", ")
+            );
+            findings.push(
+                Finding::new(Check::LibcShadow, capture.filename(), message).with_symbols(shadowed),
+            );
+        }
+        findings
+    }
+}
+
+/// Whether interposing on libc is what the library is for.
+fn is_interposer(capture: &AbiCapture) -> bool {
+    let name = capture.soname().unwrap_or_else(|| {
+        let filename = capture.filename();
+        filename.rsplit('/').next().unwrap_or(filename)
+    });
+    INTERPOSERS.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// The `libc-shadow` check with the built-in names.
+pub(crate) fn libc_shadows(report: &AbiReport) -> Vec<Finding> {
+    LibcNames::builtin().check(report)
+}
@@ -0,0 +1,427 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Signing serialized reports, so a baseline fetched from infrastructure can be trusted before
+//! it gates anything.
+//!
+//! Keys and signatures use the minisign formats: a baseline signed with `minisign -S` verifies
+//! here, and ours verify with `minisign -V`. Files are signed prehashed (BLAKE2b-512, the `ED`
+//! algorithm minisign defaults to), and legacy `Ed` signatures over the whole file are accepted
+//! too. Each signature carries a trusted comment (by default the signing time and file name),
+//! which is signed along with the signature and so can be relied on once verified.
+//!
+//! Secret keys are stored unencrypted, like those of `minisign -G -W`; password protected
+//! minisign keys need the scrypt KDF, which isn't supported.
+//!
+//! The cryptography is RustCrypto's (`ed25519-dalek` and `blake2`); this module only deals with
+//! the minisign file formats around it.
+
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Blake2b512, Digest};
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+const ALG_LEGACY: &[u8; 2] = b"Ed"; // the key algorithm, and signatures over the data itself
+const ALG_PREHASHED: &[u8; 2] = b"ED"; // signatures over the BLAKE2b-512 of the data
+const KDF_NONE: &[u8; 2] = b"\0\0";
+const KDF_SCRYPT: &[u8; 2] = b"Sc";
+const CHECKSUM_BLAKE2B: &[u8; 2] = b"B2";
+
+type KeyId = [u8; 8];
+
+/// A key to verify signatures with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    key_id: KeyId,
+    key: [u8; PUBLIC_KEY_LENGTH],
+}
+
+/// A key to sign with
+#[derive(Clone)]
+pub struct SecretKey {
+    key_id: KeyId,
+    seed: [u8; 32],
+    public: [u8; PUBLIC_KEY_LENGTH],
+}
+
+/// A detached signature, as in a `.minisig` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    prehashed: bool,
+    key_id: KeyId,
+    signature: [u8; SIGNATURE_LENGTH],
+    trusted_comment: String,
+    global_signature: [u8; SIGNATURE_LENGTH], // over the signature and the trusted comment
+}
+
+impl PublicKey {
+    /// Parse a minisign public key, either a whole `.pub` file or just its base64 line.
+    pub fn from_minisign(text: &str) -> Result<Self> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+            .ok_or_else(|| invalid("no public key found"))?;
+        let bytes = base64_decode(line)?;
+        let mut reader = Reader::new(&bytes, "public key");
+        if reader.take::<2>()? != *ALG_LEGACY {
+            return Err(invalid("not an Ed25519 public key"));
+        }
+        let key = Self {
+            key_id: reader.take()?,
+            key: reader.take()?,
+        };
+        reader.finish()?;
+        Ok(key)
+    }
+
+    /// The contents of a minisign `.pub` file.
+    pub fn to_minisign(&self) -> String {
+        let bytes = [ALG_LEGACY.as_slice(), &self.key_id, &self.key].concat();
+        format!(
+            "{UNTRUSTED_PREFIX}minisign public key {}\n{}\n",
+            key_id_hex(&self.key_id),
+            base64_encode(&bytes)
+        )
+    }
+
+    /// The key id as minisign shows it, e.g. `E8A5B1D4C0F37A52`.
+    pub fn key_id(&self) -> String {
+        key_id_hex(&self.key_id)
+    }
+
+    /// Check a signature of `data`, returning its (now trusted) comment.
+    pub fn verify<'a>(&self, data: &[u8], signature: &'a Signature) -> Result<&'a str> {
+        if signature.key_id != self.key_id {
+            return Err(invalid(format!(
+                "signed with key {}, not with key {}",
+                key_id_hex(&signature.key_id),
+                self.key_id()
+            )));
+        }
+        let signed = match signature.prehashed {
+            true => prehash(data).to_vec(),
+            false => data.to_vec(),
+        };
+        if !ed25519_verify(&self.key, &signed, &signature.signature) {
+            return Err(invalid("signature verification failed"));
+        }
+        let global = [
+            signature.signature.as_slice(),
+            signature.trusted_comment.as_bytes(),
+        ]
+        .concat();
+        if !ed25519_verify(&self.key, &global, &signature.global_signature) {
+            return Err(invalid("trusted comment verification failed"));
+        }
+        Ok(&signature.trusted_comment)
+    }
+}
+
+impl SecretKey {
+    /// A new key pair from 32 random bytes and a random 8 byte key id.
+    pub fn from_random(seed: [u8; 32], key_id: [u8; 8]) -> Self {
+        Self {
+            key_id,
+            seed,
+            public: ed25519_public_key(&seed),
+        }
+    }
+
+    /// A new key pair, from the system's random number generator.
+    #[cfg(feature = "fs")]
+    pub fn generate() -> Result<Self> {
+        use std::io::Read;
+
+        let mut random = [0u8; 40];
+        std::fs::File::open("/dev/urandom")?.read_exact(&mut random)?;
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&random[..32]);
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&random[32..]);
+        Ok(Self::from_random(seed, key_id))
+    }
+
+    /// Parse an unencrypted minisign secret key file.
+    pub fn from_minisign(text: &str) -> Result<Self> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX))
+            .ok_or_else(|| invalid("no secret key found"))?;
+        let bytes = base64_decode(line)?;
+        let mut reader = Reader::new(&bytes, "secret key");
+        if reader.take::<2>()? != *ALG_LEGACY {
+            return Err(invalid("not an Ed25519 secret key"));
+        }
+        match &reader.take::<2>()? {
+            KDF_NONE => {}
+            KDF_SCRYPT => {
+                return Err(invalid(
+                    "password protected secret keys are not supported (see minisign -W)",
+                ))
+            }
+            _ => return Err(invalid("unknown key derivation function")),
+        }
+        if reader.take::<2>()? != *CHECKSUM_BLAKE2B {
+            return Err(invalid("unknown secret key checksum"));
+        }
+        reader.take::<{ 32 + 8 + 8 }>()?; // KDF salt and limits
+        let key_id: KeyId = reader.take()?;
+        let seed: [u8; 32] = reader.take()?;
+        let public: [u8; PUBLIC_KEY_LENGTH] = reader.take()?;
+        let checksum: [u8; 32] = reader.take()?;
+        reader.finish()?;
+
+        let key = Self {
+            key_id,
+            seed,
+            public,
+        };
+        if checksum != key.checksum() {
+            return Err(invalid("secret key checksum mismatch"));
+        }
+        if ed25519_public_key(&seed) != public {
+            return Err(invalid("secret key doesn't match its public key"));
+        }
+        Ok(key)
+    }
+
+    /// The contents of an unencrypted minisign secret key file.
+    pub fn to_minisign(&self) -> String {
+        let bytes = [
+            ALG_LEGACY.as_slice(),
+            KDF_NONE,
+            CHECKSUM_BLAKE2B,
+            &[0; 32 + 8 + 8],
+            &self.key_id,
+            &self.seed,
+            &self.public,
+            &self.checksum(),
+        ]
+        .concat();
+        format!(
+            "{UNTRUSTED_PREFIX}minisign secret key {}\n{}\n",
+            key_id_hex(&self.key_id),
+            base64_encode(&bytes)
+        )
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.public,
+        }
+    }
+
+    /// Sign `data` (prehashed), attaching a trusted comment, which must be a single line.
+    pub fn sign(&self, data: &[u8], trusted_comment: &str) -> Result<Signature> {
+        if trusted_comment.contains(['\n', '\r']) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the trusted comment must be a single line",
+            ));
+        }
+        let signature = ed25519_sign(&self.seed, &prehash(data));
+        let global = [signature.as_slice(), trusted_comment.as_bytes()].concat();
+        Ok(Signature {
+            prehashed: true,
+            key_id: self.key_id,
+            signature,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature: ed25519_sign(&self.seed, &global),
+        })
+    }
+
+    /// BLAKE2b-256 over the algorithm, key id and libsodium style secret key (seed and public
+    /// key), as minisign checks it.
+    fn checksum(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update(ALG_LEGACY);
+        hasher.update(self.key_id);
+        hasher.update(self.seed);
+        hasher.update(self.public);
+        hasher.finalize().into()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    /// Leaves out the key itself, which has no business in logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("key_id", &key_id_hex(&self.key_id))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signature {
+    /// Parse a `.minisig` file.
+    pub fn from_minisign(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let mut line = lines.next().ok_or_else(|| invalid("empty signature"))?;
+        if line.starts_with(UNTRUSTED_PREFIX) {
+            line = lines.next().ok_or_else(|| invalid("no signature found"))?;
+        }
+        let bytes = base64_decode(line.trim())?;
+        let mut reader = Reader::new(&bytes, "signature");
+        let prehashed = match &reader.take::<2>()? {
+            ALG_PREHASHED => true,
+            ALG_LEGACY => false,
+            _ => return Err(invalid("unknown signature algorithm")),
+        };
+        let key_id = reader.take()?;
+        let signature = reader.take()?;
+        reader.finish()?;
+
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix(TRUSTED_PREFIX))
+            .ok_or_else(|| invalid("no trusted comment found"))?;
+        let global = base64_decode(
+            lines
+                .next()
+                .ok_or_else(|| invalid("no trusted comment signature found"))?
+                .trim(),
+        )?;
+        let mut reader = Reader::new(&global, "trusted comment signature");
+        let global_signature = reader.take()?;
+        reader.finish()?;
+
+        Ok(Self {
+            prehashed,
+            key_id,
+            signature,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature,
+        })
+    }
+
+    /// The contents of a `.minisig` file.
+    pub fn to_minisign(&self) -> String {
+        let algorithm = match self.prehashed {
+            true => ALG_PREHASHED,
+            false => ALG_LEGACY,
+        };
+        let bytes = [algorithm.as_slice(), &self.key_id, &self.signature].concat();
+        format!(
+            "{UNTRUSTED_PREFIX}signature from abireport secret key\n{}\n{TRUSTED_PREFIX}{}\n{}\n",
+            base64_encode(&bytes),
+            self.trusted_comment,
+            base64_encode(&self.global_signature)
+        )
+    }
+
+    /// The key id of the key that made the signature.
+    pub fn key_id(&self) -> String {
+        key_id_hex(&self.key_id)
+    }
+
+    /// The trusted comment, only to be relied on after [PublicKey::verify].
+    pub fn trusted_comment(&self) -> &str {
+        &self.trusted_comment
+    }
+}
+
+fn prehash(data: &[u8]) -> [u8; 64] {
+    Blake2b512::digest(data).into()
+}
+
+fn ed25519_public_key(seed: &[u8; 32]) -> [u8; PUBLIC_KEY_LENGTH] {
+    SigningKey::from_bytes(seed).verifying_key().to_bytes()
+}
+
+fn ed25519_sign(seed: &[u8; 32], message: &[u8]) -> [u8; SIGNATURE_LENGTH] {
+    SigningKey::from_bytes(seed).sign(message).to_bytes()
+}
+
+/// Strict verification, rejecting the malleable and small order encodings libsodium (and so
+/// minisign) rejects too.
+fn ed25519_verify(
+    key: &[u8; PUBLIC_KEY_LENGTH],
+    message: &[u8],
+    signature: &[u8; SIGNATURE_LENGTH],
+) -> bool {
+    let signature = ed25519_dalek::Signature::from_bytes(signature);
+    VerifyingKey::from_bytes(key).is_ok_and(|key| key.verify_strict(message, &signature).is_ok())
+}
+
+/// minisign shows key ids as a little endian 64 bit number.
+fn key_id_hex(key_id: &KeyId) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// Fixed size fields of a decoded key or signature
+struct Reader<'a> {
+    bytes: &'a [u8],
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], what: &'static str) -> Self {
+        Self { bytes, what }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        if self.bytes.len() < N {
+            return Err(invalid(format!("{} is too short", self.what)));
+        }
+        let (field, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        let mut out = [0u8; N];
+        out.copy_from_slice(field);
+        Ok(out)
+    }
+
+    fn finish(self) -> Result<()> {
+        match self.bytes.is_empty() {
+            true => Ok(()),
+            false => Err(invalid(format!("{} is too long", self.what))),
+        }
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let word = chunk.iter().enumerate().fold(0u32, |word, (idx, byte)| {
+            word | u32::from(*byte) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            match idx <= chunk.len() {
+                true => out.push(BASE64[(word >> (18 - 6 * idx) & 0x3f) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut word, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE64
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| invalid("invalid base64"))?;
+        word = (word << 6 | value as u32) & 0xffffff; // only the unread bits matter
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((word >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Suggesting which installed files go into the -devel subpackage.
+//!
+//! What is only needed to build against a library (the unversioned `libfoo.so` link, static
+//! archives, headers, pkg-config and CMake files) goes into -devel. The libraries themselves,
+//! the soname links the dynamic linker looks for, executables and everything else stay in the
+//! runtime package.
+
+use crate::tree::{EntryKind, InstallTree, TreeEntry};
+use crate::ElfKind;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Subpackage {
+    Runtime,
+    Devel,
+}
+
+/// Why a file belongs where it was put
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SplitReason {
+    LinkSymlink,    // libfoo.so -> libfoo.so.1, only used by the linker
+    StaticArchive,  // .a
+    LibtoolArchive, // .la
+    Header,         // anything under include/, or a C/C++ header elsewhere
+    PkgConfig,      // .pc
+    CMakeConfig,    // .cmake under a cmake/ directory
+    Aclocal,        // autoconf macros
+    SharedLibrary,  // a shared object, including plugins
+    SonameSymlink,  // the name the dynamic linker looks for
+    Executable,
+    Other,
+}
+
+impl SplitReason {
+    pub fn subpackage(self) -> Subpackage {
+        match self {
+            Self::LinkSymlink
+            | Self::StaticArchive
+            | Self::LibtoolArchive
+            | Self::Header
+            | Self::PkgConfig
+            | Self::CMakeConfig
+            | Self::Aclocal => Subpackage::Devel,
+            Self::SharedLibrary | Self::SonameSymlink | Self::Executable | Self::Other => {
+                Subpackage::Runtime
+            }
+        }
+    }
+}
+
+/// Where one installed file should go
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SplitSuggestion {
+    pub path: String,
+    pub subpackage: Subpackage,
+    pub reason: SplitReason,
+}
+
+impl InstallTree {
+    /// A suggestion for every file and symlink in the tree, in path order.
+    pub fn suggest_split(&self) -> Vec<SplitSuggestion> {
+        self.entries()
+            .iter()
+            .map(|entry| {
+                let reason = self.split_reason(entry);
+                SplitSuggestion {
+                    path: entry.path.clone(),
+                    subpackage: reason.subpackage(),
+                    reason,
+                }
+            })
+            .collect()
+    }
+
+    fn split_reason(&self, entry: &TreeEntry) -> SplitReason {
+        match &entry.kind {
+            EntryKind::Elf { elf_kind, .. } => match elf_kind {
+                ElfKind::SharedObject => SplitReason::SharedLibrary,
+                ElfKind::Executable => SplitReason::Executable,
+                ElfKind::Unknown => SplitReason::Other,
+            },
+            EntryKind::Symlink { .. } => match self.resolve(&entry.path).map(|e| &e.kind) {
+                Some(EntryKind::Elf {
+                    elf_kind: ElfKind::SharedObject,
+                    soname,
+                }) => {
+                    if soname.as_deref() == Some(entry.file_name()) {
+                        SplitReason::SonameSymlink
+                    } else if entry.file_name().ends_with(".so") {
+                        SplitReason::LinkSymlink
+                    } else {
+                        SplitReason::Other
+                    }
+                }
+                _ => reason_by_name(&entry.path),
+            },
+            EntryKind::File => reason_by_name(&entry.path),
+        }
+    }
+}
+
+fn reason_by_name(path: &str) -> SplitReason {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default();
+    match extension {
+        "a" => SplitReason::StaticArchive,
+        "la" => SplitReason::LibtoolArchive,
+        "pc" => SplitReason::PkgConfig,
+        "cmake" if path.contains("/cmake/") => SplitReason::CMakeConfig,
+        "m4" if path.contains("/aclocal/") => SplitReason::Aclocal,
+        "h" | "hh" | "hpp" | "hxx" => SplitReason::Header,
+        _ if path.contains("/include/") => SplitReason::Header,
+        _ => SplitReason::Other,
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Summary statistics over a report, e.g. for distro-wide health dashboards.
+
+use crate::{AbiReport, ElfKind, ExportKinds};
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Totals and top-N rankings over the captures of a report
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ReportStats {
+    pub files: usize,
+    pub by_kind: BTreeMap<ElfKind, usize>,
+    pub by_arch: BTreeMap<String, usize>, // "unknown" for captures without an arch
+    pub exports: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub export_kinds: ExportKinds, // the exports, by functions and data
+    pub imports: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub relocations: u64, // dynamic and PLT relocations, see Relocations
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub symbolic_relocations: u64, // the ones needing a symbol lookup
+    pub most_imported: Vec<SymbolCount>, // imported by the most files, most first
+    pub largest_exporters: Vec<ExportCount>, // shared objects with the most exports, most first
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub most_exported_data: Vec<ExportCount>, // shared objects with the most data exports, most first
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub most_relocated: Vec<RelocationCount>, // the most symbolic relocations, most first
+    pub orphaned_sonames: Vec<String>, // sonames no file of their arch needs, natural sorted
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub least_hidden: Vec<VisibilityCount>, // shared objects exporting the most of what they define, worst first
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SymbolCount {
+    pub symbol: String, // name@version
+    pub files: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ExportCount {
+    pub filename: String,
+    pub exports: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data: usize, // of the exports
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RelocationCount {
+    pub filename: String,
+    pub symbolic: u64,
+    pub total: u64,
+}
+
+/// A shared object's [VisibilityCoverage](crate::VisibilityCoverage), for ranking
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct VisibilityCount {
+    pub filename: String,
+    pub exported: u64,
+    pub defined: u64,
+}
+
+impl AbiReport {
+    /// Summarize the report, keeping the `top` entries of each ranking.
+    ///
+    /// Ties are broken by name, so the rankings are stable across runs.
+    pub fn stats(&self, top: usize) -> ReportStats {
+        let mut stats = ReportStats {
+            files: self.captures().len(),
+            ..Default::default()
+        };
+
+        let mut import_counts = HashMap::<String, usize>::new();
+        let mut needed = HashSet::new();
+        let mut exporters = vec![];
+        let mut relocated = vec![];
+        let mut least_hidden = vec![];
+        for capture in self.captures() {
+            *stats.by_kind.entry(capture.elf_kind()).or_default() += 1;
+            let arch = capture.arch().unwrap_or("unknown");
+            *stats.by_arch.entry(arch.to_string()).or_default() += 1;
+            let kinds = capture.export_kinds();
+            stats.exports += capture.dynsym_exports().len();
+            stats.export_kinds += kinds;
+            stats.imports += capture.dynsym_imports().len();
+            if let Some(relocations) = capture.relocations() {
+                stats.relocations += relocations.total();
+                stats.symbolic_relocations += relocations.symbolic();
+                relocated.push(RelocationCount {
+                    filename: capture.filename().to_string(),
+                    symbolic: relocations.symbolic(),
+                    total: relocations.total(),
+                });
+            }
+
+            // count files, not .dynsym entries (duplicate entries do happen)
+            let mut seen = HashSet::new();
+            for symbol in capture.dynsym_imports() {
+                if seen.insert(symbol.to_string()) {
+                    *import_counts.entry(symbol.to_string()).or_default() += 1;
+                }
+            }
+            needed.extend(capture.needed_deps().iter().map(|dep| (arch, dep.as_str())));
+            if capture.elf_kind() == ElfKind::SharedObject {
+                exporters.push(ExportCount {
+                    filename: capture.filename().to_string(),
+                    exports: capture.dynsym_exports().len(),
+                    data: kinds.data,
+                });
+                if let Some(coverage) = capture.visibility().filter(|c| c.defined > 0) {
+                    least_hidden.push((
+                        coverage.ratio(),
+                        VisibilityCount {
+                            filename: capture.filename().to_string(),
+                            exported: coverage.exported,
+                            defined: coverage.defined,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let mut most_imported = import_counts
+            .into_iter()
+            .map(|(symbol, files)| SymbolCount { symbol, files })
+            .collect::<Vec<_>>();
+        most_imported.sort_by(|a, b| b.files.cmp(&a.files).then(a.symbol.natural_cmp(&b.symbol)));
+        most_imported.truncate(top);
+        stats.most_imported = most_imported;
+
+        let mut data_exporters = exporters
+            .iter()
+            .filter(|count| count.data > 0)
+            .cloned()
+            .collect::<Vec<_>>();
+        data_exporters.sort_by(|a, b| {
+            b.data
+                .cmp(&a.data)
+                .then(a.filename.natural_cmp(&b.filename))
+        });
+        data_exporters.truncate(top);
+        stats.most_exported_data = data_exporters;
+
+        exporters.sort_by(|a, b| {
+            b.exports
+                .cmp(&a.exports)
+                .then(a.filename.natural_cmp(&b.filename))
+        });
+        exporters.truncate(top);
+        stats.largest_exporters = exporters;
+
+        relocated.sort_by(|a, b| {
+            b.symbolic
+                .cmp(&a.symbolic)
+                .then(a.filename.natural_cmp(&b.filename))
+        });
+        relocated.truncate(top);
+        stats.most_relocated = relocated;
+
+        // by share exported, then by the number of symbols exported
+        least_hidden.sort_by(|(a_ratio, a), (b_ratio, b)| {
+            b_ratio
+                .total_cmp(a_ratio)
+                .then(b.exported.cmp(&a.exported))
+                .then(a.filename.natural_cmp(&b.filename))
+        });
+        least_hidden.truncate(top);
+        stats.least_hidden = least_hidden.into_iter().map(|(_, count)| count).collect();
+
+        // a library only needed by files of another architecture is still orphaned
+        let mut orphaned = self
+            .captures()
+            .iter()
+            .filter_map(|capture| {
+                let arch = capture.arch().unwrap_or("unknown");
+                capture
+                    .soname()
+                    .filter(|soname| !needed.contains(&(arch, *soname)))
+            })
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        orphaned.sort_by(|a, b| a.natural_cmp(b));
+        orphaned.dedup();
+        stats.orphaned_sonames = orphaned;
+
+        stats
+    }
+}
@@ -0,0 +1,290 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Streaming report formats, for scans too large to hold an entire AbiReport in memory.
+//!
+//! A stream is a sequence of records: a header, one record per capture (in whatever order they
+//! were produced in) and a footer carrying the capture count and digest, so truncated streams
+//! are detected by readers. Two encodings exist:
+//!
+//! - JSON Lines: one externally tagged JSON object per line
+//!   (`{"stream_header":…}`, `{"capture":…}`, `{"stream_footer":…}`)
+//! - Records: [RECORDS_MAGIC] followed by CBOR records, each prefixed by its u32 LE length
+//!
+//! The footer digest is computed exactly like the AbiReport digest, so a stream of sorted
+//! captures carries the same digest as the equivalent report. Readers only check the count, as
+//! captures carrying fields from newer (additive) schema changes won't reserialize identically.
+
+use crate::report::ReportHeader;
+use crate::schema;
+use crate::AbiCapture;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Error, ErrorKind, Result, Write};
+
+/// Magic number at the start of length prefixed record streams
+pub const RECORDS_MAGIC: &[u8; 8] = b"abirecs\0";
+/// Every JSON Lines stream starts with this
+pub const JSON_LINES_PREFIX: &[u8] = b"{\"stream_header\":";
+
+/// Refuse to allocate records larger than this (a corrupted length prefix shouldn't OOM us)
+const MAX_RECORD_SIZE: u32 = 256 * 1024 * 1024;
+
+/// How the records of a stream are encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamFormat {
+    JsonLines,
+    Records,
+}
+
+/// What gets written, borrowing the captures rather than converting them
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordRef<'a> {
+    StreamHeader(&'a ReportHeader), // the header digest is left empty, see the footer
+    Capture(&'a AbiCapture),
+    StreamFooter(&'a StreamFooter),
+}
+
+/// What gets read back, mirroring [RecordRef]
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    StreamHeader(ReportHeader),
+    Capture(serde_json::Value), // kept raw so captures can be migrated one by one
+    StreamFooter(StreamFooter),
+}
+
+#[derive(Serialize, Deserialize)]
+struct StreamFooter {
+    count: u64,
+    digest: String,
+}
+
+/// Running digest matching the one AbiReport computes over its serialized captures
+#[derive(Default)]
+struct StreamDigest {
+    hasher: Sha256,
+    count: u64,
+}
+
+impl StreamDigest {
+    fn update(&mut self, capture: &AbiCapture) -> Result<()> {
+        self.hasher.update(if self.count == 0 { "[" } else { "," });
+        self.hasher.update(serde_json::to_vec(capture)?);
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> StreamFooter {
+        let mut hasher = self.hasher;
+        hasher.update(if self.count == 0 { "[]" } else { "]" });
+        StreamFooter {
+            count: self.count,
+            digest: format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/// Writes captures out as they are produced.
+pub struct StreamWriter<W: Write> {
+    writer: W,
+    format: StreamFormat,
+    digest: StreamDigest,
+}
+
+impl<W: Write> StreamWriter<W> {
+    /// Start a stream, writing out its header right away.
+    pub fn new(mut writer: W, format: StreamFormat, header: &ReportHeader) -> Result<Self> {
+        if format == StreamFormat::Records {
+            writer.write_all(RECORDS_MAGIC)?;
+        }
+        let header = ReportHeader {
+            digest: String::new(),
+            ..header.clone()
+        };
+
+        let mut stream = Self {
+            writer,
+            format,
+            digest: StreamDigest::default(),
+        };
+        stream.write_record(&RecordRef::StreamHeader(&header))?;
+        Ok(stream)
+    }
+
+    pub fn write_capture(&mut self, capture: &AbiCapture) -> Result<()> {
+        self.digest.update(capture)?;
+        self.write_record(&RecordRef::Capture(capture))
+    }
+
+    /// Write the footer, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let footer = std::mem::take(&mut self.digest).finish();
+        self.write_record(&RecordRef::StreamFooter(&footer))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_record(&mut self, record: &RecordRef) -> Result<()> {
+        match self.format {
+            StreamFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")
+            }
+            StreamFormat::Records => {
+                let mut buf = vec![];
+                ciborium::into_writer(record, &mut buf).map_err(|e| Error::other(e.to_string()))?;
+                let len = u32::try_from(buf.len())
+                    .map_err(|_| Error::other("record too large for a length prefix"))?;
+                self.writer.write_all(&len.to_le_bytes())?;
+                self.writer.write_all(&buf)
+            }
+        }
+    }
+}
+
+/// Reads captures back one at a time from a stream.
+///
+/// Iteration yields an error (and then stops) if the stream is truncated or its footer doesn't
+/// match the number of captures read.
+pub struct StreamReader<R: BufRead> {
+    reader: R,
+    format: StreamFormat,
+    header: ReportHeader,
+    count: u64,
+    done: bool,
+}
+
+impl<R: BufRead> StreamReader<R> {
+    pub fn new(mut reader: R, format: StreamFormat) -> Result<Self> {
+        if format == StreamFormat::Records {
+            let mut magic = [0u8; 8];
+            reader.read_exact(&mut magic)?;
+            if &magic != RECORDS_MAGIC {
+                return Err(invalid("missing record stream magic"));
+            }
+        }
+
+        let header = match read_record(&mut reader, format)? {
+            Some(Record::StreamHeader(header)) => header,
+            _ => return Err(invalid("stream does not start with a header")),
+        };
+        if header.schema_version == 0 || header.schema_version > schema::SCHEMA_VERSION {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "stream schema version {} is not supported (current: {})",
+                    header.schema_version,
+                    schema::SCHEMA_VERSION
+                ),
+            ));
+        }
+
+        Ok(Self {
+            reader,
+            format,
+            header,
+            count: 0,
+            done: false,
+        })
+    }
+
+    pub fn header(&self) -> &ReportHeader {
+        &self.header
+    }
+
+    fn next_capture(&mut self) -> Result<Option<AbiCapture>> {
+        match read_record(&mut self.reader, self.format)? {
+            Some(Record::Capture(capture)) => {
+                self.count += 1;
+                self.migrate(capture).map(Some)
+            }
+            Some(Record::StreamFooter(footer)) => {
+                if self.count != footer.count {
+                    return Err(invalid("capture count does not match the stream footer"));
+                }
+                Ok(None)
+            }
+            Some(Record::StreamHeader(_)) => Err(invalid("unexpected second stream header")),
+            None => Err(invalid("stream ended without a footer")),
+        }
+    }
+
+    /// Run a single capture through the report migrations by wrapping it in a minimal report.
+    fn migrate(&self, capture: serde_json::Value) -> Result<AbiCapture> {
+        if self.header.schema_version == schema::SCHEMA_VERSION {
+            return Ok(serde_json::from_value(capture)?);
+        }
+        let mut document = serde_json::json!({
+            "header": { "schema_version": self.header.schema_version },
+            "captures": [capture],
+        });
+        schema::migrate(&mut document)?;
+        Ok(serde_json::from_value(document["captures"][0].take())?)
+    }
+}
+
+impl<R: BufRead> Iterator for StreamReader<R> {
+    type Item = Result<AbiCapture>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_capture() {
+            Ok(Some(capture)) => Some(Ok(capture)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Returns None on a clean end of stream.
+fn read_record<R: BufRead>(reader: &mut R, format: StreamFormat) -> Result<Option<Record>> {
+    match format {
+        StreamFormat::JsonLines => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                if !line.trim().is_empty() {
+                    return Ok(Some(serde_json::from_str(&line)?));
+                }
+            }
+        }
+        StreamFormat::Records => {
+            let mut len = [0u8; 4];
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err),
+            }
+            let len = u32::from_le_bytes(len);
+            if len > MAX_RECORD_SIZE {
+                return Err(invalid("record length exceeds the sanity limit"));
+            }
+            let mut buf = vec![0u8; len as usize];
+            reader.read_exact(&mut buf)?;
+            ciborium::from_reader(buf.as_slice())
+                .map(Some)
+                .map_err(|e| invalid(&e.to_string()))
+        }
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("malformed report stream: {msg}"),
+    )
+}
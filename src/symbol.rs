@@ -0,0 +1,339 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Dynamic symbols as captured from .dynsym.
+//!
+//! A symbol is identified by its name together with its GNU symbol version, as `foo@VER_1` and
+//! `foo@VER_2` are different symbols as far as the dynamic linker is concerned. Of the versions
+//! a library exports a symbol with, one is the default (`foo@@VER_2`) that new links bind to;
+//! the others are kept for compatibility with binaries linked against them.
+
+use natural_sort_rs::NaturalSortable;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Symbols the toolchain (crt files, linker scripts, libgcc) puts into every binary, whatever
+/// its sources: they come and go with toolchain changes rather than with the library's ABI.
+const BOILERPLATE: [&str; 15] = [
+    "_init",
+    "_fini",
+    "_start",
+    "_end",
+    "_edata",
+    "__bss_start",
+    "__data_start",
+    "data_start",
+    "__dso_handle",
+    "_IO_stdin_used",
+    "__gmon_start__",
+    "__TMC_END__",
+    "_ITM_registerTMCloneTable",
+    "_ITM_deregisterTMCloneTable",
+    "__cxa_finalize",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Symbol {
+    pub name: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub version: Option<String>, // GNU symbol version (verdef for exports, verneed for imports)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub compat: bool, // exports only: a non-default version (`foo@VER`, not `foo@@VER`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: SymbolKind, // STT_* type
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub provider: Option<String>, // imports only: the DT_NEEDED entry supplying it, once resolved
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub size: Option<u64>, // exported functions and objects only: st_size
+}
+
+/// A [Symbol] borrowing its strings rather than owning copies of them: from the file data as
+/// parsed (see [crate::AbiCaptureRef], where imports aren't resolved yet so there is no
+/// provider), or from the string arena of a [crate::CompactReport].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SymbolRef<'data> {
+    pub name: &'data str,
+    pub version: Option<&'data str>,
+    pub compat: bool,
+    pub kind: SymbolKind,
+    pub provider: Option<&'data str>,
+    pub size: Option<u64>,
+}
+
+/// Exports counted by what they are: functions or data. Exported data carries ABI risks
+/// functions don't, as executables referencing it get a copy relocation, baking its size in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ExportKinds {
+    pub functions: usize, // STT_FUNC and STT_GNU_IFUNC
+    pub data: usize,      // STT_OBJECT, STT_TLS and STT_COMMON
+    pub other: usize,     // untyped, or captured before symbol types were
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version_nodes: usize, // the markers of the version definitions, see Symbol::is_version_node
+}
+
+/// The symbol type (STT_*) of a dynamic symbol
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SymbolKind {
+    Function, // STT_FUNC
+    Object,   // STT_OBJECT
+    Tls,      // STT_TLS
+    Ifunc,    // STT_GNU_IFUNC
+    Common,   // STT_COMMON
+    NoType,   // STT_NOTYPE
+    Other,    // anything else
+    #[default]
+    Unknown, // not recorded (captures from before symbol types were captured)
+}
+
+impl Symbol {
+    /// An unversioned symbol of unknown type.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: None,
+            compat: false,
+            kind: SymbolKind::Unknown,
+            provider: None,
+            size: None,
+        }
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Mark the version as a non-default one, kept for binaries linked against it.
+    pub fn with_compat(mut self, compat: bool) -> Self {
+        self.compat = compat;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: SymbolKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Whether this is one of the symbols the toolchain generates for every binary (`_init`,
+    /// `_fini`, `__bss_start`, `_edata`, `__libc_csu_init`, ...). Captures keep them, but diffs
+    /// leave them out unless asked not to, see [crate::diff::DiffOptions].
+    pub fn is_boilerplate(&self) -> bool {
+        is_boilerplate(&self.name)
+    }
+
+    /// Whether this is the marker of a version definition (`ZLIB_1.2.0@ZLIB_1.2.0`): an absolute,
+    /// empty object the linker emits for each version node, named after it, rather than data.
+    pub fn is_version_node(&self) -> bool {
+        is_version_node(&self.name, self.version.as_deref(), self.kind, self.size)
+    }
+
+    /// Whether the name is mangled by a C++ (`_Z...`) or Rust (`_ZN...17h<hash>E`, or `_R...`
+    /// in the v0 scheme) compiler, rather than being a plain C (`extern "C"`) symbol.
+    pub fn is_mangled(&self) -> bool {
+        is_mangled(&self.name)
+    }
+
+    /// Natural order by name, then by version (unversioned first).
+    pub fn natural_cmp(&self, other: &Self) -> Ordering {
+        self.name
+            .natural_cmp(&other.name)
+            .then_with(|| match (&self.version, &other.version) {
+                (Some(a), Some(b)) => a.natural_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            })
+    }
+
+    /// A copy borrowing the strings of this one.
+    pub fn to_ref(&self) -> SymbolRef<'_> {
+        SymbolRef {
+            name: &self.name,
+            version: self.version.as_deref(),
+            compat: self.compat,
+            kind: self.kind,
+            provider: self.provider.as_deref(),
+            size: self.size,
+        }
+    }
+}
+
+impl SymbolRef<'_> {
+    /// See [Symbol::is_boilerplate].
+    pub fn is_boilerplate(&self) -> bool {
+        is_boilerplate(self.name)
+    }
+
+    /// See [Symbol::is_mangled].
+    pub fn is_mangled(&self) -> bool {
+        is_mangled(self.name)
+    }
+
+    /// See [Symbol::is_version_node].
+    pub fn is_version_node(&self) -> bool {
+        is_version_node(self.name, self.version, self.kind, self.size)
+    }
+
+    /// See [Symbol::natural_cmp].
+    pub fn natural_cmp(&self, other: &Self) -> Ordering {
+        self.name
+            .natural_cmp(other.name)
+            .then_with(|| match (self.version, other.version) {
+                (Some(a), Some(b)) => a.natural_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            })
+    }
+
+    /// An owned copy.
+    pub fn to_symbol(&self) -> Symbol {
+        Symbol {
+            name: self.name.to_string(),
+            version: self.version.map(str::to_string),
+            compat: self.compat,
+            kind: self.kind,
+            provider: self.provider.map(str::to_string),
+            size: self.size,
+        }
+    }
+}
+
+/// See [Symbol::is_boilerplate].
+pub(crate) fn is_boilerplate(name: &str) -> bool {
+    BOILERPLATE.contains(&name) || name.starts_with("__libc_csu_")
+}
+
+/// See [Symbol::is_version_node]. The section index (SHN_ABS) isn't captured, but nothing else
+/// is an empty object named after its own version.
+fn is_version_node(name: &str, version: Option<&str>, kind: SymbolKind, size: Option<u64>) -> bool {
+    kind == SymbolKind::Object && version == Some(name) && size.unwrap_or(0) == 0
+}
+
+/// See [Symbol::is_mangled]. Both schemes follow their prefix with an upper case letter or a
+/// digit, which C names starting with `_Z` or `_R` (reserved as they are) needn't.
+pub(crate) fn is_mangled(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    matches!(bytes, [b'_', b'Z' | b'R', next, ..] if next.is_ascii_uppercase() || next.is_ascii_digit())
+}
+
+impl ExportKinds {
+    /// Count `symbols` by kind.
+    pub fn count<'a>(symbols: impl IntoIterator<Item = &'a Symbol>) -> Self {
+        let mut kinds = Self::default();
+        for symbol in symbols {
+            match symbol.kind {
+                _ if symbol.is_version_node() => kinds.version_nodes += 1,
+                kind if kind.is_function() => kinds.functions += 1,
+                kind if kind.is_data() => kinds.data += 1,
+                _ => kinds.other += 1,
+            }
+        }
+        kinds
+    }
+
+    pub fn total(&self) -> usize {
+        self.functions + self.data + self.other + self.version_nodes
+    }
+}
+
+impl std::ops::AddAssign for ExportKinds {
+    fn add_assign(&mut self, other: Self) {
+        self.functions += other.functions;
+        self.data += other.data;
+        self.other += other.other;
+        self.version_nodes += other.version_nodes;
+    }
+}
+
+impl SymbolKind {
+    /// Code: a function, or an indirect function resolved to one at load time.
+    pub fn is_function(self) -> bool {
+        matches!(self, Self::Function | Self::Ifunc)
+    }
+
+    /// A variable: a data object, thread-local or common.
+    pub fn is_data(self) -> bool {
+        matches!(self, Self::Object | Self::Tls | Self::Common)
+    }
+
+    pub(crate) fn from_st_type(st_type: u8) -> Self {
+        match st_type {
+            elf::abi::STT_FUNC => Self::Function,
+            elf::abi::STT_OBJECT => Self::Object,
+            elf::abi::STT_TLS => Self::Tls,
+            elf::abi::STT_GNU_IFUNC => Self::Ifunc,
+            elf::abi::STT_COMMON => Self::Common,
+            elf::abi::STT_NOTYPE => Self::NoType,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<SymbolRef<'_>> for Symbol {
+    fn from(symbol: SymbolRef<'_>) -> Self {
+        symbol.to_symbol()
+    }
+}
+
+/// `name@version`, or just `name` when unversioned
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{version}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Function => "function",
+            Self::Object => "object",
+            Self::Tls => "tls",
+            Self::Ifunc => "ifunc",
+            Self::Common => "common",
+            Self::NoType => "notype",
+            Self::Other => "other",
+            Self::Unknown => "unknown",
+        })
+    }
+}
@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Everything installed under a staging root, not just the ELF files.
+//!
+//! Packaging questions (what goes into -devel, do the .pc files match the libraries) need the
+//! symlinks and the non-ELF files a scan skips. Paths are recorded as they will be installed,
+//! i.e. relative to the root but starting with `/`, and symlinks are resolved within the tree.
+
+use crate::scan::has_elf_magic;
+use crate::{parse_elf_sonames, ElfKind, Limits};
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+/// How many symlinks to follow before giving up on a loop
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// The files and symlinks under a staging root
+#[derive(Clone, Debug)]
+pub struct InstallTree {
+    root: PathBuf,
+    entries: Vec<TreeEntry>, // sorted by path
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub path: String, // the install path, e.g. /usr/lib/libz.so.1
+    pub kind: EntryKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Elf {
+        elf_kind: ElfKind,
+        soname: Option<String>,
+    },
+    Symlink {
+        target: String, // as stored in the link, relative or absolute
+    },
+}
+
+impl InstallTree {
+    /// Walk everything under `root`. Directories are not recorded, and symlinks are never
+    /// followed while walking.
+    pub fn walk(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let mut entries = vec![];
+        walk_dir(&root, &root, &mut entries)?;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self { root, entries })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> &[TreeEntry] {
+        &self.entries
+    }
+
+    /// The entry at an install path.
+    pub fn get(&self, path: &str) -> Option<&TreeEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.path.as_str().cmp(path))
+            .ok()
+            .map(|idx| &self.entries[idx])
+    }
+
+    /// The entry a path ends up at after following symlinks within the tree, if any.
+    pub fn resolve(&self, path: &str) -> Option<&TreeEntry> {
+        let mut entry = self.get(path)?;
+        for _ in 0..MAX_SYMLINK_HOPS {
+            match &entry.kind {
+                EntryKind::Symlink { target } => {
+                    entry = self.get(&link_target(&entry.path, target))?;
+                }
+                _ => return Some(entry),
+            }
+        }
+        None
+    }
+
+    /// Where an install path is in the staging root.
+    pub fn staged_path(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+}
+
+impl TreeEntry {
+    /// The last component of the path.
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+fn walk_dir(root: &Path, dir: &Path, entries: &mut Vec<TreeEntry>) -> Result<()> {
+    for dir_entry in fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        let file_type = dir_entry.file_type()?;
+        if file_type.is_dir() {
+            walk_dir(root, &path, entries)?;
+            continue;
+        }
+
+        let kind = if file_type.is_symlink() {
+            let target = fs::read_link(&path)?;
+            EntryKind::Symlink {
+                target: target.to_string_lossy().into_owned(),
+            }
+        } else if file_type.is_file() && has_elf_magic(&path) {
+            // the tree is only about what is installed where, so a broken ELF is just a file
+            match parse_elf_sonames(&path.to_string_lossy(), &Limits::default()) {
+                Ok(capture) => EntryKind::Elf {
+                    elf_kind: capture.elf_kind(),
+                    soname: capture.soname().map(str::to_string),
+                },
+                Err(_) => EntryKind::File,
+            }
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else {
+            // devices, sockets and fifos have no business in a package
+            continue;
+        };
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        entries.push(TreeEntry {
+            path: format!("/{}", relative.to_string_lossy()),
+            kind,
+        });
+    }
+    Ok(())
+}
+
+/// The install path a symlink at `path` points to, with `.` and `..` resolved lexically.
+fn link_target(path: &str, target: &str) -> String {
+    let mut components = match target.starts_with('/') {
+        true => vec![],
+        false => {
+            let mut components = path.split('/').collect::<Vec<_>>();
+            components.pop();
+            components
+        }
+    };
+    for component in target.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    let path = components
+        .into_iter()
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    format!("/{}", path.join("/"))
+}
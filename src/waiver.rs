@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reviewed exceptions to check findings.
+//!
+//! Some findings are accepted on purpose, say a bundled library upstream insists on. Rather than
+//! turning a check off, a waivers file (TOML) records each exception, why it was accepted and
+//! until when:
+//!
+//! ```toml
+//! [[waiver]]
+//! check = "bundled-library"
+//! file = "**/libfoo.so*"           # glob, default: any file
+//! justification = "upstream vendors zlib, see foo#123"
+//! expires = 2026-06-30             # optional
+//!
+//! [[waiver]]
+//! check = "protected-data"
+//! symbol = "foo_*_table"           # every symbol of the finding has to match
+//! justification = "only used from within libfoo"
+//! ```
+//!
+//! Expired waivers stop applying, and waivers matching no finding are reported as unused, so
+//! exceptions get reviewed again instead of piling up for good.
+
+use crate::{Check, Finding};
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+
+/// One accepted exception
+#[derive(Clone, Debug)]
+pub struct Waiver {
+    pub check: Check,
+    pub file: Option<String>, // glob matched against the finding's filename
+    pub symbol: Option<String>, // glob every symbol of the finding has to match
+    pub justification: String, // why it was accepted, never empty
+    pub expires: Option<[u32; 3]>, // year, month, day: the last day it applies
+    file_glob: Option<GlobMatcher>,
+    symbol_glob: Option<GlobMatcher>,
+}
+
+/// The waivers of a waivers file, in file order
+#[derive(Clone, Debug, Default)]
+pub struct Waivers {
+    waivers: Vec<Waiver>,
+}
+
+/// The findings of a check run, sorted out by [Waivers::apply]
+#[derive(Debug, Default)]
+pub struct Waived<'a> {
+    pub findings: Vec<Finding>,             // not waived
+    pub waived: Vec<(Finding, &'a Waiver)>, // with the first waiver applying to each
+    pub expired: Vec<&'a Waiver>,           // past their expiry, whether they'd match or not
+    pub unused: Vec<&'a Waiver>,            // in force, but matching no finding
+}
+
+/// A `[[waiver]]` table as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaiverEntry {
+    check: String, // a check id, as on the command line
+    file: Option<String>,
+    symbol: Option<String>,
+    justification: String,
+    expires: Option<toml::value::Datetime>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaiversFile {
+    #[serde(default)]
+    waiver: Vec<WaiverEntry>,
+}
+
+impl Waivers {
+    /// Parse a waivers file, see the module docs.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let file = toml::from_str::<WaiversFile>(contents)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        let waivers = file
+            .waiver
+            .into_iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                Waiver::new(entry)
+                    .map_err(|err| Error::new(err.kind(), format!("waiver {}: {err}", idx + 1)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { waivers })
+    }
+
+    pub fn waivers(&self) -> &[Waiver] {
+        &self.waivers
+    }
+
+    /// Sort `findings` into those waived (by the first waiver in force matching them) and
+    /// those not, as of `today` (year, month, day).
+    pub fn apply(&self, findings: Vec<Finding>, today: [u32; 3]) -> Waived<'_> {
+        let (in_force, expired) = self
+            .waivers
+            .iter()
+            .partition::<Vec<_>, _>(|waiver| !waiver.is_expired(today));
+        let mut waived = Waived {
+            expired,
+            ..Waived::default()
+        };
+        let mut used = vec![false; in_force.len()];
+        for finding in findings {
+            match in_force.iter().position(|waiver| waiver.matches(&finding)) {
+                Some(idx) => {
+                    used[idx] = true;
+                    waived.waived.push((finding, in_force[idx]));
+                }
+                None => waived.findings.push(finding),
+            }
+        }
+        waived.unused = in_force
+            .into_iter()
+            .zip(used)
+            .filter_map(|(waiver, used)| (!used).then_some(waiver))
+            .collect();
+        waived
+    }
+}
+
+impl Waiver {
+    fn new(entry: WaiverEntry) -> Result<Self> {
+        if entry.justification.trim().is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the justification is empty",
+            ));
+        }
+        let expires = match entry.expires {
+            Some(expires) => match (expires.date, expires.time) {
+                (Some(date), None) => Some([date.year.into(), date.month.into(), date.day.into()]),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("expires {expires} isn't a date (e.g. 2026-06-30)"),
+                    ))
+                }
+            },
+            None => None,
+        };
+        Ok(Self {
+            check: entry.check.parse()?,
+            file_glob: entry.file.as_deref().map(glob).transpose()?,
+            symbol_glob: entry.symbol.as_deref().map(glob).transpose()?,
+            file: entry.file,
+            symbol: entry.symbol,
+            justification: entry.justification,
+            expires,
+        })
+    }
+
+    /// Whether the waiver covers the finding (regardless of expiry). Waivers with a symbol
+    /// pattern only cover findings about symbols.
+    pub fn matches(&self, finding: &Finding) -> bool {
+        finding.check == self.check
+            && self
+                .file_glob
+                .as_ref()
+                .is_none_or(|glob| glob.is_match(&finding.filename))
+            && self.symbol_glob.as_ref().is_none_or(|glob| {
+                !finding.symbols.is_empty()
+                    && finding.symbols.iter().all(|symbol| glob.is_match(symbol))
+            })
+    }
+
+    /// Whether `today` (year, month, day) is past the expiry date.
+    pub fn is_expired(&self, today: [u32; 3]) -> bool {
+        self.expires.is_some_and(|expires| today > expires)
+    }
+}
+
+/// `check`, `check file` or `check file symbol`, with the expiry date if any
+impl fmt::Display for Waiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.check)?;
+        for pattern in [&self.file, &self.symbol].into_iter().flatten() {
+            write!(f, " {pattern}")?;
+        }
+        if let Some([year, month, day]) = self.expires {
+            write!(f, " (expires {year:04}-{month:02}-{day:02})")?;
+        }
+        Ok(())
+    }
+}
+
+fn glob(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid pattern {pattern:?}: {err}"),
+            )
+        })
+}
@@ -0,0 +1,115 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Problems found while capturing a damaged ELF file.
+//!
+//! A truncated download or a corrupted string table shouldn't cost us everything else we can
+//! still read from a file, so instead of failing the capture outright, the broken part is left
+//! out and a warning describing it is recorded in the capture.
+//!
+//! Likewise, a path a scan can't look into is skipped with a [ScanWarning], rather than ending the
+//! scan or being printed from within the library.
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum CaptureWarning {
+    /// The section header table is unusable; only the program headers were used
+    SectionHeaders { error: String },
+    /// .dynsym (or its string table) is unusable; no symbols were captured
+    DynamicSymbols { error: String },
+    /// A single .dynsym entry (or Mach-O symbol table entry, or PE export name) has a bad name
+    /// and was skipped
+    SymbolName { index: usize, error: String },
+    /// The symbol version tables are unusable; no symbol versions were captured
+    SymbolVersions { error: String },
+    /// A single .dynsym entry has a bad version and was captured unversioned
+    SymbolVersion { index: usize, error: String },
+    /// The dynamic section is unusable; no dependencies were captured
+    Dynamic { error: String },
+    /// A single dynamic entry has a bad string and was skipped
+    DynamicEntry { tag: i64, error: String },
+    /// The notes could not be read; the build-id (and ABI tag) may be missing
+    BuildId { error: String },
+    /// .gnu_debuglink is present but could not be read
+    DebugLink { error: String },
+    /// .symtab is unusable; no visibility coverage was measured
+    SymbolTable { error: String },
+}
+
+impl fmt::Display for CaptureWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SectionHeaders { error } => write!(f, "section headers unusable: {error}"),
+            Self::DynamicSymbols { error } => write!(f, "dynamic symbols unusable: {error}"),
+            Self::SymbolName { index, error } => {
+                write!(f, "skipped dynamic symbol {index}: {error}")
+            }
+            Self::SymbolVersions { error } => write!(f, "symbol versions unusable: {error}"),
+            Self::SymbolVersion { index, error } => {
+                write!(f, "unversioned dynamic symbol {index}: {error}")
+            }
+            Self::Dynamic { error } => write!(f, "dynamic section unusable: {error}"),
+            Self::DynamicEntry { tag, error } => {
+                write!(f, "skipped dynamic entry (tag {tag:#x}): {error}")
+            }
+            Self::BuildId { error } => write!(f, "build-id unreadable: {error}"),
+            Self::DebugLink { error } => write!(f, "debuglink unreadable: {error}"),
+            Self::SymbolTable { error } => write!(f, "symbol table unusable: {error}"),
+        }
+    }
+}
+
+/// A path skipped while looking for the files to scan, see [crate::Scanner::discover_with_warnings]
+///
+/// Displays as the full message, including what was done about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScanWarning {
+    /// An input path could not be inspected
+    Uninspectable { path: PathBuf, error: String },
+    /// An input path is neither a file nor a directory
+    NotAFile { path: PathBuf },
+    /// A directory could not be read
+    UnreadableDir { path: PathBuf, error: String },
+    /// An ignore file could not be read or parsed, so none of its rules apply (`error` names the
+    /// file, and the line if it's a bad pattern)
+    IgnoreFile { path: PathBuf, error: String },
+}
+
+impl fmt::Display for ScanWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uninspectable { path, error } => {
+                write!(
+                    f,
+                    "{} could not be inspected ({error}). Skipping.",
+                    path.display()
+                )
+            }
+            Self::NotAFile { path } => {
+                write!(
+                    f,
+                    "{} is neither a file nor a directory. Skipping.",
+                    path.display()
+                )
+            }
+            Self::UnreadableDir { path, error } => {
+                write!(
+                    f,
+                    "{} could not be read ({error}). Skipping.",
+                    path.display()
+                )
+            }
+            Self::IgnoreFile { error, .. } => write!(f, "{error}. Ignoring it."),
+        }
+    }
+}
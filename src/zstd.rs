@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: Copyright © 2020-2025 Serpent OS Developers
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Zstd compressed reports (`.json.zst`, `.bin.zst`, ...).
+//!
+//! Whole repository reports repeat the same symbol and library names endlessly and shrink
+//! to a fraction of their size. Compressed reports are recognized by their magic number when
+//! read, so any format can be compressed; the compressed stream may consist of several frames,
+//! as written by [ZstdWriter] on every flush, or by concatenating files.
+
+use crate::format::ZSTD_MAGIC;
+use ruzstd::decoding::StreamingDecoder;
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use std::io::{BufRead, Error, ErrorKind, Read, Result, Write};
+
+/// A writer compressing everything written to it with zstd.
+///
+/// Data is compressed a frame at a time, so it is buffered until the next flush (or drop,
+/// where errors are ignored as with `BufWriter`: flush before dropping to see them).
+pub struct ZstdWriter<W: Write> {
+    inner: Option<W>, // taken by finish()
+    buffer: Vec<u8>,  // not yet compressed
+}
+
+impl<W: Write> ZstdWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: Some(inner),
+            buffer: vec![],
+        }
+    }
+
+    /// Compress whatever is buffered and return the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.inner.take().expect("only taken here"))
+    }
+}
+
+impl<W: Write> Write for ZstdWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let Some(inner) = &mut self.inner else {
+            return Ok(());
+        };
+        if !self.buffer.is_empty() {
+            let frame = compress_to_vec(self.buffer.as_slice(), CompressionLevel::Fastest);
+            inner.write_all(&frame)?;
+            self.buffer.clear();
+        }
+        inner.flush()
+    }
+}
+
+impl<W: Write> Drop for ZstdWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Decompress all the zstd frames from `reader`.
+pub(crate) fn decompress<R: BufRead>(mut reader: R) -> Result<Vec<u8>> {
+    let mut out = vec![];
+    loop {
+        // read rather than peek at the magic, which may straddle the end of the buffer
+        let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+        reader
+            .by_ref()
+            .take(ZSTD_MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+        if magic.is_empty() {
+            return Ok(out);
+        }
+        if magic != ZSTD_MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "trailing data after the zstd compressed report",
+            ));
+        }
+        StreamingDecoder::new(magic.as_slice().chain(&mut reader))
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?
+            .read_to_end(&mut out)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn decompress_frames_split_anywhere() {
+        let mut writer = ZstdWriter::new(vec![]);
+        writer.write_all(b"{\"first\": \"frame\",").unwrap();
+        writer.flush().unwrap();
+        let first_len = writer.inner.as_ref().unwrap().len();
+        writer.write_all(b" \"second\": \"frame\"}").unwrap();
+        let compressed = writer.finish().unwrap();
+
+        // wherever the buffer ends relative to the second frame's magic
+        for capacity in first_len..=first_len + 5 {
+            let reader = BufReader::with_capacity(capacity, compressed.as_slice());
+            assert_eq!(
+                decompress(reader).unwrap(),
+                b"{\"first\": \"frame\", \"second\": \"frame\"}",
+                "buffer of {capacity} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn decompress_rejects_trailing_data() {
+        let mut compressed = ZstdWriter::new(vec![]);
+        compressed.write_all(b"report").unwrap();
+        let mut compressed = compressed.finish().unwrap();
+        compressed.extend_from_slice(b"junk");
+        let err = decompress(compressed.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}